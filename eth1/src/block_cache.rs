@@ -0,0 +1,127 @@
+use std::collections::VecDeque;
+
+use types::primitives::H256;
+use types::types::Eth1Data;
+
+use crate::Error;
+
+/// An Eth1 block along with the state of the deposit contract at the end of it.
+#[derive(Debug, PartialEq, Clone)]
+pub struct Eth1Block {
+    pub hash: H256,
+    pub number: u64,
+    pub timestamp: u64,
+    pub deposit_root: H256,
+    pub deposit_count: u64,
+}
+
+impl Eth1Block {
+    /// The `Eth1Data` a validator would vote for if it chose this block.
+    pub fn eth1_data(&self) -> Eth1Data {
+        Eth1Data {
+            deposit_root: self.deposit_root,
+            deposit_count: self.deposit_count,
+            block_hash: self.hash,
+        }
+    }
+}
+
+/// A contiguous run of Eth1 blocks, oldest first.
+#[derive(Debug, Default, Clone)]
+pub struct BlockCache {
+    blocks: VecDeque<Eth1Block>,
+}
+
+impl BlockCache {
+    pub fn len(&self) -> usize {
+        self.blocks.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.blocks.is_empty()
+    }
+
+    pub fn earliest_block_number(&self) -> Option<u64> {
+        self.blocks.front().map(|block| block.number)
+    }
+
+    pub fn latest_block_number(&self) -> Option<u64> {
+        self.blocks.back().map(|block| block.number)
+    }
+
+    /// Appends `block`, which must directly follow the latest block in the cache.
+    pub fn insert(&mut self, block: Eth1Block) -> Result<(), Error> {
+        if let Some(latest) = self.latest_block_number() {
+            if block.number != latest + 1 {
+                return Err(Error::NonConsecutiveBlock {
+                    expected: latest + 1,
+                    got: block.number,
+                });
+            }
+        }
+
+        self.blocks.push_back(block);
+        Ok(())
+    }
+
+    /// Drops the oldest blocks until at most `len` remain.
+    pub fn truncate(&mut self, len: usize) {
+        while self.blocks.len() > len {
+            self.blocks.pop_front();
+        }
+    }
+
+    pub fn block_by_number(&self, number: u64) -> Option<&Eth1Block> {
+        let earliest = self.earliest_block_number()?;
+        let index = number.checked_sub(earliest)?;
+        self.blocks.get(index as usize)
+    }
+
+    pub fn block_by_hash(&self, hash: &H256) -> Option<&Eth1Block> {
+        self.blocks.iter().rev().find(|block| block.hash == *hash)
+    }
+
+    pub fn iter(&self) -> impl DoubleEndedIterator<Item = &Eth1Block> {
+        self.blocks.iter()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn block(number: u64) -> Eth1Block {
+        Eth1Block {
+            hash: H256::from_low_u64_be(number + 1),
+            number,
+            timestamp: number * 14,
+            deposit_root: H256::zero(),
+            deposit_count: 0,
+        }
+    }
+
+    #[test]
+    fn insert_and_truncate() {
+        let mut cache = BlockCache::default();
+        for number in 10..20 {
+            cache.insert(block(number)).unwrap();
+        }
+
+        assert_eq!(
+            cache.insert(block(21)),
+            Err(Error::NonConsecutiveBlock {
+                expected: 20,
+                got: 21
+            })
+        );
+
+        cache.truncate(4);
+        assert_eq!(cache.len(), 4);
+        assert_eq!(cache.earliest_block_number(), Some(16));
+        assert_eq!(cache.latest_block_number(), Some(19));
+        assert_eq!(cache.block_by_number(15), None);
+        assert_eq!(cache.block_by_number(17), Some(&block(17)));
+        assert_eq!(cache.block_by_number(20), None);
+        assert_eq!(cache.block_by_hash(&block(18).hash), Some(&block(18)));
+    }
+}
@@ -0,0 +1,214 @@
+use bls::{PublicKeyBytes, SignatureBytes};
+use ssz::{Decode, Encode};
+use types::primitives::{Gwei, H256};
+use types::types::DepositData;
+
+use crate::Error;
+
+/// `keccak256("DepositEvent(bytes,bytes,bytes,bytes,bytes)")`, the first topic of every log
+/// emitted by the deposit contract.
+pub const DEPOSIT_EVENT_TOPIC: &str =
+    "0x649bbc62d0e31342afea4e5cd82d4049e7e1ee912fc0889aa790803be39038c5";
+
+/// The number of bytes in an ABI word.
+const WORD_LEN: usize = 32;
+
+const PUBKEY_LEN: usize = 48;
+const WITHDRAWAL_CREDENTIALS_LEN: usize = 32;
+const AMOUNT_LEN: usize = 8;
+const SIGNATURE_LEN: usize = 96;
+const INDEX_LEN: usize = 8;
+
+/// A `DepositEvent` emitted by the deposit contract, parsed into the types used by the beacon
+/// chain.
+#[derive(Debug, PartialEq, Clone)]
+pub struct DepositLog {
+    pub deposit_data: DepositData,
+    /// The number of the Eth1 block that included the deposit.
+    pub block_number: u64,
+    /// The index of the deposit in the deposit contract.
+    pub index: u64,
+}
+
+impl DepositLog {
+    /// Parses the ABI-encoded `data` field of a `DepositEvent` log.
+    ///
+    /// The pubkey and signature are not validated. The deposit contract accepts anything of the
+    /// right length, so invalid deposits are still part of the deposit tree.
+    pub fn from_log_data(data: &[u8], block_number: u64) -> Result<Self, Error> {
+        let pubkey = read_bytes_field(data, 0, PUBKEY_LEN)?;
+        let withdrawal_credentials = read_bytes_field(data, 1, WITHDRAWAL_CREDENTIALS_LEN)?;
+        let amount = read_bytes_field(data, 2, AMOUNT_LEN)?;
+        let signature = read_bytes_field(data, 3, SIGNATURE_LEN)?;
+        let index = read_bytes_field(data, 4, INDEX_LEN)?;
+
+        let deposit_data = DepositData {
+            pubkey: PublicKeyBytes::from_bytes(pubkey).map_err(Error::DepositLogSsz)?,
+            withdrawal_credentials: H256::from_slice(withdrawal_credentials),
+            amount: Gwei::from_ssz_bytes(amount).map_err(Error::DepositLogSsz)?,
+            signature: SignatureBytes::from_bytes(signature).map_err(Error::DepositLogSsz)?,
+        };
+
+        Ok(Self {
+            deposit_data,
+            block_number,
+            index: u64::from_ssz_bytes(index).map_err(Error::DepositLogSsz)?,
+        })
+    }
+
+    /// Produces the ABI-encoded `data` field of the `DepositEvent` that the deposit contract
+    /// emits for `self`. The inverse of `Self::from_log_data`.
+    pub fn to_log_data(&self) -> Vec<u8> {
+        let fields = [
+            self.deposit_data.pubkey.as_ssz_bytes(),
+            self.deposit_data.withdrawal_credentials.as_bytes().to_vec(),
+            self.deposit_data.amount.as_ssz_bytes(),
+            self.deposit_data.signature.as_ssz_bytes(),
+            self.index.as_ssz_bytes(),
+        ];
+
+        let mut head = Vec::with_capacity(fields.len() * WORD_LEN);
+        let mut tail = vec![];
+
+        for field in &fields {
+            head.extend_from_slice(&encode_word(fields.len() * WORD_LEN + tail.len()));
+            tail.extend_from_slice(&encode_word(field.len()));
+            tail.extend_from_slice(field);
+            tail.resize(padded_len(tail.len()), 0);
+        }
+
+        head.append(&mut tail);
+        head
+    }
+}
+
+/// Reads the `i`th dynamic `bytes` argument from ABI-encoded `data`, checking that it is
+/// `expected_len` bytes long.
+fn read_bytes_field(data: &[u8], i: usize, expected_len: usize) -> Result<&[u8], Error> {
+    let offset = read_word(data, i * WORD_LEN)?;
+    let len = read_word(data, offset)?;
+
+    if len != expected_len {
+        return Err(Error::InvalidDepositLog(format!(
+            "field {} has length {}, expected {}",
+            i, len, expected_len
+        )));
+    }
+
+    let start = offset.saturating_add(WORD_LEN);
+    data.get(start..start.saturating_add(len))
+        .ok_or_else(|| Error::InvalidDepositLog(format!("field {} is out of bounds", i)))
+}
+
+/// Reads a big-endian ABI word at `position`, rejecting values that do not fit in a `u32`.
+fn read_word(data: &[u8], position: usize) -> Result<usize, Error> {
+    let word = data
+        .get(position..position.saturating_add(WORD_LEN))
+        .ok_or_else(|| Error::InvalidDepositLog(format!("no word at byte {}", position)))?;
+
+    let (high, low) = word.split_at(WORD_LEN - 4);
+    if high.iter().any(|byte| *byte != 0) {
+        return Err(Error::InvalidDepositLog(format!(
+            "word at byte {} is too large",
+            position
+        )));
+    }
+
+    let mut array = [0; 4];
+    array.copy_from_slice(low);
+    Ok(u32::from_be_bytes(array) as usize)
+}
+
+fn encode_word(value: usize) -> [u8; WORD_LEN] {
+    let mut word = [0; WORD_LEN];
+    word[WORD_LEN - 8..].copy_from_slice(&(value as u64).to_be_bytes());
+    word
+}
+
+fn padded_len(len: usize) -> usize {
+    len + (WORD_LEN - len % WORD_LEN) % WORD_LEN
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn example_log() -> DepositLog {
+        DepositLog {
+            deposit_data: DepositData {
+                pubkey: PublicKeyBytes::from_bytes(&[0xaa; PUBKEY_LEN]).unwrap(),
+                withdrawal_credentials: H256::repeat_byte(0xbb),
+                amount: Gwei::new(32_000_000_000),
+                signature: SignatureBytes::from_bytes(&[0xcc; SIGNATURE_LEN]).unwrap(),
+            },
+            block_number: 42,
+            index: 5,
+        }
+    }
+
+    #[test]
+    fn log_data_layout() {
+        let data = example_log().to_log_data();
+
+        // 5 offsets, then 5 length-prefixed fields each padded to a whole number of words.
+        assert_eq!(data.len(), 576);
+        assert_eq!(read_word(&data, 0).unwrap(), 0xa0);
+        assert_eq!(read_word(&data, 32).unwrap(), 0x100);
+        assert_eq!(read_word(&data, 64).unwrap(), 0x140);
+        assert_eq!(read_word(&data, 96).unwrap(), 0x180);
+        assert_eq!(read_word(&data, 128).unwrap(), 0x200);
+        assert_eq!(read_word(&data, 0x140).unwrap(), AMOUNT_LEN);
+        assert_eq!(&data[0x160..0x168], &32_000_000_000_u64.to_le_bytes()[..]);
+        assert_eq!(&data[0x168..0x180], &[0; 24][..]);
+    }
+
+    #[test]
+    fn round_trip() {
+        let log = example_log();
+
+        assert_eq!(
+            DepositLog::from_log_data(&log.to_log_data(), log.block_number),
+            Ok(log)
+        );
+    }
+
+    #[test]
+    fn reject_truncated_log() {
+        let data = example_log().to_log_data();
+
+        for len in &[0, 31, 160, 400, data.len() - 32] {
+            assert!(
+                DepositLog::from_log_data(&data[..*len], 0).is_err(),
+                "len: {}",
+                len
+            );
+        }
+    }
+
+    #[test]
+    fn reject_wrong_field_length() {
+        let mut data = example_log().to_log_data();
+        // Claim the pubkey is 47 bytes long.
+        data[0xa0 + WORD_LEN - 1] = 47;
+
+        assert_eq!(
+            DepositLog::from_log_data(&data, 0),
+            Err(Error::InvalidDepositLog(
+                "field 0 has length 47, expected 48".to_string()
+            ))
+        );
+    }
+
+    #[test]
+    fn reject_huge_offset() {
+        let mut data = example_log().to_log_data();
+        data[0] = 1;
+
+        assert_eq!(
+            DepositLog::from_log_data(&data, 0),
+            Err(Error::InvalidDepositLog(
+                "word at byte 0 is too large".to_string()
+            ))
+        );
+    }
+}
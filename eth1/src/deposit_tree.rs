@@ -0,0 +1,275 @@
+use eth2_hashing::hash32_concat;
+use tree_hash::{mix_in_length, TreeHash};
+use types::consts::DEPOSIT_CONTRACT_TREE_DEPTH;
+use types::primitives::H256;
+use types::types::DepositData;
+
+use crate::Error;
+
+const DEPTH: usize = DEPOSIT_CONTRACT_TREE_DEPTH as usize;
+
+/// The incremental Merkle tree maintained by the deposit contract.
+///
+/// Keeps every leaf so that proofs can be produced against any historical deposit root, and
+/// remembers the root after each deposit so that `Eth1Data` for any Eth1 block can be built
+/// without rehashing.
+#[derive(Clone, Debug)]
+pub struct DepositTree {
+    leaves: Vec<H256>,
+    /// The left-hand siblings of the next leaf, as in the deposit contract.
+    branch: [H256; DEPTH],
+    /// `roots[i]` is the deposit root after `i + 1` deposits.
+    roots: Vec<H256>,
+    zero_hashes: [H256; DEPTH + 1],
+}
+
+impl Default for DepositTree {
+    fn default() -> Self {
+        let mut zero_hashes = [H256::zero(); DEPTH + 1];
+        for height in 1..=DEPTH {
+            zero_hashes[height] = hash_pair(&zero_hashes[height - 1], &zero_hashes[height - 1]);
+        }
+
+        Self {
+            leaves: vec![],
+            branch: [H256::zero(); DEPTH],
+            roots: vec![],
+            zero_hashes,
+        }
+    }
+}
+
+impl DepositTree {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// The number of deposits in the tree.
+    pub fn len(&self) -> usize {
+        self.leaves.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.leaves.is_empty()
+    }
+
+    /// Appends the leaf for `deposit_data`.
+    pub fn push(&mut self, deposit_data: &DepositData) -> Result<(), Error> {
+        self.push_leaf(deposit_data.tree_hash_root())
+    }
+
+    /// Appends `leaf`, mirroring the deposit contract's `deposit` function.
+    pub fn push_leaf(&mut self, leaf: H256) -> Result<(), Error> {
+        let count = self.leaves.len() as u64 + 1;
+        if count >= 1 << DEPTH {
+            return Err(Error::DepositTreeFull);
+        }
+
+        let mut node = leaf;
+        let mut size = count;
+        for height in 0..DEPTH {
+            if size & 1 == 1 {
+                self.branch[height] = node;
+                break;
+            }
+            node = hash_pair(&self.branch[height], &node);
+            size /= 2;
+        }
+
+        self.leaves.push(leaf);
+        self.roots.push(self.current_root());
+        Ok(())
+    }
+
+    /// The deposit root once the first `count` deposits were made, as returned by the deposit
+    /// contract's `get_deposit_root`.
+    pub fn root_at(&self, count: usize) -> Result<H256, Error> {
+        match count {
+            0 => Ok(self.length_mixed_root(self.zero_hashes[DEPTH], 0)),
+            _ => self
+                .roots
+                .get(count - 1)
+                .cloned()
+                .ok_or(Error::UnknownDepositCount {
+                    count,
+                    known: self.leaves.len(),
+                }),
+        }
+    }
+
+    /// The deposit root covering every deposit in the tree.
+    pub fn root(&self) -> H256 {
+        self.roots
+            .last()
+            .cloned()
+            .unwrap_or_else(|| self.length_mixed_root(self.zero_hashes[DEPTH], 0))
+    }
+
+    /// The proof for leaf `index` against the root of the first `count` deposits, in the form
+    /// expected by `Deposit::proof`: `DEPTH` siblings followed by the length mix-in.
+    pub fn proof(&self, index: usize, count: usize) -> Result<Vec<H256>, Error> {
+        if count > self.leaves.len() {
+            return Err(Error::UnknownDepositCount {
+                count,
+                known: self.leaves.len(),
+            });
+        }
+        if index >= count {
+            return Err(Error::DepositIndexOutOfBounds { index, count });
+        }
+
+        let mut proof = Vec::with_capacity(DEPTH + 1);
+        let mut level = self.leaves[..count].to_vec();
+        let mut position = index;
+
+        for height in 0..DEPTH {
+            let sibling = level
+                .get(position ^ 1)
+                .cloned()
+                .unwrap_or(self.zero_hashes[height]);
+            proof.push(sibling);
+
+            level = level
+                .chunks(2)
+                .map(|pair| hash_pair(&pair[0], pair.get(1).unwrap_or(&self.zero_hashes[height])))
+                .collect();
+            position /= 2;
+        }
+
+        proof.push(length_chunk(count));
+        Ok(proof)
+    }
+
+    fn current_root(&self) -> H256 {
+        let mut node = self.zero_hashes[0];
+        let mut size = self.leaves.len();
+        for height in 0..DEPTH {
+            node = if size & 1 == 1 {
+                hash_pair(&self.branch[height], &node)
+            } else {
+                hash_pair(&node, &self.zero_hashes[height])
+            };
+            size /= 2;
+        }
+        self.length_mixed_root(node, self.leaves.len())
+    }
+
+    fn length_mixed_root(&self, root: H256, count: usize) -> H256 {
+        mix_in_length(&root, count)
+    }
+}
+
+/// Checks `proof` the same way `process_deposit` does.
+pub fn verify_proof(leaf: H256, proof: &[H256], index: usize, root: H256) -> bool {
+    if proof.len() != DEPTH + 1 {
+        return false;
+    }
+
+    let computed = proof
+        .iter()
+        .enumerate()
+        .fold(leaf, |node, (height, sibling)| {
+            if (index >> height) & 1 == 1 {
+                hash_pair(sibling, &node)
+            } else {
+                hash_pair(&node, sibling)
+            }
+        });
+
+    computed == root
+}
+
+fn hash_pair(left: &H256, right: &H256) -> H256 {
+    H256::from(hash32_concat(left.as_fixed_bytes(), right.as_fixed_bytes()))
+}
+
+fn length_chunk(count: usize) -> H256 {
+    let mut chunk = [0; 32];
+    chunk[..8].copy_from_slice(&(count as u64).to_le_bytes());
+    H256::from(chunk)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use eth2_hashing::hash_fixed;
+
+    fn leaf(i: u64) -> H256 {
+        H256::from(hash_fixed(&i.to_le_bytes()))
+    }
+
+    /// Computes the deposit root from scratch with `tree_hash`.
+    fn reference_root(leaves: &[H256]) -> H256 {
+        let bytes: Vec<u8> = leaves.iter().flat_map(|l| l.as_bytes().to_vec()).collect();
+        let root = tree_hash::merkle_root(&bytes, 1 << DEPTH);
+        mix_in_length(&root, leaves.len())
+    }
+
+    #[test]
+    fn empty_root() {
+        let tree = DepositTree::new();
+
+        assert_eq!(tree.root(), reference_root(&[]));
+        assert_eq!(tree.root_at(0), Ok(reference_root(&[])));
+    }
+
+    #[test]
+    fn roots_match_reference() {
+        let mut tree = DepositTree::new();
+        let leaves: Vec<H256> = (0..9).map(leaf).collect();
+
+        for l in &leaves {
+            tree.push_leaf(*l).unwrap();
+        }
+
+        for count in 0..=leaves.len() {
+            assert_eq!(
+                tree.root_at(count),
+                Ok(reference_root(&leaves[..count])),
+                "count: {}",
+                count
+            );
+        }
+        assert_eq!(tree.root(), reference_root(&leaves));
+        assert_eq!(
+            tree.root_at(10),
+            Err(Error::UnknownDepositCount {
+                count: 10,
+                known: 9
+            })
+        );
+    }
+
+    #[test]
+    fn proofs_verify_against_historical_roots() {
+        let mut tree = DepositTree::new();
+        for i in 0..7 {
+            tree.push_leaf(leaf(i)).unwrap();
+        }
+
+        for count in 1..=7 {
+            let root = tree.root_at(count).unwrap();
+            for index in 0..count {
+                let proof = tree.proof(index, count).unwrap();
+                assert_eq!(proof.len(), DEPTH + 1);
+                assert!(verify_proof(leaf(index as u64), &proof, index, root));
+                assert!(!verify_proof(leaf(index as u64 + 1), &proof, index, root));
+            }
+        }
+    }
+
+    #[test]
+    fn proof_bounds() {
+        let mut tree = DepositTree::new();
+        tree.push_leaf(leaf(0)).unwrap();
+
+        assert_eq!(
+            tree.proof(1, 1),
+            Err(Error::DepositIndexOutOfBounds { index: 1, count: 1 })
+        );
+        assert_eq!(
+            tree.proof(0, 2),
+            Err(Error::UnknownDepositCount { count: 2, known: 1 })
+        );
+    }
+}
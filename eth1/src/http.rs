@@ -0,0 +1,329 @@
+//! A minimal blocking JSON-RPC client for the handful of Eth1 methods the follower needs.
+//!
+//! Only plain `http://` endpoints are supported. Every request uses a fresh connection with
+//! `Connection: close`, which keeps response parsing simple.
+
+use std::io::{Read, Write};
+use std::net::TcpStream;
+use std::time::Duration;
+
+use serde_json::{json, Value};
+use types::primitives::H256;
+
+use crate::block_cache::Eth1Block;
+use crate::deposit_log::{DepositLog, DEPOSIT_EVENT_TOPIC};
+use crate::Error;
+
+/// An Eth1 block as reported by the node, before the deposit contract state is attached.
+#[derive(Debug, PartialEq, Clone)]
+pub struct BlockHeader {
+    pub hash: H256,
+    pub number: u64,
+    pub timestamp: u64,
+}
+
+impl BlockHeader {
+    pub fn into_eth1_block(self, deposit_root: H256, deposit_count: u64) -> Eth1Block {
+        Eth1Block {
+            hash: self.hash,
+            number: self.number,
+            timestamp: self.timestamp,
+            deposit_root,
+            deposit_count,
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct HttpClient {
+    host: String,
+    path: String,
+    timeout: Duration,
+}
+
+impl HttpClient {
+    pub fn new(endpoint: &str, timeout: Duration) -> Result<Self, Error> {
+        let mut scheme_and_rest = endpoint.splitn(2, "://");
+        let rest = match (scheme_and_rest.next(), scheme_and_rest.next()) {
+            (Some("http"), Some(rest)) => rest,
+            _ => return Err(Error::InvalidEndpoint(endpoint.to_string())),
+        };
+
+        let (host, path) = match rest.find('/') {
+            Some(i) => (&rest[..i], &rest[i..]),
+            None => (rest, "/"),
+        };
+
+        if host.is_empty() {
+            return Err(Error::InvalidEndpoint(endpoint.to_string()));
+        }
+
+        let host = if host.contains(':') {
+            host.to_string()
+        } else {
+            format!("{}:80", host)
+        };
+
+        Ok(Self {
+            host,
+            path: path.to_string(),
+            timeout,
+        })
+    }
+
+    /// `eth_blockNumber`
+    pub fn block_number(&self) -> Result<u64, Error> {
+        let result = self.call("eth_blockNumber", json!([]))?;
+        parse_quantity(&result)
+    }
+
+    /// `eth_getBlockByNumber`, returning `None` if the node does not know the block yet.
+    pub fn block_by_number(&self, number: u64) -> Result<Option<BlockHeader>, Error> {
+        let result = self.call(
+            "eth_getBlockByNumber",
+            json!([encode_quantity(number), false]),
+        )?;
+
+        if result.is_null() {
+            return Ok(None);
+        }
+
+        Ok(Some(BlockHeader {
+            hash: parse_hash(field(&result, "hash")?)?,
+            number: parse_quantity(field(&result, "number")?)?,
+            timestamp: parse_quantity(field(&result, "timestamp")?)?,
+        }))
+    }
+
+    /// `eth_getLogs` for `DepositEvent`s emitted by `contract` in blocks `from..=to`.
+    pub fn deposit_logs(
+        &self,
+        contract: &str,
+        from: u64,
+        to: u64,
+    ) -> Result<Vec<DepositLog>, Error> {
+        let result = self.call(
+            "eth_getLogs",
+            json!([{
+                "address": contract,
+                "fromBlock": encode_quantity(from),
+                "toBlock": encode_quantity(to),
+                "topics": [DEPOSIT_EVENT_TOPIC],
+            }]),
+        )?;
+
+        let logs = result
+            .as_array()
+            .ok_or_else(|| Error::InvalidResponse(format!("expected logs, got {}", result)))?;
+
+        logs.iter()
+            .map(|log| {
+                let block_number = parse_quantity(field(log, "blockNumber")?)?;
+                let data = parse_data(field(log, "data")?)?;
+                DepositLog::from_log_data(&data, block_number)
+            })
+            .collect()
+    }
+
+    /// Sends a JSON-RPC request and returns its `result`.
+    pub fn call(&self, method: &str, params: Value) -> Result<Value, Error> {
+        let body = json!({
+            "jsonrpc": "2.0",
+            "method": method,
+            "params": params,
+            "id": 1,
+        })
+        .to_string();
+
+        let response = self.post(&body)?;
+        let mut response: Value = serde_json::from_slice(&response)
+            .map_err(|e| Error::InvalidResponse(format!("invalid JSON: {}", e)))?;
+
+        if let Some(error) = response.get("error") {
+            return Err(Error::Rpc(error.to_string()));
+        }
+
+        response
+            .get_mut("result")
+            .map(Value::take)
+            .ok_or_else(|| Error::InvalidResponse("missing result".to_string()))
+    }
+
+    fn post(&self, body: &str) -> Result<Vec<u8>, Error> {
+        let mut stream = TcpStream::connect(&self.host)?;
+        stream.set_read_timeout(Some(self.timeout))?;
+        stream.set_write_timeout(Some(self.timeout))?;
+
+        write!(
+            stream,
+            "POST {} HTTP/1.1\r\n\
+             Host: {}\r\n\
+             Content-Type: application/json\r\n\
+             Content-Length: {}\r\n\
+             Connection: close\r\n\
+             \r\n\
+             {}",
+            self.path,
+            self.host,
+            body.len(),
+            body
+        )?;
+
+        let mut response = vec![];
+        stream.read_to_end(&mut response)?;
+
+        parse_response(&response)
+    }
+}
+
+/// Extracts the body from a complete HTTP/1.1 response.
+fn parse_response(response: &[u8]) -> Result<Vec<u8>, Error> {
+    let header_end = find(response, b"\r\n\r\n")
+        .ok_or_else(|| Error::InvalidResponse("incomplete HTTP headers".to_string()))?;
+    let head = String::from_utf8_lossy(&response[..header_end]);
+    let body = &response[header_end + 4..];
+
+    let mut lines = head.split("\r\n");
+    let status = lines.next().unwrap_or_default();
+    match status.split_whitespace().nth(1) {
+        Some("200") => {}
+        _ => return Err(Error::InvalidResponse(format!("HTTP status: {}", status))),
+    }
+
+    let mut content_length = None;
+    let mut chunked = false;
+    for line in lines {
+        let mut parts = line.splitn(2, ':');
+        let name = parts.next().unwrap_or_default().trim().to_lowercase();
+        let value = parts.next().unwrap_or_default().trim();
+
+        if name == "content-length" {
+            content_length = Some(value.parse::<usize>().map_err(|_| {
+                Error::InvalidResponse(format!("invalid Content-Length: {}", value))
+            })?);
+        } else if name == "transfer-encoding" && value.eq_ignore_ascii_case("chunked") {
+            chunked = true;
+        }
+    }
+
+    if chunked {
+        decode_chunked(body)
+    } else if let Some(len) = content_length {
+        body.get(..len)
+            .map(<[u8]>::to_vec)
+            .ok_or_else(|| Error::InvalidResponse("truncated body".to_string()))
+    } else {
+        Ok(body.to_vec())
+    }
+}
+
+fn decode_chunked(mut body: &[u8]) -> Result<Vec<u8>, Error> {
+    let mut decoded = vec![];
+
+    loop {
+        let line_end = find(body, b"\r\n")
+            .ok_or_else(|| Error::InvalidResponse("incomplete chunk size".to_string()))?;
+        let size_line = String::from_utf8_lossy(&body[..line_end]);
+        let size_hex = size_line.split(';').next().unwrap_or_default().trim();
+        let size = usize::from_str_radix(size_hex, 16)
+            .map_err(|_| Error::InvalidResponse(format!("invalid chunk size: {}", size_hex)))?;
+
+        body = &body[line_end + 2..];
+        if size == 0 {
+            return Ok(decoded);
+        }
+
+        let chunk = body
+            .get(..size)
+            .ok_or_else(|| Error::InvalidResponse("truncated chunk".to_string()))?;
+        decoded.extend_from_slice(chunk);
+        body = body.get(size + 2..).unwrap_or_default();
+    }
+}
+
+fn find(haystack: &[u8], needle: &[u8]) -> Option<usize> {
+    haystack
+        .windows(needle.len())
+        .position(|window| window == needle)
+}
+
+fn field<'a>(object: &'a Value, name: &str) -> Result<&'a Value, Error> {
+    object
+        .get(name)
+        .ok_or_else(|| Error::InvalidResponse(format!("missing field {}", name)))
+}
+
+pub(crate) fn encode_quantity(value: u64) -> String {
+    format!("0x{:x}", value)
+}
+
+pub(crate) fn parse_quantity(value: &Value) -> Result<u64, Error> {
+    value
+        .as_str()
+        .filter(|s| s.starts_with("0x"))
+        .and_then(|s| u64::from_str_radix(&s[2..], 16).ok())
+        .ok_or_else(|| Error::InvalidResponse(format!("invalid quantity: {}", value)))
+}
+
+pub(crate) fn parse_data(value: &Value) -> Result<Vec<u8>, Error> {
+    value
+        .as_str()
+        .filter(|s| s.starts_with("0x"))
+        .and_then(|s| hex::decode(&s[2..]).ok())
+        .ok_or_else(|| Error::InvalidResponse(format!("invalid data: {}", value)))
+}
+
+fn parse_hash(value: &Value) -> Result<H256, Error> {
+    let bytes = parse_data(value)?;
+    if bytes.len() != 32 {
+        return Err(Error::InvalidResponse(format!("invalid hash: {}", value)));
+    }
+    Ok(H256::from_slice(&bytes))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn endpoint_parsing() {
+        let client = HttpClient::new("http://localhost:8545", Duration::from_secs(1)).unwrap();
+        assert_eq!(client.host, "localhost:8545");
+        assert_eq!(client.path, "/");
+
+        let client = HttpClient::new("http://example.com/rpc", Duration::from_secs(1)).unwrap();
+        assert_eq!(client.host, "example.com:80");
+        assert_eq!(client.path, "/rpc");
+
+        assert!(HttpClient::new("https://example.com", Duration::from_secs(1)).is_err());
+        assert!(HttpClient::new("http:///rpc", Duration::from_secs(1)).is_err());
+    }
+
+    #[test]
+    fn content_length_response() {
+        let response = b"HTTP/1.1 200 OK\r\nContent-Length: 4\r\n\r\nbodyextra";
+        assert_eq!(parse_response(response), Ok(b"body".to_vec()));
+    }
+
+    #[test]
+    fn chunked_response() {
+        let response =
+            b"HTTP/1.1 200 OK\r\nTransfer-Encoding: chunked\r\n\r\n4\r\nbody\r\n6;x=y\r\n chunk\r\n0\r\n\r\n";
+        assert_eq!(parse_response(response), Ok(b"body chunk".to_vec()));
+    }
+
+    #[test]
+    fn error_status() {
+        let response = b"HTTP/1.1 500 Internal Server Error\r\nContent-Length: 0\r\n\r\n";
+        assert!(parse_response(response).is_err());
+    }
+
+    #[test]
+    fn quantities() {
+        assert_eq!(encode_quantity(0), "0x0");
+        assert_eq!(encode_quantity(255), "0xff");
+        assert_eq!(parse_quantity(&json!("0x1b4")), Ok(436));
+        assert!(parse_quantity(&json!("1b4")).is_err());
+        assert!(parse_quantity(&json!(436)).is_err());
+    }
+}
@@ -0,0 +1,63 @@
+//! Follows the Eth1 deposit contract: imports `DepositEvent` logs into a local deposit tree,
+//! tracks candidate `Eth1Data` and chooses the `eth1_data` vote for block proposals.
+
+pub mod block_cache;
+pub mod deposit_log;
+pub mod deposit_tree;
+pub mod http;
+pub mod mock_server;
+pub mod service;
+pub mod vote;
+
+pub use crate::block_cache::{BlockCache, Eth1Block};
+pub use crate::deposit_log::DepositLog;
+pub use crate::deposit_tree::DepositTree;
+pub use crate::service::{Eth1Config, PollingHandle, Service};
+
+use types::primitives::H256;
+
+#[derive(Debug, PartialEq)]
+pub enum Error {
+    Io(String),
+    InvalidEndpoint(String),
+    /// The endpoint replied with something other than the expected JSON-RPC response.
+    InvalidResponse(String),
+    /// The endpoint returned a JSON-RPC error object.
+    Rpc(String),
+    InvalidDepositLog(String),
+    DepositLogSsz(ssz::DecodeError),
+    /// A log was skipped or repeated, the deposit tree cannot be built.
+    NonConsecutiveDeposit {
+        expected: u64,
+        got: u64,
+    },
+    DepositTreeFull,
+    UnknownDepositCount {
+        count: usize,
+        known: usize,
+    },
+    DepositIndexOutOfBounds {
+        index: usize,
+        count: usize,
+    },
+    NonConsecutiveBlock {
+        expected: u64,
+        got: u64,
+    },
+    MissingBlock(u64),
+    /// The state refers to a deposit root that differs from the local deposit tree.
+    DepositRootMismatch {
+        count: usize,
+        expected: H256,
+        local: H256,
+    },
+    /// Not enough Eth1 blocks have been imported to vote.
+    Eth1DataUnavailable,
+    SszTypes(ssz_types::Error),
+}
+
+impl From<std::io::Error> for Error {
+    fn from(e: std::io::Error) -> Self {
+        Error::Io(e.to_string())
+    }
+}
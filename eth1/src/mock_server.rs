@@ -0,0 +1,291 @@
+//! An in-process stand-in for an Eth1 node that serves the JSON-RPC methods used by
+//! `HttpClient` from an in-memory chain.
+
+use std::io::{BufRead, BufReader, Read, Write};
+use std::net::{SocketAddr, TcpListener, TcpStream};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Mutex};
+use std::thread::{self, JoinHandle};
+
+use eth2_hashing::Context;
+use serde_json::{json, Value};
+use types::primitives::H256;
+use types::types::DepositData;
+
+use crate::deposit_log::{DepositLog, DEPOSIT_EVENT_TOPIC};
+use crate::http::{encode_quantity, parse_quantity};
+
+/// The seconds between mock blocks, roughly matching mainnet.
+const SECONDS_PER_BLOCK: u64 = 14;
+
+#[derive(Debug, Clone)]
+pub struct MockBlock {
+    pub hash: H256,
+    pub number: u64,
+    pub timestamp: u64,
+    pub deposits: Vec<DepositLog>,
+}
+
+/// An Eth1 chain with a single deposit contract.
+#[derive(Debug, Clone)]
+pub struct MockChain {
+    pub deposit_contract_address: String,
+    pub genesis_timestamp: u64,
+    blocks: Vec<MockBlock>,
+    pending_deposits: Vec<DepositData>,
+    deposit_count: u64,
+}
+
+impl MockChain {
+    /// Creates a chain containing only the genesis block.
+    pub fn new(deposit_contract_address: &str, genesis_timestamp: u64) -> Self {
+        let mut chain = Self {
+            deposit_contract_address: deposit_contract_address.to_lowercase(),
+            genesis_timestamp,
+            blocks: vec![],
+            pending_deposits: vec![],
+            deposit_count: 0,
+        };
+        chain.mine();
+        chain
+    }
+
+    pub fn head(&self) -> &MockBlock {
+        self.blocks
+            .last()
+            .expect("the chain always has a genesis block")
+    }
+
+    pub fn block(&self, number: u64) -> Option<&MockBlock> {
+        self.blocks.get(number as usize)
+    }
+
+    /// Queues a deposit for inclusion in the next mined block.
+    pub fn deposit(&mut self, deposit_data: DepositData) {
+        self.pending_deposits.push(deposit_data);
+    }
+
+    /// Mines a block containing every pending deposit.
+    pub fn mine(&mut self) -> &MockBlock {
+        let number = self.blocks.len() as u64;
+
+        let mut context = Context::new();
+        context.update(&number.to_le_bytes());
+        context.update(self.deposit_contract_address.as_bytes());
+
+        let deposits = self
+            .pending_deposits
+            .drain(..)
+            .map(|deposit_data| DepositLog {
+                deposit_data,
+                block_number: number,
+                index: 0,
+            })
+            .collect::<Vec<_>>();
+
+        let first_index = self.deposit_count;
+        self.deposit_count += deposits.len() as u64;
+
+        self.blocks.push(MockBlock {
+            hash: H256::from(context.finalize()),
+            number,
+            timestamp: self.genesis_timestamp + number * SECONDS_PER_BLOCK,
+            deposits: deposits
+                .into_iter()
+                .zip(first_index..)
+                .map(|(log, index)| DepositLog { index, ..log })
+                .collect(),
+        });
+
+        self.head()
+    }
+
+    pub fn mine_blocks(&mut self, count: u64) {
+        for _ in 0..count {
+            self.mine();
+        }
+    }
+
+    /// Handles a single JSON-RPC request object.
+    pub fn handle_request(&self, request: &Value) -> Value {
+        let id = request.get("id").cloned().unwrap_or(Value::Null);
+        let method = request.get("method").and_then(Value::as_str);
+        let params = request.get("params").cloned().unwrap_or(Value::Null);
+
+        let result = match method {
+            Some("eth_blockNumber") => Ok(json!(encode_quantity(self.head().number))),
+            Some("eth_getBlockByNumber") => self.get_block_by_number(&params),
+            Some("eth_getLogs") => self.get_logs(&params),
+            Some(other) => Err((-32601, format!("the method {} does not exist", other))),
+            None => Err((-32600, "invalid request".to_string())),
+        };
+
+        match result {
+            Ok(result) => json!({ "jsonrpc": "2.0", "id": id, "result": result }),
+            Err((code, message)) => json!({
+                "jsonrpc": "2.0",
+                "id": id,
+                "error": { "code": code, "message": message },
+            }),
+        }
+    }
+
+    fn get_block_by_number(&self, params: &Value) -> Result<Value, (i64, String)> {
+        let number = self.block_number_param(&params[0])?;
+
+        Ok(self.block(number).map_or(Value::Null, |block| {
+            json!({
+                "hash": format!("0x{}", hex::encode(block.hash.as_bytes())),
+                "number": encode_quantity(block.number),
+                "timestamp": encode_quantity(block.timestamp),
+            })
+        }))
+    }
+
+    fn get_logs(&self, params: &Value) -> Result<Value, (i64, String)> {
+        let filter = &params[0];
+        let from = self.block_number_param(&filter["fromBlock"])?;
+        let to = self.block_number_param(&filter["toBlock"])?;
+
+        let address_matches = match filter["address"].as_str() {
+            None => true,
+            Some(address) => address.to_lowercase() == self.deposit_contract_address,
+        };
+        let topic_matches = match filter["topics"].get(0) {
+            None | Some(Value::Null) => true,
+            Some(topic) => topic == DEPOSIT_EVENT_TOPIC,
+        };
+
+        if !address_matches || !topic_matches {
+            return Ok(json!([]));
+        }
+
+        let logs = self
+            .blocks
+            .iter()
+            .filter(|block| block.number >= from && block.number <= to)
+            .flat_map(|block| {
+                block.deposits.iter().map(move |log| {
+                    json!({
+                        "address": self.deposit_contract_address,
+                        "blockHash": format!("0x{}", hex::encode(block.hash.as_bytes())),
+                        "blockNumber": encode_quantity(block.number),
+                        "data": format!("0x{}", hex::encode(log.to_log_data())),
+                        "topics": [DEPOSIT_EVENT_TOPIC],
+                    })
+                })
+            })
+            .collect::<Vec<_>>();
+
+        Ok(Value::Array(logs))
+    }
+
+    fn block_number_param(&self, value: &Value) -> Result<u64, (i64, String)> {
+        match value.as_str() {
+            Some("latest") | Some("pending") => Ok(self.head().number),
+            Some("earliest") => Ok(0),
+            _ => parse_quantity(value).map_err(|e| (-32602, format!("{:?}", e))),
+        }
+    }
+}
+
+/// Serves a shared `MockChain` over HTTP on a local port until dropped.
+pub struct MockServer {
+    pub chain: Arc<Mutex<MockChain>>,
+    address: SocketAddr,
+    shutdown: Arc<AtomicBool>,
+    handle: Option<JoinHandle<()>>,
+}
+
+impl MockServer {
+    pub fn start(chain: MockChain) -> std::io::Result<Self> {
+        let listener = TcpListener::bind("127.0.0.1:0")?;
+        let address = listener.local_addr()?;
+        let chain = Arc::new(Mutex::new(chain));
+        let shutdown = Arc::new(AtomicBool::new(false));
+
+        let handle = {
+            let chain = Arc::clone(&chain);
+            let shutdown = Arc::clone(&shutdown);
+            thread::spawn(move || {
+                for stream in listener.incoming() {
+                    if shutdown.load(Ordering::SeqCst) {
+                        break;
+                    }
+                    if let Ok(stream) = stream {
+                        // A client that hangs up early must not take the server down.
+                        let _ = serve(stream, &chain);
+                    }
+                }
+            })
+        };
+
+        Ok(Self {
+            chain,
+            address,
+            shutdown,
+            handle: Some(handle),
+        })
+    }
+
+    pub fn endpoint(&self) -> String {
+        format!("http://{}", self.address)
+    }
+}
+
+impl Drop for MockServer {
+    fn drop(&mut self) {
+        self.shutdown.store(true, Ordering::SeqCst);
+        // Wake the accept loop so it can observe the flag.
+        let _ = TcpStream::connect(self.address);
+        if let Some(handle) = self.handle.take() {
+            let _ = handle.join();
+        }
+    }
+}
+
+fn serve(stream: TcpStream, chain: &Mutex<MockChain>) -> std::io::Result<()> {
+    let mut reader = BufReader::new(stream.try_clone()?);
+
+    let mut content_length = 0;
+    let mut line = String::new();
+    loop {
+        line.clear();
+        if reader.read_line(&mut line)? == 0 {
+            return Ok(());
+        }
+        let header = line.trim_end();
+        if header.is_empty() {
+            break;
+        }
+        let mut parts = header.splitn(2, ':');
+        if parts
+            .next()
+            .map(|n| n.trim().eq_ignore_ascii_case("content-length"))
+            == Some(true)
+        {
+            content_length = parts.next().unwrap_or_default().trim().parse().unwrap_or(0);
+        }
+    }
+
+    let mut body = vec![0; content_length];
+    reader.read_exact(&mut body)?;
+
+    let (status, response) = match serde_json::from_slice::<Value>(&body) {
+        Ok(request) => {
+            let chain = chain.lock().expect("mock chain lock poisoned");
+            ("200 OK", chain.handle_request(&request).to_string())
+        }
+        Err(e) => ("400 Bad Request", e.to_string()),
+    };
+
+    let mut stream = stream;
+    write!(
+        stream,
+        "HTTP/1.1 {}\r\nContent-Type: application/json\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{}",
+        status,
+        response.len(),
+        response
+    )?;
+    stream.flush()
+}
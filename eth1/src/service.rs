@@ -0,0 +1,334 @@
+use std::cmp::{self, Ordering};
+use std::marker::PhantomData;
+use std::sync::mpsc::{self, RecvTimeoutError, Sender};
+use std::sync::{Arc, Mutex, RwLock};
+use std::thread::{self, JoinHandle};
+use std::time::Duration;
+
+use ssz_types::FixedVector;
+use typenum::Unsigned;
+use types::beacon_state::BeaconState;
+use types::config::Config;
+use types::primitives::H256;
+use types::types::{Deposit, Eth1Data};
+
+use crate::block_cache::{BlockCache, Eth1Block};
+use crate::deposit_log::DepositLog;
+use crate::deposit_tree::DepositTree;
+use crate::http::HttpClient;
+use crate::vote::eth1_vote;
+use crate::Error;
+
+#[derive(Debug, Clone)]
+pub struct Eth1Config {
+    /// A plain `http://` JSON-RPC endpoint.
+    pub endpoint: String,
+    /// The `0x`-prefixed address of the deposit contract.
+    pub deposit_contract_address: String,
+    /// Logs before this block are never requested.
+    pub deposit_contract_deploy_block: u64,
+    /// The size of the block range requested in a single `eth_getLogs` call.
+    pub blocks_per_log_query: u64,
+    /// The number of Eth1 blocks kept for voting.
+    pub block_cache_len: usize,
+    pub request_timeout: Duration,
+    /// How long `Service::spawn` waits between updates.
+    pub update_interval: Duration,
+}
+
+impl Eth1Config {
+    pub fn new<C: Config>(
+        endpoint: &str,
+        deposit_contract_address: &str,
+        deposit_contract_deploy_block: u64,
+    ) -> Self {
+        Self {
+            endpoint: endpoint.to_string(),
+            deposit_contract_address: deposit_contract_address.to_string(),
+            deposit_contract_deploy_block,
+            blocks_per_log_query: 1_000,
+            // Enough for the voting range and a voting period's worth of lag behind it.
+            block_cache_len: 4 * C::eth1_follow_distance() as usize,
+            request_timeout: Duration::from_secs(10),
+            update_interval: Duration::from_secs(7),
+        }
+    }
+}
+
+#[derive(Debug, Default)]
+struct Cache {
+    deposit_tree: DepositTree,
+    deposit_logs: Vec<DepositLog>,
+    /// The first block whose logs have not been imported yet.
+    next_log_block: Option<u64>,
+    blocks: BlockCache,
+    head_block_number: Option<u64>,
+}
+
+/// Follows the deposit contract through a JSON-RPC endpoint.
+///
+/// Only blocks at least `ETH1_FOLLOW_DISTANCE` behind the Eth1 head are imported. Those are the
+/// only blocks a validator may vote for, and staying that far behind keeps reorgs from
+/// invalidating the deposit tree.
+pub struct Service<C: Config> {
+    config: Eth1Config,
+    client: HttpClient,
+    cache: RwLock<Cache>,
+    /// Serializes calls to `update` without blocking readers of `cache` during requests.
+    update_lock: Mutex<()>,
+    /// The error of the last update made by the polling thread, `None` if it succeeded.
+    last_error: Mutex<Option<Error>>,
+    phantom: PhantomData<C>,
+}
+
+impl<C: Config> Service<C> {
+    pub fn new(config: Eth1Config) -> Result<Self, Error> {
+        let client = HttpClient::new(&config.endpoint, config.request_timeout)?;
+
+        Ok(Self {
+            config,
+            client,
+            cache: RwLock::new(Cache::default()),
+            update_lock: Mutex::new(()),
+            last_error: Mutex::new(None),
+            phantom: PhantomData,
+        })
+    }
+
+    pub fn config(&self) -> &Eth1Config {
+        &self.config
+    }
+
+    /// Imports new deposit logs and blocks from the endpoint.
+    pub fn update(&self) -> Result<(), Error> {
+        let _update = self.update_lock.lock().expect("update lock poisoned");
+
+        let head = self.client.block_number()?;
+        if let Some(target) = head
+            .checked_sub(C::eth1_follow_distance())
+            .filter(|target| *target >= self.config.deposit_contract_deploy_block)
+        {
+            self.update_deposit_logs(target)?;
+            self.update_blocks(target)?;
+        }
+
+        self.cache
+            .write()
+            .expect("eth1 cache lock poisoned")
+            .head_block_number = Some(head);
+        Ok(())
+    }
+
+    fn update_deposit_logs(&self, target: u64) -> Result<(), Error> {
+        let mut from = self
+            .read_cache(|cache| cache.next_log_block)
+            .unwrap_or(self.config.deposit_contract_deploy_block);
+
+        while from <= target {
+            let to = cmp::min(
+                from.saturating_add(self.config.blocks_per_log_query.saturating_sub(1)),
+                target,
+            );
+            let mut logs =
+                self.client
+                    .deposit_logs(&self.config.deposit_contract_address, from, to)?;
+            logs.sort_by_key(|log| log.index);
+
+            let mut cache = self.cache.write().expect("eth1 cache lock poisoned");
+            let mut tree = cache.deposit_tree.clone();
+            for log in &logs {
+                let expected = tree.len() as u64;
+                if log.index != expected {
+                    return Err(Error::NonConsecutiveDeposit {
+                        expected,
+                        got: log.index,
+                    });
+                }
+                tree.push(&log.deposit_data)?;
+            }
+
+            cache.deposit_tree = tree;
+            cache.deposit_logs.append(&mut logs);
+            cache.next_log_block = Some(to + 1);
+            from = to + 1;
+        }
+
+        Ok(())
+    }
+
+    fn update_blocks(&self, target: u64) -> Result<(), Error> {
+        let cache_len = self.config.block_cache_len as u64;
+        let earliest_useful = cmp::max(
+            self.config.deposit_contract_deploy_block,
+            (target + 1).saturating_sub(cache_len),
+        );
+
+        let next = self.read_cache(|cache| cache.blocks.latest_block_number());
+        let start = match next {
+            Some(latest) if latest + 1 >= earliest_useful => latest + 1,
+            _ => {
+                // The cache is empty or too far behind to be extended, start over.
+                let mut cache = self.cache.write().expect("eth1 cache lock poisoned");
+                cache.blocks = BlockCache::default();
+                earliest_useful
+            }
+        };
+
+        for number in start..=target {
+            let header = self
+                .client
+                .block_by_number(number)?
+                .ok_or(Error::MissingBlock(number))?;
+            if header.number != number {
+                return Err(Error::InvalidResponse(format!(
+                    "requested block {}, got {}",
+                    number, header.number
+                )));
+            }
+
+            let mut cache = self.cache.write().expect("eth1 cache lock poisoned");
+            let deposit_count = deposit_count_at(&cache.deposit_logs, number);
+            let deposit_root = cache.deposit_tree.root_at(deposit_count)?;
+
+            cache
+                .blocks
+                .insert(header.into_eth1_block(deposit_root, deposit_count as u64))?;
+            cache.blocks.truncate(self.config.block_cache_len);
+        }
+
+        Ok(())
+    }
+
+    /// The latest Eth1 block number seen by the last successful update.
+    pub fn head_block_number(&self) -> Option<u64> {
+        self.read_cache(|cache| cache.head_block_number)
+    }
+
+    /// The number of deposits imported so far.
+    pub fn deposit_count(&self) -> usize {
+        self.read_cache(|cache| cache.deposit_tree.len())
+    }
+
+    pub fn deposit_log(&self, index: usize) -> Option<DepositLog> {
+        self.read_cache(|cache| cache.deposit_logs.get(index).cloned())
+    }
+
+    pub fn block_by_number(&self, number: u64) -> Option<Eth1Block> {
+        self.read_cache(|cache| cache.blocks.block_by_number(number).cloned())
+    }
+
+    /// The blocks currently available for voting, oldest first.
+    pub fn blocks(&self) -> Vec<Eth1Block> {
+        self.read_cache(|cache| cache.blocks.iter().cloned().collect())
+    }
+
+    /// The `Eth1Data` a proposer should include in a block built on `state`.
+    pub fn eth1_vote(&self, state: &BeaconState<C>) -> Result<Eth1Data, Error> {
+        self.read_cache(|cache| {
+            let head = cache.head_block_number.ok_or(Error::Eth1DataUnavailable)?;
+            eth1_vote(state, &cache.blocks, head).ok_or(Error::Eth1DataUnavailable)
+        })
+    }
+
+    /// The deposits a block built on `state` must include, with proofs against
+    /// `state.eth1_data.deposit_root`.
+    pub fn deposits_for_inclusion(&self, state: &BeaconState<C>) -> Result<Vec<Deposit>, Error> {
+        self.read_cache(|cache| {
+            let count = state.eth1_data.deposit_count as usize;
+            let local = cache.deposit_tree.root_at(count)?;
+            if local != state.eth1_data.deposit_root {
+                return Err(Error::DepositRootMismatch {
+                    count,
+                    expected: state.eth1_data.deposit_root,
+                    local,
+                });
+            }
+
+            let start = state.eth1_deposit_index as usize;
+            let end = cmp::min(count, start.saturating_add(C::MaxDeposits::to_usize()));
+
+            (start..end)
+                .map(|index| {
+                    let proof = cache.deposit_tree.proof(index, count)?;
+                    Ok(Deposit {
+                        proof: FixedVector::new(proof).map_err(Error::SszTypes)?,
+                        data: cache.deposit_logs[index].deposit_data.clone(),
+                    })
+                })
+                .collect()
+        })
+    }
+
+    /// The deposit root after the first `count` deposits.
+    pub fn deposit_root_at(&self, count: usize) -> Result<H256, Error> {
+        self.read_cache(|cache| cache.deposit_tree.root_at(count))
+    }
+
+    /// Calls `update` every `update_interval` on a background thread until the returned handle
+    /// is dropped. Failed updates are retried on the next tick, and their error is kept for
+    /// `take_last_error`.
+    pub fn spawn(service: Arc<Self>) -> PollingHandle
+    where
+        C: Send + Sync + 'static,
+    {
+        let (stop, stopped) = mpsc::channel::<()>();
+        let interval = service.config.update_interval;
+
+        let handle = thread::spawn(move || loop {
+            let result = service.update();
+            *service.last_error.lock().expect("last error lock poisoned") = result.err();
+            match stopped.recv_timeout(interval) {
+                Err(RecvTimeoutError::Timeout) => continue,
+                _ => break,
+            }
+        });
+
+        PollingHandle {
+            stop: Some(stop),
+            handle: Some(handle),
+        }
+    }
+
+    /// Takes the error of the last update made by the polling thread, if it failed.
+    ///
+    /// Returns `None` if that update succeeded, no update has been made yet, or the error has
+    /// already been taken.
+    pub fn take_last_error(&self) -> Option<Error> {
+        self.last_error
+            .lock()
+            .expect("last error lock poisoned")
+            .take()
+    }
+
+    fn read_cache<T>(&self, f: impl FnOnce(&Cache) -> T) -> T {
+        f(&self.cache.read().expect("eth1 cache lock poisoned"))
+    }
+}
+
+/// Stops the polling thread started by `Service::spawn` when dropped.
+pub struct PollingHandle {
+    stop: Option<Sender<()>>,
+    handle: Option<JoinHandle<()>>,
+}
+
+impl Drop for PollingHandle {
+    fn drop(&mut self) {
+        // Dropping the sender disconnects the channel, which wakes the thread.
+        self.stop.take();
+        if let Some(handle) = self.handle.take() {
+            let _ = handle.join();
+        }
+    }
+}
+
+/// The number of deposits made in blocks up to and including `block_number`.
+fn deposit_count_at(logs: &[DepositLog], block_number: u64) -> usize {
+    logs.binary_search_by(|log| {
+        if log.block_number <= block_number {
+            Ordering::Less
+        } else {
+            Ordering::Greater
+        }
+    })
+    .unwrap_or_else(|index| index)
+}
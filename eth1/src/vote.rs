@@ -0,0 +1,109 @@
+use std::cmp;
+
+use typenum::Unsigned;
+use types::beacon_state::BeaconState;
+use types::config::Config;
+use types::types::Eth1Data;
+
+use crate::block_cache::BlockCache;
+
+/// Chooses the `Eth1Data` to include in a block proposed on top of `state`, following
+/// `get_eth1_vote` from the v0.8 validator guide.
+///
+/// `head` is the number of the latest Eth1 block; distances are measured from it. Returns `None`
+/// when `blocks` does not reach back to `head - ETH1_FOLLOW_DISTANCE`, the default vote.
+///
+/// The block referenced by `state.eth1_data` determines `previous_eth1_distance`. If it is not
+/// cached, only votes for new `Eth1Data` are considered valid.
+pub fn eth1_vote<C: Config>(
+    state: &BeaconState<C>,
+    blocks: &BlockCache,
+    head: u64,
+) -> Option<Eth1Data> {
+    let follow_distance = C::eth1_follow_distance();
+    let eth1_data = |distance: u64| {
+        head.checked_sub(distance)
+            .and_then(|number| blocks.block_by_number(number))
+            .map(|block| block.eth1_data())
+    };
+
+    let default_vote = eth1_data(follow_distance)?;
+
+    let previous_eth1_distance = blocks
+        .block_by_hash(&state.eth1_data.block_hash)
+        .map_or(2 * follow_distance, |block| {
+            head.saturating_sub(block.number)
+        });
+
+    let new_eth1_data = (follow_distance..2 * follow_distance)
+        .filter_map(eth1_data)
+        .collect::<Vec<_>>();
+    // The spec indexes into `all_eth1_data` to break ties; a new vote may be beyond
+    // `previous_eth1_distance`, so search the union of both ranges instead.
+    let candidates = (follow_distance..cmp::max(previous_eth1_distance, 2 * follow_distance))
+        .filter_map(eth1_data)
+        .collect::<Vec<_>>();
+    let all_eth1_data = &candidates[..cmp::min(
+        candidates.len(),
+        previous_eth1_distance.saturating_sub(follow_distance) as usize,
+    )];
+
+    let period_tail_start = integer_squareroot(C::SlotsPerEth1VotingPeriod::to_u64());
+    let valid_votes = state
+        .eth1_data_votes
+        .iter()
+        .enumerate()
+        .filter(|(slot, vote)| {
+            let period_tail =
+                *slot as u64 % C::SlotsPerEth1VotingPeriod::to_u64() >= period_tail_start;
+            new_eth1_data.contains(vote) || (period_tail && all_eth1_data.contains(vote))
+        })
+        .map(|(_, vote)| vote)
+        .collect::<Vec<&Eth1Data>>();
+
+    // Most votes wins, ties go to the vote with the smallest distance.
+    let vote = valid_votes.iter().cloned().max_by_key(|vote| {
+        let count = valid_votes.iter().filter(|other| *other == vote).count();
+        let distance_rank = candidates
+            .iter()
+            .position(|candidate| candidate == *vote)
+            .unwrap_or(candidates.len());
+        (count, cmp::Reverse(distance_rank))
+    });
+
+    Some(vote.cloned().unwrap_or(default_vote))
+}
+
+/// `integer_squareroot` from the spec.
+pub fn integer_squareroot(n: u64) -> u64 {
+    let mut x = n;
+    let mut y = x / 2 + x % 2;
+    while y < x {
+        x = y;
+        y = (x + n / x) / 2;
+    }
+    x
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn squareroot() {
+        let cases = [
+            (0, 0),
+            (1, 1),
+            (2, 1),
+            (3, 1),
+            (4, 2),
+            (15, 3),
+            (16, 4),
+            (1024, 32),
+        ];
+        for (n, root) in &cases {
+            assert_eq!(integer_squareroot(*n), *root, "n: {}", n);
+        }
+        assert_eq!(integer_squareroot((1 << 62) + 1), 1 << 31);
+    }
+}
@@ -0,0 +1,392 @@
+use std::sync::Arc;
+use std::thread;
+use std::time::{Duration, Instant};
+
+use bls::{PublicKeyBytes, SignatureBytes};
+use eth1::deposit_tree::verify_proof;
+use eth1::mock_server::{MockChain, MockServer};
+use eth1::{DepositTree, Error, Eth1Config, Service};
+use ssz_types::{BitVector, PersistentList, PersistentVector};
+use tree_hash::TreeHash;
+use types::beacon_state::BeaconState;
+use types::config::{Config, QuickConfig};
+use types::primitives::{Epoch, Gwei, Shard, Signature, Slot, H256};
+use types::types::{BeaconBlockHeader, Checkpoint, DepositData, Eth1Data, Fork};
+
+const CONTRACT: &str = "0x1234567890123456789012345678901234567890";
+
+fn follow_distance() -> u64 {
+    QuickConfig::eth1_follow_distance()
+}
+
+fn deposit_data(i: u8) -> DepositData {
+    DepositData {
+        pubkey: PublicKeyBytes::from_bytes(&[i; 48]).unwrap(),
+        withdrawal_credentials: H256::repeat_byte(i),
+        amount: Gwei::new(32_000_000_000),
+        signature: SignatureBytes::from_bytes(&[i; 96]).unwrap(),
+    }
+}
+
+fn start(chain: MockChain) -> (MockServer, Service<QuickConfig>) {
+    let server = MockServer::start(chain).expect("mock server should start");
+    let service = Service::new(Eth1Config::new::<QuickConfig>(
+        &server.endpoint(),
+        CONTRACT,
+        0,
+    ))
+    .expect("endpoint should be valid");
+    (server, service)
+}
+
+fn checkpoint() -> Checkpoint {
+    Checkpoint {
+        epoch: Epoch::new(0),
+        root: H256::zero(),
+    }
+}
+
+fn state(eth1_data: Eth1Data, votes: Vec<Eth1Data>) -> BeaconState<QuickConfig> {
+    BeaconState {
+        genesis_time: 0,
+        slot: Slot::new(0),
+        fork: Fork {
+            previous_version: [0; 4],
+            current_version: [0; 4],
+            epoch: Epoch::new(0),
+        },
+        latest_block_header: BeaconBlockHeader {
+            slot: Slot::new(0),
+            parent_root: H256::zero(),
+            state_root: H256::zero(),
+            body_root: H256::zero(),
+            signature: Signature::empty_signature(),
+        },
+        block_roots: PersistentVector::default(),
+        state_roots: PersistentVector::default(),
+        historical_roots: PersistentList::default(),
+        eth1_data,
+        eth1_data_votes: PersistentList::new(votes).expect("too many votes"),
+        eth1_deposit_index: 0,
+        validators: PersistentList::default(),
+        balances: PersistentList::default(),
+        start_shard: Shard::new(0),
+        randao_mixes: PersistentVector::default(),
+        active_index_roots: PersistentVector::default(),
+        compact_committees_roots: PersistentVector::default(),
+        slashings: PersistentVector::default(),
+        previous_epoch_attestations: PersistentList::default(),
+        current_epoch_attestations: PersistentList::default(),
+        previous_crosslinks: PersistentVector::default(),
+        current_crosslinks: PersistentVector::default(),
+        justification_bits: BitVector::new(),
+        previous_justified_checkpoint: checkpoint(),
+        current_justified_checkpoint: checkpoint(),
+        finalized_checkpoint: checkpoint(),
+    }
+}
+
+#[test]
+fn nothing_imported_before_follow_distance() {
+    let mut chain = MockChain::new(CONTRACT, 1_000);
+    chain.mine_blocks(follow_distance() - 1);
+    let (_server, service) = start(chain);
+
+    service.update().unwrap();
+
+    assert_eq!(service.head_block_number(), Some(follow_distance() - 1));
+    assert!(service.blocks().is_empty());
+    assert_eq!(
+        service.eth1_vote(&state(Eth1Data::default(), vec![])),
+        Err(Error::Eth1DataUnavailable)
+    );
+}
+
+#[test]
+fn imports_deposits_and_blocks() {
+    let mut chain = MockChain::new(CONTRACT, 1_000);
+    chain.mine_blocks(2);
+    for i in 0..3 {
+        chain.deposit(deposit_data(i));
+    }
+    chain.mine();
+    chain.mine_blocks(4);
+    chain.deposit(deposit_data(3));
+    chain.deposit(deposit_data(4));
+    chain.mine();
+    chain.mine_blocks(2 * follow_distance());
+
+    let head = chain.head().number;
+    let server = MockServer::start(chain).unwrap();
+    let mut config = Eth1Config::new::<QuickConfig>(&server.endpoint(), CONTRACT, 0);
+    // Split the range over several log queries.
+    config.blocks_per_log_query = 3;
+    let service = Service::<QuickConfig>::new(config).unwrap();
+
+    service.update().unwrap();
+
+    assert_eq!(service.head_block_number(), Some(head));
+    assert_eq!(service.deposit_count(), 5);
+
+    let mut expected_tree = DepositTree::new();
+    for i in 0..5 {
+        let log = service.deposit_log(i).unwrap();
+        assert_eq!(log.deposit_data, deposit_data(i as u8));
+        assert_eq!(log.index, i as u64);
+        expected_tree.push(&log.deposit_data).unwrap();
+    }
+
+    let blocks = service.blocks();
+    assert_eq!(blocks.first().unwrap().number, 0);
+    assert_eq!(blocks.last().unwrap().number, head - follow_distance());
+
+    let chain = server.chain.lock().unwrap();
+    for block in &blocks {
+        let expected_count = match block.number {
+            0..=2 => 0,
+            3..=7 => 3,
+            _ => 5,
+        };
+        assert_eq!(
+            block.deposit_count, expected_count,
+            "block {}",
+            block.number
+        );
+        assert_eq!(
+            block.deposit_root,
+            expected_tree.root_at(expected_count as usize).unwrap()
+        );
+        assert_eq!(block.hash, chain.block(block.number).unwrap().hash);
+        assert_eq!(
+            block.timestamp,
+            chain.block(block.number).unwrap().timestamp
+        );
+    }
+}
+
+#[test]
+fn incremental_updates() {
+    let mut chain = MockChain::new(CONTRACT, 1_000);
+    chain.deposit(deposit_data(0));
+    chain.mine_blocks(follow_distance() + 1);
+    let (server, service) = start(chain);
+
+    service.update().unwrap();
+    assert_eq!(service.deposit_count(), 1);
+    assert_eq!(service.blocks().len(), 2);
+
+    {
+        let mut chain = server.chain.lock().unwrap();
+        chain.deposit(deposit_data(1));
+        chain.mine_blocks(3);
+    }
+
+    service.update().unwrap();
+    // The new deposit is still within the follow distance.
+    assert_eq!(service.deposit_count(), 1);
+    assert_eq!(service.blocks().len(), 5);
+
+    server.chain.lock().unwrap().mine_blocks(follow_distance());
+
+    service.update().unwrap();
+    assert_eq!(service.deposit_count(), 2);
+    assert_eq!(
+        service.deposit_log(1).unwrap().deposit_data,
+        deposit_data(1)
+    );
+}
+
+#[test]
+fn block_cache_is_bounded() {
+    let mut chain = MockChain::new(CONTRACT, 1_000);
+    chain.mine_blocks(10 * follow_distance());
+    let (_server, service) = start(chain);
+
+    service.update().unwrap();
+
+    let cache_len = service.config().block_cache_len;
+    let blocks = service.blocks();
+    assert_eq!(blocks.len(), cache_len);
+    assert_eq!(
+        blocks.last().unwrap().number,
+        service.head_block_number().unwrap() - follow_distance()
+    );
+}
+
+#[test]
+fn eth1_voting() {
+    let mut chain = MockChain::new(CONTRACT, 1_000);
+    chain.deposit(deposit_data(0));
+    chain.mine_blocks(4 * follow_distance());
+    let head = chain.head().number;
+    let (_server, service) = start(chain);
+    service.update().unwrap();
+
+    let data_at = |distance: u64| {
+        service
+            .block_by_number(head - distance)
+            .expect("block should be cached")
+            .eth1_data()
+    };
+    let previous = data_at(3 * follow_distance());
+
+    // Without votes the block at the follow distance is chosen.
+    assert_eq!(
+        service.eth1_vote(&state(previous.clone(), vec![])),
+        Ok(data_at(follow_distance()))
+    );
+
+    // The most popular new vote wins.
+    let votes = vec![
+        data_at(follow_distance() + 5),
+        data_at(follow_distance() + 2),
+        data_at(follow_distance() + 5),
+    ];
+    assert_eq!(
+        service.eth1_vote(&state(previous.clone(), votes)),
+        Ok(data_at(follow_distance() + 5))
+    );
+
+    // Ties are broken in favour of the most recent block.
+    let votes = vec![
+        data_at(follow_distance() + 5),
+        data_at(follow_distance() + 2),
+    ];
+    assert_eq!(
+        service.eth1_vote(&state(previous.clone(), votes)),
+        Ok(data_at(follow_distance() + 2))
+    );
+
+    // Votes for unknown blocks, or blocks beyond `2 * ETH1_FOLLOW_DISTANCE` early in the period,
+    // are ignored.
+    let unknown = Eth1Data {
+        block_hash: H256::repeat_byte(1),
+        ..Eth1Data::default()
+    };
+    let old = data_at(2 * follow_distance() + 1);
+    let votes = vec![unknown.clone(), unknown, old.clone(), old.clone()];
+    assert_eq!(
+        service.eth1_vote(&state(previous.clone(), votes)),
+        Ok(data_at(follow_distance()))
+    );
+
+    // Later in the period, votes back to the previous `Eth1Data` are valid.
+    let mut votes = vec![Eth1Data::default(); 4];
+    votes.push(old.clone());
+    assert_eq!(service.eth1_vote(&state(previous, votes)), Ok(old));
+}
+
+#[test]
+fn deposits_for_inclusion_have_valid_proofs() {
+    let mut chain = MockChain::new(CONTRACT, 1_000);
+    for i in 0..20 {
+        chain.deposit(deposit_data(i));
+        chain.mine();
+    }
+    chain.mine_blocks(follow_distance());
+    let (_server, service) = start(chain);
+    service.update().unwrap();
+
+    let eth1_data = service.block_by_number(15).unwrap().eth1_data();
+    assert_eq!(eth1_data.deposit_count, 15);
+
+    let mut state = state(eth1_data.clone(), vec![]);
+    state.eth1_deposit_index = 3;
+
+    let deposits = service.deposits_for_inclusion(&state).unwrap();
+    let max_deposits = 16;
+    assert_eq!(deposits.len(), std::cmp::min(max_deposits, 15 - 3));
+
+    for (i, deposit) in deposits.iter().enumerate() {
+        let index = 3 + i;
+        assert_eq!(deposit.data, deposit_data(index as u8));
+        assert!(verify_proof(
+            deposit.data.tree_hash_root(),
+            &deposit.proof,
+            index,
+            eth1_data.deposit_root,
+        ));
+    }
+
+    state.eth1_data.deposit_root = H256::repeat_byte(7);
+    match service.deposits_for_inclusion(&state) {
+        Err(Error::DepositRootMismatch { count: 15, .. }) => {}
+        other => panic!("unexpected result: {:?}", other),
+    }
+}
+
+#[test]
+fn endpoint_errors() {
+    let server = MockServer::start(MockChain::new(CONTRACT, 0)).unwrap();
+    let endpoint = server.endpoint();
+    drop(server);
+
+    let service =
+        Service::<QuickConfig>::new(Eth1Config::new::<QuickConfig>(&endpoint, CONTRACT, 0))
+            .unwrap();
+    match service.update() {
+        Err(Error::Io(_)) => {}
+        other => panic!("unexpected result: {:?}", other),
+    }
+
+    let config = Eth1Config::new::<QuickConfig>("ws://localhost:8546", CONTRACT, 0);
+    assert_eq!(
+        Service::<QuickConfig>::new(config).err(),
+        Some(Error::InvalidEndpoint("ws://localhost:8546".to_string()))
+    );
+}
+
+#[test]
+fn polling_thread() {
+    let mut chain = MockChain::new(CONTRACT, 1_000);
+    chain.mine_blocks(follow_distance());
+    let server = MockServer::start(chain).unwrap();
+
+    let mut config = Eth1Config::new::<QuickConfig>(&server.endpoint(), CONTRACT, 0);
+    config.update_interval = Duration::from_millis(10);
+    let service = Arc::new(Service::<QuickConfig>::new(config).unwrap());
+    let handle = Service::spawn(service.clone());
+
+    server.chain.lock().unwrap().mine_blocks(5);
+
+    let deadline = Instant::now() + Duration::from_secs(10);
+    while service.head_block_number() != Some(follow_distance() + 5) {
+        assert!(Instant::now() < deadline, "polling thread made no progress");
+        thread::sleep(Duration::from_millis(10));
+    }
+
+    drop(handle);
+    assert_eq!(Arc::strong_count(&service), 1);
+}
+
+#[test]
+fn polling_thread_keeps_last_error() {
+    let mut chain = MockChain::new(CONTRACT, 1_000);
+    chain.mine_blocks(follow_distance());
+    let server = MockServer::start(chain).unwrap();
+
+    let mut config = Eth1Config::new::<QuickConfig>(&server.endpoint(), CONTRACT, 0);
+    config.update_interval = Duration::from_millis(10);
+    let service = Arc::new(Service::<QuickConfig>::new(config).unwrap());
+    let _handle = Service::spawn(service.clone());
+
+    let deadline = Instant::now() + Duration::from_secs(10);
+    while service.head_block_number().is_none() {
+        assert!(Instant::now() < deadline, "polling thread made no progress");
+        thread::sleep(Duration::from_millis(10));
+    }
+
+    // The endpoint goes away, so every following update fails.
+    drop(server);
+    let error = loop {
+        if let Some(error) = service.take_last_error() {
+            break error;
+        }
+        assert!(Instant::now() < deadline, "polling thread kept no error");
+        thread::sleep(Duration::from_millis(10));
+    };
+    match error {
+        Error::Io(_) => {}
+        other => panic!("unexpected error: {:?}", other),
+    }
+}
@@ -0,0 +1,294 @@
+//! Payload encodings shared by gossip and req/resp.
+
+use std::cmp;
+use std::fmt;
+use std::str::FromStr;
+
+use ssz::Decode;
+
+use crate::Error;
+
+/// The maximum size of a gossip message, before any compression.
+pub const GOSSIP_MAX_SIZE: usize = 1 << 20;
+/// The maximum size of a single req/resp chunk, before any compression.
+pub const MAX_CHUNK_SIZE: usize = 1 << 20;
+
+/// The encoding suffix of gossip topics and req/resp protocol IDs.
+#[derive(Debug, PartialEq, Eq, Hash, Clone, Copy)]
+pub enum Encoding {
+    /// Plain SSZ. Req/resp payloads are prefixed with their length as an unsigned varint.
+    Ssz,
+    /// SSZ compressed with Snappy: one block per gossip message, and the framing format after
+    /// the varint length prefix of req/resp payloads.
+    SszSnappy,
+}
+
+impl Encoding {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Encoding::Ssz => "ssz",
+            Encoding::SszSnappy => "ssz_snappy",
+        }
+    }
+
+    /// Encodes a gossip message.
+    pub fn encode_gossip(self, ssz_bytes: Vec<u8>) -> Result<Vec<u8>, Error> {
+        check_len(ssz_bytes.len(), GOSSIP_MAX_SIZE)?;
+        match self {
+            Encoding::Ssz => Ok(ssz_bytes),
+            Encoding::SszSnappy => Ok(ssz_snappy::compress_block(&ssz_bytes)?),
+        }
+    }
+
+    /// Decodes a gossip message into at most `max_len` SSZ bytes.
+    pub fn decode_gossip(self, data: &[u8], max_len: usize) -> Result<Vec<u8>, Error> {
+        let max_len = cmp::min(max_len, GOSSIP_MAX_SIZE);
+        match self {
+            Encoding::Ssz => {
+                check_len(data.len(), max_len)?;
+                Ok(data.to_vec())
+            }
+            Encoding::SszSnappy => {
+                check_len(data.len(), ssz_snappy::max_compressed_len(max_len))?;
+                Ok(ssz_snappy::decompress_block(data, max_len)?)
+            }
+        }
+    }
+
+    /// Appends a length-prefixed req/resp payload to `buf`.
+    pub fn encode_payload(self, ssz_bytes: &[u8], buf: &mut Vec<u8>) -> Result<(), Error> {
+        check_len(ssz_bytes.len(), MAX_CHUNK_SIZE)?;
+        match self {
+            Encoding::Ssz => {
+                encode_varint(ssz_bytes.len() as u64, buf);
+                buf.extend_from_slice(ssz_bytes);
+            }
+            Encoding::SszSnappy => ssz_snappy::encode_framed(ssz_bytes, buf)?,
+        }
+        Ok(())
+    }
+
+    /// Reads a length-prefixed req/resp payload from the start of `bytes`, returning its SSZ
+    /// bytes and the number of bytes consumed.
+    ///
+    /// The length prefix is checked against `max_len` before anything is copied or decompressed.
+    pub fn decode_payload(self, bytes: &[u8], max_len: usize) -> Result<(Vec<u8>, usize), Error> {
+        match self {
+            Encoding::Ssz => {
+                let (len, prefix_len) = decode_varint(bytes)?;
+                let len = check_len_u64(len, max_len)?;
+                let payload =
+                    bytes
+                        .get(prefix_len..prefix_len + len)
+                        .ok_or(Error::TruncatedPayload {
+                            len,
+                            available: bytes.len() - prefix_len,
+                        })?;
+                Ok((payload.to_vec(), prefix_len + len))
+            }
+            Encoding::SszSnappy => Ok(ssz_snappy::decode_framed(bytes, max_len)?),
+        }
+    }
+}
+
+impl fmt::Display for Encoding {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+impl FromStr for Encoding {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Self, Error> {
+        match s {
+            "ssz" => Ok(Encoding::Ssz),
+            "ssz_snappy" => Ok(Encoding::SszSnappy),
+            other => Err(Error::UnknownEncoding(other.to_string())),
+        }
+    }
+}
+
+/// Appends `value` as an unsigned LEB128 varint, as used by protobuf.
+pub fn encode_varint(value: u64, buf: &mut Vec<u8>) {
+    ssz_snappy::encode_varint(value, buf)
+}
+
+/// Reads an unsigned LEB128 varint from the start of `bytes`, returning the value and the number
+/// of bytes it occupied.
+pub fn decode_varint(bytes: &[u8]) -> Result<(u64, usize), Error> {
+    Ok(ssz_snappy::decode_varint(bytes)?)
+}
+
+/// The largest payload that can hold a valid SSZ encoding of `T`, capped at `limit`.
+pub fn max_ssz_len<T: Decode>(limit: usize) -> usize {
+    cmp::min(T::ssz_max_len(), limit)
+}
+
+/// Decodes a `T`, rejecting payloads outside its SSZ length bounds before any parsing.
+pub fn decode_ssz<T: Decode>(bytes: &[u8]) -> Result<T, Error> {
+    if bytes.len() < T::ssz_min_len() {
+        return Err(Error::PayloadTooSmall {
+            len: bytes.len(),
+            min: T::ssz_min_len(),
+        });
+    }
+    check_len(bytes.len(), T::ssz_max_len())?;
+    Ok(T::from_ssz_bytes(bytes)?)
+}
+
+fn check_len(len: usize, max: usize) -> Result<(), Error> {
+    if len > max {
+        Err(Error::PayloadTooLarge { len, max })
+    } else {
+        Ok(())
+    }
+}
+
+fn check_len_u64(len: u64, max: usize) -> Result<usize, Error> {
+    if len > max as u64 {
+        Err(Error::PayloadTooLarge {
+            len: len as usize,
+            max,
+        })
+    } else {
+        Ok(len as usize)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn varint_round_trip() {
+        let cases: &[(u64, &[u8])] = &[
+            (0, &[0x00]),
+            (1, &[0x01]),
+            (127, &[0x7f]),
+            (128, &[0x80, 0x01]),
+            (300, &[0xac, 0x02]),
+            (16_384, &[0x80, 0x80, 0x01]),
+            (
+                u64::max_value(),
+                &[0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0x01],
+            ),
+        ];
+
+        for (value, encoding) in cases {
+            let mut buf = vec![];
+            encode_varint(*value, &mut buf);
+            assert_eq!(&buf[..], *encoding, "value: {}", value);
+            assert_eq!(decode_varint(&buf), Ok((*value, buf.len())));
+        }
+    }
+
+    #[test]
+    fn invalid_varints() {
+        assert_eq!(decode_varint(&[]), Err(Error::TruncatedVarint));
+        assert_eq!(decode_varint(&[0x80]), Err(Error::TruncatedVarint));
+        // Padded with a redundant zero byte.
+        assert_eq!(decode_varint(&[0x81, 0x00]), Err(Error::InvalidVarint));
+        // Overflows a `u64`.
+        assert_eq!(
+            decode_varint(&[0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0x02]),
+            Err(Error::InvalidVarint)
+        );
+        assert_eq!(decode_varint(&[0x80; 11]), Err(Error::InvalidVarint));
+    }
+
+    #[test]
+    fn payload_round_trip() {
+        let mut buf = vec![];
+        Encoding::Ssz.encode_payload(&[1, 2, 3], &mut buf).unwrap();
+        Encoding::Ssz.encode_payload(&[], &mut buf).unwrap();
+
+        assert_eq!(buf, vec![3, 1, 2, 3, 0]);
+        assert_eq!(
+            Encoding::Ssz.decode_payload(&buf, MAX_CHUNK_SIZE),
+            Ok((vec![1, 2, 3], 4))
+        );
+        assert_eq!(
+            Encoding::Ssz.decode_payload(&buf[4..], MAX_CHUNK_SIZE),
+            Ok((vec![], 1))
+        );
+    }
+
+    #[test]
+    fn oversized_payload_is_rejected_before_reading() {
+        let mut buf = vec![];
+        encode_varint(u64::max_value(), &mut buf);
+
+        assert_eq!(
+            Encoding::Ssz.decode_payload(&buf, 16),
+            Err(Error::PayloadTooLarge {
+                len: u64::max_value() as usize,
+                max: 16
+            })
+        );
+        assert_eq!(
+            Encoding::Ssz.decode_payload(&[4, 1, 2], 16),
+            Err(Error::TruncatedPayload {
+                len: 4,
+                available: 2
+            })
+        );
+    }
+
+    #[test]
+    fn snappy_round_trip() {
+        let ssz_bytes = vec![5; 10_000];
+
+        let gossip = Encoding::SszSnappy
+            .encode_gossip(ssz_bytes.clone())
+            .unwrap();
+        assert!(gossip.len() < ssz_bytes.len());
+        assert_eq!(
+            Encoding::SszSnappy.decode_gossip(&gossip, GOSSIP_MAX_SIZE),
+            Ok(ssz_bytes.clone())
+        );
+
+        let mut buf = vec![];
+        Encoding::SszSnappy
+            .encode_payload(&ssz_bytes, &mut buf)
+            .unwrap();
+        Encoding::SszSnappy.encode_payload(&[], &mut buf).unwrap();
+
+        let (payload, consumed) = Encoding::SszSnappy
+            .decode_payload(&buf, MAX_CHUNK_SIZE)
+            .unwrap();
+        assert_eq!(payload, ssz_bytes);
+        assert_eq!(
+            Encoding::SszSnappy.decode_payload(&buf[consumed..], MAX_CHUNK_SIZE),
+            Ok((vec![], buf.len() - consumed))
+        );
+        assert_eq!(
+            Encoding::SszSnappy.decode_payload(&buf, 100),
+            Err(Error::PayloadTooLarge {
+                len: 10_000,
+                max: 100
+            })
+        );
+    }
+
+    #[test]
+    fn snappy_gossip_is_bounded_after_decompression() {
+        let oversized = ssz_snappy::compress_block(&vec![0; GOSSIP_MAX_SIZE + 1]).unwrap();
+
+        assert_eq!(
+            Encoding::SszSnappy.decode_gossip(&oversized, GOSSIP_MAX_SIZE),
+            Err(Error::PayloadTooLarge {
+                len: GOSSIP_MAX_SIZE + 1,
+                max: GOSSIP_MAX_SIZE
+            })
+        );
+    }
+
+    #[test]
+    fn encoding_names() {
+        for encoding in &[Encoding::Ssz, Encoding::SszSnappy] {
+            assert_eq!(encoding.to_string().parse(), Ok(*encoding));
+        }
+        assert_eq!(Encoding::SszSnappy.to_string(), "ssz_snappy");
+    }
+}
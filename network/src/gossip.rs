@@ -0,0 +1,208 @@
+//! Phase 0 gossip topics and messages.
+
+use std::fmt;
+use std::str::FromStr;
+
+use ssz::{Decode, Encode};
+use types::config::Config;
+use types::types::{Attestation, AttesterSlashing, BeaconBlock, ProposerSlashing, VoluntaryExit};
+
+use crate::codec::{decode_ssz, max_ssz_len, Encoding, GOSSIP_MAX_SIZE};
+use crate::Error;
+
+const TOPIC_PREFIX: &str = "/eth2/";
+
+#[derive(Debug, PartialEq, Eq, Hash, Clone, Copy)]
+pub enum GossipKind {
+    BeaconBlock,
+    BeaconAttestation,
+    VoluntaryExit,
+    ProposerSlashing,
+    AttesterSlashing,
+}
+
+impl GossipKind {
+    pub const ALL: [GossipKind; 5] = [
+        GossipKind::BeaconBlock,
+        GossipKind::BeaconAttestation,
+        GossipKind::VoluntaryExit,
+        GossipKind::ProposerSlashing,
+        GossipKind::AttesterSlashing,
+    ];
+
+    pub fn as_str(self) -> &'static str {
+        match self {
+            GossipKind::BeaconBlock => "beacon_block",
+            GossipKind::BeaconAttestation => "beacon_attestation",
+            GossipKind::VoluntaryExit => "voluntary_exit",
+            GossipKind::ProposerSlashing => "proposer_slashing",
+            GossipKind::AttesterSlashing => "attester_slashing",
+        }
+    }
+}
+
+/// A gossip topic such as `/eth2/beacon_block/ssz`.
+#[derive(Debug, PartialEq, Eq, Hash, Clone, Copy)]
+pub struct GossipTopic {
+    pub kind: GossipKind,
+    pub encoding: Encoding,
+}
+
+impl GossipTopic {
+    pub fn new(kind: GossipKind, encoding: Encoding) -> Self {
+        Self { kind, encoding }
+    }
+}
+
+impl fmt::Display for GossipTopic {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(
+            f,
+            "{}{}/{}",
+            TOPIC_PREFIX,
+            self.kind.as_str(),
+            self.encoding
+        )
+    }
+}
+
+impl FromStr for GossipTopic {
+    type Err = Error;
+
+    fn from_str(topic: &str) -> Result<Self, Error> {
+        let unknown = || Error::UnknownTopic(topic.to_string());
+
+        if !topic.starts_with(TOPIC_PREFIX) {
+            return Err(unknown());
+        }
+        let mut parts = topic[TOPIC_PREFIX.len()..].split('/');
+
+        let kind = parts
+            .next()
+            .and_then(|name| GossipKind::ALL.iter().find(|kind| kind.as_str() == name))
+            .cloned()
+            .ok_or_else(unknown)?;
+        let encoding = parts.next().ok_or_else(unknown)?.parse()?;
+
+        if parts.next().is_some() {
+            return Err(unknown());
+        }
+
+        Ok(Self { kind, encoding })
+    }
+}
+
+#[derive(Debug, PartialEq, Clone)]
+pub enum GossipMessage<C: Config> {
+    BeaconBlock(Box<BeaconBlock<C>>),
+    BeaconAttestation(Box<Attestation<C>>),
+    VoluntaryExit(VoluntaryExit),
+    ProposerSlashing(Box<ProposerSlashing>),
+    AttesterSlashing(Box<AttesterSlashing<C>>),
+}
+
+impl<C: Config> GossipMessage<C> {
+    pub fn kind(&self) -> GossipKind {
+        match self {
+            GossipMessage::BeaconBlock(_) => GossipKind::BeaconBlock,
+            GossipMessage::BeaconAttestation(_) => GossipKind::BeaconAttestation,
+            GossipMessage::VoluntaryExit(_) => GossipKind::VoluntaryExit,
+            GossipMessage::ProposerSlashing(_) => GossipKind::ProposerSlashing,
+            GossipMessage::AttesterSlashing(_) => GossipKind::AttesterSlashing,
+        }
+    }
+
+    pub fn encode(&self, encoding: Encoding) -> Result<Vec<u8>, Error> {
+        let ssz_bytes = match self {
+            GossipMessage::BeaconBlock(block) => block.as_ssz_bytes(),
+            GossipMessage::BeaconAttestation(attestation) => attestation.as_ssz_bytes(),
+            GossipMessage::VoluntaryExit(exit) => exit.as_ssz_bytes(),
+            GossipMessage::ProposerSlashing(slashing) => slashing.as_ssz_bytes(),
+            GossipMessage::AttesterSlashing(slashing) => slashing.as_ssz_bytes(),
+        };
+        encoding.encode_gossip(ssz_bytes)
+    }
+
+    pub fn decode(topic: GossipTopic, data: &[u8]) -> Result<Self, Error> {
+        let encoding = topic.encoding;
+
+        let message = match topic.kind {
+            GossipKind::BeaconBlock => {
+                GossipMessage::BeaconBlock(Box::new(decode_message(encoding, data)?))
+            }
+            GossipKind::BeaconAttestation => {
+                GossipMessage::BeaconAttestation(Box::new(decode_message(encoding, data)?))
+            }
+            GossipKind::VoluntaryExit => {
+                GossipMessage::VoluntaryExit(decode_message(encoding, data)?)
+            }
+            GossipKind::ProposerSlashing => {
+                GossipMessage::ProposerSlashing(Box::new(decode_message(encoding, data)?))
+            }
+            GossipKind::AttesterSlashing => {
+                GossipMessage::AttesterSlashing(Box::new(decode_message(encoding, data)?))
+            }
+        };
+
+        Ok(message)
+    }
+}
+
+/// Decodes a `T`, decompressing no more than its maximum SSZ length.
+fn decode_message<T: Decode>(encoding: Encoding, data: &[u8]) -> Result<T, Error> {
+    let bytes = encoding.decode_gossip(data, max_ssz_len::<T>(GOSSIP_MAX_SIZE))?;
+    decode_ssz(&bytes)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use types::config::MinimalConfig;
+
+    #[test]
+    fn topic_names() {
+        for kind in &GossipKind::ALL {
+            let topic = GossipTopic::new(*kind, Encoding::Ssz);
+            let name = topic.to_string();
+
+            assert_eq!(name, format!("/eth2/{}/ssz", kind.as_str()));
+            assert_eq!(name.parse(), Ok(topic));
+        }
+    }
+
+    #[test]
+    fn undersized_block() {
+        let topic = GossipTopic::new(GossipKind::BeaconBlock, Encoding::Ssz);
+
+        assert_eq!(
+            GossipMessage::<MinimalConfig>::decode(topic, &[0; 10]),
+            Err(Error::PayloadTooSmall {
+                len: 10,
+                min: <BeaconBlock<MinimalConfig> as Decode>::ssz_min_len(),
+            })
+        );
+    }
+
+    #[test]
+    fn invalid_topics() {
+        for topic in &[
+            "",
+            "/eth2/",
+            "/eth2/beacon_block",
+            "/eth2/beacon_block/ssz/extra",
+            "/eth2/shard_block/ssz",
+            "/eth1/beacon_block/ssz",
+        ] {
+            assert_eq!(
+                topic.parse::<GossipTopic>(),
+                Err(Error::UnknownTopic(topic.to_string()))
+            );
+        }
+
+        assert_eq!(
+            "/eth2/beacon_block/json".parse::<GossipTopic>(),
+            Err(Error::UnknownEncoding("json".to_string()))
+        );
+    }
+}
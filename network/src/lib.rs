@@ -0,0 +1,84 @@
+//! Phase 0 gossip topics, req/resp protocols and their wire encodings, on top of a swappable
+//! transport.
+
+pub mod codec;
+pub mod gossip;
+pub mod memory_transport;
+pub mod network;
+pub mod rpc;
+pub mod sync;
+pub mod tcp_transport;
+pub mod transport;
+
+pub use crate::codec::Encoding;
+pub use crate::gossip::{GossipKind, GossipMessage, GossipTopic};
+pub use crate::memory_transport::{MemoryHub, MemoryTransport};
+pub use crate::network::{Network, NetworkEvent, RequestId};
+pub use crate::rpc::{Protocol, ProtocolId, ResponseCode, RpcRequest, RpcResponse};
+pub use crate::sync::{BeaconChain, BlockError, SyncManager, SyncState};
+pub use crate::tcp_transport::TcpTransport;
+pub use crate::transport::{Frame, PeerId, Transport};
+
+#[derive(Debug, PartialEq)]
+pub enum Error {
+    Io(String),
+    Ssz(ssz::DecodeError),
+    InvalidVarint,
+    TruncatedVarint,
+    PayloadTooLarge {
+        len: usize,
+        max: usize,
+    },
+    /// A payload too short to hold any valid encoding of the expected type.
+    PayloadTooSmall {
+        len: usize,
+        min: usize,
+    },
+    TruncatedPayload {
+        len: usize,
+        available: usize,
+    },
+    TrailingBytes(usize),
+    UnknownEncoding(String),
+    UnknownTopic(String),
+    UnknownProtocol(String),
+    /// A response had the wrong number of chunks for its protocol.
+    UnexpectedChunkCount(usize),
+    TooManyChunks {
+        len: usize,
+        max: usize,
+    },
+    /// A response arrived for a protocol that has none.
+    UnexpectedResponse(Protocol),
+    /// A response arrived for a request that was never made or is no longer pending.
+    UnexpectedResponseId(RequestId),
+    InvalidFrame(String),
+    UnknownPeer(PeerId),
+    TransportClosed,
+    /// A malformed Snappy block or frame.
+    Snappy(ssz_snappy::Error),
+}
+
+impl From<ssz::DecodeError> for Error {
+    fn from(e: ssz::DecodeError) -> Self {
+        Error::Ssz(e)
+    }
+}
+
+impl From<ssz_snappy::Error> for Error {
+    fn from(e: ssz_snappy::Error) -> Self {
+        match e {
+            ssz_snappy::Error::InvalidVarint => Error::InvalidVarint,
+            ssz_snappy::Error::TruncatedVarint => Error::TruncatedVarint,
+            ssz_snappy::Error::TooLarge { len, max } => Error::PayloadTooLarge { len, max },
+            ssz_snappy::Error::Ssz(e) => Error::Ssz(e),
+            other => Error::Snappy(other),
+        }
+    }
+}
+
+impl From<std::io::Error> for Error {
+    fn from(e: std::io::Error) -> Self {
+        Error::Io(e.to_string())
+    }
+}
@@ -0,0 +1,193 @@
+use std::collections::{HashMap, HashSet};
+use std::sync::mpsc::{channel, Receiver, RecvTimeoutError, Sender};
+use std::sync::{Arc, Mutex, MutexGuard};
+use std::time::Duration;
+
+use crate::transport::{Frame, PeerId, Transport};
+use crate::Error;
+
+#[derive(Default)]
+struct Nodes {
+    inboxes: HashMap<PeerId, Sender<(PeerId, Frame)>>,
+    /// Pairs of nodes that have disconnected from each other, smallest ID first.
+    disconnected: HashSet<(PeerId, PeerId)>,
+}
+
+impl Nodes {
+    fn connected(&self, a: &PeerId, b: &PeerId) -> bool {
+        a != b && self.inboxes.contains_key(b) && !self.disconnected.contains(&pair(a, b))
+    }
+}
+
+fn pair(a: &PeerId, b: &PeerId) -> (PeerId, PeerId) {
+    if a < b {
+        (a.clone(), b.clone())
+    } else {
+        (b.clone(), a.clone())
+    }
+}
+
+/// Connects any number of `MemoryTransport`s, every one of which can reach all the others.
+#[derive(Default, Clone)]
+pub struct MemoryHub {
+    nodes: Arc<Mutex<Nodes>>,
+}
+
+impl MemoryHub {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Adds a node to the hub, connected to every other node. Replaces any node that joined
+    /// with the same `peer_id`.
+    pub fn join(&self, peer_id: &str) -> MemoryTransport {
+        let peer_id = PeerId::new(peer_id);
+        let (sender, receiver) = channel();
+
+        let mut nodes = lock(&self.nodes);
+        nodes.inboxes.insert(peer_id.clone(), sender);
+        nodes
+            .disconnected
+            .retain(|(a, b)| *a != peer_id && *b != peer_id);
+
+        MemoryTransport {
+            peer_id,
+            nodes: Arc::clone(&self.nodes),
+            receiver: Mutex::new(receiver),
+        }
+    }
+}
+
+fn lock(nodes: &Mutex<Nodes>) -> MutexGuard<'_, Nodes> {
+    nodes.lock().expect("memory hub lock poisoned")
+}
+
+/// A node attached to a `MemoryHub`. Leaves the hub when dropped.
+pub struct MemoryTransport {
+    peer_id: PeerId,
+    nodes: Arc<Mutex<Nodes>>,
+    receiver: Mutex<Receiver<(PeerId, Frame)>>,
+}
+
+impl Transport for MemoryTransport {
+    fn local_peer_id(&self) -> &PeerId {
+        &self.peer_id
+    }
+
+    fn peers(&self) -> Vec<PeerId> {
+        let nodes = lock(&self.nodes);
+        let mut peers = nodes
+            .inboxes
+            .keys()
+            .filter(|peer| nodes.connected(&self.peer_id, peer))
+            .cloned()
+            .collect::<Vec<_>>();
+        peers.sort();
+        peers
+    }
+
+    fn send(&self, peer: &PeerId, frame: Frame) -> Result<(), Error> {
+        let nodes = lock(&self.nodes);
+        if !nodes.connected(&self.peer_id, peer) {
+            return Err(Error::UnknownPeer(peer.clone()));
+        }
+
+        nodes.inboxes[peer]
+            .send((self.peer_id.clone(), frame))
+            .map_err(|_| Error::UnknownPeer(peer.clone()))
+    }
+
+    fn recv_timeout(&self, timeout: Duration) -> Result<Option<(PeerId, Frame)>, Error> {
+        let receiver = self
+            .receiver
+            .lock()
+            .expect("memory transport lock poisoned");
+        match receiver.recv_timeout(timeout) {
+            Ok(message) => Ok(Some(message)),
+            Err(RecvTimeoutError::Timeout) => Ok(None),
+            // Unreachable while `self` is in the hub, which holds our sender.
+            Err(RecvTimeoutError::Disconnected) => Err(Error::TransportClosed),
+        }
+    }
+
+    /// Stops `peer` and this node from reaching each other.
+    fn disconnect(&self, peer: &PeerId) {
+        if *peer != self.peer_id {
+            lock(&self.nodes)
+                .disconnected
+                .insert(pair(&self.peer_id, peer));
+        }
+    }
+}
+
+impl Drop for MemoryTransport {
+    fn drop(&mut self) {
+        if let Ok(mut nodes) = self.nodes.lock() {
+            nodes.inboxes.remove(&self.peer_id);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn gossip(data: u8) -> Frame {
+        Frame::Gossip {
+            topic: "topic".to_string(),
+            data: vec![data],
+        }
+    }
+
+    #[test]
+    fn send_and_receive() {
+        let hub = MemoryHub::new();
+        let a = hub.join("a");
+        let b = hub.join("b");
+        let c = hub.join("c");
+
+        assert_eq!(a.peers(), vec![PeerId::new("b"), PeerId::new("c")]);
+
+        a.send(&PeerId::new("b"), gossip(1)).unwrap();
+        c.send(&PeerId::new("b"), gossip(2)).unwrap();
+
+        let timeout = Duration::from_millis(100);
+        assert_eq!(
+            b.recv_timeout(timeout),
+            Ok(Some((PeerId::new("a"), gossip(1))))
+        );
+        assert_eq!(
+            b.recv_timeout(timeout),
+            Ok(Some((PeerId::new("c"), gossip(2))))
+        );
+        assert_eq!(b.recv_timeout(Duration::from_millis(1)), Ok(None));
+    }
+
+    #[test]
+    fn unknown_and_departed_peers() {
+        let hub = MemoryHub::new();
+        let a = hub.join("a");
+        let b = hub.join("b");
+
+        assert_eq!(
+            a.send(&PeerId::new("a"), gossip(0)),
+            Err(Error::UnknownPeer(PeerId::new("a")))
+        );
+
+        let c = hub.join("c");
+        a.disconnect(&PeerId::new("c"));
+        assert_eq!(a.peers(), vec![PeerId::new("b")]);
+        assert_eq!(
+            c.send(&PeerId::new("a"), gossip(0)),
+            Err(Error::UnknownPeer(PeerId::new("a")))
+        );
+        assert_eq!(c.peers(), vec![PeerId::new("b")]);
+
+        drop(b);
+        assert!(a.peers().is_empty());
+        assert_eq!(
+            a.send(&PeerId::new("b"), gossip(0)),
+            Err(Error::UnknownPeer(PeerId::new("b")))
+        );
+    }
+}
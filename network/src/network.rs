@@ -0,0 +1,195 @@
+use std::collections::HashMap;
+use std::marker::PhantomData;
+use std::time::Duration;
+
+use types::config::Config;
+
+use crate::codec::Encoding;
+use crate::gossip::{GossipMessage, GossipTopic};
+use crate::rpc::{ProtocolId, ResponseCode, RpcRequest, RpcResponse};
+use crate::transport::{Frame, PeerId, Transport};
+use crate::Error;
+
+/// Identifies a request within the connection to one peer.
+pub type RequestId = u64;
+
+#[derive(Debug, PartialEq)]
+pub enum NetworkEvent<C: Config> {
+    Gossip {
+        peer: PeerId,
+        message: GossipMessage<C>,
+    },
+    /// A request that should be answered with `Network::send_response`, unless it is a
+    /// `Goodbye`.
+    Request {
+        peer: PeerId,
+        id: RequestId,
+        request: RpcRequest,
+    },
+    Response {
+        peer: PeerId,
+        id: RequestId,
+        response: RpcResponse<C>,
+    },
+    /// A peer sent something that could not be decoded. Invalid requests have already been
+    /// answered with `ResponseCode::InvalidRequest`.
+    InvalidMessage { peer: PeerId, error: Error },
+}
+
+/// Encodes beacon chain messages on top of a `Transport`.
+///
+/// Gossip is flooded directly to every connected peer; there is no mesh or deduplication.
+pub struct Network<C: Config, T: Transport> {
+    transport: T,
+    encoding: Encoding,
+    next_request_id: RequestId,
+    /// The protocol of every request still waiting for a response, needed to decode it.
+    pending: HashMap<(PeerId, RequestId), ProtocolId>,
+    phantom: PhantomData<C>,
+}
+
+impl<C: Config, T: Transport> Network<C, T> {
+    pub fn new(transport: T, encoding: Encoding) -> Self {
+        Self {
+            transport,
+            encoding,
+            next_request_id: 0,
+            pending: HashMap::new(),
+            phantom: PhantomData,
+        }
+    }
+
+    pub fn transport(&self) -> &T {
+        &self.transport
+    }
+
+    pub fn local_peer_id(&self) -> &PeerId {
+        self.transport.local_peer_id()
+    }
+
+    pub fn peers(&self) -> Vec<PeerId> {
+        self.transport.peers()
+    }
+
+    /// Sends `message` to every connected peer, returning how many were reached.
+    pub fn publish(&self, message: &GossipMessage<C>) -> Result<usize, Error> {
+        let topic = GossipTopic::new(message.kind(), self.encoding).to_string();
+        let data = message.encode(self.encoding)?;
+
+        let reached = self
+            .transport
+            .peers()
+            .iter()
+            .filter(|peer| {
+                let frame = Frame::Gossip {
+                    topic: topic.clone(),
+                    data: data.clone(),
+                };
+                self.transport.send(peer, frame).is_ok()
+            })
+            .count();
+
+        Ok(reached)
+    }
+
+    pub fn send_request(
+        &mut self,
+        peer: &PeerId,
+        request: &RpcRequest,
+    ) -> Result<RequestId, Error> {
+        let protocol = ProtocolId::new(request.protocol(), self.encoding);
+        let id = self.next_request_id;
+        self.next_request_id += 1;
+
+        self.transport.send(
+            peer,
+            Frame::Request {
+                id,
+                protocol: protocol.to_string(),
+                data: request.encode(self.encoding)?,
+            },
+        )?;
+
+        if protocol.protocol.expects_response() {
+            self.pending.insert((peer.clone(), id), protocol);
+        }
+        Ok(id)
+    }
+
+    pub fn send_response(
+        &self,
+        peer: &PeerId,
+        id: RequestId,
+        response: &RpcResponse<C>,
+    ) -> Result<(), Error> {
+        self.transport.send(
+            peer,
+            Frame::Response {
+                id,
+                data: response.encode(self.encoding)?,
+            },
+        )
+    }
+
+    /// Forgets requests to `peer` and drops the connection.
+    pub fn disconnect(&mut self, peer: &PeerId) {
+        self.pending
+            .retain(|(pending_peer, _), _| pending_peer != peer);
+        self.transport.disconnect(peer);
+    }
+
+    /// Forgets a request whose response is no longer wanted, e.g. after a timeout. Returns
+    /// whether it was still pending.
+    pub fn cancel_request(&mut self, peer: &PeerId, id: RequestId) -> bool {
+        self.pending.remove(&(peer.clone(), id)).is_some()
+    }
+
+    /// Waits up to `timeout` for the next message from any peer.
+    pub fn poll(&mut self, timeout: Duration) -> Result<Option<NetworkEvent<C>>, Error> {
+        let (peer, frame) = match self.transport.recv_timeout(timeout)? {
+            Some(received) => received,
+            None => return Ok(None),
+        };
+
+        let event = match frame {
+            Frame::Gossip { topic, data } => {
+                match topic
+                    .parse()
+                    .and_then(|topic| GossipMessage::decode(topic, &data))
+                {
+                    Ok(message) => NetworkEvent::Gossip { peer, message },
+                    Err(error) => NetworkEvent::InvalidMessage { peer, error },
+                }
+            }
+            Frame::Request { id, protocol, data } => {
+                match protocol
+                    .parse()
+                    .and_then(|protocol| RpcRequest::decode(protocol, &data))
+                {
+                    Ok(request) => NetworkEvent::Request { peer, id, request },
+                    Err(error) => {
+                        let response = RpcResponse::error(
+                            ResponseCode::InvalidRequest,
+                            &format!("{:?}", error),
+                        );
+                        // The peer may already be gone; the event is reported either way.
+                        let _ = self.send_response(&peer, id, &response);
+                        NetworkEvent::InvalidMessage { peer, error }
+                    }
+                }
+            }
+            Frame::Response { id, data } => match self.pending.remove(&(peer.clone(), id)) {
+                Some(protocol) => match RpcResponse::decode(protocol, &data) {
+                    Ok(response) => NetworkEvent::Response { peer, id, response },
+                    Err(error) => NetworkEvent::InvalidMessage { peer, error },
+                },
+                None => NetworkEvent::InvalidMessage {
+                    peer,
+                    error: Error::UnexpectedResponseId(id),
+                },
+            },
+        };
+
+        Ok(Some(event))
+    }
+}
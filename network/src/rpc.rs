@@ -0,0 +1,563 @@
+//! Phase 0 req/resp protocols.
+//!
+//! A request is a single length-prefixed payload. A response is a sequence of chunks, each a
+//! result byte followed by a length-prefixed payload.
+
+use std::fmt;
+use std::str::FromStr;
+
+use ssz::{Decode, DecodeError, Encode};
+use ssz_derive::{Decode, Encode};
+use ssz_types::VariableList;
+use typenum::{Unsigned, U1024, U256};
+use types::config::Config;
+use types::primitives::{Epoch, Slot, Version, H256};
+use types::types::BeaconBlock;
+
+use crate::codec::{decode_ssz, max_ssz_len, Encoding, MAX_CHUNK_SIZE};
+use crate::Error;
+
+const PROTOCOL_PREFIX: &str = "/eth2/beacon_chain/req/";
+const PROTOCOL_VERSION: &str = "1";
+
+/// The maximum number of blocks requested or returned at once.
+pub type MaxRequestBlocks = U1024;
+/// The maximum length of the message in an error response.
+pub type MaxErrorLen = U256;
+
+#[derive(Debug, PartialEq, Eq, Hash, Clone, Copy)]
+pub enum Protocol {
+    Status,
+    Goodbye,
+    BeaconBlocksByRange,
+    BeaconBlocksByRoot,
+}
+
+impl Protocol {
+    pub const ALL: [Protocol; 4] = [
+        Protocol::Status,
+        Protocol::Goodbye,
+        Protocol::BeaconBlocksByRange,
+        Protocol::BeaconBlocksByRoot,
+    ];
+
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Protocol::Status => "status",
+            Protocol::Goodbye => "goodbye",
+            Protocol::BeaconBlocksByRange => "beacon_blocks_by_range",
+            Protocol::BeaconBlocksByRoot => "beacon_blocks_by_root",
+        }
+    }
+
+    /// `Goodbye` is the only request that is not answered.
+    pub fn expects_response(self) -> bool {
+        self != Protocol::Goodbye
+    }
+}
+
+/// A protocol ID such as `/eth2/beacon_chain/req/status/1/ssz`.
+#[derive(Debug, PartialEq, Eq, Hash, Clone, Copy)]
+pub struct ProtocolId {
+    pub protocol: Protocol,
+    pub encoding: Encoding,
+}
+
+impl ProtocolId {
+    pub fn new(protocol: Protocol, encoding: Encoding) -> Self {
+        Self { protocol, encoding }
+    }
+}
+
+impl fmt::Display for ProtocolId {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(
+            f,
+            "{}{}/{}/{}",
+            PROTOCOL_PREFIX,
+            self.protocol.as_str(),
+            PROTOCOL_VERSION,
+            self.encoding
+        )
+    }
+}
+
+impl FromStr for ProtocolId {
+    type Err = Error;
+
+    fn from_str(id: &str) -> Result<Self, Error> {
+        let unknown = || Error::UnknownProtocol(id.to_string());
+
+        if !id.starts_with(PROTOCOL_PREFIX) {
+            return Err(unknown());
+        }
+        let parts = id[PROTOCOL_PREFIX.len()..].split('/').collect::<Vec<_>>();
+
+        match parts.as_slice() {
+            [name, PROTOCOL_VERSION, encoding] => Ok(Self {
+                protocol: Protocol::ALL
+                    .iter()
+                    .find(|protocol| protocol.as_str() == *name)
+                    .cloned()
+                    .ok_or_else(unknown)?,
+                encoding: encoding.parse()?,
+            }),
+            _ => Err(unknown()),
+        }
+    }
+}
+
+#[derive(Debug, PartialEq, Eq, Clone, Encode, Decode)]
+pub struct StatusMessage {
+    pub head_fork_version: Version,
+    pub finalized_root: H256,
+    pub finalized_epoch: Epoch,
+    pub head_root: H256,
+    pub head_slot: Slot,
+}
+
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+pub enum GoodbyeReason {
+    ClientShutdown,
+    IrrelevantNetwork,
+    Fault,
+    Unknown(u64),
+}
+
+impl From<u64> for GoodbyeReason {
+    fn from(code: u64) -> Self {
+        match code {
+            1 => GoodbyeReason::ClientShutdown,
+            2 => GoodbyeReason::IrrelevantNetwork,
+            3 => GoodbyeReason::Fault,
+            other => GoodbyeReason::Unknown(other),
+        }
+    }
+}
+
+impl From<GoodbyeReason> for u64 {
+    fn from(reason: GoodbyeReason) -> Self {
+        match reason {
+            GoodbyeReason::ClientShutdown => 1,
+            GoodbyeReason::IrrelevantNetwork => 2,
+            GoodbyeReason::Fault => 3,
+            GoodbyeReason::Unknown(other) => other,
+        }
+    }
+}
+
+impl Encode for GoodbyeReason {
+    fn is_ssz_fixed_len() -> bool {
+        <u64 as Encode>::is_ssz_fixed_len()
+    }
+
+    fn ssz_fixed_len() -> usize {
+        <u64 as Encode>::ssz_fixed_len()
+    }
+
+    fn ssz_bytes_len(&self) -> usize {
+        u64::from(*self).ssz_bytes_len()
+    }
+
+    fn ssz_append(&self, buf: &mut Vec<u8>) {
+        u64::from(*self).ssz_append(buf)
+    }
+}
+
+impl Decode for GoodbyeReason {
+    fn is_ssz_fixed_len() -> bool {
+        <u64 as Decode>::is_ssz_fixed_len()
+    }
+
+    fn ssz_fixed_len() -> usize {
+        <u64 as Decode>::ssz_fixed_len()
+    }
+
+    fn from_ssz_bytes(bytes: &[u8]) -> Result<Self, DecodeError> {
+        u64::from_ssz_bytes(bytes).map(Self::from)
+    }
+}
+
+#[derive(Debug, PartialEq, Eq, Clone, Encode, Decode)]
+pub struct BeaconBlocksByRangeRequest {
+    pub head_block_root: H256,
+    pub start_slot: Slot,
+    pub count: u64,
+    pub step: u64,
+}
+
+impl BeaconBlocksByRangeRequest {
+    /// The slots covered by the request, in order.
+    pub fn slots(&self) -> impl Iterator<Item = Slot> {
+        let start = self.start_slot;
+        let step = self.step;
+        (0..self.count).map(move |i| start.saturating_add(i.saturating_mul(step)))
+    }
+}
+
+#[derive(Debug, PartialEq, Clone, Encode, Decode)]
+pub struct BeaconBlocksByRootRequest {
+    pub block_roots: VariableList<H256, MaxRequestBlocks>,
+}
+
+#[derive(Debug, PartialEq, Clone)]
+pub enum RpcRequest {
+    Status(StatusMessage),
+    Goodbye(GoodbyeReason),
+    BeaconBlocksByRange(BeaconBlocksByRangeRequest),
+    BeaconBlocksByRoot(BeaconBlocksByRootRequest),
+}
+
+impl RpcRequest {
+    pub fn protocol(&self) -> Protocol {
+        match self {
+            RpcRequest::Status(_) => Protocol::Status,
+            RpcRequest::Goodbye(_) => Protocol::Goodbye,
+            RpcRequest::BeaconBlocksByRange(_) => Protocol::BeaconBlocksByRange,
+            RpcRequest::BeaconBlocksByRoot(_) => Protocol::BeaconBlocksByRoot,
+        }
+    }
+
+    pub fn encode(&self, encoding: Encoding) -> Result<Vec<u8>, Error> {
+        let ssz_bytes = match self {
+            RpcRequest::Status(status) => status.as_ssz_bytes(),
+            RpcRequest::Goodbye(reason) => reason.as_ssz_bytes(),
+            RpcRequest::BeaconBlocksByRange(request) => request.as_ssz_bytes(),
+            RpcRequest::BeaconBlocksByRoot(request) => request.as_ssz_bytes(),
+        };
+
+        let mut buf = vec![];
+        encoding.encode_payload(&ssz_bytes, &mut buf)?;
+        Ok(buf)
+    }
+
+    pub fn decode(id: ProtocolId, bytes: &[u8]) -> Result<Self, Error> {
+        let max_len = match id.protocol {
+            Protocol::Status => max_ssz_len::<StatusMessage>(MAX_CHUNK_SIZE),
+            Protocol::Goodbye => max_ssz_len::<GoodbyeReason>(MAX_CHUNK_SIZE),
+            Protocol::BeaconBlocksByRange => {
+                max_ssz_len::<BeaconBlocksByRangeRequest>(MAX_CHUNK_SIZE)
+            }
+            Protocol::BeaconBlocksByRoot => {
+                max_ssz_len::<BeaconBlocksByRootRequest>(MAX_CHUNK_SIZE)
+            }
+        };
+        let (ssz_bytes, consumed) = id.encoding.decode_payload(bytes, max_len)?;
+        if consumed != bytes.len() {
+            return Err(Error::TrailingBytes(bytes.len() - consumed));
+        }
+
+        let request = match id.protocol {
+            Protocol::Status => RpcRequest::Status(decode_ssz(&ssz_bytes)?),
+            Protocol::Goodbye => RpcRequest::Goodbye(decode_ssz(&ssz_bytes)?),
+            Protocol::BeaconBlocksByRange => {
+                RpcRequest::BeaconBlocksByRange(decode_ssz(&ssz_bytes)?)
+            }
+            Protocol::BeaconBlocksByRoot => RpcRequest::BeaconBlocksByRoot(decode_ssz(&ssz_bytes)?),
+        };
+
+        Ok(request)
+    }
+}
+
+/// The result byte that starts every response chunk.
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+pub enum ResponseCode {
+    Success,
+    InvalidRequest,
+    ServerError,
+    Unknown(u8),
+}
+
+impl From<u8> for ResponseCode {
+    fn from(byte: u8) -> Self {
+        match byte {
+            0 => ResponseCode::Success,
+            1 => ResponseCode::InvalidRequest,
+            2 => ResponseCode::ServerError,
+            other => ResponseCode::Unknown(other),
+        }
+    }
+}
+
+impl From<ResponseCode> for u8 {
+    fn from(code: ResponseCode) -> Self {
+        match code {
+            ResponseCode::Success => 0,
+            ResponseCode::InvalidRequest => 1,
+            ResponseCode::ServerError => 2,
+            ResponseCode::Unknown(other) => other,
+        }
+    }
+}
+
+#[derive(Debug, PartialEq, Clone)]
+pub enum RpcResponse<C: Config> {
+    Status(StatusMessage),
+    /// Blocks in ascending slot order. Empty if the responder has none of the requested blocks.
+    BeaconBlocksByRange(Vec<BeaconBlock<C>>),
+    BeaconBlocksByRoot(Vec<BeaconBlock<C>>),
+    /// Terminates the response, possibly after some successful chunks have been dropped.
+    Error {
+        code: ResponseCode,
+        message: String,
+    },
+}
+
+impl<C: Config> RpcResponse<C> {
+    pub fn error(code: ResponseCode, message: &str) -> Self {
+        RpcResponse::Error {
+            code,
+            message: message.to_string(),
+        }
+    }
+
+    pub fn encode(&self, encoding: Encoding) -> Result<Vec<u8>, Error> {
+        let mut buf = vec![];
+        let success_chunk = |ssz_bytes: &[u8], buf: &mut Vec<u8>| {
+            buf.push(ResponseCode::Success.into());
+            encoding.encode_payload(ssz_bytes, buf)
+        };
+
+        match self {
+            RpcResponse::Status(status) => success_chunk(&status.as_ssz_bytes(), &mut buf)?,
+            RpcResponse::BeaconBlocksByRange(blocks) | RpcResponse::BeaconBlocksByRoot(blocks) => {
+                if blocks.len() > MaxRequestBlocks::to_usize() {
+                    return Err(Error::TooManyChunks {
+                        len: blocks.len(),
+                        max: MaxRequestBlocks::to_usize(),
+                    });
+                }
+                for block in blocks {
+                    success_chunk(&block.as_ssz_bytes(), &mut buf)?;
+                }
+            }
+            RpcResponse::Error { code, message } => {
+                let mut message = message.as_bytes().to_vec();
+                message.truncate(MaxErrorLen::to_usize());
+
+                buf.push((*code).into());
+                encoding.encode_payload(&message, &mut buf)?;
+            }
+        }
+
+        Ok(buf)
+    }
+
+    pub fn decode(id: ProtocolId, mut bytes: &[u8]) -> Result<Self, Error> {
+        let mut chunks = vec![];
+
+        while let Some((code, rest)) = bytes.split_first() {
+            let code = ResponseCode::from(*code);
+            let max_len = match (code, id.protocol) {
+                (ResponseCode::Success, Protocol::Status) => {
+                    max_ssz_len::<StatusMessage>(MAX_CHUNK_SIZE)
+                }
+                (ResponseCode::Success, Protocol::Goodbye) => MAX_CHUNK_SIZE,
+                (ResponseCode::Success, _) => max_ssz_len::<BeaconBlock<C>>(MAX_CHUNK_SIZE),
+                _ => MaxErrorLen::to_usize(),
+            };
+            let (payload, consumed) = id.encoding.decode_payload(rest, max_len)?;
+            bytes = &rest[consumed..];
+
+            if code != ResponseCode::Success {
+                return Ok(RpcResponse::Error {
+                    code,
+                    message: String::from_utf8_lossy(&payload).into_owned(),
+                });
+            }
+            chunks.push(payload);
+        }
+
+        match id.protocol {
+            Protocol::Status => match chunks.as_slice() {
+                [chunk] => Ok(RpcResponse::Status(decode_ssz(chunk)?)),
+                _ => Err(Error::UnexpectedChunkCount(chunks.len())),
+            },
+            Protocol::Goodbye => Err(Error::UnexpectedResponse(id.protocol)),
+            Protocol::BeaconBlocksByRange | Protocol::BeaconBlocksByRoot => {
+                if chunks.len() > MaxRequestBlocks::to_usize() {
+                    return Err(Error::TooManyChunks {
+                        len: chunks.len(),
+                        max: MaxRequestBlocks::to_usize(),
+                    });
+                }
+
+                let blocks = chunks
+                    .iter()
+                    .map(|chunk| decode_ssz(chunk))
+                    .collect::<Result<Vec<_>, _>>()?;
+
+                if id.protocol == Protocol::BeaconBlocksByRange {
+                    Ok(RpcResponse::BeaconBlocksByRange(blocks))
+                } else {
+                    Ok(RpcResponse::BeaconBlocksByRoot(blocks))
+                }
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use types::config::MinimalConfig;
+
+    fn status() -> StatusMessage {
+        StatusMessage {
+            head_fork_version: [0, 0, 0, 1],
+            finalized_root: H256::repeat_byte(1),
+            finalized_epoch: Epoch::new(2),
+            head_root: H256::repeat_byte(3),
+            head_slot: Slot::new(40),
+        }
+    }
+
+    fn id(protocol: Protocol) -> ProtocolId {
+        ProtocolId::new(protocol, Encoding::Ssz)
+    }
+
+    #[test]
+    fn protocol_ids() {
+        assert_eq!(
+            id(Protocol::BeaconBlocksByRange).to_string(),
+            "/eth2/beacon_chain/req/beacon_blocks_by_range/1/ssz"
+        );
+
+        for protocol in &Protocol::ALL {
+            assert_eq!(id(*protocol).to_string().parse(), Ok(id(*protocol)));
+        }
+
+        for bad in &[
+            "/eth2/beacon_chain/req/status/2/ssz",
+            "/eth2/beacon_chain/req/hello/1/ssz",
+            "/eth2/beacon_chain/req/status/1",
+        ] {
+            assert_eq!(
+                bad.parse::<ProtocolId>(),
+                Err(Error::UnknownProtocol(bad.to_string()))
+            );
+        }
+    }
+
+    #[test]
+    fn request_round_trips() {
+        let requests = vec![
+            RpcRequest::Status(status()),
+            RpcRequest::Goodbye(GoodbyeReason::Fault),
+            RpcRequest::Goodbye(GoodbyeReason::Unknown(42)),
+            RpcRequest::BeaconBlocksByRange(BeaconBlocksByRangeRequest {
+                head_block_root: H256::repeat_byte(4),
+                start_slot: Slot::new(8),
+                count: 16,
+                step: 1,
+            }),
+            RpcRequest::BeaconBlocksByRoot(BeaconBlocksByRootRequest {
+                block_roots: VariableList::new(vec![H256::repeat_byte(5), H256::repeat_byte(6)])
+                    .unwrap(),
+            }),
+        ];
+
+        for request in requests {
+            let bytes = request.encode(Encoding::Ssz).unwrap();
+            assert_eq!(
+                RpcRequest::decode(id(request.protocol()), &bytes),
+                Ok(request)
+            );
+        }
+    }
+
+    #[test]
+    fn status_request_layout() {
+        let bytes = RpcRequest::Status(status()).encode(Encoding::Ssz).unwrap();
+
+        // A one-byte varint prefix followed by the 84-byte container.
+        assert_eq!(bytes[0], 84);
+        assert_eq!(bytes.len(), 85);
+        assert_eq!(&bytes[1..5], &[0, 0, 0, 1]);
+    }
+
+    #[test]
+    fn request_with_trailing_bytes() {
+        let mut bytes = RpcRequest::Goodbye(GoodbyeReason::ClientShutdown)
+            .encode(Encoding::Ssz)
+            .unwrap();
+        bytes.push(0);
+
+        assert_eq!(
+            RpcRequest::decode(id(Protocol::Goodbye), &bytes),
+            Err(Error::TrailingBytes(1))
+        );
+    }
+
+    #[test]
+    fn payloads_outside_ssz_bounds() {
+        let mut bytes = RpcRequest::Status(status()).encode(Encoding::Ssz).unwrap();
+        bytes[0] = 85;
+        bytes.push(0);
+        assert_eq!(
+            RpcRequest::decode(id(Protocol::Status), &bytes),
+            Err(Error::PayloadTooLarge { len: 85, max: 84 })
+        );
+
+        assert_eq!(
+            RpcRequest::decode(id(Protocol::Status), &[2, 0, 0]),
+            Err(Error::PayloadTooSmall { len: 2, min: 84 })
+        );
+
+        // Rejected by the length prefix alone.
+        let max = max_ssz_len::<BeaconBlock<MinimalConfig>>(MAX_CHUNK_SIZE);
+        let mut bytes = vec![ResponseCode::Success.into()];
+        crate::codec::encode_varint(max as u64 + 1, &mut bytes);
+        assert_eq!(
+            RpcResponse::<MinimalConfig>::decode(id(Protocol::BeaconBlocksByRoot), &bytes),
+            Err(Error::PayloadTooLarge { len: max + 1, max })
+        );
+    }
+
+    #[test]
+    fn error_response() {
+        let response = RpcResponse::<MinimalConfig>::error(ResponseCode::ServerError, "oops");
+        let bytes = response.encode(Encoding::Ssz).unwrap();
+
+        assert_eq!(bytes, vec![2, 4, b'o', b'o', b'p', b's']);
+        assert_eq!(
+            RpcResponse::decode(id(Protocol::BeaconBlocksByRange), &bytes),
+            Ok(response)
+        );
+    }
+
+    #[test]
+    fn status_response_needs_one_chunk() {
+        let response = RpcResponse::<MinimalConfig>::Status(status());
+        let mut bytes = response.encode(Encoding::Ssz).unwrap();
+
+        assert_eq!(
+            RpcResponse::decode(id(Protocol::Status), &bytes),
+            Ok(response.clone())
+        );
+
+        bytes.extend(response.encode(Encoding::Ssz).unwrap());
+        assert_eq!(
+            RpcResponse::<MinimalConfig>::decode(id(Protocol::Status), &bytes),
+            Err(Error::UnexpectedChunkCount(2))
+        );
+        assert_eq!(
+            RpcResponse::<MinimalConfig>::decode(id(Protocol::Status), &[]),
+            Err(Error::UnexpectedChunkCount(0))
+        );
+    }
+
+    #[test]
+    fn range_request_slots() {
+        let request = BeaconBlocksByRangeRequest {
+            head_block_root: H256::zero(),
+            start_slot: Slot::new(10),
+            count: 4,
+            step: 3,
+        };
+
+        assert_eq!(request.slots().collect::<Vec<_>>(), vec![10, 13, 16, 19]);
+    }
+}
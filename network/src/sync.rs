@@ -0,0 +1,687 @@
+//! Catching up with peers.
+//!
+//! Range sync downloads blocks in batches from the peers advertising the highest head, starting
+//! just after our finalized checkpoint. Gossip blocks whose parent is unknown are held back while
+//! their ancestors are fetched by root.
+
+use std::collections::{HashMap, HashSet};
+use std::time::{Duration, Instant};
+
+use ssz_types::VariableList;
+use tree_hash::SignedRoot;
+use typenum::Unsigned;
+use types::config::Config;
+use types::primitives::{Slot, H256};
+use types::types::BeaconBlock;
+
+use crate::gossip::GossipMessage;
+use crate::network::{Network, NetworkEvent, RequestId};
+use crate::rpc::{
+    BeaconBlocksByRangeRequest, BeaconBlocksByRootRequest, GoodbyeReason, MaxRequestBlocks,
+    ResponseCode, RpcRequest, RpcResponse, StatusMessage,
+};
+use crate::transport::{PeerId, Transport};
+use crate::Error;
+
+/// The number of slots requested at once during range sync.
+pub const BATCH_SIZE: u64 = 64;
+pub const DEFAULT_REQUEST_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// Failed attempts at a single batch before range sync starts over.
+const MAX_BATCH_ATTEMPTS: usize = 5;
+/// Gossip blocks held back while their ancestors are looked up.
+const MAX_ORPHANS: usize = 64;
+/// Orphan chains longer than this are left to range sync.
+const MAX_PARENT_DEPTH: usize = 32;
+
+/// Peers start with a score of zero and are disconnected once it drops to `MIN_SCORE`.
+const MIN_SCORE: i32 = -100;
+/// For responses that are provably wrong, such as blocks that fail the state transition.
+const INVALID_RESPONSE_PENALTY: i32 = 100;
+/// For timeouts, error responses and anything else an honest peer might occasionally do.
+const FAILED_REQUEST_PENALTY: i32 = 20;
+
+/// The root blocks are known by, which leaves out the signature.
+pub fn block_root<C: Config>(block: &BeaconBlock<C>) -> H256 {
+    block.signed_root()
+}
+
+#[derive(Debug, PartialEq)]
+pub enum BlockError {
+    /// The parent must be imported first.
+    UnknownParent(H256),
+    /// The block failed the state transition.
+    Invalid(String),
+}
+
+/// The part of the beacon chain that sync drives: the state transition, fork choice and the
+/// blocks imported so far.
+pub trait BeaconChain<C: Config> {
+    /// Our head and finalized checkpoint, as advertised to peers.
+    fn status(&self) -> StatusMessage;
+
+    /// Whether `root` has been imported, whether or not it is canonical.
+    fn contains_block(&self, root: H256) -> bool;
+
+    fn block_by_root(&self, root: H256) -> Option<BeaconBlock<C>>;
+
+    /// Canonical blocks at `start_slot`, `start_slot + step`, ... for `count` slots, in ascending
+    /// slot order. Empty slots are skipped.
+    fn blocks_by_range(&self, start_slot: Slot, count: u64, step: u64) -> Vec<BeaconBlock<C>>;
+
+    /// Runs the state transition on `block` and adds it to fork choice.
+    fn import_block(&mut self, block: BeaconBlock<C>) -> Result<(), BlockError>;
+}
+
+#[derive(Debug, PartialEq, Clone, Copy)]
+pub enum SyncState {
+    /// No peer has advertised a head we are missing.
+    Synced,
+    /// Downloading the slots from `next_slot` up to and including `target_slot`.
+    Syncing { next_slot: Slot, target_slot: Slot },
+}
+
+struct PeerInfo {
+    status: Option<StatusMessage>,
+    score: i32,
+}
+
+#[derive(Debug, Clone, Copy)]
+enum Request {
+    Status,
+    Batch { start_slot: Slot, count: u64 },
+    ParentLookup(H256),
+}
+
+struct RangeSync {
+    /// The first slot after our finalized block when range sync started.
+    start_slot: Slot,
+    next_slot: Slot,
+    target_slot: Slot,
+    target_root: H256,
+    /// Whether a request for the batch starting at `next_slot` is outstanding.
+    in_flight: bool,
+    /// Consecutive failed batches.
+    attempts: usize,
+    /// Peers that failed a batch. They are not asked again until range sync starts over.
+    failed_peers: HashSet<PeerId>,
+}
+
+/// Keeps our chain in step with connected peers, and answers their sync requests from it.
+pub struct SyncManager<C: Config, T: Transport, B: BeaconChain<C>> {
+    network: Network<C, T>,
+    chain: B,
+    peers: HashMap<PeerId, PeerInfo>,
+    requests: HashMap<(PeerId, RequestId), (Request, Instant)>,
+    request_timeout: Duration,
+    range: Option<RangeSync>,
+    /// Blocks waiting for their parent and the peers that sent them, by block root.
+    orphans: HashMap<H256, (PeerId, BeaconBlock<C>)>,
+    /// Roots with an outstanding parent lookup.
+    lookups: HashSet<H256>,
+}
+
+impl<C: Config, T: Transport, B: BeaconChain<C>> SyncManager<C, T, B> {
+    pub fn new(network: Network<C, T>, chain: B) -> Self {
+        Self {
+            network,
+            chain,
+            peers: HashMap::new(),
+            requests: HashMap::new(),
+            request_timeout: DEFAULT_REQUEST_TIMEOUT,
+            range: None,
+            orphans: HashMap::new(),
+            lookups: HashSet::new(),
+        }
+    }
+
+    pub fn with_request_timeout(mut self, timeout: Duration) -> Self {
+        self.request_timeout = timeout;
+        self
+    }
+
+    pub fn network(&self) -> &Network<C, T> {
+        &self.network
+    }
+
+    pub fn chain(&self) -> &B {
+        &self.chain
+    }
+
+    pub fn state(&self) -> SyncState {
+        match &self.range {
+            Some(range) => SyncState::Syncing {
+                next_slot: range.next_slot,
+                target_slot: range.target_slot,
+            },
+            None => SyncState::Synced,
+        }
+    }
+
+    /// The peers sync is tracking, including those that have not answered `Status` yet.
+    pub fn peers(&self) -> Vec<PeerId> {
+        let mut peers = self.peers.keys().cloned().collect::<Vec<_>>();
+        peers.sort();
+        peers
+    }
+
+    pub fn peer_score(&self, peer: &PeerId) -> Option<i32> {
+        self.peers.get(peer).map(|info| info.score)
+    }
+
+    pub fn peer_status(&self, peer: &PeerId) -> Option<&StatusMessage> {
+        self.peers.get(peer).and_then(|info| info.status.as_ref())
+    }
+
+    /// Starts tracking `peer` by asking for its status.
+    pub fn add_peer(&mut self, peer: &PeerId) {
+        self.peers.entry(peer.clone()).or_insert(PeerInfo {
+            status: None,
+            score: 0,
+        });
+        self.send_request(
+            peer,
+            RpcRequest::Status(self.chain.status()),
+            Request::Status,
+        );
+    }
+
+    /// Handles the next network event arriving within `timeout`, then retries requests that
+    /// timed out and schedules more downloads.
+    ///
+    /// Events sync has no use for, such as attestations, are handed back to the caller.
+    pub fn poll(&mut self, timeout: Duration) -> Result<Option<NetworkEvent<C>>, Error> {
+        let unhandled = match self.network.poll(timeout)? {
+            Some(event) => self.handle_event(event),
+            None => None,
+        };
+
+        self.expire_requests();
+        self.schedule_range_sync();
+
+        Ok(unhandled)
+    }
+
+    /// Imports a block received over gossip, first looking up its ancestors if necessary.
+    pub fn on_gossip_block(&mut self, peer: &PeerId, block: BeaconBlock<C>) {
+        let root = block_root(&block);
+        if self.chain.contains_block(root) || self.orphans.contains_key(&root) {
+            return;
+        }
+
+        if self.chain.contains_block(block.parent_root) {
+            self.import(peer, block, root);
+        } else {
+            self.add_orphan(peer, block, root);
+        }
+    }
+
+    fn handle_event(&mut self, event: NetworkEvent<C>) -> Option<NetworkEvent<C>> {
+        match event {
+            NetworkEvent::Gossip {
+                peer,
+                message: GossipMessage::BeaconBlock(block),
+            } => self.on_gossip_block(&peer, *block),
+            NetworkEvent::Request { peer, id, request } => self.on_request(peer, id, request),
+            NetworkEvent::Response { peer, id, response } => {
+                match self.requests.remove(&(peer.clone(), id)) {
+                    Some((request, _)) => self.on_response(peer, request, response),
+                    None => return Some(NetworkEvent::Response { peer, id, response }),
+                }
+            }
+            // A response that failed to decode is retried once its request times out.
+            NetworkEvent::InvalidMessage { peer, .. } => {
+                self.penalize(&peer, FAILED_REQUEST_PENALTY)
+            }
+            other => return Some(other),
+        }
+        None
+    }
+
+    fn on_request(&mut self, peer: PeerId, id: RequestId, request: RpcRequest) {
+        let response = match request {
+            RpcRequest::Status(status) => {
+                self.peers.entry(peer.clone()).or_insert(PeerInfo {
+                    status: None,
+                    score: 0,
+                });
+                let response = RpcResponse::Status(self.chain.status());
+                // Answer before deciding whether to keep the peer, so it can make the same call.
+                let _ = self.network.send_response(&peer, id, &response);
+                self.on_status(&peer, status);
+                return;
+            }
+            RpcRequest::Goodbye(_) => {
+                self.remove_peer(&peer);
+                return;
+            }
+            RpcRequest::BeaconBlocksByRange(request) => {
+                if request.step == 0 {
+                    RpcResponse::error(ResponseCode::InvalidRequest, "step must not be zero")
+                } else {
+                    let count = request.count.min(MaxRequestBlocks::to_u64());
+                    let blocks =
+                        self.chain
+                            .blocks_by_range(request.start_slot, count, request.step);
+                    RpcResponse::BeaconBlocksByRange(blocks)
+                }
+            }
+            RpcRequest::BeaconBlocksByRoot(request) => RpcResponse::BeaconBlocksByRoot(
+                request
+                    .block_roots
+                    .iter()
+                    .filter_map(|root| self.chain.block_by_root(*root))
+                    .collect(),
+            ),
+        };
+
+        if self.network.send_response(&peer, id, &response).is_err() {
+            self.remove_peer(&peer);
+        }
+    }
+
+    fn on_response(&mut self, peer: PeerId, request: Request, response: RpcResponse<C>) {
+        match (request, response) {
+            (Request::Status, RpcResponse::Status(status)) => self.on_status(&peer, status),
+            (Request::Batch { start_slot, count }, RpcResponse::BeaconBlocksByRange(blocks)) => {
+                self.on_batch(&peer, start_slot, count, blocks)
+            }
+            (Request::ParentLookup(root), RpcResponse::BeaconBlocksByRoot(blocks)) => {
+                self.on_parent_lookup(&peer, root, blocks)
+            }
+            (request, _) => self.fail_request(&peer, request, FAILED_REQUEST_PENALTY),
+        }
+    }
+
+    fn on_status(&mut self, peer: &PeerId, status: StatusMessage) {
+        let ours = self.chain.status();
+        let same_fork = status.head_fork_version == ours.head_fork_version;
+        let same_finalized = status.finalized_epoch != ours.finalized_epoch
+            || status.finalized_root == ours.finalized_root;
+
+        if !same_fork || !same_finalized {
+            self.goodbye(peer, GoodbyeReason::IrrelevantNetwork);
+            return;
+        }
+
+        if let Some(info) = self.peers.get_mut(peer) {
+            info.status = Some(status);
+        }
+    }
+
+    fn on_batch(
+        &mut self,
+        peer: &PeerId,
+        start_slot: Slot,
+        count: u64,
+        blocks: Vec<BeaconBlock<C>>,
+    ) {
+        let end_slot = start_slot.saturating_add(count);
+        let batch = Request::Batch { start_slot, count };
+
+        let mut previous_slot = None;
+        for block in &blocks {
+            let in_order = match previous_slot {
+                Some(previous) => block.slot > previous,
+                None => true,
+            };
+            if !in_order || block.slot < start_slot || block.slot >= end_slot {
+                self.fail_request(peer, batch, INVALID_RESPONSE_PENALTY);
+                return;
+            }
+            previous_slot = Some(block.slot);
+        }
+
+        for block in blocks {
+            let root = block_root(&block);
+            if self.chain.contains_block(root) {
+                continue;
+            }
+            // Batches start after our finalized block, so every parent should be known by now.
+            if self.chain.import_block(block).is_err() {
+                self.fail_request(peer, batch, INVALID_RESPONSE_PENALTY);
+                return;
+            }
+            self.import_orphans(root);
+        }
+
+        let finished = match &mut self.range {
+            Some(range) if range.next_slot == start_slot => {
+                range.next_slot = end_slot;
+                range.in_flight = false;
+                range.attempts = 0;
+                range.next_slot > range.target_slot
+            }
+            _ => false,
+        };
+
+        if finished {
+            self.finish_range_sync();
+        }
+    }
+
+    fn on_parent_lookup(&mut self, peer: &PeerId, root: H256, blocks: Vec<BeaconBlock<C>>) {
+        self.lookups.remove(&root);
+
+        let position = blocks.iter().position(|block| block_root(block) == root);
+        let block = match position {
+            Some(position) => blocks
+                .into_iter()
+                .nth(position)
+                .expect("position is in bounds"),
+            None => {
+                // Honest peers have the ancestors of the blocks they gossip, so neither an empty
+                // response nor the wrong block is expected.
+                let penalty = if blocks.is_empty() {
+                    FAILED_REQUEST_PENALTY
+                } else {
+                    INVALID_RESPONSE_PENALTY
+                };
+                self.penalize(peer, penalty);
+                self.drop_orphans(root);
+                return;
+            }
+        };
+
+        if self.chain.contains_block(block.parent_root) {
+            self.import(peer, block, root);
+        } else {
+            self.add_orphan(peer, block, root);
+        }
+    }
+
+    /// Imports a block whose parent is known, followed by any orphans waiting for it.
+    fn import(&mut self, peer: &PeerId, block: BeaconBlock<C>, root: H256) {
+        match self.chain.import_block(block) {
+            Ok(()) => self.import_orphans(root),
+            Err(_) => {
+                self.penalize(peer, INVALID_RESPONSE_PENALTY);
+                self.drop_orphans(root);
+            }
+        }
+    }
+
+    fn import_orphans(&mut self, parent: H256) {
+        let mut parents = vec![parent];
+
+        while let Some(parent) = parents.pop() {
+            for root in self.children(parent) {
+                let (peer, block) = self.orphans.remove(&root).expect("child is an orphan");
+                match self.chain.import_block(block) {
+                    Ok(()) => parents.push(root),
+                    Err(_) => {
+                        self.penalize(&peer, INVALID_RESPONSE_PENALTY);
+                        self.drop_orphans(root);
+                    }
+                }
+            }
+        }
+    }
+
+    fn add_orphan(&mut self, peer: &PeerId, block: BeaconBlock<C>, root: H256) {
+        let parent = block.parent_root;
+
+        let mut depth = 1;
+        let mut ancestor = parent;
+        while let Some((_, orphan)) = self.orphans.get(&ancestor) {
+            depth += 1;
+            ancestor = orphan.parent_root;
+        }
+
+        if self.orphans.len() >= MAX_ORPHANS || depth > MAX_PARENT_DEPTH {
+            self.drop_orphans(root);
+            return;
+        }
+
+        self.orphans.insert(root, (peer.clone(), block));
+
+        if !self.orphans.contains_key(&parent) && self.lookups.insert(parent) {
+            let request = BeaconBlocksByRootRequest {
+                block_roots: VariableList::from(vec![parent]),
+            };
+            self.send_request(
+                peer,
+                RpcRequest::BeaconBlocksByRoot(request),
+                Request::ParentLookup(parent),
+            );
+        }
+    }
+
+    /// Forgets every orphan descended from `root`.
+    fn drop_orphans(&mut self, root: H256) {
+        let mut parents = vec![root];
+        while let Some(parent) = parents.pop() {
+            for child in self.children(parent) {
+                self.orphans.remove(&child);
+                parents.push(child);
+            }
+        }
+    }
+
+    fn children(&self, parent: H256) -> Vec<H256> {
+        self.orphans
+            .iter()
+            .filter(|(_, (_, block))| block.parent_root == parent)
+            .map(|(root, _)| *root)
+            .collect()
+    }
+
+    /// Starts range sync towards the best advertised head we are missing, or requests the next
+    /// batch of the current one.
+    fn schedule_range_sync(&mut self) {
+        if self.range.is_none() {
+            let ours = self.chain.status();
+            let target = self
+                .peers
+                .values()
+                .filter_map(|info| info.status.as_ref())
+                .filter(|status| {
+                    status.head_slot > ours.head_slot
+                        && !self.chain.contains_block(status.head_root)
+                })
+                .max_by_key(|status| status.head_slot)
+                .map(|status| (status.head_slot, status.head_root));
+
+            if let Some((target_slot, target_root)) = target {
+                let finalized_slot = ours.finalized_epoch.start_slot::<C>();
+                self.range = Some(RangeSync {
+                    start_slot: finalized_slot + 1,
+                    next_slot: finalized_slot + 1,
+                    target_slot,
+                    target_root,
+                    in_flight: false,
+                    attempts: 0,
+                    failed_peers: HashSet::new(),
+                });
+            }
+        }
+
+        let (start_slot, count) = match &self.range {
+            Some(range) if !range.in_flight => (
+                range.next_slot,
+                BATCH_SIZE.min((range.target_slot - range.next_slot).as_u64() + 1),
+            ),
+            _ => return,
+        };
+
+        match self.batch_peer(start_slot) {
+            Some((peer, head_block_root)) => {
+                if let Some(range) = &mut self.range {
+                    range.in_flight = true;
+                }
+                let request = BeaconBlocksByRangeRequest {
+                    head_block_root,
+                    start_slot,
+                    count,
+                    step: 1,
+                };
+                self.send_request(
+                    &peer,
+                    RpcRequest::BeaconBlocksByRange(request),
+                    Request::Batch { start_slot, count },
+                );
+            }
+            // Nobody left to ask; start over once a peer advertises a useful head again.
+            None => self.range = None,
+        }
+    }
+
+    /// The peer with the highest head that covers `start_slot` and has not failed a batch.
+    fn batch_peer(&self, start_slot: Slot) -> Option<(PeerId, H256)> {
+        let failed_peers = self.range.as_ref().map(|range| &range.failed_peers)?;
+
+        let mut candidates = self
+            .peers
+            .iter()
+            .filter(|(peer, _)| !failed_peers.contains(*peer))
+            .filter_map(|(peer, info)| info.status.as_ref().map(|status| (peer, status)))
+            .filter(|(_, status)| status.head_slot >= start_slot)
+            .collect::<Vec<_>>();
+        // Highest head first, then by peer ID so the choice does not depend on hash order.
+        candidates.sort_by(|(a, a_status), (b, b_status)| {
+            b_status.head_slot.cmp(&a_status.head_slot).then(a.cmp(b))
+        });
+
+        candidates
+            .first()
+            .map(|(peer, status)| ((*peer).clone(), status.head_root))
+    }
+
+    fn finish_range_sync(&mut self) {
+        let range = match self.range.take() {
+            Some(range) => range,
+            None => return,
+        };
+
+        if self.chain.contains_block(range.target_root) {
+            return;
+        }
+
+        // The head that started range sync never arrived. Either its peers reorged away from it
+        // or they advertised a head they do not have; ask again rather than chase it forever.
+        let advertisers = self
+            .peers
+            .iter()
+            .filter(|(_, info)| match &info.status {
+                Some(status) => status.head_root == range.target_root,
+                None => false,
+            })
+            .map(|(peer, _)| peer.clone())
+            .collect::<Vec<_>>();
+
+        for peer in advertisers {
+            if let Some(info) = self.peers.get_mut(&peer) {
+                info.status = None;
+            }
+            self.penalize(&peer, FAILED_REQUEST_PENALTY);
+            if self.peers.contains_key(&peer) {
+                self.send_request(
+                    &peer,
+                    RpcRequest::Status(self.chain.status()),
+                    Request::Status,
+                );
+            }
+        }
+    }
+
+    fn expire_requests(&mut self) {
+        let now = Instant::now();
+        let timeout = self.request_timeout;
+        let expired = self
+            .requests
+            .iter()
+            .filter(|(_, (_, sent))| now.duration_since(*sent) >= timeout)
+            .map(|(key, _)| key.clone())
+            .collect::<Vec<_>>();
+
+        for (peer, id) in expired {
+            if let Some((request, _)) = self.requests.remove(&(peer.clone(), id)) {
+                self.network.cancel_request(&peer, id);
+                self.fail_request(&peer, request, FAILED_REQUEST_PENALTY);
+            }
+        }
+    }
+
+    /// Penalizes `peer` and makes the request available to retry.
+    fn fail_request(&mut self, peer: &PeerId, request: Request, penalty: i32) {
+        match request {
+            Request::Status => {}
+            Request::Batch { start_slot, .. } => {
+                let give_up = match &mut self.range {
+                    Some(range) if range.next_slot == start_slot => {
+                        // The next peer may be on a different fork from the blocks imported so
+                        // far, so start again from our finalized block.
+                        range.next_slot = range.start_slot;
+                        range.in_flight = false;
+                        range.attempts += 1;
+                        range.failed_peers.insert(peer.clone());
+                        range.attempts >= MAX_BATCH_ATTEMPTS
+                    }
+                    _ => false,
+                };
+                if give_up {
+                    self.range = None;
+                }
+            }
+            Request::ParentLookup(root) => {
+                self.lookups.remove(&root);
+                self.drop_orphans(root);
+            }
+        }
+
+        self.penalize(peer, penalty);
+    }
+
+    fn penalize(&mut self, peer: &PeerId, penalty: i32) {
+        let banned = match self.peers.get_mut(peer) {
+            Some(info) => {
+                info.score -= penalty;
+                info.score <= MIN_SCORE
+            }
+            None => false,
+        };
+
+        if banned {
+            self.goodbye(peer, GoodbyeReason::Fault);
+        }
+    }
+
+    fn goodbye(&mut self, peer: &PeerId, reason: GoodbyeReason) {
+        // The peer is dropped whether or not it hears about it.
+        let _ = self
+            .network
+            .send_request(peer, &RpcRequest::Goodbye(reason));
+        self.remove_peer(peer);
+    }
+
+    fn remove_peer(&mut self, peer: &PeerId) {
+        self.peers.remove(peer);
+        self.network.disconnect(peer);
+
+        let requests = self
+            .requests
+            .keys()
+            .filter(|(requested, _)| requested == peer)
+            .cloned()
+            .collect::<Vec<_>>();
+        for key in requests {
+            if let Some((request, _)) = self.requests.remove(&key) {
+                // The peer is already gone, so there is no one left to penalize.
+                self.fail_request(peer, request, 0);
+            }
+        }
+    }
+
+    fn send_request(&mut self, peer: &PeerId, request: RpcRequest, kind: Request) {
+        match self.network.send_request(peer, &request) {
+            Ok(id) => {
+                self.requests
+                    .insert((peer.clone(), id), (kind, Instant::now()));
+            }
+            Err(_) => {
+                self.fail_request(peer, kind, 0);
+                self.remove_peer(peer);
+            }
+        }
+    }
+}
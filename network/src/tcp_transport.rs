@@ -0,0 +1,253 @@
+//! Length-prefixed frames over plain TCP connections.
+//!
+//! Each side sends its peer ID when a connection opens. After that, every frame is preceded by
+//! its length as a little-endian `u32`.
+
+use std::collections::HashMap;
+use std::io::{self, Read, Write};
+use std::net::{Shutdown, SocketAddr, TcpListener, TcpStream, ToSocketAddrs};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::mpsc::{channel, Receiver, RecvTimeoutError, Sender};
+use std::sync::{Arc, Mutex};
+use std::thread::{self, JoinHandle};
+use std::time::Duration;
+
+use crate::transport::{Frame, PeerId, Transport, MAX_FRAME_LEN};
+use crate::Error;
+
+/// Peer IDs are short; anything longer is treated as garbage.
+const MAX_PEER_ID_LEN: usize = 256;
+const HANDSHAKE_TIMEOUT: Duration = Duration::from_secs(5);
+
+type Connections = Arc<Mutex<HashMap<PeerId, TcpStream>>>;
+
+pub struct TcpTransport {
+    peer_id: PeerId,
+    local_addr: SocketAddr,
+    connections: Connections,
+    sender: Sender<(PeerId, Frame)>,
+    receiver: Mutex<Receiver<(PeerId, Frame)>>,
+    shutdown: Arc<AtomicBool>,
+    listener: Option<JoinHandle<()>>,
+}
+
+impl TcpTransport {
+    /// Listens for incoming connections on `address`.
+    pub fn bind<A: ToSocketAddrs>(peer_id: &str, address: A) -> Result<Self, Error> {
+        let listener = TcpListener::bind(address)?;
+        let local_addr = listener.local_addr()?;
+        let peer_id = PeerId::new(peer_id);
+        let connections = Connections::default();
+        let (sender, receiver) = channel();
+        let shutdown = Arc::new(AtomicBool::new(false));
+
+        let handle = {
+            let peer_id = peer_id.clone();
+            let connections = Arc::clone(&connections);
+            let sender = sender.clone();
+            let shutdown = Arc::clone(&shutdown);
+
+            thread::spawn(move || {
+                for stream in listener.incoming() {
+                    if shutdown.load(Ordering::SeqCst) {
+                        break;
+                    }
+                    if let Ok(stream) = stream {
+                        // A failed handshake only affects that connection.
+                        let _ = accept(stream, &peer_id, &connections, &sender);
+                    }
+                }
+            })
+        };
+
+        Ok(Self {
+            peer_id,
+            local_addr,
+            connections,
+            sender,
+            receiver: Mutex::new(receiver),
+            shutdown,
+            listener: Some(handle),
+        })
+    }
+
+    pub fn local_addr(&self) -> SocketAddr {
+        self.local_addr
+    }
+
+    /// Opens a connection to the node listening on `address`, returning its peer ID.
+    pub fn connect<A: ToSocketAddrs>(&self, address: A) -> Result<PeerId, Error> {
+        let stream = TcpStream::connect(address)?;
+        accept(stream, &self.peer_id, &self.connections, &self.sender)
+    }
+}
+
+/// Exchanges peer IDs over `stream` and starts reading frames from it.
+fn accept(
+    mut stream: TcpStream,
+    local_peer_id: &PeerId,
+    connections: &Connections,
+    sender: &Sender<(PeerId, Frame)>,
+) -> Result<PeerId, Error> {
+    stream.set_read_timeout(Some(HANDSHAKE_TIMEOUT))?;
+    write_message(&mut stream, local_peer_id.as_str().as_bytes())?;
+    let remote = read_message(&mut stream, MAX_PEER_ID_LEN)?;
+    let remote = String::from_utf8(remote)
+        .map(|id| PeerId::new(&id))
+        .map_err(|_| Error::InvalidFrame("peer ID is not UTF-8".to_string()))?;
+    stream.set_read_timeout(None)?;
+
+    let reader = stream.try_clone()?;
+    let previous = connections
+        .lock()
+        .expect("tcp connections lock poisoned")
+        .insert(remote.clone(), stream);
+    if let Some(previous) = previous {
+        let _ = previous.shutdown(Shutdown::Both);
+    }
+
+    let connections = Arc::clone(connections);
+    let sender = sender.clone();
+    let peer = remote.clone();
+    thread::spawn(move || read_frames(reader, peer, connections, sender));
+
+    Ok(remote)
+}
+
+fn read_frames(
+    mut stream: TcpStream,
+    peer: PeerId,
+    connections: Connections,
+    sender: Sender<(PeerId, Frame)>,
+) {
+    loop {
+        let frame =
+            read_message(&mut stream, MAX_FRAME_LEN).and_then(|bytes| Frame::from_bytes(&bytes));
+
+        match frame {
+            Ok(frame) => {
+                if sender.send((peer.clone(), frame)).is_err() {
+                    break;
+                }
+            }
+            // The peer closed the connection or broke the framing; either way it is gone.
+            Err(_) => break,
+        }
+    }
+
+    let mut connections = connections.lock().expect("tcp connections lock poisoned");
+    // Only forget the peer if it has not reconnected on a newer stream in the meantime.
+    let addresses = |s: &TcpStream| Some((s.local_addr().ok()?, s.peer_addr().ok()?));
+    let is_current = match connections.get(&peer).and_then(addresses) {
+        Some(current) => Some(current) == addresses(&stream),
+        None => false,
+    };
+    if is_current {
+        connections.remove(&peer);
+    }
+}
+
+fn write_message(stream: &mut TcpStream, bytes: &[u8]) -> Result<(), Error> {
+    let mut message = Vec::with_capacity(4 + bytes.len());
+    message.extend_from_slice(&(bytes.len() as u32).to_le_bytes());
+    message.extend_from_slice(bytes);
+    stream.write_all(&message)?;
+    Ok(())
+}
+
+/// Reads a length-prefixed message, refusing lengths above `max_len` before reading the body.
+fn read_message(stream: &mut TcpStream, max_len: usize) -> Result<Vec<u8>, Error> {
+    let mut len = [0; 4];
+    stream.read_exact(&mut len)?;
+    let len = u32::from_le_bytes(len) as usize;
+
+    if len > max_len {
+        return Err(Error::PayloadTooLarge { len, max: max_len });
+    }
+
+    // Grow the buffer as bytes arrive rather than trusting the prefix with an allocation.
+    let mut bytes = vec![];
+    Read::by_ref(stream)
+        .take(len as u64)
+        .read_to_end(&mut bytes)?;
+    if bytes.len() != len {
+        return Err(io::Error::from(io::ErrorKind::UnexpectedEof).into());
+    }
+    Ok(bytes)
+}
+
+impl Transport for TcpTransport {
+    fn local_peer_id(&self) -> &PeerId {
+        &self.peer_id
+    }
+
+    fn peers(&self) -> Vec<PeerId> {
+        let mut peers = self
+            .connections
+            .lock()
+            .expect("tcp connections lock poisoned")
+            .keys()
+            .cloned()
+            .collect::<Vec<_>>();
+        peers.sort();
+        peers
+    }
+
+    fn send(&self, peer: &PeerId, frame: Frame) -> Result<(), Error> {
+        let bytes = frame.to_bytes();
+        if bytes.len() > MAX_FRAME_LEN {
+            return Err(Error::PayloadTooLarge {
+                len: bytes.len(),
+                max: MAX_FRAME_LEN,
+            });
+        }
+
+        let mut connections = self
+            .connections
+            .lock()
+            .expect("tcp connections lock poisoned");
+        let stream = connections
+            .get_mut(peer)
+            .ok_or_else(|| Error::UnknownPeer(peer.clone()))?;
+
+        write_message(stream, &bytes)
+    }
+
+    fn recv_timeout(&self, timeout: Duration) -> Result<Option<(PeerId, Frame)>, Error> {
+        let receiver = self.receiver.lock().expect("tcp receiver lock poisoned");
+        match receiver.recv_timeout(timeout) {
+            Ok(message) => Ok(Some(message)),
+            Err(RecvTimeoutError::Timeout) => Ok(None),
+            // Unreachable while `self` holds a sender.
+            Err(RecvTimeoutError::Disconnected) => Err(Error::TransportClosed),
+        }
+    }
+
+    fn disconnect(&self, peer: &PeerId) {
+        let stream = self
+            .connections
+            .lock()
+            .expect("tcp connections lock poisoned")
+            .remove(peer);
+        if let Some(stream) = stream {
+            let _ = stream.shutdown(Shutdown::Both);
+        }
+    }
+}
+
+impl Drop for TcpTransport {
+    fn drop(&mut self) {
+        self.shutdown.store(true, Ordering::SeqCst);
+        // Wake the accept loop so it can observe the flag.
+        let _ = TcpStream::connect(self.local_addr);
+        if let Some(handle) = self.listener.take() {
+            let _ = handle.join();
+        }
+
+        if let Ok(connections) = self.connections.lock() {
+            for stream in connections.values() {
+                let _ = stream.shutdown(Shutdown::Both);
+            }
+        }
+    }
+}
@@ -0,0 +1,187 @@
+//! The byte-level connection between nodes.
+//!
+//! `Network` only needs to send framed messages to known peers and receive them back, so the
+//! underlying connection can be swapped between in-memory channels for tests and TCP.
+
+use std::fmt;
+use std::time::Duration;
+
+use crate::codec::{decode_varint, encode_varint};
+use crate::Error;
+
+/// The largest frame a stream transport will accept. Responders must keep responses below it.
+pub const MAX_FRAME_LEN: usize = 32 << 20;
+
+#[derive(Debug, PartialEq, Eq, Hash, PartialOrd, Ord, Clone)]
+pub struct PeerId(String);
+
+impl PeerId {
+    pub fn new(id: &str) -> Self {
+        PeerId(id.to_string())
+    }
+
+    pub fn as_str(&self) -> &str {
+        &self.0
+    }
+}
+
+impl fmt::Display for PeerId {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str(&self.0)
+    }
+}
+
+/// A message exchanged between two nodes.
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub enum Frame {
+    Gossip {
+        topic: String,
+        data: Vec<u8>,
+    },
+    Request {
+        id: u64,
+        protocol: String,
+        data: Vec<u8>,
+    },
+    /// The complete response to the request `id` made by the receiver.
+    Response {
+        id: u64,
+        data: Vec<u8>,
+    },
+}
+
+impl Frame {
+    const GOSSIP: u8 = 0;
+    const REQUEST: u8 = 1;
+    const RESPONSE: u8 = 2;
+
+    /// Serializes the frame for stream transports.
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let mut buf = vec![];
+        let (tag, id, name, data) = match self {
+            Frame::Gossip { topic, data } => (Self::GOSSIP, 0, topic.as_str(), data),
+            Frame::Request { id, protocol, data } => (Self::REQUEST, *id, protocol.as_str(), data),
+            Frame::Response { id, data } => (Self::RESPONSE, *id, "", data),
+        };
+
+        buf.push(tag);
+        encode_varint(id, &mut buf);
+        encode_varint(name.len() as u64, &mut buf);
+        buf.extend_from_slice(name.as_bytes());
+        encode_varint(data.len() as u64, &mut buf);
+        buf.extend_from_slice(data);
+        buf
+    }
+
+    pub fn from_bytes(bytes: &[u8]) -> Result<Self, Error> {
+        let (tag, rest) = bytes
+            .split_first()
+            .ok_or_else(|| Error::InvalidFrame("empty frame".to_string()))?;
+
+        let (id, rest) = read_varint(rest)?;
+        let (name, rest) = read_bytes(rest)?;
+        let (data, rest) = read_bytes(rest)?;
+
+        if !rest.is_empty() {
+            return Err(Error::InvalidFrame(format!(
+                "{} trailing bytes",
+                rest.len()
+            )));
+        }
+
+        let name = String::from_utf8(name.to_vec())
+            .map_err(|_| Error::InvalidFrame("name is not UTF-8".to_string()))?;
+        let data = data.to_vec();
+
+        match *tag {
+            Self::GOSSIP => Ok(Frame::Gossip { topic: name, data }),
+            Self::REQUEST => Ok(Frame::Request {
+                id,
+                protocol: name,
+                data,
+            }),
+            Self::RESPONSE => Ok(Frame::Response { id, data }),
+            other => Err(Error::InvalidFrame(format!("unknown tag {}", other))),
+        }
+    }
+}
+
+fn read_varint(bytes: &[u8]) -> Result<(u64, &[u8]), Error> {
+    let (value, len) = decode_varint(bytes)?;
+    Ok((value, &bytes[len..]))
+}
+
+fn read_bytes(bytes: &[u8]) -> Result<(&[u8], &[u8]), Error> {
+    let (len, rest) = read_varint(bytes)?;
+    if len > rest.len() as u64 {
+        return Err(Error::InvalidFrame(format!(
+            "length {} exceeds the {} remaining bytes",
+            len,
+            rest.len()
+        )));
+    }
+    Ok(rest.split_at(len as usize))
+}
+
+/// A connection to a set of peers.
+pub trait Transport: Send {
+    fn local_peer_id(&self) -> &PeerId;
+
+    /// The peers `send` can currently reach.
+    fn peers(&self) -> Vec<PeerId>;
+
+    fn send(&self, peer: &PeerId, frame: Frame) -> Result<(), Error>;
+
+    /// Waits up to `timeout` for the next frame from any peer.
+    fn recv_timeout(&self, timeout: Duration) -> Result<Option<(PeerId, Frame)>, Error>;
+
+    fn disconnect(&self, peer: &PeerId);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn frame_round_trips() {
+        let frames = vec![
+            Frame::Gossip {
+                topic: "/eth2/beacon_block/ssz".to_string(),
+                data: vec![1, 2, 3],
+            },
+            Frame::Request {
+                id: 300,
+                protocol: "/eth2/beacon_chain/req/status/1/ssz".to_string(),
+                data: vec![],
+            },
+            Frame::Response {
+                id: u64::max_value(),
+                data: vec![0; 200],
+            },
+        ];
+
+        for frame in frames {
+            assert_eq!(Frame::from_bytes(&frame.to_bytes()), Ok(frame));
+        }
+    }
+
+    #[test]
+    fn invalid_frames() {
+        let bytes = Frame::Response {
+            id: 1,
+            data: vec![1, 2],
+        }
+        .to_bytes();
+
+        assert!(Frame::from_bytes(&[]).is_err());
+        assert!(Frame::from_bytes(&bytes[..bytes.len() - 1]).is_err());
+        assert!(Frame::from_bytes(&[bytes.clone(), vec![0]].concat()).is_err());
+
+        let mut unknown_tag = bytes;
+        unknown_tag[0] = 3;
+        assert_eq!(
+            Frame::from_bytes(&unknown_tag),
+            Err(Error::InvalidFrame("unknown tag 3".to_string()))
+        );
+    }
+}
@@ -0,0 +1,344 @@
+use std::collections::HashMap;
+use std::time::{Duration, Instant};
+
+use network::rpc::StatusMessage;
+use network::sync::block_root;
+use network::{
+    BeaconChain, BlockError, Encoding, Frame, GossipMessage, MemoryHub, MemoryTransport, Network,
+    PeerId, ProtocolId, RpcResponse, SyncManager, SyncState, Transport,
+};
+use ssz_types::VariableList;
+use types::config::MinimalConfig;
+use types::primitives::{Epoch, Signature, Slot, Version, H256};
+use types::types::{BeaconBlock, BeaconBlockBody, Eth1Data};
+
+type C = MinimalConfig;
+type Node = SyncManager<C, MemoryTransport, TestChain>;
+
+const TIMEOUT: Duration = Duration::from_secs(10);
+
+/// Stands in for the state transition: a block is valid if its state root is derived from its
+/// slot. Fork choice picks the highest slot.
+fn expected_state_root(slot: Slot) -> H256 {
+    H256::from_low_u64_be(slot.as_u64())
+}
+
+fn block(slot: Slot, parent_root: H256, state_root: H256) -> BeaconBlock<C> {
+    BeaconBlock {
+        slot,
+        parent_root,
+        state_root,
+        body: BeaconBlockBody {
+            randao_reveal: Signature::empty_signature(),
+            eth1_data: Eth1Data::default(),
+            graffiti: [0; 32],
+            proposer_slashings: VariableList::default(),
+            attester_slashings: VariableList::default(),
+            attestations: VariableList::default(),
+            deposits: VariableList::default(),
+            voluntary_exits: VariableList::default(),
+            transfers: VariableList::default(),
+        },
+        signature: Signature::empty_signature(),
+    }
+}
+
+struct TestChain {
+    fork_version: Version,
+    genesis_root: H256,
+    head_root: H256,
+    blocks: HashMap<H256, BeaconBlock<C>>,
+}
+
+impl TestChain {
+    fn genesis() -> Self {
+        let genesis = block(
+            Slot::new(0),
+            H256::zero(),
+            expected_state_root(Slot::new(0)),
+        );
+        let genesis_root = block_root(&genesis);
+
+        let mut blocks = HashMap::new();
+        blocks.insert(genesis_root, genesis);
+
+        Self {
+            fork_version: [0; 4],
+            genesis_root,
+            head_root: genesis_root,
+            blocks,
+        }
+    }
+
+    /// Extends the head with one block per slot up to `head_slot`, leaving out `skipped` slots.
+    /// Blocks from `invalid_from` onwards fail the state transition but are stored regardless.
+    fn extend(mut self, head_slot: u64, skipped: &[u64], invalid_from: Option<u64>) -> Self {
+        for slot in self.head().slot.as_u64() + 1..=head_slot {
+            if skipped.contains(&slot) {
+                continue;
+            }
+            let slot = Slot::new(slot);
+            let state_root = match invalid_from {
+                Some(invalid) if slot >= invalid => H256::repeat_byte(0xff),
+                _ => expected_state_root(slot),
+            };
+            let block = block(slot, self.head_root, state_root);
+            self.head_root = block_root(&block);
+            self.blocks.insert(self.head_root, block);
+        }
+        self
+    }
+
+    fn head(&self) -> &BeaconBlock<C> {
+        &self.blocks[&self.head_root]
+    }
+
+    fn canonical_chain(&self) -> Vec<BeaconBlock<C>> {
+        let mut chain = vec![];
+        let mut root = self.head_root;
+        while let Some(block) = self.blocks.get(&root) {
+            chain.push(block.clone());
+            root = block.parent_root;
+        }
+        chain.reverse();
+        chain
+    }
+}
+
+impl BeaconChain<C> for TestChain {
+    fn status(&self) -> StatusMessage {
+        StatusMessage {
+            head_fork_version: self.fork_version,
+            finalized_root: self.genesis_root,
+            finalized_epoch: Epoch::new(0),
+            head_root: self.head_root,
+            head_slot: self.head().slot,
+        }
+    }
+
+    fn contains_block(&self, root: H256) -> bool {
+        self.blocks.contains_key(&root)
+    }
+
+    fn block_by_root(&self, root: H256) -> Option<BeaconBlock<C>> {
+        self.blocks.get(&root).cloned()
+    }
+
+    fn blocks_by_range(&self, start_slot: Slot, count: u64, step: u64) -> Vec<BeaconBlock<C>> {
+        self.canonical_chain()
+            .into_iter()
+            .filter(|block| {
+                let offset = block.slot.as_u64().wrapping_sub(start_slot.as_u64());
+                block.slot >= start_slot && offset / step < count && offset / step * step == offset
+            })
+            .collect()
+    }
+
+    fn import_block(&mut self, block: BeaconBlock<C>) -> Result<(), BlockError> {
+        let parent_slot = match self.blocks.get(&block.parent_root) {
+            Some(parent) => parent.slot,
+            None => return Err(BlockError::UnknownParent(block.parent_root)),
+        };
+        if block.slot <= parent_slot || block.state_root != expected_state_root(block.slot) {
+            return Err(BlockError::Invalid("bad state root".to_string()));
+        }
+
+        let root = block_root(&block);
+        if block.slot > self.head().slot {
+            self.head_root = root;
+        }
+        self.blocks.insert(root, block);
+        Ok(())
+    }
+}
+
+fn node(hub: &MemoryHub, peer_id: &str, chain: TestChain) -> Node {
+    SyncManager::new(Network::new(hub.join(peer_id), Encoding::Ssz), chain)
+}
+
+/// Polls every node in turn until `done` holds for `client`.
+fn run_until<F: Fn(&Node) -> bool>(client: &mut Node, peers: &mut [Node], done: F) {
+    let deadline = Instant::now() + TIMEOUT;
+    while !done(client) {
+        assert!(Instant::now() < deadline, "sync did not finish in time");
+
+        assert_eq!(client.poll(Duration::from_millis(1)), Ok(None));
+        for peer in peers.iter_mut() {
+            assert_eq!(peer.poll(Duration::from_millis(1)), Ok(None));
+        }
+    }
+}
+
+fn head_root(node: &Node) -> H256 {
+    node.chain().status().head_root
+}
+
+#[test]
+fn range_sync_from_one_peer() {
+    let hub = MemoryHub::new();
+    // Long enough for several batches, with a run of empty slots spanning a whole batch.
+    let mut empty = (70..140).collect::<Vec<_>>();
+    empty.push(5);
+    let server = node(
+        &hub,
+        "server",
+        TestChain::genesis().extend(200, &empty, None),
+    );
+    let target = head_root(&server);
+
+    let mut client = node(&hub, "client", TestChain::genesis());
+    client.add_peer(&PeerId::new("server"));
+
+    let mut peers = vec![server];
+    run_until(&mut client, &mut peers, |client| {
+        head_root(client) == target
+    });
+
+    assert_eq!(
+        client.chain().canonical_chain(),
+        peers[0].chain().canonical_chain()
+    );
+    assert_eq!(client.state(), SyncState::Synced);
+    assert_eq!(client.peer_score(&PeerId::new("server")), Some(0));
+}
+
+#[test]
+fn invalid_batches_are_retried_with_another_peer() {
+    let hub = MemoryHub::new();
+    // The liar advertises the higher head, so it is asked first.
+    let liar = node(
+        &hub,
+        "liar",
+        TestChain::genesis().extend(120, &[], Some(90)),
+    );
+    let honest = node(&hub, "honest", TestChain::genesis().extend(100, &[3], None));
+    let target = head_root(&honest);
+
+    let mut client = node(&hub, "client", TestChain::genesis());
+    client.add_peer(&PeerId::new("liar"));
+    client.add_peer(&PeerId::new("honest"));
+
+    let mut peers = vec![liar, honest];
+    run_until(&mut client, &mut peers, |client| {
+        head_root(client) == target && client.state() == SyncState::Synced
+    });
+
+    assert_eq!(client.peers(), vec![PeerId::new("honest")]);
+    assert_eq!(client.peer_score(&PeerId::new("honest")), Some(0));
+}
+
+#[test]
+fn unresponsive_peers_time_out() {
+    let hub = MemoryHub::new();
+    let silent = hub.join("silent");
+    let honest = node(&hub, "honest", TestChain::genesis().extend(20, &[], None));
+    let target = head_root(&honest);
+
+    let mut client =
+        node(&hub, "client", TestChain::genesis()).with_request_timeout(Duration::from_millis(50));
+    client.add_peer(&PeerId::new("silent"));
+    client.add_peer(&PeerId::new("honest"));
+
+    // Answer the status request with a better head than the honest peer, then go quiet.
+    let (peer, frame) = silent.recv_timeout(TIMEOUT).unwrap().unwrap();
+    let id = match frame {
+        Frame::Request { id, protocol, .. } => {
+            assert_eq!(protocol, "/eth2/beacon_chain/req/status/1/ssz");
+            id
+        }
+        other => panic!("unexpected frame: {:?}", other),
+    };
+    let status = StatusMessage {
+        head_root: H256::repeat_byte(9),
+        head_slot: Slot::new(50),
+        ..client.chain().status()
+    };
+    let response = RpcResponse::<C>::Status(status)
+        .encode(Encoding::Ssz)
+        .unwrap();
+    silent
+        .send(&peer, Frame::Response { id, data: response })
+        .unwrap();
+
+    let mut peers = vec![honest];
+    run_until(&mut client, &mut peers, |client| {
+        head_root(client) == target
+    });
+
+    // It may have been disconnected already.
+    if let Some(score) = client.peer_score(&PeerId::new("silent")) {
+        assert!(score < 0);
+    }
+    assert_eq!(client.peer_score(&PeerId::new("honest")), Some(0));
+    // The silent peer was only ever sent status and range requests.
+    while let Ok(Some((_, frame))) = silent.recv_timeout(Duration::from_millis(1)) {
+        if let Frame::Request { protocol, .. } = frame {
+            let protocol = protocol.parse::<ProtocolId>().unwrap();
+            assert_ne!(protocol.protocol.as_str(), "beacon_blocks_by_root");
+        }
+    }
+}
+
+#[test]
+fn orphan_gossip_blocks_are_looked_up_by_root() {
+    let hub = MemoryHub::new();
+    let server = node(&hub, "server", TestChain::genesis().extend(6, &[2], None));
+    let target = head_root(&server);
+
+    // No status exchange, so range sync never starts.
+    let mut client = node(&hub, "client", TestChain::genesis());
+    let head = server.chain().head().clone();
+    assert_eq!(
+        server
+            .network()
+            .publish(&GossipMessage::BeaconBlock(Box::new(head))),
+        Ok(1)
+    );
+
+    let mut peers = vec![server];
+    run_until(&mut client, &mut peers, |client| {
+        head_root(client) == target
+    });
+
+    assert_eq!(
+        client.chain().canonical_chain(),
+        peers[0].chain().canonical_chain()
+    );
+    assert_eq!(client.state(), SyncState::Synced);
+}
+
+#[test]
+fn invalid_orphan_gossip_penalizes_its_sender() {
+    let hub = MemoryHub::new();
+    let server = node(&hub, "server", TestChain::genesis().extend(4, &[], Some(3)));
+
+    let mut client = node(&hub, "client", TestChain::genesis());
+    client.add_peer(&PeerId::new("server"));
+    let head = server.chain().head().clone();
+    server
+        .network()
+        .publish(&GossipMessage::BeaconBlock(Box::new(head)))
+        .unwrap();
+
+    let mut peers = vec![server];
+    run_until(&mut client, &mut peers, |client| client.peers().is_empty());
+
+    assert_eq!(client.chain().head().slot, 2);
+}
+
+#[test]
+fn peers_on_another_network_are_dropped() {
+    let hub = MemoryHub::new();
+    let mut other_chain = TestChain::genesis().extend(10, &[], None);
+    other_chain.fork_version = [1, 0, 0, 0];
+    let other = node(&hub, "other", other_chain);
+
+    let mut client = node(&hub, "client", TestChain::genesis());
+    client.add_peer(&PeerId::new("other"));
+
+    let mut peers = vec![other];
+    run_until(&mut client, &mut peers, |client| client.peers().is_empty());
+
+    assert_eq!(client.chain().head().slot, 0);
+    assert_eq!(client.state(), SyncState::Synced);
+}
@@ -0,0 +1,302 @@
+use std::time::{Duration, Instant};
+
+use network::rpc::{BeaconBlocksByRangeRequest, GoodbyeReason, StatusMessage};
+use network::{
+    Encoding, Error, Frame, GossipMessage, MemoryHub, Network, NetworkEvent, PeerId, ResponseCode,
+    RpcRequest, RpcResponse, TcpTransport, Transport,
+};
+use ssz_types::VariableList;
+use types::config::MinimalConfig;
+use types::primitives::{Epoch, Signature, Slot, ValidatorIndex, H256};
+use types::types::{BeaconBlock, BeaconBlockBody, Eth1Data, VoluntaryExit};
+
+type C = MinimalConfig;
+
+const TIMEOUT: Duration = Duration::from_secs(5);
+
+fn block(slot: Slot) -> BeaconBlock<C> {
+    BeaconBlock {
+        slot,
+        parent_root: H256::from_low_u64_be(slot.as_u64()),
+        state_root: H256::zero(),
+        body: BeaconBlockBody {
+            randao_reveal: Signature::empty_signature(),
+            eth1_data: Eth1Data::default(),
+            graffiti: [7; 32],
+            proposer_slashings: VariableList::default(),
+            attester_slashings: VariableList::default(),
+            attestations: VariableList::default(),
+            deposits: VariableList::default(),
+            voluntary_exits: VariableList::default(),
+            transfers: VariableList::default(),
+        },
+        signature: Signature::empty_signature(),
+    }
+}
+
+fn status() -> StatusMessage {
+    StatusMessage {
+        head_fork_version: [0; 4],
+        finalized_root: H256::repeat_byte(1),
+        finalized_epoch: Epoch::new(1),
+        head_root: H256::repeat_byte(2),
+        head_slot: Slot::new(20),
+    }
+}
+
+/// Polls until an event arrives, failing the test after `TIMEOUT`.
+fn next_event<T: Transport>(network: &mut Network<C, T>) -> NetworkEvent<C> {
+    let deadline = Instant::now() + TIMEOUT;
+    loop {
+        if let Some(event) = network.poll(Duration::from_millis(10)).unwrap() {
+            return event;
+        }
+        assert!(Instant::now() < deadline, "no event before the timeout");
+    }
+}
+
+/// Runs the same conversation between two connected nodes regardless of the transport.
+fn conversation<T: Transport>(mut a: Network<C, T>, mut b: Network<C, T>) {
+    let a_id = a.local_peer_id().clone();
+    let b_id = b.local_peer_id().clone();
+
+    // Gossip.
+    let message = GossipMessage::BeaconBlock(Box::new(block(Slot::new(3))));
+    assert_eq!(a.publish(&message), Ok(1));
+    assert_eq!(
+        next_event(&mut b),
+        NetworkEvent::Gossip {
+            peer: a_id.clone(),
+            message,
+        }
+    );
+
+    let exit = GossipMessage::VoluntaryExit(VoluntaryExit {
+        epoch: Epoch::new(4),
+        validator_index: ValidatorIndex::new(5),
+        signature: Signature::empty_signature(),
+    });
+    assert_eq!(b.publish(&exit), Ok(1));
+    assert_eq!(
+        next_event(&mut a),
+        NetworkEvent::Gossip {
+            peer: b_id.clone(),
+            message: exit,
+        }
+    );
+
+    // Status handshake.
+    let request_id = a
+        .send_request(&b_id, &RpcRequest::Status(status()))
+        .unwrap();
+    let incoming_id = match next_event(&mut b) {
+        NetworkEvent::Request {
+            peer,
+            id,
+            request: RpcRequest::Status(received),
+        } => {
+            assert_eq!(peer, a_id);
+            assert_eq!(received, status());
+            id
+        }
+        other => panic!("unexpected event: {:?}", other),
+    };
+    b.send_response(&a_id, incoming_id, &RpcResponse::Status(status()))
+        .unwrap();
+    assert_eq!(
+        next_event(&mut a),
+        NetworkEvent::Response {
+            peer: b_id.clone(),
+            id: request_id,
+            response: RpcResponse::Status(status()),
+        }
+    );
+
+    // Blocks by range, answered with several chunks.
+    let range = BeaconBlocksByRangeRequest {
+        head_block_root: H256::repeat_byte(2),
+        start_slot: Slot::new(1),
+        count: 3,
+        step: 1,
+    };
+    let request_id = a
+        .send_request(&b_id, &RpcRequest::BeaconBlocksByRange(range.clone()))
+        .unwrap();
+    let incoming_id = match next_event(&mut b) {
+        NetworkEvent::Request {
+            id,
+            request: RpcRequest::BeaconBlocksByRange(received),
+            ..
+        } => {
+            assert_eq!(received, range);
+            id
+        }
+        other => panic!("unexpected event: {:?}", other),
+    };
+    let blocks = range.slots().map(block).collect::<Vec<_>>();
+    b.send_response(
+        &a_id,
+        incoming_id,
+        &RpcResponse::BeaconBlocksByRange(blocks.clone()),
+    )
+    .unwrap();
+    assert_eq!(
+        next_event(&mut a),
+        NetworkEvent::Response {
+            peer: b_id.clone(),
+            id: request_id,
+            response: RpcResponse::BeaconBlocksByRange(blocks),
+        }
+    );
+
+    // Goodbye is delivered but never answered.
+    a.send_request(&b_id, &RpcRequest::Goodbye(GoodbyeReason::ClientShutdown))
+        .unwrap();
+    match next_event(&mut b) {
+        NetworkEvent::Request {
+            request: RpcRequest::Goodbye(GoodbyeReason::ClientShutdown),
+            ..
+        } => {}
+        other => panic!("unexpected event: {:?}", other),
+    }
+    assert_eq!(a.poll(Duration::from_millis(50)), Ok(None));
+}
+
+#[test]
+fn conversation_over_memory() {
+    let hub = MemoryHub::new();
+    let a = Network::new(hub.join("a"), Encoding::Ssz);
+    let b = Network::new(hub.join("b"), Encoding::Ssz);
+
+    conversation(a, b);
+}
+
+#[test]
+fn conversation_over_memory_with_snappy() {
+    let hub = MemoryHub::new();
+    let a = Network::new(hub.join("a"), Encoding::SszSnappy);
+    let b = Network::new(hub.join("b"), Encoding::SszSnappy);
+
+    conversation(a, b);
+}
+
+#[test]
+fn conversation_over_tcp() {
+    let a = TcpTransport::bind("a", "127.0.0.1:0").unwrap();
+    let b = TcpTransport::bind("b", "127.0.0.1:0").unwrap();
+
+    assert_eq!(a.connect(b.local_addr()), Ok(PeerId::new("b")));
+
+    // The listener registers the connection on its own thread.
+    let deadline = Instant::now() + TIMEOUT;
+    while b.peers().is_empty() {
+        assert!(Instant::now() < deadline, "connection was not accepted");
+        std::thread::sleep(Duration::from_millis(5));
+    }
+    assert_eq!(b.peers(), vec![PeerId::new("a")]);
+
+    conversation(
+        Network::new(a, Encoding::Ssz),
+        Network::new(b, Encoding::Ssz),
+    );
+}
+
+#[test]
+fn tcp_disconnect() {
+    let a = TcpTransport::bind("a", "127.0.0.1:0").unwrap();
+    let b = TcpTransport::bind("b", "127.0.0.1:0").unwrap();
+    a.connect(b.local_addr()).unwrap();
+
+    drop(b);
+
+    let deadline = Instant::now() + TIMEOUT;
+    while !a.peers().is_empty() {
+        assert!(Instant::now() < deadline, "disconnect was not noticed");
+        std::thread::sleep(Duration::from_millis(5));
+    }
+    assert_eq!(
+        a.send(
+            &PeerId::new("b"),
+            Frame::Response {
+                id: 0,
+                data: vec![]
+            }
+        ),
+        Err(Error::UnknownPeer(PeerId::new("b")))
+    );
+}
+
+#[test]
+fn invalid_request_is_answered_with_an_error() {
+    let hub = MemoryHub::new();
+    let raw = hub.join("raw");
+    let mut node = Network::<C, _>::new(hub.join("node"), Encoding::Ssz);
+
+    raw.send(
+        &PeerId::new("node"),
+        Frame::Request {
+            id: 9,
+            protocol: "/eth2/beacon_chain/req/status/1/ssz".to_string(),
+            // A length prefix that does not match the 84-byte status container.
+            data: vec![2, 0, 0],
+        },
+    )
+    .unwrap();
+
+    match next_event(&mut node) {
+        NetworkEvent::InvalidMessage {
+            error: Error::PayloadTooSmall { len: 2, min: 84 },
+            ..
+        } => {}
+        other => panic!("unexpected event: {:?}", other),
+    }
+
+    let (peer, frame) = raw.recv_timeout(TIMEOUT).unwrap().unwrap();
+    assert_eq!(peer, PeerId::new("node"));
+    match frame {
+        Frame::Response { id: 9, data } => {
+            assert_eq!(data[0], u8::from(ResponseCode::InvalidRequest))
+        }
+        other => panic!("unexpected frame: {:?}", other),
+    }
+}
+
+#[test]
+fn unsolicited_messages() {
+    let hub = MemoryHub::new();
+    let raw = hub.join("raw");
+    let mut node = Network::<C, _>::new(hub.join("node"), Encoding::Ssz);
+    let node_id = PeerId::new("node");
+
+    raw.send(
+        &node_id,
+        Frame::Response {
+            id: 1,
+            data: vec![],
+        },
+    )
+    .unwrap();
+    assert_eq!(
+        next_event(&mut node),
+        NetworkEvent::InvalidMessage {
+            peer: PeerId::new("raw"),
+            error: Error::UnexpectedResponseId(1),
+        }
+    );
+
+    raw.send(
+        &node_id,
+        Frame::Gossip {
+            topic: "/eth2/beacon_block/ssz".to_string(),
+            data: vec![1, 2, 3],
+        },
+    )
+    .unwrap();
+    match next_event(&mut node) {
+        NetworkEvent::InvalidMessage {
+            error: Error::PayloadTooSmall { len: 3, .. },
+            ..
+        } => {}
+        other => panic!("unexpected event: {:?}", other),
+    }
+}
@@ -0,0 +1,124 @@
+//! Field by field comparison of two values, through their serde representations.
+use serde_json::Value;
+use std::fmt;
+
+/// A leaf of the value that differs between the two sides.
+#[derive(Debug, PartialEq)]
+pub struct Difference {
+    /// The path to the leaf, e.g. `validators[3].effective_balance`.
+    pub path: String,
+    /// The leaf on the left, or `None` if the left side does not have it (e.g. a shorter list).
+    pub left: Option<Value>,
+    /// The leaf on the right, like `left`.
+    pub right: Option<Value>,
+}
+
+impl fmt::Display for Difference {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let side = |value: &Option<Value>| match value {
+            Some(value) => value.to_string(),
+            None => "<missing>".to_string(),
+        };
+
+        write!(
+            f,
+            "{}: {} -> {}",
+            self.path,
+            side(&self.left),
+            side(&self.right)
+        )
+    }
+}
+
+/// Returns every leaf that differs between `left` and `right`, with fields in alphabetical order
+/// and elements in order.
+///
+/// Objects are compared field by field and arrays element by element, so a single changed
+/// validator balance is reported as one difference rather than the whole validator list.
+pub fn diff(left: &Value, right: &Value) -> Vec<Difference> {
+    let mut differences = vec![];
+    diff_at(String::new(), Some(left), Some(right), &mut differences);
+    differences
+}
+
+fn diff_at(
+    path: String,
+    left: Option<&Value>,
+    right: Option<&Value>,
+    differences: &mut Vec<Difference>,
+) {
+    match (left, right) {
+        (Some(Value::Object(left)), Some(Value::Object(right))) => {
+            for (key, left_value) in left {
+                diff_at(
+                    field_path(&path, key),
+                    Some(left_value),
+                    right.get(key),
+                    differences,
+                );
+            }
+            for (key, right_value) in right.iter().filter(|(key, _)| !left.contains_key(*key)) {
+                diff_at(field_path(&path, key), None, Some(right_value), differences);
+            }
+        }
+        (Some(Value::Array(left)), Some(Value::Array(right))) => {
+            for i in 0..std::cmp::max(left.len(), right.len()) {
+                diff_at(
+                    format!("{}[{}]", path, i),
+                    left.get(i),
+                    right.get(i),
+                    differences,
+                );
+            }
+        }
+        (left, right) if left != right => differences.push(Difference {
+            path,
+            left: left.cloned(),
+            right: right.cloned(),
+        }),
+        _ => {}
+    }
+}
+
+fn field_path(path: &str, key: &str) -> String {
+    if path.is_empty() {
+        key.to_string()
+    } else {
+        format!("{}.{}", path, key)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn reports_leaves() {
+        let left = json!({
+            "slot": 1,
+            "validators": [{ "balance": 32 }, { "balance": 31 }],
+            "root": "0x00",
+        });
+        let right = json!({
+            "slot": 2,
+            "validators": [{ "balance": 32 }, { "balance": 30 }, { "balance": 1 }],
+            "root": "0x00",
+        });
+
+        let differences = diff(&left, &right)
+            .iter()
+            .map(ToString::to_string)
+            .collect::<Vec<_>>();
+
+        assert_eq!(
+            differences,
+            vec![
+                "slot: 1 -> 2",
+                "validators[1].balance: 31 -> 30",
+                "validators[2]: <missing> -> {\"balance\":1}",
+            ]
+        );
+        assert!(diff(&left, &left).is_empty());
+    }
+}
@@ -0,0 +1,465 @@
+//! `ssz-tool` decodes, encodes, hashes and compares SSZ encoded values of the types in the `types`
+//! crate, for any of its configs.
+//!
+//! ```text
+//! ssz-tool --config minimal decode BeaconState state.ssz --format spec-yaml
+//! ssz-tool encode Attestation attestation.yaml --output attestation.ssz
+//! ssz-tool root BeaconBlock block.ssz
+//! ssz-tool diff BeaconState pre.ssz post.ssz
+//! ```
+//!
+//! A file of `-` is read from stdin. The `spec-yaml` format is YAML with byte arrays as hex
+//! strings, like the YAML of the spec tests.
+
+// Printing its results is what the tool is for.
+#![allow(clippy::print_stdout)]
+
+mod diff;
+
+use clap::{App, AppSettings, Arg, ArgMatches, SubCommand};
+use serde::de::DeserializeOwned;
+use serde::Serialize;
+use ssz::{Decode, Encode};
+use std::fs;
+use std::io::{self, Read, Write};
+use std::process;
+use std::str::FromStr;
+use tree_hash::{Hash256, SignedRoot, TreeHash};
+use types::beacon_state::BeaconState;
+use types::config::{Config, MainnetConfig, MinimalConfig, QuickConfig};
+use types::serde_utils::Spec;
+use types::types::*;
+
+/// The names of the configs, as accepted by `--config`.
+const CONFIG_NAMES: &[&str] = &["mainnet", "minimal", "quick"];
+
+/// The names of the types, as accepted by each subcommand.
+const TYPE_NAMES: &[&str] = &[
+    "Attestation",
+    "AttestationData",
+    "AttestationDataAndCustodyBit",
+    "AttestationDuty",
+    "AttesterSlashing",
+    "BeaconBlock",
+    "BeaconBlockBody",
+    "BeaconBlockHeader",
+    "BeaconState",
+    "Checkpoint",
+    "Crosslink",
+    "Deposit",
+    "DepositData",
+    "Eth1Data",
+    "Fork",
+    "HistoricalBatch",
+    "IndexedAttestation",
+    "PendingAttestation",
+    "ProposerSlashing",
+    "Transfer",
+    "Validator",
+    "VoluntaryExit",
+];
+
+/// The encodings a value can be read from or written as.
+#[derive(Debug, PartialEq, Clone, Copy)]
+enum Format {
+    Ssz,
+    Json,
+    Yaml,
+    SpecYaml,
+}
+
+impl FromStr for Format {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "ssz" => Ok(Format::Ssz),
+            "json" => Ok(Format::Json),
+            "yaml" => Ok(Format::Yaml),
+            "spec-yaml" => Ok(Format::SpecYaml),
+            _ => Err(format!("Unknown format: {}", s)),
+        }
+    }
+}
+
+/// Every type the tool handles.
+trait Type: Encode + Decode + Serialize + DeserializeOwned + TreeHash {}
+
+impl<T: Encode + Decode + Serialize + DeserializeOwned + TreeHash> Type for T {}
+
+/// A subcommand, run once the type it applies to is known.
+trait Command {
+    /// Runs the command for `T`, where `signed_root` computes the signed root of types that have
+    /// one.
+    fn run<T: Type>(&self, signed_root: Option<fn(&T) -> Hash256>) -> Result<(), String>;
+}
+
+/// Runs `command` for the type called `type_name`, with config `C`.
+///
+/// The serde derives of generic types require `C` to implement serde too, as every config does.
+fn dispatch<C: Config + Serialize + DeserializeOwned, F: Command>(
+    type_name: &str,
+    command: &F,
+) -> Result<(), String> {
+    match type_name {
+        "Attestation" => command.run::<Attestation<C>>(Some(SignedRoot::signed_root)),
+        "AttestationData" => command.run::<AttestationData>(Some(SignedRoot::signed_root)),
+        "AttestationDataAndCustodyBit" => {
+            command.run::<AttestationDataAndCustodyBit>(Some(SignedRoot::signed_root))
+        }
+        "AttestationDuty" => command.run::<AttestationDuty>(Some(SignedRoot::signed_root)),
+        "AttesterSlashing" => command.run::<AttesterSlashing<C>>(None),
+        "BeaconBlock" => command.run::<BeaconBlock<C>>(Some(SignedRoot::signed_root)),
+        "BeaconBlockBody" => command.run::<BeaconBlockBody<C>>(Some(SignedRoot::signed_root)),
+        "BeaconBlockHeader" => command.run::<BeaconBlockHeader>(Some(SignedRoot::signed_root)),
+        "BeaconState" => command.run::<BeaconState<C>>(None),
+        "Checkpoint" => command.run::<Checkpoint>(None),
+        "Crosslink" => command.run::<Crosslink>(None),
+        "Deposit" => command.run::<Deposit>(None),
+        "DepositData" => command.run::<DepositData>(Some(SignedRoot::signed_root)),
+        "Eth1Data" => command.run::<Eth1Data>(None),
+        "Fork" => command.run::<Fork>(Some(SignedRoot::signed_root)),
+        "HistoricalBatch" => command.run::<HistoricalBatch<C>>(None),
+        "IndexedAttestation" => command.run::<IndexedAttestation<C>>(Some(SignedRoot::signed_root)),
+        "PendingAttestation" => command.run::<PendingAttestation<C>>(None),
+        "ProposerSlashing" => command.run::<ProposerSlashing>(None),
+        "Transfer" => command.run::<Transfer>(Some(SignedRoot::signed_root)),
+        "Validator" => command.run::<Validator>(None),
+        "VoluntaryExit" => command.run::<VoluntaryExit>(Some(SignedRoot::signed_root)),
+        _ => Err(format!("Unknown type: {}", type_name)),
+    }
+}
+
+/// Runs `command` for the type called `type_name`, with the config called `config_name`.
+fn dispatch_config<F: Command>(
+    config_name: &str,
+    type_name: &str,
+    command: &F,
+) -> Result<(), String> {
+    match config_name {
+        "mainnet" => dispatch::<MainnetConfig, _>(type_name, command),
+        "minimal" => dispatch::<MinimalConfig, _>(type_name, command),
+        "quick" => dispatch::<QuickConfig, _>(type_name, command),
+        _ => Err(format!("Unknown config: {}", config_name)),
+    }
+}
+
+fn read_file(path: &str) -> Result<Vec<u8>, String> {
+    if path == "-" {
+        let mut bytes = vec![];
+        io::stdin()
+            .read_to_end(&mut bytes)
+            .map_err(|e| format!("Unable to read stdin: {}", e))?;
+        Ok(bytes)
+    } else {
+        fs::read(path).map_err(|e| format!("Unable to read {}: {}", path, e))
+    }
+}
+
+fn parse<T: Type>(bytes: &[u8], format: Format) -> Result<T, String> {
+    match format {
+        Format::Ssz => T::from_ssz_bytes(bytes).map_err(|e| format!("Invalid SSZ: {}", e)),
+        Format::Json => serde_json::from_slice(bytes).map_err(|e| format!("Invalid JSON: {}", e)),
+        Format::Yaml => serde_yaml::from_slice(bytes).map_err(|e| format!("Invalid YAML: {}", e)),
+        Format::SpecYaml => serde_yaml::from_slice(bytes)
+            .map(|Spec(value)| value)
+            .map_err(|e| format!("Invalid YAML: {}", e)),
+    }
+}
+
+fn serialize<T: Type>(value: &T, format: Format) -> Result<Vec<u8>, String> {
+    match format {
+        Format::Ssz => Ok(value.as_ssz_bytes()),
+        Format::Json => serde_json::to_vec_pretty(value).map_err(|e| e.to_string()),
+        Format::Yaml => serde_yaml::to_vec(value).map_err(|e| e.to_string()),
+        Format::SpecYaml => serde_yaml::to_vec(&Spec(value)).map_err(|e| e.to_string()),
+    }
+}
+
+fn load<T: Type>(path: &str, format: Format) -> Result<T, String> {
+    parse(&read_file(path)?, format)
+}
+
+/// Writes `bytes` to the file at `path`, or to stdout if there is none.
+fn write_output(path: Option<&str>, bytes: &[u8]) -> Result<(), String> {
+    match path {
+        Some(path) => {
+            fs::write(path, bytes).map_err(|e| format!("Unable to write {}: {}", path, e))
+        }
+        None => {
+            let stdout = io::stdout();
+            let mut stdout = stdout.lock();
+            stdout
+                .write_all(bytes)
+                .and_then(|()| stdout.flush())
+                .map_err(|e| format!("Unable to write to stdout: {}", e))
+        }
+    }
+}
+
+/// Reads a value in one format and writes it in another: `decode` and `encode`.
+struct Convert<'a> {
+    file: &'a str,
+    from: Format,
+    to: Format,
+    output: Option<&'a str>,
+}
+
+impl<'a> Command for Convert<'a> {
+    fn run<T: Type>(&self, _: Option<fn(&T) -> Hash256>) -> Result<(), String> {
+        let value: T = load(self.file, self.from)?;
+        let bytes = serialize(&value, self.to)?;
+        write_output(self.output, &bytes)
+    }
+}
+
+/// Prints the roots of a value: `root`.
+struct Roots<'a> {
+    file: &'a str,
+    format: Format,
+}
+
+impl<'a> Command for Roots<'a> {
+    fn run<T: Type>(&self, signed_root: Option<fn(&T) -> Hash256>) -> Result<(), String> {
+        let value: T = load(self.file, self.format)?;
+
+        println!("tree_hash_root: {:#x}", value.tree_hash_root());
+        if let Some(signed_root) = signed_root {
+            println!("signed_root: {:#x}", signed_root(&value));
+        }
+
+        Ok(())
+    }
+}
+
+/// Prints the fields that differ between two values: `diff`.
+struct Diff<'a> {
+    left: &'a str,
+    right: &'a str,
+    format: Format,
+}
+
+impl<'a> Command for Diff<'a> {
+    fn run<T: Type>(&self, _: Option<fn(&T) -> Hash256>) -> Result<(), String> {
+        let to_value = |path| -> Result<serde_json::Value, String> {
+            serde_json::to_value(load::<T>(path, self.format)?).map_err(|e| e.to_string())
+        };
+
+        let differences = diff::diff(&to_value(self.left)?, &to_value(self.right)?);
+        if differences.is_empty() {
+            println!("No differences");
+        }
+        for difference in differences {
+            println!("{}", difference);
+        }
+
+        Ok(())
+    }
+}
+
+fn format_arg<'a, 'b>(name: &'a str, default: &'a str, help: &'a str) -> Arg<'a, 'b> {
+    Arg::with_name(name)
+        .long(name)
+        .takes_value(true)
+        .possible_values(&["ssz", "json", "yaml", "spec-yaml"])
+        .default_value(default)
+        .help(help)
+}
+
+fn type_arg<'a, 'b>() -> Arg<'a, 'b> {
+    Arg::with_name("type")
+        .required(true)
+        .possible_values(TYPE_NAMES)
+        .help("The type of the value")
+}
+
+fn file_arg<'a, 'b>(name: &'a str, help: &'a str) -> Arg<'a, 'b> {
+    Arg::with_name(name).required(true).help(help)
+}
+
+fn app<'a, 'b>() -> App<'a, 'b> {
+    App::new("ssz-tool")
+        .about("Inspects SSZ encoded values of the types in the `types` crate")
+        .setting(AppSettings::SubcommandRequiredElseHelp)
+        .arg(
+            Arg::with_name("config")
+                .long("config")
+                .takes_value(true)
+                .possible_values(CONFIG_NAMES)
+                .default_value("mainnet")
+                .help("The config that sets the lengths of lists and vectors"),
+        )
+        .subcommand(
+            SubCommand::with_name("decode")
+                .about("Prints an SSZ encoded value as JSON or YAML")
+                .arg(type_arg())
+                .arg(file_arg("file", "The SSZ file, or - for stdin"))
+                .arg(format_arg("format", "json", "The format to print"))
+                .arg(format_arg("input-format", "ssz", "The format of the file")),
+        )
+        .subcommand(
+            SubCommand::with_name("encode")
+                .about("SSZ encodes a value given as JSON or YAML")
+                .arg(type_arg())
+                .arg(file_arg("file", "The JSON or YAML file, or - for stdin"))
+                .arg(
+                    Arg::with_name("output")
+                        .long("output")
+                        .short("o")
+                        .takes_value(true)
+                        .help("The file to write, instead of stdout"),
+                )
+                .arg(format_arg("input-format", "yaml", "The format of the file")),
+        )
+        .subcommand(
+            SubCommand::with_name("root")
+                .about("Prints the tree hash root and, if the type has one, the signed root")
+                .arg(type_arg())
+                .arg(file_arg("file", "The file, or - for stdin"))
+                .arg(format_arg("input-format", "ssz", "The format of the file")),
+        )
+        .subcommand(
+            SubCommand::with_name("diff")
+                .about("Prints each field that differs between two values")
+                .arg(type_arg())
+                .arg(file_arg("left", "The older value"))
+                .arg(file_arg("right", "The newer value"))
+                .arg(format_arg(
+                    "input-format",
+                    "ssz",
+                    "The format of both files",
+                )),
+        )
+}
+
+/// Returns the value of an argument that has a default or is required.
+fn value<'a>(matches: &'a ArgMatches, name: &str) -> &'a str {
+    matches
+        .value_of(name)
+        .expect("Argument is required or has a default")
+}
+
+fn format(matches: &ArgMatches, name: &str) -> Format {
+    value(matches, name)
+        .parse()
+        .expect("clap only accepts the possible values")
+}
+
+fn run(matches: &ArgMatches) -> Result<(), String> {
+    let config = value(matches, "config");
+
+    match matches.subcommand() {
+        ("decode", Some(matches)) => dispatch_config(
+            config,
+            value(matches, "type"),
+            &Convert {
+                file: value(matches, "file"),
+                from: format(matches, "input-format"),
+                to: format(matches, "format"),
+                output: None,
+            },
+        ),
+        ("encode", Some(matches)) => dispatch_config(
+            config,
+            value(matches, "type"),
+            &Convert {
+                file: value(matches, "file"),
+                from: format(matches, "input-format"),
+                to: Format::Ssz,
+                output: matches.value_of("output"),
+            },
+        ),
+        ("root", Some(matches)) => dispatch_config(
+            config,
+            value(matches, "type"),
+            &Roots {
+                file: value(matches, "file"),
+                format: format(matches, "input-format"),
+            },
+        ),
+        ("diff", Some(matches)) => dispatch_config(
+            config,
+            value(matches, "type"),
+            &Diff {
+                left: value(matches, "left"),
+                right: value(matches, "right"),
+                format: format(matches, "input-format"),
+            },
+        ),
+        _ => unreachable!("A subcommand is required"),
+    }
+}
+
+fn main() {
+    if let Err(e) = run(&app().get_matches()) {
+        eprintln!("{}", e);
+        process::exit(1);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Does nothing, but only compiles for types the tool handles.
+    struct Noop;
+
+    impl Command for Noop {
+        fn run<T: Type>(&self, _: Option<fn(&T) -> Hash256>) -> Result<(), String> {
+            Ok(())
+        }
+    }
+
+    #[test]
+    fn every_type_and_config_dispatches() {
+        for config in CONFIG_NAMES {
+            for type_name in TYPE_NAMES {
+                assert_eq!(
+                    dispatch_config(config, type_name, &Noop),
+                    Ok(()),
+                    "{} {}",
+                    config,
+                    type_name
+                );
+            }
+        }
+
+        assert!(dispatch_config("mainnet", "Unknown", &Noop).is_err());
+        assert!(dispatch_config("unknown", "Fork", &Noop).is_err());
+    }
+
+    #[test]
+    fn formats_round_trip() {
+        // An empty (all zero) signature, as other bytes need to be a valid point.
+        let bytes = [vec![1; 104], vec![0; 96]].concat();
+        let header: BeaconBlockHeader = parse(&bytes, Format::Ssz).unwrap();
+
+        for &format in &[Format::Ssz, Format::Json, Format::Yaml, Format::SpecYaml] {
+            let bytes = serialize(&header, format).unwrap();
+            assert_eq!(
+                parse::<BeaconBlockHeader>(&bytes, format),
+                Ok(header.clone())
+            );
+        }
+    }
+
+    #[test]
+    fn parses_arguments() {
+        let matches = app()
+            .get_matches_from_safe(vec![
+                "ssz-tool", "--config", "minimal", "decode", "Fork", "-", "--format", "yaml",
+            ])
+            .unwrap();
+        assert_eq!(value(&matches, "config"), "minimal");
+
+        let (_, matches) = matches.subcommand();
+        let matches = matches.unwrap();
+        assert_eq!(value(matches, "type"), "Fork");
+        assert_eq!(format(matches, "format"), Format::Yaml);
+        assert_eq!(format(matches, "input-format"), Format::Ssz);
+
+        assert!(app()
+            .get_matches_from_safe(vec!["ssz-tool", "root", "Unknown", "-"])
+            .is_err());
+    }
+}
@@ -0,0 +1,25 @@
+#![no_main]
+#[macro_use] extern crate libfuzzer_sys;
+extern crate arbitrary;
+extern crate ssz;
+extern crate tree_hash;
+extern crate types;
+
+use arbitrary::{Arbitrary, Unstructured};
+use ssz::{Decode, Encode};
+use tree_hash::TreeHash;
+use types::beacon_state::BeaconState;
+use types::config::MinimalConfig;
+
+// Generate a `BeaconState`, then check that it survives an SSZ round trip unchanged and that the
+// decoded copy has the same tree hash root.
+fuzz_target!(|data: &[u8]| {
+    let mut unstructured = Unstructured::new(data);
+    if let Ok(value) = BeaconState::<MinimalConfig>::arbitrary(&mut unstructured) {
+        let bytes = value.as_ssz_bytes();
+        let decoded = BeaconState::<MinimalConfig>::from_ssz_bytes(&bytes).expect("generated value must decode");
+        assert_eq!(decoded, value);
+        assert_eq!(decoded.as_ssz_bytes(), bytes);
+        assert_eq!(decoded.tree_hash_root(), value.tree_hash_root());
+    }
+});
@@ -0,0 +1,24 @@
+#![no_main]
+#[macro_use] extern crate libfuzzer_sys;
+extern crate arbitrary;
+extern crate ssz;
+extern crate tree_hash;
+extern crate types;
+
+use arbitrary::{Arbitrary, Unstructured};
+use ssz::{Decode, Encode};
+use tree_hash::TreeHash;
+use types::types::Deposit;
+
+// Generate a `Deposit`, then check that it survives an SSZ round trip unchanged and that the
+// decoded copy has the same tree hash root.
+fuzz_target!(|data: &[u8]| {
+    let mut unstructured = Unstructured::new(data);
+    if let Ok(value) = Deposit::arbitrary(&mut unstructured) {
+        let bytes = value.as_ssz_bytes();
+        let decoded = Deposit::from_ssz_bytes(&bytes).expect("generated value must decode");
+        assert_eq!(decoded, value);
+        assert_eq!(decoded.as_ssz_bytes(), bytes);
+        assert_eq!(decoded.tree_hash_root(), value.tree_hash_root());
+    }
+});
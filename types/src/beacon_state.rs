@@ -1,11 +1,14 @@
 use serde::{Deserialize, Serialize};
-use ssz_derive::{Decode, Encode};
-use ssz_types::{BitVector, FixedVector, VariableList};
+use ssz_derive::{Decode, DecodeView, Encode, SszSchema};
+use ssz_types::{BitVector, PersistentList, PersistentVector};
 use tree_hash_derive::TreeHash;
 
 use crate::{config::*, consts, primitives::*, types::*};
 
-#[derive(Debug, PartialEq, Clone, Serialize, Deserialize, Encode, Decode, TreeHash)]
+#[derive(
+    Debug, PartialEq, Clone, Serialize, Deserialize, Encode, Decode, DecodeView, SszSchema, TreeHash,
+)]
+#[tree_hash(cached)]
 pub struct BeaconState<C: Config> {
     pub genesis_time: u64,
     pub slot: Slot,
@@ -13,36 +16,37 @@ pub struct BeaconState<C: Config> {
 
     // History
     pub latest_block_header: BeaconBlockHeader,
-    pub block_roots: FixedVector<H256, C::SlotsPerHistoricalRoot>,
-    pub state_roots: FixedVector<H256, C::SlotsPerHistoricalRoot>,
-    pub historical_roots: VariableList<H256, C::HistoricalRootsLimit>,
+    pub block_roots: PersistentVector<H256, C::SlotsPerHistoricalRoot>,
+    pub state_roots: PersistentVector<H256, C::SlotsPerHistoricalRoot>,
+    pub historical_roots: PersistentList<H256, C::HistoricalRootsLimit>,
 
     // Eth1 Data
     pub eth1_data: Eth1Data,
-    pub eth1_data_votes: VariableList<Eth1Data, C::SlotsPerEth1VotingPeriod>,
+    pub eth1_data_votes: PersistentList<Eth1Data, C::SlotsPerEth1VotingPeriod>,
     pub eth1_deposit_index: u64,
 
     // Registry
-    pub validators: VariableList<Validator, C::ValidatorRegistryLimit>,
-    pub balances: VariableList<u64, C::ValidatorRegistryLimit>,
+    pub validators: PersistentList<Validator, C::ValidatorRegistryLimit>,
+    pub balances: PersistentList<Gwei, C::ValidatorRegistryLimit>,
 
     // Shuffling
-    pub start_shard: u64,
-    pub randao_mixes: FixedVector<H256, C::EpochsPerHistoricalVector>,
-    pub active_index_roots: FixedVector<H256, C::EpochsPerHistoricalVector>,
-    pub compact_committees_roots: FixedVector<H256, C::EpochsPerHistoricalVector>,
+    pub start_shard: Shard,
+    pub randao_mixes: PersistentVector<H256, C::EpochsPerHistoricalVector>,
+    pub active_index_roots: PersistentVector<H256, C::EpochsPerHistoricalVector>,
+    pub compact_committees_roots: PersistentVector<H256, C::EpochsPerHistoricalVector>,
 
     // Slashings
-    pub slashings: FixedVector<u64, C::EpochsPerSlashingsVector>,
+    pub slashings: PersistentVector<u64, C::EpochsPerSlashingsVector>,
 
     // Attestations
     pub previous_epoch_attestations:
-        VariableList<PendingAttestation<C>, C::MaxAttestationsPerEpoch>,
-    pub current_epoch_attestations: VariableList<PendingAttestation<C>, C::MaxAttestationsPerEpoch>,
+        PersistentList<PendingAttestation<C>, C::MaxAttestationsPerEpoch>,
+    pub current_epoch_attestations:
+        PersistentList<PendingAttestation<C>, C::MaxAttestationsPerEpoch>,
 
     // Crosslinks
-    pub previous_crosslinks: FixedVector<Crosslink, C::ShardCount>,
-    pub current_crosslinks: FixedVector<Crosslink, C::ShardCount>,
+    pub previous_crosslinks: PersistentVector<Crosslink, C::ShardCount>,
+    pub current_crosslinks: PersistentVector<Crosslink, C::ShardCount>,
 
     // Finality
     pub justification_bits: BitVector<consts::JustificationBitsLength>,
@@ -50,3 +54,177 @@ pub struct BeaconState<C: Config> {
     pub current_justified_checkpoint: Checkpoint,
     pub finalized_checkpoint: Checkpoint,
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::MinimalConfig;
+    use tree_hash::{generalized_index, CachedTreeHash, MultiProof, Proof, TreeHash};
+
+    fn header() -> BeaconBlockHeader {
+        BeaconBlockHeader {
+            slot: Slot::new(0),
+            parent_root: H256::zero(),
+            state_root: H256::zero(),
+            body_root: H256::zero(),
+            signature: Signature::empty_signature(),
+        }
+    }
+
+    fn validator(effective_balance: u64) -> Validator {
+        Validator {
+            pubkey: PublicKey::default(),
+            withdrawal_credentials: H256::zero(),
+            effective_balance: Gwei::new(effective_balance),
+            slashed: false,
+            activation_eligibility_epoch: Epoch::new(0),
+            activation_epoch: Epoch::new(0),
+            exit_epoch: Epoch::new(10),
+            withdrawable_epoch: Epoch::new(20),
+        }
+    }
+
+    fn state() -> BeaconState<MinimalConfig> {
+        BeaconState {
+            genesis_time: 0,
+            slot: Slot::new(42),
+            fork: Fork {
+                previous_version: [0; 4],
+                current_version: [0; 4],
+                epoch: Epoch::new(0),
+            },
+            latest_block_header: header(),
+            block_roots: PersistentVector::from(vec![]),
+            state_roots: PersistentVector::from(vec![]),
+            historical_roots: PersistentList::from(vec![H256::repeat_byte(1)]),
+            eth1_data: Eth1Data::default(),
+            eth1_data_votes: PersistentList::from(vec![]),
+            eth1_deposit_index: 0,
+            validators: PersistentList::from(vec![validator(32), validator(31), validator(30)]),
+            balances: PersistentList::from(vec![Gwei::new(32), Gwei::new(31), Gwei::new(30)]),
+            start_shard: Shard::new(0),
+            randao_mixes: PersistentVector::from(vec![]),
+            active_index_roots: PersistentVector::from(vec![]),
+            compact_committees_roots: PersistentVector::from(vec![]),
+            slashings: PersistentVector::from(vec![]),
+            previous_epoch_attestations: PersistentList::from(vec![]),
+            current_epoch_attestations: PersistentList::from(vec![]),
+            previous_crosslinks: PersistentVector::from(vec![]),
+            current_crosslinks: PersistentVector::from(vec![]),
+            justification_bits: BitVector::new(),
+            previous_justified_checkpoint: Checkpoint::default(),
+            current_justified_checkpoint: Checkpoint::default(),
+            finalized_checkpoint: Checkpoint {
+                epoch: Epoch::new(3),
+                root: H256::repeat_byte(7),
+            },
+        }
+    }
+
+    #[test]
+    fn generalized_indices() {
+        type State = BeaconState<MinimalConfig>;
+
+        // 25 fields make 32 leaves, `finalized_checkpoint` is the 25th and `root` its second field.
+        assert_eq!(
+            generalized_index::<State>("finalized_checkpoint"),
+            Ok(32 + 24)
+        );
+        assert_eq!(
+            generalized_index::<State>("finalized_checkpoint.root"),
+            Ok((32 + 24) * 2 + 1)
+        );
+        // The validators sit below their list's data root, which has 2^40 leaves.
+        assert_eq!(
+            generalized_index::<State>("validators[2].effective_balance"),
+            Ok((((32 + 10) * 2) << 40 | 2) * 8 + 2)
+        );
+        assert_eq!(
+            generalized_index::<State>("validators.__len__"),
+            Ok((32 + 10) * 2 + 1)
+        );
+    }
+
+    #[test]
+    fn prove_fields() {
+        type State = BeaconState<MinimalConfig>;
+        let state = state();
+        let root = state.tree_hash_root();
+
+        let gindex = generalized_index::<State>("finalized_checkpoint.root").unwrap();
+        let proof = Proof::new(&state, gindex).unwrap();
+        assert_eq!(proof.leaf, H256::repeat_byte(7).tree_hash_root());
+        assert!(proof.verify(&root));
+
+        let gindex = generalized_index::<State>("validators[1].effective_balance").unwrap();
+        let proof = Proof::new(&state, gindex).unwrap();
+        assert_eq!(proof.leaf, 31u64.tree_hash_root());
+        assert!(proof.verify(&root));
+
+        let mut changed = state.clone();
+        changed
+            .validators
+            .update(1, |validator| validator.effective_balance = Gwei::new(0))
+            .unwrap();
+        assert!(!proof.verify(&changed.tree_hash_root()));
+        assert!(proof.verify(&state.tree_hash_root()));
+
+        let gindices = [
+            "slot",
+            "validators[0].slashed",
+            "balances[2]",
+            "validators.__len__",
+        ]
+        .iter()
+        .map(|path| generalized_index::<State>(path).unwrap())
+        .collect::<Vec<_>>();
+        let proof = MultiProof::new(&state, &gindices).unwrap();
+        assert!(proof.verify(&root));
+        assert!(!proof.verify(&changed.tree_hash_root()));
+    }
+
+    #[test]
+    fn cached_tree_hash_root() {
+        let mut state = state();
+        let mut cache = state.new_tree_hash_cache();
+        assert_eq!(cache.root(), state.tree_hash_root());
+
+        state.slot = Slot::new(99);
+        state
+            .validators
+            .update(0, |validator| validator.slashed = true)
+            .unwrap();
+        assert_eq!(
+            state.recalculate_tree_hash_root(&mut cache),
+            state.tree_hash_root()
+        );
+        assert_eq!(cache, state.new_tree_hash_cache());
+    }
+
+    #[test]
+    fn schema() {
+        use ssz::{Schema, SszSchema};
+
+        let schema = BeaconState::<MinimalConfig>::ssz_schema();
+        let fields = schema.fields().unwrap();
+        assert_eq!(fields.len(), 25);
+        assert_eq!(fields[0].name, "genesis_time");
+
+        let validators = schema.field("validators").unwrap();
+        assert_eq!(
+            validators.ty,
+            "PersistentList<Validator,C::ValidatorRegistryLimit>"
+        );
+        assert_eq!(
+            validators.schema.to_string(),
+            "List[Validator, 1099511627776]"
+        );
+
+        let slot = schema.field("slot").unwrap();
+        assert_eq!(slot.schema, Schema::Uint { bytes: 8 });
+
+        let bits = schema.field("justification_bits").unwrap();
+        assert_eq!(bits.schema, Schema::Bitvector { length: 4 });
+        assert_eq!(Validator::ssz_schema().fixed_len(), 121);
+    }
+}
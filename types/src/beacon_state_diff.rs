@@ -0,0 +1,592 @@
+//! Compact differences between two `BeaconState`s, e.g. between consecutive slots, that can be
+//! stored or sent instead of the newer state and applied to the older one to reproduce it.
+use serde::{Deserialize, Serialize};
+use ssz::{Decode, Encode, SszSchema};
+use ssz_derive::{Decode, Encode, SszSchema};
+use ssz_types::{BitVector, PersistentList, PersistentVector, VariableList};
+use tree_hash::TreeHash;
+use typenum::Unsigned;
+
+use crate::beacon_state::BeaconState;
+use crate::{config::*, consts, primitives::*, types::*};
+
+/// An element of a vector or list in a `BeaconStateDiff`.
+pub trait Element: Clone + PartialEq + Encode + Decode + SszSchema + TreeHash {}
+
+impl<T: Clone + PartialEq + Encode + Decode + SszSchema + TreeHash> Element for T {}
+
+#[derive(Debug, PartialEq, Clone)]
+pub enum Error {
+    /// The state the diff is applied to is not the state it was computed from.
+    PreRootMismatch { expected: H256, found: H256 },
+    /// Applying the diff did not reproduce the state it was computed to.
+    PostRootMismatch { expected: H256, found: H256 },
+    /// The diff has an entry for an index that `field` of the state does not have.
+    OutOfBounds { field: &'static str, index: u64 },
+}
+
+/// The value at `index` of a vector or list.
+#[derive(Debug, PartialEq, Clone, Deserialize, Serialize, Encode, Decode, SszSchema)]
+pub struct Entry<T: Element> {
+    pub index: u64,
+    pub value: T,
+}
+
+/// The changes to a list: its new length and the values that changed or were appended, in order
+/// of their index.
+#[derive(Debug, PartialEq, Clone, Deserialize, Serialize, Encode, Decode, SszSchema)]
+#[serde(bound(serialize = "T: Serialize", deserialize = "T: Deserialize<'de>"))]
+pub struct ListDiff<T: Element, N: Unsigned> {
+    pub len: u64,
+    pub entries: VariableList<Entry<T>, N>,
+}
+
+/// The change to the balance at `index`, wrapping so that a decrease fits in a `u64` (a decrease
+/// of 1 is `u64::max_value()`).
+///
+/// A balance appended to the list is a change from 0.
+#[derive(Debug, PartialEq, Clone, Copy, Deserialize, Serialize, Encode, Decode, SszSchema)]
+pub struct BalanceDelta {
+    pub index: u64,
+    pub delta: u64,
+}
+
+impl BalanceDelta {
+    /// Returns the change as a signed amount of Gwei.
+    pub fn signed(&self) -> i64 {
+        self.delta as i64
+    }
+}
+
+/// The changes to the balances: their new length and the deltas of those that changed, in order of
+/// their index.
+#[derive(Debug, PartialEq, Clone, Deserialize, Serialize, Encode, Decode, SszSchema)]
+#[serde(bound = "")]
+pub struct BalancesDiff<N: Unsigned> {
+    pub len: u64,
+    pub deltas: VariableList<BalanceDelta, N>,
+}
+
+/// The fields of a `BeaconState` that changed between two states, along with the roots of both.
+///
+/// Fields other than lists and vectors are `None` if they are unchanged. Vectors, which are mostly
+/// rotated one entry at a time, only hold the entries that changed. Lists also hold their length,
+/// which is enough to reproduce a list that was cleared, like `current_epoch_attestations` at the
+/// start of an epoch.
+#[derive(Debug, PartialEq, Clone, Deserialize, Serialize, Encode, Decode, SszSchema)]
+pub struct BeaconStateDiff<C: Config> {
+    /// The `tree_hash_root` of the state the diff applies to.
+    pub pre_root: H256,
+    /// The `tree_hash_root` of the state the diff reproduces.
+    pub post_root: H256,
+
+    pub genesis_time: Option<u64>,
+    pub slot: Option<Slot>,
+    pub fork: Option<Fork>,
+
+    // History
+    pub latest_block_header: Option<BeaconBlockHeader>,
+    pub block_roots: VariableList<Entry<H256>, C::SlotsPerHistoricalRoot>,
+    pub state_roots: VariableList<Entry<H256>, C::SlotsPerHistoricalRoot>,
+    pub historical_roots: ListDiff<H256, C::HistoricalRootsLimit>,
+
+    // Eth1 Data
+    pub eth1_data: Option<Eth1Data>,
+    pub eth1_data_votes: ListDiff<Eth1Data, C::SlotsPerEth1VotingPeriod>,
+    pub eth1_deposit_index: Option<u64>,
+
+    // Registry
+    pub validators: ListDiff<Validator, C::ValidatorRegistryLimit>,
+    pub balances: BalancesDiff<C::ValidatorRegistryLimit>,
+
+    // Shuffling
+    pub start_shard: Option<Shard>,
+    pub randao_mixes: VariableList<Entry<H256>, C::EpochsPerHistoricalVector>,
+    pub active_index_roots: VariableList<Entry<H256>, C::EpochsPerHistoricalVector>,
+    pub compact_committees_roots: VariableList<Entry<H256>, C::EpochsPerHistoricalVector>,
+
+    // Slashings
+    pub slashings: VariableList<Entry<u64>, C::EpochsPerSlashingsVector>,
+
+    // Attestations
+    pub previous_epoch_attestations: ListDiff<PendingAttestation<C>, C::MaxAttestationsPerEpoch>,
+    pub current_epoch_attestations: ListDiff<PendingAttestation<C>, C::MaxAttestationsPerEpoch>,
+
+    // Crosslinks
+    pub previous_crosslinks: VariableList<Entry<Crosslink>, C::ShardCount>,
+    pub current_crosslinks: VariableList<Entry<Crosslink>, C::ShardCount>,
+
+    // Finality
+    pub justification_bits: Option<BitVector<consts::JustificationBitsLength>>,
+    pub previous_justified_checkpoint: Option<Checkpoint>,
+    pub current_justified_checkpoint: Option<Checkpoint>,
+    pub finalized_checkpoint: Option<Checkpoint>,
+}
+
+impl<C: Config> BeaconStateDiff<C> {
+    /// Returns the diff that turns `pre` into `post`.
+    pub fn new(pre: &BeaconState<C>, post: &BeaconState<C>) -> Self {
+        Self {
+            pre_root: pre.tree_hash_root(),
+            post_root: post.tree_hash_root(),
+            genesis_time: changed(&pre.genesis_time, &post.genesis_time),
+            slot: changed(&pre.slot, &post.slot),
+            fork: changed(&pre.fork, &post.fork),
+            latest_block_header: changed(&pre.latest_block_header, &post.latest_block_header),
+            block_roots: vector_diff(&pre.block_roots, &post.block_roots),
+            state_roots: vector_diff(&pre.state_roots, &post.state_roots),
+            historical_roots: list_diff(&pre.historical_roots, &post.historical_roots),
+            eth1_data: changed(&pre.eth1_data, &post.eth1_data),
+            eth1_data_votes: list_diff(&pre.eth1_data_votes, &post.eth1_data_votes),
+            eth1_deposit_index: changed(&pre.eth1_deposit_index, &post.eth1_deposit_index),
+            validators: list_diff(&pre.validators, &post.validators),
+            balances: balances_diff(&pre.balances, &post.balances),
+            start_shard: changed(&pre.start_shard, &post.start_shard),
+            randao_mixes: vector_diff(&pre.randao_mixes, &post.randao_mixes),
+            active_index_roots: vector_diff(&pre.active_index_roots, &post.active_index_roots),
+            compact_committees_roots: vector_diff(
+                &pre.compact_committees_roots,
+                &post.compact_committees_roots,
+            ),
+            slashings: vector_diff(&pre.slashings, &post.slashings),
+            previous_epoch_attestations: list_diff(
+                &pre.previous_epoch_attestations,
+                &post.previous_epoch_attestations,
+            ),
+            current_epoch_attestations: list_diff(
+                &pre.current_epoch_attestations,
+                &post.current_epoch_attestations,
+            ),
+            previous_crosslinks: vector_diff(&pre.previous_crosslinks, &post.previous_crosslinks),
+            current_crosslinks: vector_diff(&pre.current_crosslinks, &post.current_crosslinks),
+            justification_bits: changed(&pre.justification_bits, &post.justification_bits),
+            previous_justified_checkpoint: changed(
+                &pre.previous_justified_checkpoint,
+                &post.previous_justified_checkpoint,
+            ),
+            current_justified_checkpoint: changed(
+                &pre.current_justified_checkpoint,
+                &post.current_justified_checkpoint,
+            ),
+            finalized_checkpoint: changed(&pre.finalized_checkpoint, &post.finalized_checkpoint),
+        }
+    }
+
+    /// Returns the state the diff was computed to, by applying it to `pre`.
+    ///
+    /// Returns `Err` unless `pre` has the root of the state the diff was computed from and the
+    /// result has the root of the state it was computed to.
+    pub fn apply(&self, pre: &BeaconState<C>) -> Result<BeaconState<C>, Error> {
+        let found = pre.tree_hash_root();
+        if found != self.pre_root {
+            return Err(Error::PreRootMismatch {
+                expected: self.pre_root,
+                found,
+            });
+        }
+
+        let mut state = pre.clone();
+        replace(&mut state.genesis_time, &self.genesis_time);
+        replace(&mut state.slot, &self.slot);
+        replace(&mut state.fork, &self.fork);
+        replace(&mut state.latest_block_header, &self.latest_block_header);
+        apply_vector(&mut state.block_roots, &self.block_roots, "block_roots")?;
+        apply_vector(&mut state.state_roots, &self.state_roots, "state_roots")?;
+        apply_list(
+            &mut state.historical_roots,
+            &self.historical_roots,
+            "historical_roots",
+        )?;
+        replace(&mut state.eth1_data, &self.eth1_data);
+        apply_list(
+            &mut state.eth1_data_votes,
+            &self.eth1_data_votes,
+            "eth1_data_votes",
+        )?;
+        replace(&mut state.eth1_deposit_index, &self.eth1_deposit_index);
+        apply_list(&mut state.validators, &self.validators, "validators")?;
+        apply_balances(&mut state.balances, &self.balances)?;
+        replace(&mut state.start_shard, &self.start_shard);
+        apply_vector(&mut state.randao_mixes, &self.randao_mixes, "randao_mixes")?;
+        apply_vector(
+            &mut state.active_index_roots,
+            &self.active_index_roots,
+            "active_index_roots",
+        )?;
+        apply_vector(
+            &mut state.compact_committees_roots,
+            &self.compact_committees_roots,
+            "compact_committees_roots",
+        )?;
+        apply_vector(&mut state.slashings, &self.slashings, "slashings")?;
+        apply_list(
+            &mut state.previous_epoch_attestations,
+            &self.previous_epoch_attestations,
+            "previous_epoch_attestations",
+        )?;
+        apply_list(
+            &mut state.current_epoch_attestations,
+            &self.current_epoch_attestations,
+            "current_epoch_attestations",
+        )?;
+        apply_vector(
+            &mut state.previous_crosslinks,
+            &self.previous_crosslinks,
+            "previous_crosslinks",
+        )?;
+        apply_vector(
+            &mut state.current_crosslinks,
+            &self.current_crosslinks,
+            "current_crosslinks",
+        )?;
+        replace(&mut state.justification_bits, &self.justification_bits);
+        replace(
+            &mut state.previous_justified_checkpoint,
+            &self.previous_justified_checkpoint,
+        );
+        replace(
+            &mut state.current_justified_checkpoint,
+            &self.current_justified_checkpoint,
+        );
+        replace(&mut state.finalized_checkpoint, &self.finalized_checkpoint);
+
+        let found = state.tree_hash_root();
+        if found != self.post_root {
+            return Err(Error::PostRootMismatch {
+                expected: self.post_root,
+                found,
+            });
+        }
+
+        Ok(state)
+    }
+}
+
+fn changed<T: Clone + PartialEq>(pre: &T, post: &T) -> Option<T> {
+    if pre == post {
+        None
+    } else {
+        Some(post.clone())
+    }
+}
+
+fn replace<T: Clone>(field: &mut T, value: &Option<T>) {
+    if let Some(value) = value {
+        *field = value.clone();
+    }
+}
+
+/// Returns the entries of `post` that differ from `pre`, or that `pre` does not have.
+fn entries<'a, T: Element + 'a>(
+    pre: impl Fn(usize) -> Option<&'a T>,
+    post: impl Iterator<Item = &'a T>,
+) -> Vec<Entry<T>> {
+    post.enumerate()
+        .filter(|(i, value)| pre(*i) != Some(value))
+        .map(|(i, value)| Entry {
+            index: i as u64,
+            value: value.clone(),
+        })
+        .collect()
+}
+
+fn vector_diff<T: Element, N: Unsigned>(
+    pre: &PersistentVector<T, N>,
+    post: &PersistentVector<T, N>,
+) -> VariableList<Entry<T>, N> {
+    // A vector has `N` entries, so they always fit.
+    VariableList::from(entries(|i| pre.get(i), post.iter()))
+}
+
+fn list_diff<T: Element, N: Unsigned>(
+    pre: &PersistentList<T, N>,
+    post: &PersistentList<T, N>,
+) -> ListDiff<T, N> {
+    ListDiff {
+        len: post.len() as u64,
+        entries: VariableList::from(entries(|i| pre.get(i), post.iter())),
+    }
+}
+
+fn balances_diff<N: Unsigned>(
+    pre: &PersistentList<Gwei, N>,
+    post: &PersistentList<Gwei, N>,
+) -> BalancesDiff<N> {
+    let deltas = post
+        .iter()
+        .enumerate()
+        .filter(|(i, balance)| pre.get(*i) != Some(balance))
+        .map(|(i, balance)| BalanceDelta {
+            index: i as u64,
+            delta: balance
+                .as_u64()
+                .wrapping_sub(pre.get(i).map_or(0, |pre| pre.as_u64())),
+        })
+        .collect::<Vec<_>>();
+
+    BalancesDiff {
+        len: post.len() as u64,
+        deltas: VariableList::from(deltas),
+    }
+}
+
+fn apply_vector<T: Element, N: Unsigned>(
+    vector: &mut PersistentVector<T, N>,
+    entries: &VariableList<Entry<T>, N>,
+    field: &'static str,
+) -> Result<(), Error> {
+    for entry in entries.iter() {
+        vector
+            .set(entry.index as usize, entry.value.clone())
+            .map_err(|_| Error::OutOfBounds {
+                field,
+                index: entry.index,
+            })?;
+    }
+    Ok(())
+}
+
+/// Truncates `list` to `len`, by rebuilding it as `PersistentList` can only grow.
+fn truncate<T: Element, N: Unsigned>(list: &mut PersistentList<T, N>, len: u64) {
+    if len < list.len() as u64 {
+        *list = PersistentList::from(list.iter().take(len as usize).cloned().collect::<Vec<_>>());
+    }
+}
+
+/// Sets the value at `index`, or appends it if `index` is just past the end of `list`.
+fn set_or_push<T: Element, N: Unsigned>(
+    list: &mut PersistentList<T, N>,
+    index: u64,
+    f: impl FnOnce(&mut T),
+    default: impl FnOnce() -> T,
+) -> Result<(), ssz_types::Error> {
+    if index < list.len() as u64 {
+        list.update(index as usize, f)
+    } else if index == list.len() as u64 {
+        let mut value = default();
+        f(&mut value);
+        list.push(value)
+    } else {
+        Err(ssz_types::Error::OutOfBounds {
+            i: index as usize,
+            len: list.len(),
+        })
+    }
+}
+
+fn apply_list<T: Element, N: Unsigned>(
+    list: &mut PersistentList<T, N>,
+    diff: &ListDiff<T, N>,
+    field: &'static str,
+) -> Result<(), Error> {
+    truncate(list, diff.len);
+    for entry in diff.entries.iter() {
+        set_or_push(
+            list,
+            entry.index,
+            |value| *value = entry.value.clone(),
+            || entry.value.clone(),
+        )
+        .map_err(|_| Error::OutOfBounds {
+            field,
+            index: entry.index,
+        })?;
+    }
+    Ok(())
+}
+
+fn apply_balances<N: Unsigned>(
+    balances: &mut PersistentList<Gwei, N>,
+    diff: &BalancesDiff<N>,
+) -> Result<(), Error> {
+    truncate(balances, diff.len);
+    for delta in diff.deltas.iter() {
+        set_or_push(
+            balances,
+            delta.index,
+            |balance| *balance = Gwei::new(balance.as_u64().wrapping_add(delta.delta)),
+            Gwei::default,
+        )
+        .map_err(|_| Error::OutOfBounds {
+            field: "balances",
+            index: delta.index,
+        })?;
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::MinimalConfig;
+    use ssz_types::BitList;
+
+    type State = BeaconState<MinimalConfig>;
+    type Diff = BeaconStateDiff<MinimalConfig>;
+
+    fn validator(effective_balance: u64) -> Validator {
+        Validator {
+            pubkey: PublicKey::default(),
+            withdrawal_credentials: H256::zero(),
+            effective_balance: Gwei::new(effective_balance),
+            slashed: false,
+            activation_eligibility_epoch: Epoch::new(0),
+            activation_epoch: Epoch::new(0),
+            exit_epoch: Epoch::new(10),
+            withdrawable_epoch: Epoch::new(20),
+        }
+    }
+
+    fn attestation(proposer_index: u64) -> PendingAttestation<MinimalConfig> {
+        PendingAttestation {
+            aggregation_bits: BitList::with_capacity(4).unwrap(),
+            data: AttestationData {
+                beacon_block_root: H256::zero(),
+                source: Checkpoint::default(),
+                target: Checkpoint::default(),
+                crosslink: Crosslink::default(),
+            },
+            inclusion_delay: 1,
+            proposer_index: ValidatorIndex::new(proposer_index),
+        }
+    }
+
+    fn state() -> State {
+        BeaconState {
+            genesis_time: 0,
+            slot: Slot::new(42),
+            fork: Fork {
+                previous_version: [0; 4],
+                current_version: [0; 4],
+                epoch: Epoch::new(0),
+            },
+            latest_block_header: BeaconBlockHeader {
+                slot: Slot::new(0),
+                parent_root: H256::zero(),
+                state_root: H256::zero(),
+                body_root: H256::zero(),
+                signature: Signature::empty_signature(),
+            },
+            block_roots: PersistentVector::from(vec![]),
+            state_roots: PersistentVector::from(vec![]),
+            historical_roots: PersistentList::from(vec![]),
+            eth1_data: Eth1Data::default(),
+            eth1_data_votes: PersistentList::from(vec![]),
+            eth1_deposit_index: 0,
+            validators: PersistentList::from(vec![validator(32), validator(31), validator(30)]),
+            balances: PersistentList::from(vec![Gwei::new(32), Gwei::new(31), Gwei::new(30)]),
+            start_shard: Shard::new(0),
+            randao_mixes: PersistentVector::from(vec![]),
+            active_index_roots: PersistentVector::from(vec![]),
+            compact_committees_roots: PersistentVector::from(vec![]),
+            slashings: PersistentVector::from(vec![]),
+            previous_epoch_attestations: PersistentList::from(vec![]),
+            current_epoch_attestations: PersistentList::from(vec![attestation(0), attestation(1)]),
+            previous_crosslinks: PersistentVector::from(vec![]),
+            current_crosslinks: PersistentVector::from(vec![]),
+            justification_bits: BitVector::new(),
+            previous_justified_checkpoint: Checkpoint::default(),
+            current_justified_checkpoint: Checkpoint::default(),
+            finalized_checkpoint: Checkpoint::default(),
+        }
+    }
+
+    /// The next slot of `state`, which is the first of an epoch.
+    fn next(state: &State) -> State {
+        let mut next = state.clone();
+        next.slot = Slot::new(43);
+        next.block_roots.set(42, H256::repeat_byte(1)).unwrap();
+        next.validators
+            .update(1, |validator| validator.slashed = true)
+            .unwrap();
+        next.validators.push(validator(1)).unwrap();
+        next.balances.set(0, Gwei::new(33)).unwrap();
+        next.balances.set(1, Gwei::new(20)).unwrap();
+        next.balances.push(Gwei::new(1)).unwrap();
+        next.previous_epoch_attestations = next.current_epoch_attestations.clone();
+        next.current_epoch_attestations = PersistentList::empty();
+        next.finalized_checkpoint = Checkpoint {
+            epoch: Epoch::new(3),
+            root: H256::repeat_byte(7),
+        };
+        next
+    }
+
+    #[test]
+    fn apply_reproduces_post() {
+        let pre = state();
+        let post = next(&pre);
+        let diff = Diff::new(&pre, &post);
+
+        assert_eq!(diff.slot, Some(Slot::new(43)));
+        assert_eq!(diff.genesis_time, None);
+        assert_eq!(diff.block_roots.len(), 1);
+        assert_eq!(
+            diff.validators
+                .entries
+                .iter()
+                .map(|entry| entry.index)
+                .collect::<Vec<_>>(),
+            vec![1, 3]
+        );
+        assert_eq!(
+            diff.balances
+                .deltas
+                .iter()
+                .map(BalanceDelta::signed)
+                .collect::<Vec<_>>(),
+            vec![1, -11, 1]
+        );
+        assert_eq!(diff.current_epoch_attestations.len, 0);
+        assert!(diff.current_epoch_attestations.entries.is_empty());
+
+        assert_eq!(diff.apply(&pre), Ok(post.clone()));
+        assert_eq!(Diff::new(&post, &pre).apply(&post), Ok(pre.clone()));
+
+        let unchanged = Diff::new(&pre, &pre);
+        assert_eq!(unchanged.slot, None);
+        assert!(unchanged.validators.entries.is_empty());
+        assert_eq!(unchanged.apply(&pre), Ok(pre));
+    }
+
+    #[test]
+    fn ssz_round_trip() {
+        let pre = state();
+        let diff = Diff::new(&pre, &next(&pre));
+        let bytes = diff.as_ssz_bytes();
+
+        assert!(bytes.len() < next(&pre).as_ssz_bytes().len());
+        assert_eq!(Diff::from_ssz_bytes(&bytes), Ok(diff));
+    }
+
+    #[test]
+    fn apply_verifies_roots() {
+        let pre = state();
+        let post = next(&pre);
+        let mut diff = Diff::new(&pre, &post);
+
+        assert_eq!(
+            diff.apply(&post),
+            Err(Error::PreRootMismatch {
+                expected: pre.tree_hash_root(),
+                found: post.tree_hash_root(),
+            })
+        );
+
+        diff.start_shard = Some(Shard::new(1));
+        assert!(match diff.apply(&pre) {
+            Err(Error::PostRootMismatch { expected, .. }) => expected == post.tree_hash_root(),
+            _ => false,
+        });
+
+        let mut diff = Diff::new(&pre, &post);
+        diff.validators.entries[1].index = 5;
+        assert_eq!(
+            diff.apply(&pre),
+            Err(Error::OutOfBounds {
+                field: "validators",
+                index: 5,
+            })
+        );
+    }
+}
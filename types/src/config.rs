@@ -209,6 +209,9 @@ where
     fn ejection_balance() -> u64 {
         16_000_000_000
     }
+    fn eth1_follow_distance() -> u64 {
+        1024
+    }
     fn genesis_epoch() -> u64 {
         0
     }
@@ -233,8 +236,8 @@ where
     fn min_epochs_to_inactivity_penalty() -> u64 {
         4
     }
-    fn min_genesis_active_validator_count() -> u64 {
-        64
+    fn min_genesis_active_validator_count() -> ValidatorIndex {
+        ValidatorIndex::new(64)
     }
     // Bitcoin's 11th anniversary
     // (see <https://github.com/ethereum/eth2.0-specs/issues/1129#issue-448918350>).
@@ -316,6 +319,10 @@ impl Config for MinimalConfig {
     type SlotsPerEth1VotingPeriod = typenum::U16;
     type SlotsPerHistoricalRoot = typenum::U64;
     type ValidatorRegistryLimit = typenum::U1099511627776;
+
+    fn eth1_follow_distance() -> u64 {
+        16
+    }
 }
 
 #[derive(
@@ -341,8 +348,11 @@ impl Config for QuickConfig {
     type SlotsPerHistoricalRoot = typenum::U64;
     type ValidatorRegistryLimit = typenum::U1099511627776;
 
+    fn eth1_follow_distance() -> u64 {
+        16
+    }
     fn min_genesis_active_validator_count() -> ValidatorIndex {
-        1
+        ValidatorIndex::new(1)
     }
     fn min_genesis_time() -> u64 {
         9_476_400
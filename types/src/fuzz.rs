@@ -0,0 +1,368 @@
+//! `Arbitrary` implementations for the containers in this crate, used by the structure-aware
+//! fuzz targets in `types/fuzz`.
+//!
+//! Most field types (`H256`, BLS keys and signatures, `ssz_types` collections) are foreign, so
+//! they cannot implement `Arbitrary` here. Fields are generated through the private `Generate`
+//! trait instead and `Arbitrary` is implemented only for the types this crate defines.
+//!
+//! Generated values always encode to valid SSZ. Lists are kept short so that a fuzzer spends its
+//! input on field contents rather than on lengths, and BLS values are derived from a small range
+//! of secret keys.
+
+use arbitrary::{Arbitrary, Error, Result, Unstructured};
+use bls::{PublicKeyBytes, SignatureBytes};
+use core::cmp;
+use ssz_types::{BitList, BitVector, FixedVector, PersistentList, PersistentVector, VariableList};
+use tree_hash::TreeHash;
+use typenum::Unsigned;
+
+use crate::beacon_state::BeaconState;
+use crate::config::Config;
+use crate::primitives::*;
+use crate::types::*;
+
+/// Upper bound on the length of generated `VariableList`s.
+const MAX_LIST_LEN: usize = 8;
+
+/// Upper bound on the length of generated `BitList`s.
+const MAX_BITLIST_LEN: usize = 64;
+
+trait Generate: Sized {
+    fn generate(u: &mut Unstructured) -> Result<Self>;
+}
+
+macro_rules! generate_with_arbitrary {
+    ($($type:ty),*) => {
+        $(
+            impl Generate for $type {
+                fn generate(u: &mut Unstructured) -> Result<Self> {
+                    u.arbitrary()
+                }
+            }
+        )*
+    };
+}
+
+generate_with_arbitrary!(bool, u64, usize, [u8; 4], [u8; 32]);
+
+impl Generate for H256 {
+    fn generate(u: &mut Unstructured) -> Result<Self> {
+        let bytes: [u8; 32] = u.arbitrary()?;
+        Ok(H256::from(bytes))
+    }
+}
+
+impl Generate for SecretKey {
+    fn generate(u: &mut Unstructured) -> Result<Self> {
+        let mut bytes = [0; 48];
+        bytes[47] = u.int_in_range(1..=255)?;
+        SecretKey::from_bytes(&bytes).map_err(|_| Error::IncorrectFormat)
+    }
+}
+
+impl Generate for PublicKey {
+    fn generate(u: &mut Unstructured) -> Result<Self> {
+        Ok(PublicKey::from_secret_key(&SecretKey::generate(u)?))
+    }
+}
+
+impl Generate for Signature {
+    fn generate(u: &mut Unstructured) -> Result<Self> {
+        if u.arbitrary()? {
+            return Ok(Signature::empty_signature());
+        }
+        let message: [u8; 32] = u.arbitrary()?;
+        let domain = u.arbitrary()?;
+        Ok(Signature::new(&message, domain, &SecretKey::generate(u)?))
+    }
+}
+
+impl Generate for PublicKeyBytes {
+    fn generate(u: &mut Unstructured) -> Result<Self> {
+        let mut bytes = [0; 48];
+        u.fill_buffer(&mut bytes)?;
+        PublicKeyBytes::from_bytes(&bytes).map_err(|_| Error::IncorrectFormat)
+    }
+}
+
+impl Generate for SignatureBytes {
+    fn generate(u: &mut Unstructured) -> Result<Self> {
+        let mut bytes = [0; 96];
+        u.fill_buffer(&mut bytes)?;
+        SignatureBytes::from_bytes(&bytes).map_err(|_| Error::IncorrectFormat)
+    }
+}
+
+impl<T: Generate, N: Unsigned> Generate for VariableList<T, N> {
+    fn generate(u: &mut Unstructured) -> Result<Self> {
+        let len = u.int_in_range(0..=cmp::min(N::to_usize(), MAX_LIST_LEN))?;
+        let items = (0..len)
+            .map(|_| T::generate(u))
+            .collect::<Result<Vec<_>>>()?;
+        VariableList::new(items).map_err(|_| Error::IncorrectFormat)
+    }
+}
+
+impl<T: Generate, N: Unsigned> Generate for FixedVector<T, N> {
+    fn generate(u: &mut Unstructured) -> Result<Self> {
+        let items = (0..N::to_usize())
+            .map(|_| T::generate(u))
+            .collect::<Result<Vec<_>>>()?;
+        FixedVector::new(items).map_err(|_| Error::IncorrectFormat)
+    }
+}
+
+impl<T: Generate + TreeHash, N: Unsigned> Generate for PersistentList<T, N> {
+    fn generate(u: &mut Unstructured) -> Result<Self> {
+        let list: VariableList<T, N> = Generate::generate(u)?;
+        PersistentList::new(list.into()).map_err(|_| Error::IncorrectFormat)
+    }
+}
+
+impl<T: Generate + TreeHash, N: Unsigned> Generate for PersistentVector<T, N> {
+    fn generate(u: &mut Unstructured) -> Result<Self> {
+        let vector: FixedVector<T, N> = Generate::generate(u)?;
+        PersistentVector::new(vector.into()).map_err(|_| Error::IncorrectFormat)
+    }
+}
+
+impl<N: Unsigned + Clone> Generate for BitList<N> {
+    fn generate(u: &mut Unstructured) -> Result<Self> {
+        let len = u.int_in_range(0..=cmp::min(N::to_usize(), MAX_BITLIST_LEN))?;
+        let mut bits = BitList::with_capacity(len).map_err(|_| Error::IncorrectFormat)?;
+        for i in 0..len {
+            bits.set(i, u.arbitrary()?)
+                .map_err(|_| Error::IncorrectFormat)?;
+        }
+        Ok(bits)
+    }
+}
+
+impl<N: Unsigned + Clone> Generate for BitVector<N> {
+    fn generate(u: &mut Unstructured) -> Result<Self> {
+        let mut bits = BitVector::new();
+        for i in 0..N::to_usize() {
+            bits.set(i, u.arbitrary()?)
+                .map_err(|_| Error::IncorrectFormat)?;
+        }
+        Ok(bits)
+    }
+}
+
+macro_rules! impl_arbitrary_for_newtypes {
+    ($($name:ident),*) => {
+        $(
+            impl Generate for $name {
+                fn generate(u: &mut Unstructured) -> Result<Self> {
+                    u.arbitrary().map($name::new)
+                }
+            }
+
+            impl Arbitrary for $name {
+                fn arbitrary(u: &mut Unstructured) -> Result<Self> {
+                    Self::generate(u)
+                }
+            }
+        )*
+    };
+}
+
+impl_arbitrary_for_newtypes!(Epoch, Gwei, Shard, Slot, ValidatorIndex);
+
+/// Implements `Generate` and `Arbitrary` for a container by generating each field in order.
+macro_rules! impl_arbitrary_for_containers {
+    ($($name:ident { $($field:ident),* $(,)? })*) => {
+        $(
+            impl Generate for $name {
+                fn generate(u: &mut Unstructured) -> Result<Self> {
+                    Ok($name {
+                        $($field: Generate::generate(u)?,)*
+                    })
+                }
+            }
+
+            impl Arbitrary for $name {
+                fn arbitrary(u: &mut Unstructured) -> Result<Self> {
+                    Self::generate(u)
+                }
+            }
+        )*
+    };
+    ($($name:ident<C> { $($field:ident),* $(,)? })*) => {
+        $(
+            impl<C: Config> Generate for $name<C> {
+                fn generate(u: &mut Unstructured) -> Result<Self> {
+                    Ok($name {
+                        $($field: Generate::generate(u)?,)*
+                    })
+                }
+            }
+
+            impl<C: Config + 'static> Arbitrary for $name<C> {
+                fn arbitrary(u: &mut Unstructured) -> Result<Self> {
+                    Self::generate(u)
+                }
+            }
+        )*
+    };
+}
+
+impl_arbitrary_for_containers! {
+    AttestationData {
+        beacon_block_root,
+        source,
+        target,
+        crosslink,
+    }
+    AttestationDataAndCustodyBit {
+        data,
+        custody_bit,
+    }
+    AttestationDuty {
+        slot,
+        shard,
+        committee_index,
+        committee_len,
+    }
+    BeaconBlockHeader {
+        slot,
+        parent_root,
+        state_root,
+        body_root,
+        signature,
+    }
+    Checkpoint {
+        epoch,
+        root,
+    }
+    Crosslink {
+        shard,
+        parent_root,
+        start_epoch,
+        end_epoch,
+        data_root,
+    }
+    Deposit {
+        proof,
+        data,
+    }
+    DepositData {
+        pubkey,
+        withdrawal_credentials,
+        amount,
+        signature,
+    }
+    Eth1Data {
+        deposit_root,
+        deposit_count,
+        block_hash,
+    }
+    Fork {
+        previous_version,
+        current_version,
+        epoch,
+    }
+    ProposerSlashing {
+        proposer_index,
+        header_1,
+        header_2,
+    }
+    Transfer {
+        sender,
+        recipient,
+        amount,
+        fee,
+        slot,
+        pubkey,
+        signature,
+    }
+    Validator {
+        pubkey,
+        withdrawal_credentials,
+        effective_balance,
+        slashed,
+        activation_eligibility_epoch,
+        activation_epoch,
+        exit_epoch,
+        withdrawable_epoch,
+    }
+    VoluntaryExit {
+        epoch,
+        validator_index,
+        signature,
+    }
+}
+
+impl_arbitrary_for_containers! {
+    Attestation<C> {
+        aggregation_bits,
+        data,
+        custody_bits,
+        signature,
+    }
+    AttesterSlashing<C> {
+        attestation_1,
+        attestation_2,
+    }
+    BeaconBlock<C> {
+        slot,
+        parent_root,
+        state_root,
+        body,
+        signature,
+    }
+    BeaconBlockBody<C> {
+        randao_reveal,
+        eth1_data,
+        graffiti,
+        proposer_slashings,
+        attester_slashings,
+        attestations,
+        deposits,
+        voluntary_exits,
+        transfers,
+    }
+    HistoricalBatch<C> {
+        block_roots,
+        state_roots,
+    }
+    IndexedAttestation<C> {
+        custody_bit_0_indices,
+        custody_bit_1_indices,
+        data,
+        signature,
+    }
+    PendingAttestation<C> {
+        aggregation_bits,
+        data,
+        inclusion_delay,
+        proposer_index,
+    }
+    BeaconState<C> {
+        genesis_time,
+        slot,
+        fork,
+        latest_block_header,
+        block_roots,
+        state_roots,
+        historical_roots,
+        eth1_data,
+        eth1_data_votes,
+        eth1_deposit_index,
+        validators,
+        balances,
+        start_shard,
+        randao_mixes,
+        active_index_roots,
+        compact_committees_roots,
+        slashings,
+        previous_epoch_attestations,
+        current_epoch_attestations,
+        previous_crosslinks,
+        current_crosslinks,
+        justification_bits,
+        previous_justified_checkpoint,
+        current_justified_checkpoint,
+        finalized_checkpoint,
+    }
+}
@@ -1,5 +1,9 @@
 pub mod beacon_state;
+pub mod beacon_state_diff;
 pub mod config;
 pub mod consts;
+#[cfg(feature = "arbitrary")]
+mod fuzz;
 pub mod primitives;
+pub mod serde_utils;
 pub mod types;
@@ -1,10 +1,251 @@
+use core::fmt;
+use core::ops::{Add, AddAssign, Mul, Sub, SubAssign};
+
+use serde::{Deserialize, Serialize};
+use ssz_derive::{Decode, DecodeView, Encode, SszSchema};
+use tree_hash_derive::TreeHash;
+use typenum::Unsigned;
+
+use crate::config::Config;
+
 pub use bls::{PublicKey, SecretKey, Signature};
 pub use ethereum_types::H256;
 
-pub type Epoch = u64;
-pub type Gwei = u64;
-pub type Shard = u64;
-pub type Slot = u64;
-pub type ValidatorIndex = u64;
 pub type ValidatorId = PublicKey;
 pub type Version = [u8; 4];
+
+/// Defines a newtype around `u64` that is encoded exactly like a `u64` (SSZ, tree hash and serde).
+///
+/// The `+`, `-` and `*` operators saturate rather than overflow or underflow. The `checked_*`
+/// methods return `None` instead.
+macro_rules! u64_newtype {
+    ($(#[$attr:meta])* $name:ident) => {
+        $(#[$attr])*
+        #[derive(
+            Clone,
+            Copy,
+            Default,
+            PartialEq,
+            Eq,
+            PartialOrd,
+            Ord,
+            Hash,
+            Debug,
+            Deserialize,
+            Serialize,
+            Encode,
+            Decode,
+            DecodeView,
+            SszSchema,
+            TreeHash,
+        )]
+        #[serde(transparent)]
+        #[ssz(transparent)]
+        #[tree_hash(transparent)]
+        pub struct $name(u64);
+
+        impl $name {
+            pub const fn new(value: u64) -> Self {
+                $name(value)
+            }
+
+            pub const fn as_u64(self) -> u64 {
+                self.0
+            }
+
+            pub fn as_usize(self) -> usize {
+                self.0 as usize
+            }
+
+            pub fn checked_add<T: Into<Self>>(self, other: T) -> Option<Self> {
+                self.0.checked_add(other.into().0).map($name)
+            }
+
+            pub fn checked_sub<T: Into<Self>>(self, other: T) -> Option<Self> {
+                self.0.checked_sub(other.into().0).map($name)
+            }
+
+            pub fn checked_mul<T: Into<Self>>(self, other: T) -> Option<Self> {
+                self.0.checked_mul(other.into().0).map($name)
+            }
+
+            pub fn checked_div<T: Into<Self>>(self, other: T) -> Option<Self> {
+                self.0.checked_div(other.into().0).map($name)
+            }
+
+            pub fn saturating_add<T: Into<Self>>(self, other: T) -> Self {
+                $name(self.0.saturating_add(other.into().0))
+            }
+
+            pub fn saturating_sub<T: Into<Self>>(self, other: T) -> Self {
+                $name(self.0.saturating_sub(other.into().0))
+            }
+
+            pub fn saturating_mul<T: Into<Self>>(self, other: T) -> Self {
+                $name(self.0.saturating_mul(other.into().0))
+            }
+        }
+
+        impl From<u64> for $name {
+            fn from(value: u64) -> Self {
+                $name(value)
+            }
+        }
+
+        impl From<$name> for u64 {
+            fn from(value: $name) -> Self {
+                value.0
+            }
+        }
+
+        impl PartialEq<u64> for $name {
+            fn eq(&self, other: &u64) -> bool {
+                self.0 == *other
+            }
+        }
+
+        impl PartialOrd<u64> for $name {
+            fn partial_cmp(&self, other: &u64) -> Option<core::cmp::Ordering> {
+                self.0.partial_cmp(other)
+            }
+        }
+
+        impl<T: Into<$name>> Add<T> for $name {
+            type Output = Self;
+
+            fn add(self, other: T) -> Self {
+                self.saturating_add(other)
+            }
+        }
+
+        impl<T: Into<$name>> AddAssign<T> for $name {
+            fn add_assign(&mut self, other: T) {
+                *self = self.saturating_add(other);
+            }
+        }
+
+        impl<T: Into<$name>> Sub<T> for $name {
+            type Output = Self;
+
+            fn sub(self, other: T) -> Self {
+                self.saturating_sub(other)
+            }
+        }
+
+        impl<T: Into<$name>> SubAssign<T> for $name {
+            fn sub_assign(&mut self, other: T) {
+                *self = self.saturating_sub(other);
+            }
+        }
+
+        impl<T: Into<$name>> Mul<T> for $name {
+            type Output = Self;
+
+            fn mul(self, other: T) -> Self {
+                self.saturating_mul(other)
+            }
+        }
+
+        impl fmt::Display for $name {
+            fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+                self.0.fmt(f)
+            }
+        }
+    };
+}
+
+u64_newtype!(Epoch);
+u64_newtype!(Gwei);
+u64_newtype!(Shard);
+u64_newtype!(Slot);
+u64_newtype!(ValidatorIndex);
+
+impl Slot {
+    /// The epoch containing this slot.
+    pub fn epoch<C: Config>(self) -> Epoch {
+        Epoch(self.0 / C::SlotsPerEpoch::to_u64())
+    }
+}
+
+impl Epoch {
+    /// The first slot of this epoch.
+    pub fn start_slot<C: Config>(self) -> Slot {
+        Slot(self.0.saturating_mul(C::SlotsPerEpoch::to_u64()))
+    }
+
+    /// The last slot of this epoch.
+    pub fn end_slot<C: Config>(self) -> Slot {
+        self.start_slot::<C>() + (C::SlotsPerEpoch::to_u64() - 1)
+    }
+
+    /// The slots of this epoch, in order.
+    pub fn slot_iter<C: Config>(self) -> impl Iterator<Item = Slot> {
+        (self.start_slot::<C>().0..=self.end_slot::<C>().0).map(Slot)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::{MainnetConfig, QuickConfig};
+
+    #[test]
+    fn arithmetic_saturates() {
+        assert_eq!(Slot::new(3) + 4, Slot::new(7));
+        assert_eq!(Slot::new(3) - Slot::new(4), Slot::new(0));
+        assert_eq!(Gwei::new(u64::max_value()) + 1, Gwei::new(u64::max_value()));
+        assert_eq!(Gwei::new(u64::max_value()) * 2, Gwei::new(u64::max_value()));
+
+        let mut epoch = Epoch::new(1);
+        epoch += 2;
+        epoch -= Epoch::new(5);
+        assert_eq!(epoch, 0);
+    }
+
+    #[test]
+    fn checked_arithmetic() {
+        assert_eq!(Slot::new(3).checked_add(4), Some(Slot::new(7)));
+        assert_eq!(Slot::new(3).checked_sub(4), None);
+        assert_eq!(Gwei::new(u64::max_value()).checked_mul(2), None);
+        assert_eq!(Gwei::new(6).checked_div(0), None);
+        assert_eq!(Gwei::new(6).checked_div(4), Some(Gwei::new(1)));
+    }
+
+    #[test]
+    fn slots_and_epochs() {
+        // MainnetConfig has 8 slots per epoch.
+        assert_eq!(Slot::new(0).epoch::<MainnetConfig>(), Epoch::new(0));
+        assert_eq!(Slot::new(7).epoch::<MainnetConfig>(), Epoch::new(0));
+        assert_eq!(Slot::new(8).epoch::<MainnetConfig>(), Epoch::new(1));
+
+        let epoch = Epoch::new(2);
+        assert_eq!(epoch.start_slot::<MainnetConfig>(), Slot::new(16));
+        assert_eq!(epoch.end_slot::<MainnetConfig>(), Slot::new(23));
+        assert_eq!(
+            epoch.slot_iter::<MainnetConfig>().collect::<Vec<_>>(),
+            (16..24).map(Slot::new).collect::<Vec<_>>()
+        );
+        assert_eq!(
+            epoch.slot_iter::<QuickConfig>().collect::<Vec<_>>(),
+            vec![Slot::new(2)]
+        );
+    }
+
+    #[test]
+    fn encodings_match_u64() {
+        use ssz::Encode;
+        use ssz_types::VariableList;
+        use tree_hash::TreeHash;
+        use typenum::U8;
+
+        let slot = Slot::new(0x0102_0304);
+
+        assert_eq!(slot.as_ssz_bytes(), 0x0102_0304u64.as_ssz_bytes());
+        assert_eq!(slot.tree_hash_root(), 0x0102_0304u64.tree_hash_root());
+        // Packed into lists like a `u64`.
+        assert_eq!(
+            VariableList::<Slot, U8>::from(vec![slot; 5]).tree_hash_root(),
+            VariableList::<u64, U8>::from(vec![0x0102_0304; 5]).tree_hash_root()
+        );
+    }
+}
@@ -0,0 +1,1060 @@
+//! Serde representations for fields whose derived format differs from the YAML of the spec tests.
+//!
+//! Hashes, BLS values and bitfields are already `0x` prefixed hex strings, and `uint64`s are plain
+//! integers in both formats (`18446744073709551615` included). Byte arrays like `Version` and
+//! `graffiti` are sequences of numbers when derived and hex strings in the spec. Wrapping a value in
+//! `Spec` switches them to hex strings, both when serializing and deserializing:
+//!
+//! ```
+//! use types::primitives::Epoch;
+//! use types::serde_utils::Spec;
+//! use types::types::Fork;
+//!
+//! let fork = Fork {
+//!     previous_version: [0, 0, 0, 1],
+//!     current_version: [0, 0, 0, 2],
+//!     epoch: Epoch::new(3),
+//! };
+//! let yaml = serde_yaml::to_string(&Spec(&fork)).unwrap();
+//! assert!(yaml.contains("0x00000001"));
+//!
+//! let Spec(decoded) = serde_yaml::from_str::<Spec<Fork>>(&yaml).unwrap();
+//! assert_eq!(decoded, fork);
+//! ```
+//!
+//! `Spec` wraps the serializer or deserializer it is given, so it applies to every value nested in
+//! the one it wraps, in any format and on any thread, and to nothing else.
+use serde::de::{
+    self, DeserializeSeed, Deserializer, EnumAccess, MapAccess, SeqAccess, VariantAccess, Visitor,
+};
+use serde::ser::{
+    self, Impossible, Serialize, SerializeMap, SerializeSeq, SerializeStruct,
+    SerializeStructVariant, SerializeTuple, SerializeTupleStruct, SerializeTupleVariant,
+    Serializer,
+};
+use serde::Deserialize;
+use serde_hex::{encode as hex_encode, PrefixedHexVisitor};
+use std::fmt;
+use std::marker::PhantomData;
+
+/// The name of the newtype struct `hex_bytes` wraps byte arrays in, for `Spec` to find them.
+const HEX_BYTES: &str = "$types::serde_utils::HexBytes";
+
+/// A value (de)serialized as in the YAML of the spec tests, e.g. with a `Version` of `0x00000001`
+/// rather than `[0, 0, 0, 1]`.
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub struct Spec<T>(pub T);
+
+impl<T: Serialize> Serialize for Spec<T> {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        self.0.serialize(Wrap(serializer))
+    }
+}
+
+impl<'de, T: Deserialize<'de>> Deserialize<'de> for Spec<T> {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        T::deserialize(Wrap(deserializer)).map(Spec)
+    }
+}
+
+/// `#[serde(with = "crate::serde_utils::hex_bytes")]` for byte arrays, e.g. `[u8; 32]`.
+///
+/// Outside of `Spec`, the array keeps its derived format: serializers and deserializers see
+/// through the newtype struct it is wrapped in.
+pub mod hex_bytes {
+    use super::*;
+
+    pub fn serialize<T, S>(bytes: &T, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        T: Serialize,
+        S: Serializer,
+    {
+        serializer.serialize_newtype_struct(HEX_BYTES, bytes)
+    }
+
+    pub fn deserialize<'de, T, D>(deserializer: D) -> Result<T, D::Error>
+    where
+        T: AsMut<[u8]> + Default,
+        D: Deserializer<'de>,
+    {
+        deserializer.deserialize_newtype_struct(HEX_BYTES, BytesVisitor(PhantomData))
+    }
+}
+
+/// Reads a byte array from a sequence of numbers or, within `Spec`, from a hex string.
+struct BytesVisitor<T>(PhantomData<T>);
+
+impl<'de, T: AsMut<[u8]> + Default> Visitor<'de> for BytesVisitor<T> {
+    type Value = T;
+
+    fn expecting(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+        write!(formatter, "{} bytes", T::default().as_mut().len())
+    }
+
+    fn visit_newtype_struct<D: Deserializer<'de>>(self, deserializer: D) -> Result<T, D::Error> {
+        deserializer.deserialize_tuple(T::default().as_mut().len(), self)
+    }
+
+    fn visit_str<E: de::Error>(self, value: &str) -> Result<T, E> {
+        let bytes = PrefixedHexVisitor.visit_str::<E>(value)?;
+        let mut array = T::default();
+        if bytes.len() != array.as_mut().len() {
+            return Err(E::invalid_length(bytes.len(), &self));
+        }
+        array.as_mut().copy_from_slice(&bytes);
+        Ok(array)
+    }
+
+    fn visit_seq<A: SeqAccess<'de>>(self, mut seq: A) -> Result<T, A::Error> {
+        let mut array = T::default();
+        let len = array.as_mut().len();
+        for (i, byte) in array.as_mut().iter_mut().enumerate() {
+            *byte = seq
+                .next_element()?
+                .ok_or_else(|| de::Error::invalid_length(i, &self))?;
+        }
+        if seq.next_element::<u8>()?.is_some() {
+            return Err(de::Error::invalid_length(len + 1, &self));
+        }
+        Ok(array)
+    }
+}
+
+/// Wraps the serializers, deserializers and everything in between that `Spec` passes values
+/// through, so that it reaches every nested value.
+struct Wrap<T>(T);
+
+macro_rules! forward_serialize {
+    ($($method: ident($ty: ty)),*) => {
+        $(
+            fn $method(self, value: $ty) -> Result<S::Ok, S::Error> {
+                self.0.$method(value)
+            }
+        )*
+    };
+}
+
+impl<S: Serializer> Serializer for Wrap<S> {
+    type Ok = S::Ok;
+    type Error = S::Error;
+    type SerializeSeq = Wrap<S::SerializeSeq>;
+    type SerializeTuple = Wrap<S::SerializeTuple>;
+    type SerializeTupleStruct = Wrap<S::SerializeTupleStruct>;
+    type SerializeTupleVariant = Wrap<S::SerializeTupleVariant>;
+    type SerializeMap = Wrap<S::SerializeMap>;
+    type SerializeStruct = Wrap<S::SerializeStruct>;
+    type SerializeStructVariant = Wrap<S::SerializeStructVariant>;
+
+    forward_serialize!(
+        serialize_bool(bool),
+        serialize_i8(i8),
+        serialize_i16(i16),
+        serialize_i32(i32),
+        serialize_i64(i64),
+        serialize_u8(u8),
+        serialize_u16(u16),
+        serialize_u32(u32),
+        serialize_u64(u64),
+        serialize_f32(f32),
+        serialize_f64(f64),
+        serialize_char(char),
+        serialize_str(&str),
+        serialize_bytes(&[u8]),
+        serialize_unit_struct(&'static str)
+    );
+
+    fn serialize_none(self) -> Result<S::Ok, S::Error> {
+        self.0.serialize_none()
+    }
+
+    fn serialize_some<T: ?Sized + Serialize>(self, value: &T) -> Result<S::Ok, S::Error> {
+        self.0.serialize_some(&Spec(value))
+    }
+
+    fn serialize_unit(self) -> Result<S::Ok, S::Error> {
+        self.0.serialize_unit()
+    }
+
+    fn serialize_unit_variant(
+        self,
+        name: &'static str,
+        index: u32,
+        variant: &'static str,
+    ) -> Result<S::Ok, S::Error> {
+        self.0.serialize_unit_variant(name, index, variant)
+    }
+
+    fn serialize_newtype_struct<T: ?Sized + Serialize>(
+        self,
+        name: &'static str,
+        value: &T,
+    ) -> Result<S::Ok, S::Error> {
+        if name == HEX_BYTES {
+            let bytes = value.serialize(ByteCollector::new())?;
+            self.0.serialize_str(&hex_encode(bytes))
+        } else {
+            self.0.serialize_newtype_struct(name, &Spec(value))
+        }
+    }
+
+    fn serialize_newtype_variant<T: ?Sized + Serialize>(
+        self,
+        name: &'static str,
+        index: u32,
+        variant: &'static str,
+        value: &T,
+    ) -> Result<S::Ok, S::Error> {
+        self.0
+            .serialize_newtype_variant(name, index, variant, &Spec(value))
+    }
+
+    fn serialize_seq(self, len: Option<usize>) -> Result<Self::SerializeSeq, S::Error> {
+        self.0.serialize_seq(len).map(Wrap)
+    }
+
+    fn serialize_tuple(self, len: usize) -> Result<Self::SerializeTuple, S::Error> {
+        self.0.serialize_tuple(len).map(Wrap)
+    }
+
+    fn serialize_tuple_struct(
+        self,
+        name: &'static str,
+        len: usize,
+    ) -> Result<Self::SerializeTupleStruct, S::Error> {
+        self.0.serialize_tuple_struct(name, len).map(Wrap)
+    }
+
+    fn serialize_tuple_variant(
+        self,
+        name: &'static str,
+        index: u32,
+        variant: &'static str,
+        len: usize,
+    ) -> Result<Self::SerializeTupleVariant, S::Error> {
+        self.0
+            .serialize_tuple_variant(name, index, variant, len)
+            .map(Wrap)
+    }
+
+    fn serialize_map(self, len: Option<usize>) -> Result<Self::SerializeMap, S::Error> {
+        self.0.serialize_map(len).map(Wrap)
+    }
+
+    fn serialize_struct(
+        self,
+        name: &'static str,
+        len: usize,
+    ) -> Result<Self::SerializeStruct, S::Error> {
+        self.0.serialize_struct(name, len).map(Wrap)
+    }
+
+    fn serialize_struct_variant(
+        self,
+        name: &'static str,
+        index: u32,
+        variant: &'static str,
+        len: usize,
+    ) -> Result<Self::SerializeStructVariant, S::Error> {
+        self.0
+            .serialize_struct_variant(name, index, variant, len)
+            .map(Wrap)
+    }
+
+    fn is_human_readable(&self) -> bool {
+        self.0.is_human_readable()
+    }
+}
+
+macro_rules! forward_compound {
+    ($($trait: ident::$method: ident($($key: ident: $key_ty: ty),*)),*) => {
+        $(
+            impl<C: $trait> $trait for Wrap<C> {
+                type Ok = C::Ok;
+                type Error = C::Error;
+
+                fn $method<T: ?Sized + Serialize>(
+                    &mut self,
+                    $($key: $key_ty,)*
+                    value: &T,
+                ) -> Result<(), C::Error> {
+                    self.0.$method($($key,)* &Spec(value))
+                }
+
+                fn end(self) -> Result<C::Ok, C::Error> {
+                    self.0.end()
+                }
+            }
+        )*
+    };
+}
+
+forward_compound!(
+    SerializeSeq::serialize_element(),
+    SerializeTuple::serialize_element(),
+    SerializeTupleStruct::serialize_field(),
+    SerializeTupleVariant::serialize_field(),
+    SerializeStruct::serialize_field(key: &'static str),
+    SerializeStructVariant::serialize_field(key: &'static str)
+);
+
+impl<C: SerializeMap> SerializeMap for Wrap<C> {
+    type Ok = C::Ok;
+    type Error = C::Error;
+
+    fn serialize_key<T: ?Sized + Serialize>(&mut self, key: &T) -> Result<(), C::Error> {
+        self.0.serialize_key(&Spec(key))
+    }
+
+    fn serialize_value<T: ?Sized + Serialize>(&mut self, value: &T) -> Result<(), C::Error> {
+        self.0.serialize_value(&Spec(value))
+    }
+
+    fn end(self) -> Result<C::Ok, C::Error> {
+        self.0.end()
+    }
+}
+
+macro_rules! forward_deserialize {
+    ($($method: ident($($arg: ident: $ty: ty),*)),*) => {
+        $(
+            fn $method<V: Visitor<'de>>(
+                self,
+                $($arg: $ty,)*
+                visitor: V,
+            ) -> Result<V::Value, D::Error> {
+                self.0.$method($($arg,)* Wrap(visitor))
+            }
+        )*
+    };
+}
+
+impl<'de, D: Deserializer<'de>> Deserializer<'de> for Wrap<D> {
+    type Error = D::Error;
+
+    forward_deserialize!(
+        deserialize_any(),
+        deserialize_bool(),
+        deserialize_i8(),
+        deserialize_i16(),
+        deserialize_i32(),
+        deserialize_i64(),
+        deserialize_u8(),
+        deserialize_u16(),
+        deserialize_u32(),
+        deserialize_u64(),
+        deserialize_f32(),
+        deserialize_f64(),
+        deserialize_char(),
+        deserialize_str(),
+        deserialize_string(),
+        deserialize_bytes(),
+        deserialize_byte_buf(),
+        deserialize_option(),
+        deserialize_unit(),
+        deserialize_unit_struct(name: &'static str),
+        deserialize_seq(),
+        deserialize_tuple(len: usize),
+        deserialize_tuple_struct(name: &'static str, len: usize),
+        deserialize_map(),
+        deserialize_struct(name: &'static str, fields: &'static [&'static str]),
+        deserialize_enum(name: &'static str, variants: &'static [&'static str]),
+        deserialize_identifier(),
+        deserialize_ignored_any()
+    );
+
+    fn deserialize_newtype_struct<V: Visitor<'de>>(
+        self,
+        name: &'static str,
+        visitor: V,
+    ) -> Result<V::Value, D::Error> {
+        if name == HEX_BYTES {
+            // The spec only has hex strings, so nothing else is accepted.
+            self.0.deserialize_str(visitor)
+        } else {
+            self.0.deserialize_newtype_struct(name, Wrap(visitor))
+        }
+    }
+
+    fn is_human_readable(&self) -> bool {
+        self.0.is_human_readable()
+    }
+}
+
+macro_rules! forward_visit {
+    ($($method: ident($ty: ty)),*) => {
+        $(
+            fn $method<E: de::Error>(self, value: $ty) -> Result<V::Value, E> {
+                self.0.$method(value)
+            }
+        )*
+    };
+}
+
+impl<'de, V: Visitor<'de>> Visitor<'de> for Wrap<V> {
+    type Value = V::Value;
+
+    fn expecting(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+        self.0.expecting(formatter)
+    }
+
+    forward_visit!(
+        visit_bool(bool),
+        visit_i8(i8),
+        visit_i16(i16),
+        visit_i32(i32),
+        visit_i64(i64),
+        visit_u8(u8),
+        visit_u16(u16),
+        visit_u32(u32),
+        visit_u64(u64),
+        visit_f32(f32),
+        visit_f64(f64),
+        visit_char(char),
+        visit_str(&str),
+        visit_borrowed_str(&'de str),
+        visit_string(String),
+        visit_bytes(&[u8]),
+        visit_borrowed_bytes(&'de [u8]),
+        visit_byte_buf(Vec<u8>)
+    );
+
+    fn visit_none<E: de::Error>(self) -> Result<V::Value, E> {
+        self.0.visit_none()
+    }
+
+    fn visit_some<D: Deserializer<'de>>(self, deserializer: D) -> Result<V::Value, D::Error> {
+        self.0.visit_some(Wrap(deserializer))
+    }
+
+    fn visit_unit<E: de::Error>(self) -> Result<V::Value, E> {
+        self.0.visit_unit()
+    }
+
+    fn visit_newtype_struct<D: Deserializer<'de>>(
+        self,
+        deserializer: D,
+    ) -> Result<V::Value, D::Error> {
+        self.0.visit_newtype_struct(Wrap(deserializer))
+    }
+
+    fn visit_seq<A: SeqAccess<'de>>(self, seq: A) -> Result<V::Value, A::Error> {
+        self.0.visit_seq(Wrap(seq))
+    }
+
+    fn visit_map<A: MapAccess<'de>>(self, map: A) -> Result<V::Value, A::Error> {
+        self.0.visit_map(Wrap(map))
+    }
+
+    fn visit_enum<A: EnumAccess<'de>>(self, data: A) -> Result<V::Value, A::Error> {
+        self.0.visit_enum(Wrap(data))
+    }
+}
+
+impl<'de, S: DeserializeSeed<'de>> DeserializeSeed<'de> for Wrap<S> {
+    type Value = S::Value;
+
+    fn deserialize<D: Deserializer<'de>>(self, deserializer: D) -> Result<S::Value, D::Error> {
+        self.0.deserialize(Wrap(deserializer))
+    }
+}
+
+impl<'de, A: SeqAccess<'de>> SeqAccess<'de> for Wrap<A> {
+    type Error = A::Error;
+
+    fn next_element_seed<T: DeserializeSeed<'de>>(
+        &mut self,
+        seed: T,
+    ) -> Result<Option<T::Value>, A::Error> {
+        self.0.next_element_seed(Wrap(seed))
+    }
+
+    fn size_hint(&self) -> Option<usize> {
+        self.0.size_hint()
+    }
+}
+
+impl<'de, A: MapAccess<'de>> MapAccess<'de> for Wrap<A> {
+    type Error = A::Error;
+
+    fn next_key_seed<K: DeserializeSeed<'de>>(
+        &mut self,
+        seed: K,
+    ) -> Result<Option<K::Value>, A::Error> {
+        self.0.next_key_seed(Wrap(seed))
+    }
+
+    fn next_value_seed<T: DeserializeSeed<'de>>(&mut self, seed: T) -> Result<T::Value, A::Error> {
+        self.0.next_value_seed(Wrap(seed))
+    }
+
+    fn size_hint(&self) -> Option<usize> {
+        self.0.size_hint()
+    }
+}
+
+impl<'de, A: EnumAccess<'de>> EnumAccess<'de> for Wrap<A> {
+    type Error = A::Error;
+    type Variant = Wrap<A::Variant>;
+
+    fn variant_seed<T: DeserializeSeed<'de>>(
+        self,
+        seed: T,
+    ) -> Result<(T::Value, Self::Variant), A::Error> {
+        self.0
+            .variant_seed(Wrap(seed))
+            .map(|(value, variant)| (value, Wrap(variant)))
+    }
+}
+
+impl<'de, A: VariantAccess<'de>> VariantAccess<'de> for Wrap<A> {
+    type Error = A::Error;
+
+    fn unit_variant(self) -> Result<(), A::Error> {
+        self.0.unit_variant()
+    }
+
+    fn newtype_variant_seed<T: DeserializeSeed<'de>>(self, seed: T) -> Result<T::Value, A::Error> {
+        self.0.newtype_variant_seed(Wrap(seed))
+    }
+
+    fn tuple_variant<V: Visitor<'de>>(self, len: usize, visitor: V) -> Result<V::Value, A::Error> {
+        self.0.tuple_variant(len, Wrap(visitor))
+    }
+
+    fn struct_variant<V: Visitor<'de>>(
+        self,
+        fields: &'static [&'static str],
+        visitor: V,
+    ) -> Result<V::Value, A::Error> {
+        self.0.struct_variant(fields, Wrap(visitor))
+    }
+}
+
+/// Collects the bytes of a byte array as serde serializes it, for `Spec` to write as hex.
+struct ByteCollector<E> {
+    bytes: Vec<u8>,
+    error: PhantomData<E>,
+}
+
+impl<E: ser::Error> ByteCollector<E> {
+    fn new() -> Self {
+        Self {
+            bytes: vec![],
+            error: PhantomData,
+        }
+    }
+
+    fn unexpected() -> E {
+        E::custom("expected a byte array")
+    }
+}
+
+macro_rules! reject_serialize {
+    ($($method: ident($ty: ty)),*) => {
+        $(
+            fn $method(self, _: $ty) -> Result<Vec<u8>, E> {
+                Err(Self::unexpected())
+            }
+        )*
+    };
+}
+
+impl<E: ser::Error> Serializer for ByteCollector<E> {
+    type Ok = Vec<u8>;
+    type Error = E;
+    type SerializeSeq = Self;
+    type SerializeTuple = Self;
+    type SerializeTupleStruct = Impossible<Vec<u8>, E>;
+    type SerializeTupleVariant = Impossible<Vec<u8>, E>;
+    type SerializeMap = Impossible<Vec<u8>, E>;
+    type SerializeStruct = Impossible<Vec<u8>, E>;
+    type SerializeStructVariant = Impossible<Vec<u8>, E>;
+
+    fn serialize_u8(self, value: u8) -> Result<Vec<u8>, E> {
+        Ok(vec![value])
+    }
+
+    fn serialize_bytes(self, value: &[u8]) -> Result<Vec<u8>, E> {
+        Ok(value.to_vec())
+    }
+
+    fn serialize_seq(self, len: Option<usize>) -> Result<Self, E> {
+        Ok(Self {
+            bytes: Vec::with_capacity(len.unwrap_or(0)),
+            error: PhantomData,
+        })
+    }
+
+    fn serialize_tuple(self, len: usize) -> Result<Self, E> {
+        self.serialize_seq(Some(len))
+    }
+
+    reject_serialize!(
+        serialize_bool(bool),
+        serialize_i8(i8),
+        serialize_i16(i16),
+        serialize_i32(i32),
+        serialize_i64(i64),
+        serialize_u16(u16),
+        serialize_u32(u32),
+        serialize_u64(u64),
+        serialize_f32(f32),
+        serialize_f64(f64),
+        serialize_char(char),
+        serialize_str(&str),
+        serialize_unit_struct(&'static str)
+    );
+
+    fn serialize_none(self) -> Result<Vec<u8>, E> {
+        Err(Self::unexpected())
+    }
+
+    fn serialize_some<T: ?Sized + Serialize>(self, _: &T) -> Result<Vec<u8>, E> {
+        Err(Self::unexpected())
+    }
+
+    fn serialize_unit(self) -> Result<Vec<u8>, E> {
+        Err(Self::unexpected())
+    }
+
+    fn serialize_unit_variant(
+        self,
+        _: &'static str,
+        _: u32,
+        _: &'static str,
+    ) -> Result<Vec<u8>, E> {
+        Err(Self::unexpected())
+    }
+
+    fn serialize_newtype_struct<T: ?Sized + Serialize>(
+        self,
+        _: &'static str,
+        value: &T,
+    ) -> Result<Vec<u8>, E> {
+        value.serialize(self)
+    }
+
+    fn serialize_newtype_variant<T: ?Sized + Serialize>(
+        self,
+        _: &'static str,
+        _: u32,
+        _: &'static str,
+        _: &T,
+    ) -> Result<Vec<u8>, E> {
+        Err(Self::unexpected())
+    }
+
+    fn serialize_tuple_struct(
+        self,
+        _: &'static str,
+        _: usize,
+    ) -> Result<Self::SerializeTupleStruct, E> {
+        Err(Self::unexpected())
+    }
+
+    fn serialize_tuple_variant(
+        self,
+        _: &'static str,
+        _: u32,
+        _: &'static str,
+        _: usize,
+    ) -> Result<Self::SerializeTupleVariant, E> {
+        Err(Self::unexpected())
+    }
+
+    fn serialize_map(self, _: Option<usize>) -> Result<Self::SerializeMap, E> {
+        Err(Self::unexpected())
+    }
+
+    fn serialize_struct(self, _: &'static str, _: usize) -> Result<Self::SerializeStruct, E> {
+        Err(Self::unexpected())
+    }
+
+    fn serialize_struct_variant(
+        self,
+        _: &'static str,
+        _: u32,
+        _: &'static str,
+        _: usize,
+    ) -> Result<Self::SerializeStructVariant, E> {
+        Err(Self::unexpected())
+    }
+}
+
+impl<E: ser::Error> SerializeSeq for ByteCollector<E> {
+    type Ok = Vec<u8>;
+    type Error = E;
+
+    fn serialize_element<T: ?Sized + Serialize>(&mut self, value: &T) -> Result<(), E> {
+        self.bytes.append(&mut value.serialize(Self::new())?);
+        Ok(())
+    }
+
+    fn end(self) -> Result<Vec<u8>, E> {
+        Ok(self.bytes)
+    }
+}
+
+impl<E: ser::Error> SerializeTuple for ByteCollector<E> {
+    type Ok = Vec<u8>;
+    type Error = E;
+
+    fn serialize_element<T: ?Sized + Serialize>(&mut self, value: &T) -> Result<(), E> {
+        SerializeSeq::serialize_element(self, value)
+    }
+
+    fn end(self) -> Result<Vec<u8>, E> {
+        SerializeSeq::end(self)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::beacon_state::BeaconState;
+    use crate::config::{Config, MinimalConfig};
+    use crate::primitives::*;
+    use crate::types::*;
+    use serde::de::DeserializeOwned;
+    use ssz_types::{BitList, BitVector, PersistentList, PersistentVector, VariableList};
+
+    const SPEC_FORK: &str = "previous_version: '0x00000001'
+current_version: '0x00000002'
+epoch: 18446744073709551615
+";
+
+    const NATIVE_FORK: &str = "previous_version: [0, 0, 0, 1]
+current_version: [0, 0, 0, 2]
+epoch: 18446744073709551615
+";
+
+    fn fork() -> Fork {
+        Fork {
+            previous_version: [0, 0, 0, 1],
+            current_version: [0, 0, 0, 2],
+            epoch: Epoch::new(u64::max_value()),
+        }
+    }
+
+    fn yaml_value(yaml: &str) -> serde_yaml::Value {
+        serde_yaml::from_str(yaml).unwrap()
+    }
+
+    #[test]
+    fn native_format_is_derived() {
+        let yaml = serde_yaml::to_string(&fork()).unwrap();
+        assert_eq!(yaml_value(&yaml), yaml_value(NATIVE_FORK));
+        assert_eq!(serde_yaml::from_str::<Fork>(NATIVE_FORK).unwrap(), fork());
+        assert!(serde_yaml::from_str::<Fork>(SPEC_FORK).is_err());
+    }
+
+    #[test]
+    fn spec_format() {
+        let yaml = serde_yaml::to_string(&Spec(fork())).unwrap();
+        assert_eq!(yaml_value(&yaml), yaml_value(SPEC_FORK));
+        assert_eq!(
+            serde_yaml::from_str::<Spec<Fork>>(SPEC_FORK).unwrap(),
+            Spec(fork())
+        );
+        assert!(serde_yaml::from_str::<Spec<Fork>>(NATIVE_FORK).is_err());
+    }
+
+    #[test]
+    fn spec_reaches_nested_values() {
+        let forks = vec![Some(fork()), None];
+        let yaml = serde_yaml::to_string(&Spec(&forks)).unwrap();
+        assert_eq!(yaml.matches("0x00000002").count(), 1);
+
+        let Spec(decoded) = serde_yaml::from_str::<Spec<Vec<Option<Fork>>>>(&yaml).unwrap();
+        assert_eq!(decoded, forks);
+    }
+
+    /// A quoted `0x` prefixed hex string of `len` copies of `byte`.
+    fn hex(byte: u8, len: usize) -> String {
+        format!("'0x{}'", format!("{:02x}", byte).repeat(len))
+    }
+
+    /// A flow sequence of `len` copies of `item`.
+    fn repeated(item: &str, len: usize) -> String {
+        format!("[{}]", vec![item; len].join(", "))
+    }
+
+    /// Checks that `yaml` decodes to `value` within `Spec` and that `value` encodes back to it.
+    fn check_spec_round_trip<T>(yaml: &str, value: &T)
+    where
+        T: Serialize + DeserializeOwned + PartialEq + fmt::Debug,
+    {
+        let Spec(decoded) = serde_yaml::from_str::<Spec<T>>(yaml).unwrap();
+        assert_eq!(&decoded, value);
+
+        let encoded = serde_yaml::to_string(&Spec(value)).unwrap();
+        assert_eq!(yaml_value(&encoded), yaml_value(yaml));
+    }
+
+    fn crosslink() -> Crosslink {
+        Crosslink {
+            shard: Shard::new(3),
+            parent_root: H256::repeat_byte(0x11),
+            start_epoch: Epoch::new(1),
+            end_epoch: Epoch::new(2),
+            data_root: H256::repeat_byte(0x22),
+        }
+    }
+
+    fn crosslink_yaml() -> String {
+        format!(
+            "{{shard: 3, parent_root: {}, start_epoch: 1, end_epoch: 2, data_root: {}}}",
+            hex(0x11, 32),
+            hex(0x22, 32),
+        )
+    }
+
+    fn attestation_data() -> AttestationData {
+        AttestationData {
+            beacon_block_root: H256::repeat_byte(0x33),
+            source: Checkpoint {
+                epoch: Epoch::new(1),
+                root: H256::repeat_byte(0x44),
+            },
+            target: Checkpoint {
+                epoch: Epoch::new(2),
+                root: H256::repeat_byte(0x55),
+            },
+            crosslink: crosslink(),
+        }
+    }
+
+    fn attestation_data_yaml() -> String {
+        format!(
+            "{{beacon_block_root: {}, source: {{epoch: 1, root: {}}}, \
+             target: {{epoch: 2, root: {}}}, crosslink: {}}}",
+            hex(0x33, 32),
+            hex(0x44, 32),
+            hex(0x55, 32),
+            crosslink_yaml(),
+        )
+    }
+
+    /// A bitlist of length 3 with the first and last bits set.
+    fn bits() -> BitList<<MinimalConfig as Config>::MaxValidatorsPerCommittee> {
+        let mut bits = BitList::with_capacity(3).unwrap();
+        bits.set(0, true).unwrap();
+        bits.set(2, true).unwrap();
+        bits
+    }
+
+    #[test]
+    fn spec_beacon_block_body() {
+        let yaml = format!(
+            "randao_reveal: {signature}
+eth1_data:
+  deposit_root: {deposit_root}
+  deposit_count: 5
+  block_hash: {block_hash}
+graffiti: '0x6772616666697469000000000000000000000000000000000000000000000000'
+proposer_slashings: []
+attester_slashings: []
+attestations: []
+deposits: []
+voluntary_exits:
+- epoch: 3
+  validator_index: 7
+  signature: {signature}
+transfers: []
+",
+            signature = hex(0, 96),
+            deposit_root = hex(1, 32),
+            block_hash = hex(2, 32),
+        );
+
+        let mut graffiti = [0; 32];
+        graffiti[..8].copy_from_slice(b"graffiti");
+        let body = BeaconBlockBody::<MinimalConfig> {
+            randao_reveal: Signature::empty_signature(),
+            eth1_data: Eth1Data {
+                deposit_root: H256::repeat_byte(1),
+                deposit_count: 5,
+                block_hash: H256::repeat_byte(2),
+            },
+            graffiti,
+            proposer_slashings: VariableList::from(vec![]),
+            attester_slashings: VariableList::from(vec![]),
+            attestations: VariableList::from(vec![]),
+            deposits: VariableList::from(vec![]),
+            voluntary_exits: VariableList::from(vec![VoluntaryExit {
+                epoch: Epoch::new(3),
+                validator_index: ValidatorIndex::new(7),
+                signature: Signature::empty_signature(),
+            }]),
+            transfers: VariableList::from(vec![]),
+        };
+
+        check_spec_round_trip(&yaml, &body);
+    }
+
+    #[test]
+    fn spec_attestation() {
+        let yaml = format!(
+            "aggregation_bits: '0x0d'
+data: {data}
+custody_bits: '0x08'
+signature: {signature}
+",
+            data = attestation_data_yaml(),
+            signature = hex(0, 96),
+        );
+
+        let attestation = Attestation::<MinimalConfig> {
+            aggregation_bits: bits(),
+            data: attestation_data(),
+            custody_bits: BitList::with_capacity(3).unwrap(),
+            signature: Signature::empty_signature(),
+        };
+
+        check_spec_round_trip(&yaml, &attestation);
+    }
+
+    #[test]
+    fn spec_beacon_state() {
+        let zero_hashes = repeated(&hex(0, 32), 64);
+        let yaml = format!(
+            "genesis_time: 1578009600
+slot: 42
+fork:
+  previous_version: '0x00000001'
+  current_version: '0x00000002'
+  epoch: 3
+latest_block_header:
+  slot: 41
+  parent_root: {zero_hash}
+  state_root: {zero_hash}
+  body_root: {zero_hash}
+  signature: {signature}
+block_roots: {block_roots}
+state_roots: {zero_hashes}
+historical_roots: [{historical_root}]
+eth1_data: {{deposit_root: {zero_hash}, deposit_count: 1, block_hash: {zero_hash}}}
+eth1_data_votes: []
+eth1_deposit_index: 1
+validators: []
+balances: [32000000000, 31000000000]
+start_shard: 5
+randao_mixes: {zero_hashes}
+active_index_roots: {zero_hashes}
+compact_committees_roots: {zero_hashes}
+slashings: {slashings}
+previous_epoch_attestations: []
+current_epoch_attestations:
+- aggregation_bits: '0x0d'
+  data: {data}
+  inclusion_delay: 1
+  proposer_index: 9
+previous_crosslinks: {crosslinks}
+current_crosslinks: {crosslinks}
+justification_bits: '0x05'
+previous_justified_checkpoint: {{epoch: 1, root: {zero_hash}}}
+current_justified_checkpoint: {{epoch: 2, root: {zero_hash}}}
+finalized_checkpoint: {{epoch: 1, root: {zero_hash}}}
+",
+            zero_hash = hex(0, 32),
+            zero_hashes = zero_hashes,
+            signature = hex(0, 96),
+            block_roots = repeated(&hex(0xaa, 32), 64),
+            historical_root = hex(0xbb, 32),
+            slashings = repeated("0", 64),
+            data = attestation_data_yaml(),
+            crosslinks = repeated(&crosslink_yaml(), 8),
+        );
+
+        let mut justification_bits = BitVector::new();
+        justification_bits.set(0, true).unwrap();
+        justification_bits.set(2, true).unwrap();
+        let state = BeaconState::<MinimalConfig> {
+            genesis_time: 1_578_009_600,
+            slot: Slot::new(42),
+            fork: Fork {
+                previous_version: [0, 0, 0, 1],
+                current_version: [0, 0, 0, 2],
+                epoch: Epoch::new(3),
+            },
+            latest_block_header: BeaconBlockHeader {
+                slot: Slot::new(41),
+                parent_root: H256::zero(),
+                state_root: H256::zero(),
+                body_root: H256::zero(),
+                signature: Signature::empty_signature(),
+            },
+            block_roots: PersistentVector::from_elem(H256::repeat_byte(0xaa)),
+            state_roots: PersistentVector::default(),
+            historical_roots: PersistentList::from(vec![H256::repeat_byte(0xbb)]),
+            eth1_data: Eth1Data {
+                deposit_root: H256::zero(),
+                deposit_count: 1,
+                block_hash: H256::zero(),
+            },
+            eth1_data_votes: PersistentList::empty(),
+            eth1_deposit_index: 1,
+            validators: PersistentList::empty(),
+            balances: PersistentList::from(vec![
+                Gwei::new(32_000_000_000),
+                Gwei::new(31_000_000_000),
+            ]),
+            start_shard: Shard::new(5),
+            randao_mixes: PersistentVector::default(),
+            active_index_roots: PersistentVector::default(),
+            compact_committees_roots: PersistentVector::default(),
+            slashings: PersistentVector::default(),
+            previous_epoch_attestations: PersistentList::empty(),
+            current_epoch_attestations: PersistentList::from(vec![PendingAttestation {
+                aggregation_bits: bits(),
+                data: attestation_data(),
+                inclusion_delay: 1,
+                proposer_index: ValidatorIndex::new(9),
+            }]),
+            previous_crosslinks: PersistentVector::from_elem(crosslink()),
+            current_crosslinks: PersistentVector::from_elem(crosslink()),
+            justification_bits,
+            previous_justified_checkpoint: Checkpoint {
+                epoch: Epoch::new(1),
+                root: H256::zero(),
+            },
+            current_justified_checkpoint: Checkpoint {
+                epoch: Epoch::new(2),
+                root: H256::zero(),
+            },
+            finalized_checkpoint: Checkpoint {
+                epoch: Epoch::new(1),
+                root: H256::zero(),
+            },
+        };
+
+        check_spec_round_trip(&yaml, &state);
+    }
+
+    #[test]
+    fn rejects_invalid_bytes() {
+        for version in &[
+            "'0x000001'",
+            "'000000001'",
+            "'0x0000000001'",
+            "[0, 0, 0, 0]",
+        ] {
+            let yaml = format!(
+                "previous_version: {}\ncurrent_version: '0x00000000'\nepoch: 0",
+                version
+            );
+            assert!(
+                serde_yaml::from_str::<Spec<Fork>>(&yaml).is_err(),
+                "{}",
+                yaml
+            );
+        }
+        for version in &["[0, 0, 1]", "[0, 0, 0, 0, 1]", "'0x00000000'"] {
+            let yaml = format!(
+                "previous_version: {}\ncurrent_version: [0, 0, 0, 0]\nepoch: 0",
+                version
+            );
+            assert!(serde_yaml::from_str::<Fork>(&yaml).is_err(), "{}", yaml);
+        }
+    }
+}
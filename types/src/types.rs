@@ -1,9 +1,8 @@
 //temporary Lighthouse SSZ and hashing implementation
-use bls::PublicKeyBytes;
+use bls::{PublicKeyBytes, SignatureBytes};
 use serde::{Deserialize, Serialize};
-use ssz_derive::{Decode, Encode};
+use ssz_derive::{Decode, DecodeView, Encode, SszSchema};
 use ssz_types::{BitList, FixedVector, VariableList};
-use tree_hash::TreeHash;
 use tree_hash_derive::{SignedRoot, TreeHash};
 use typenum::{Sum, U1};
 
@@ -11,7 +10,19 @@ use crate::config::*;
 use crate::consts;
 use crate::primitives::*;
 
-#[derive(Clone, PartialEq, Debug, Deserialize, Serialize, Encode, Decode, TreeHash, SignedRoot)]
+#[derive(
+    Clone,
+    PartialEq,
+    Debug,
+    Deserialize,
+    Serialize,
+    Encode,
+    Decode,
+    DecodeView,
+    SszSchema,
+    TreeHash,
+    SignedRoot,
+)]
 pub struct Attestation<C: Config> {
     pub aggregation_bits: BitList<C::MaxValidatorsPerCommittee>,
     pub data: AttestationData,
@@ -20,7 +31,19 @@ pub struct Attestation<C: Config> {
 }
 
 #[derive(
-    Clone, PartialEq, Eq, Debug, Hash, Deserialize, Serialize, Encode, Decode, TreeHash, SignedRoot,
+    Clone,
+    PartialEq,
+    Eq,
+    Debug,
+    Hash,
+    Deserialize,
+    Serialize,
+    Encode,
+    Decode,
+    DecodeView,
+    SszSchema,
+    TreeHash,
+    SignedRoot,
 )]
 pub struct AttestationData {
     pub beacon_block_root: H256,
@@ -30,7 +53,18 @@ pub struct AttestationData {
 }
 
 #[derive(
-    Clone, PartialEq, Eq, Debug, Deserialize, Serialize, Encode, Decode, TreeHash, SignedRoot,
+    Clone,
+    PartialEq,
+    Eq,
+    Debug,
+    Deserialize,
+    Serialize,
+    Encode,
+    Decode,
+    DecodeView,
+    SszSchema,
+    TreeHash,
+    SignedRoot,
 )]
 pub struct AttestationDataAndCustodyBit {
     pub data: AttestationData,
@@ -48,6 +82,8 @@ pub struct AttestationDataAndCustodyBit {
     Serialize,
     Encode,
     Decode,
+    DecodeView,
+    SszSchema,
     TreeHash,
     SignedRoot,
 )]
@@ -58,13 +94,27 @@ pub struct AttestationDuty {
     pub committee_len: usize,
 }
 
-#[derive(Clone, PartialEq, Debug, Deserialize, Serialize, Encode, Decode, TreeHash)]
+#[derive(
+    Clone, PartialEq, Debug, Deserialize, Serialize, Encode, Decode, DecodeView, SszSchema, TreeHash,
+)]
 pub struct AttesterSlashing<C: Config> {
     pub attestation_1: IndexedAttestation<C>,
     pub attestation_2: IndexedAttestation<C>,
 }
 
-#[derive(Clone, PartialEq, Debug, Deserialize, Serialize, Encode, Decode, TreeHash, SignedRoot)]
+#[derive(
+    Clone,
+    PartialEq,
+    Debug,
+    Deserialize,
+    Serialize,
+    Encode,
+    Decode,
+    DecodeView,
+    SszSchema,
+    TreeHash,
+    SignedRoot,
+)]
 pub struct BeaconBlock<C: Config> {
     pub slot: Slot,
     pub parent_root: H256,
@@ -74,10 +124,23 @@ pub struct BeaconBlock<C: Config> {
     pub signature: Signature,
 }
 
-#[derive(Clone, PartialEq, Debug, Deserialize, Serialize, Encode, Decode, TreeHash, SignedRoot)]
+#[derive(
+    Clone,
+    PartialEq,
+    Debug,
+    Deserialize,
+    Serialize,
+    Encode,
+    Decode,
+    DecodeView,
+    SszSchema,
+    TreeHash,
+    SignedRoot,
+)]
 pub struct BeaconBlockBody<C: Config> {
     pub randao_reveal: Signature,
     pub eth1_data: Eth1Data,
+    #[serde(with = "crate::serde_utils::hex_bytes")]
     pub graffiti: [u8; 32],
     pub proposer_slashings: VariableList<ProposerSlashing, C::MaxProposerSlashings>,
     pub attester_slashings: VariableList<AttesterSlashing<C>, C::MaxAttesterSlashings>,
@@ -88,7 +151,18 @@ pub struct BeaconBlockBody<C: Config> {
 }
 
 #[derive(
-    Clone, PartialEq, Eq, Debug, Deserialize, Serialize, Encode, Decode, TreeHash, SignedRoot,
+    Clone,
+    PartialEq,
+    Eq,
+    Debug,
+    Deserialize,
+    Serialize,
+    Encode,
+    Decode,
+    DecodeView,
+    SszSchema,
+    TreeHash,
+    SignedRoot,
 )]
 pub struct BeaconBlockHeader {
     pub slot: Slot,
@@ -99,7 +173,19 @@ pub struct BeaconBlockHeader {
 }
 
 #[derive(
-    Clone, PartialEq, Eq, Debug, Default, Hash, Deserialize, Serialize, Encode, Decode, TreeHash,
+    Clone,
+    PartialEq,
+    Eq,
+    Debug,
+    Default,
+    Hash,
+    Deserialize,
+    Serialize,
+    Encode,
+    Decode,
+    DecodeView,
+    SszSchema,
+    TreeHash,
 )]
 pub struct Checkpoint {
     pub epoch: Epoch,
@@ -107,34 +193,72 @@ pub struct Checkpoint {
 }
 
 #[derive(
-    Clone, PartialEq, Eq, Debug, Default, Hash, Deserialize, Serialize, Encode, Decode, TreeHash,
+    Clone,
+    PartialEq,
+    Eq,
+    Debug,
+    Default,
+    Hash,
+    Deserialize,
+    Serialize,
+    Encode,
+    Decode,
+    DecodeView,
+    SszSchema,
+    TreeHash,
 )]
 pub struct Crosslink {
-    pub shard: u64,
+    pub shard: Shard,
     pub parent_root: H256,
     pub start_epoch: Epoch,
     pub end_epoch: Epoch,
     pub data_root: H256,
 }
 
-#[derive(Clone, PartialEq, Debug, Deserialize, Serialize, Encode, Decode, TreeHash)]
+#[derive(
+    Clone, PartialEq, Debug, Deserialize, Serialize, Encode, Decode, DecodeView, SszSchema, TreeHash,
+)]
 pub struct Deposit {
     pub proof: FixedVector<H256, Sum<consts::DepositContractTreeDepth, U1>>,
     pub data: DepositData,
 }
 
 #[derive(
-    Clone, PartialEq, Eq, Debug, Deserialize, Serialize, Encode, Decode, TreeHash, SignedRoot,
+    Clone,
+    PartialEq,
+    Eq,
+    Debug,
+    Deserialize,
+    Serialize,
+    Encode,
+    Decode,
+    DecodeView,
+    SszSchema,
+    TreeHash,
+    SignedRoot,
 )]
 pub struct DepositData {
     pub pubkey: PublicKeyBytes,
     pub withdrawal_credentials: H256,
-    pub amount: u64,
+    pub amount: Gwei,
     #[signed_root(skip_hashing)]
-    pub signature: Signature,
+    pub signature: SignatureBytes,
 }
 
-#[derive(Clone, PartialEq, Eq, Debug, Default, Deserialize, Serialize, Encode, Decode, TreeHash)]
+#[derive(
+    Clone,
+    PartialEq,
+    Eq,
+    Debug,
+    Default,
+    Deserialize,
+    Serialize,
+    Encode,
+    Decode,
+    DecodeView,
+    SszSchema,
+    TreeHash,
+)]
 pub struct Eth1Data {
     pub deposit_root: H256,
     pub deposit_count: u64,
@@ -142,21 +266,48 @@ pub struct Eth1Data {
 }
 
 #[derive(
-    Clone, PartialEq, Eq, Debug, Deserialize, Serialize, Encode, Decode, TreeHash, SignedRoot,
+    Clone,
+    PartialEq,
+    Eq,
+    Debug,
+    Deserialize,
+    Serialize,
+    Encode,
+    Decode,
+    DecodeView,
+    SszSchema,
+    TreeHash,
+    SignedRoot,
 )]
 pub struct Fork {
+    #[serde(with = "crate::serde_utils::hex_bytes")]
     pub previous_version: Version,
+    #[serde(with = "crate::serde_utils::hex_bytes")]
     pub current_version: Version,
     pub epoch: Epoch,
 }
 
-#[derive(Clone, PartialEq, Debug, Deserialize, Serialize, Encode, Decode, TreeHash)]
+#[derive(
+    Clone, PartialEq, Debug, Deserialize, Serialize, Encode, Decode, DecodeView, SszSchema, TreeHash,
+)]
 pub struct HistoricalBatch<C: Config> {
     pub block_roots: FixedVector<H256, C::SlotsPerHistoricalRoot>,
     pub state_roots: FixedVector<H256, C::SlotsPerHistoricalRoot>,
 }
 
-#[derive(Clone, PartialEq, Debug, Deserialize, Serialize, Encode, Decode, TreeHash, SignedRoot)]
+#[derive(
+    Clone,
+    PartialEq,
+    Debug,
+    Deserialize,
+    Serialize,
+    Encode,
+    Decode,
+    DecodeView,
+    SszSchema,
+    TreeHash,
+    SignedRoot,
+)]
 pub struct IndexedAttestation<C: Config> {
     pub custody_bit_0_indices: VariableList<u64, C::MaxValidatorsPerCommittee>,
     pub custody_bit_1_indices: VariableList<u64, C::MaxValidatorsPerCommittee>,
@@ -165,40 +316,77 @@ pub struct IndexedAttestation<C: Config> {
     pub signature: Signature,
 }
 
-#[derive(Clone, PartialEq, Debug, Deserialize, Serialize, Encode, Decode, TreeHash)]
+#[derive(
+    Clone, PartialEq, Debug, Deserialize, Serialize, Encode, Decode, DecodeView, SszSchema, TreeHash,
+)]
 pub struct PendingAttestation<C: Config> {
     pub aggregation_bits: BitList<C::MaxValidatorsPerCommittee>,
     pub data: AttestationData,
     pub inclusion_delay: u64,
-    pub proposer_index: u64,
+    pub proposer_index: ValidatorIndex,
 }
 
-#[derive(Clone, PartialEq, Eq, Debug, Deserialize, Serialize, Encode, Decode, TreeHash)]
+#[derive(
+    Clone,
+    PartialEq,
+    Eq,
+    Debug,
+    Deserialize,
+    Serialize,
+    Encode,
+    Decode,
+    DecodeView,
+    SszSchema,
+    TreeHash,
+)]
 pub struct ProposerSlashing {
-    pub proposer_index: u64,
+    pub proposer_index: ValidatorIndex,
     pub header_1: BeaconBlockHeader,
     pub header_2: BeaconBlockHeader,
 }
 
 #[derive(
-    Clone, PartialEq, Eq, Debug, Deserialize, Serialize, Encode, Decode, TreeHash, SignedRoot,
+    Clone,
+    PartialEq,
+    Eq,
+    Debug,
+    Deserialize,
+    Serialize,
+    Encode,
+    Decode,
+    DecodeView,
+    SszSchema,
+    TreeHash,
+    SignedRoot,
 )]
 pub struct Transfer {
-    pub sender: u64,
-    pub recipient: u64,
-    pub amount: u64,
-    pub fee: u64,
+    pub sender: ValidatorIndex,
+    pub recipient: ValidatorIndex,
+    pub amount: Gwei,
+    pub fee: Gwei,
     pub slot: Slot,
     pub pubkey: PublicKey,
     #[signed_root(skip_hashing)]
     pub signature: Signature,
 }
 
-#[derive(Clone, PartialEq, Eq, Debug, Deserialize, Serialize, Encode, Decode, TreeHash)]
+#[derive(
+    Clone,
+    PartialEq,
+    Eq,
+    Debug,
+    Deserialize,
+    Serialize,
+    Encode,
+    Decode,
+    DecodeView,
+    SszSchema,
+    TreeHash,
+)]
 pub struct Validator {
     pub pubkey: PublicKey,
     pub withdrawal_credentials: H256,
-    pub effective_balance: u64,
+    pub effective_balance: Gwei,
     pub slashed: bool,
     pub activation_eligibility_epoch: Epoch,
     pub activation_epoch: Epoch,
@@ -207,11 +395,22 @@ pub struct Validator {
 }
 
 #[derive(
-    Clone, PartialEq, Eq, Debug, Deserialize, Serialize, Encode, Decode, TreeHash, SignedRoot,
+    Clone,
+    PartialEq,
+    Eq,
+    Debug,
+    Deserialize,
+    Serialize,
+    Encode,
+    Decode,
+    DecodeView,
+    SszSchema,
+    TreeHash,
+    SignedRoot,
 )]
 pub struct VoluntaryExit {
     pub epoch: Epoch,
-    pub validator_index: u64,
+    pub validator_index: ValidatorIndex,
     #[signed_root(skip_hashing)]
     pub signature: Signature,
 }
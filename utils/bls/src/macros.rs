@@ -9,6 +9,14 @@ macro_rules! impl_ssz {
                 $byte_size
             }
 
+            fn ssz_min_len() -> usize {
+                $byte_size
+            }
+
+            fn ssz_max_len() -> usize {
+                $byte_size
+            }
+
             fn ssz_bytes_len(&self) -> usize {
                 $byte_size
             }
@@ -27,6 +35,14 @@ macro_rules! impl_ssz {
                 $byte_size
             }
 
+            fn ssz_min_len() -> usize {
+                $byte_size
+            }
+
+            fn ssz_max_len() -> usize {
+                $byte_size
+            }
+
             fn from_ssz_bytes(bytes: &[u8]) -> Result<Self, DecodeError> {
                 let len = bytes.len();
                 let expected = <Self as ssz::Decode>::ssz_fixed_len();
@@ -38,6 +54,24 @@ macro_rules! impl_ssz {
                 }
             }
         }
+
+        impl ssz::SszSchema for $type {
+            fn ssz_schema() -> ssz::Schema {
+                ssz::Schema::Vector {
+                    element: Box::new(ssz::Schema::byte()),
+                    length: $byte_size,
+                }
+            }
+        }
+
+        /// Point decompression is deferred until the view is decoded.
+        impl<'a> ssz::DecodeView<'a> for $type {
+            type View = ssz::LazyView<'a, Self>;
+
+            fn view_ssz_bytes(bytes: &'a [u8]) -> Result<Self::View, DecodeError> {
+                ssz::LazyView::new(bytes)
+            }
+        }
     };
 }
 
@@ -56,7 +90,7 @@ macro_rules! impl_tree_hash {
                 unreachable!("Vector should never be packed.")
             }
 
-            fn tree_hash_root(&self) -> Vec<u8> {
+            fn tree_hash_root(&self) -> tree_hash::Hash256 {
                 let vector: ssz_types::FixedVector<u8, ssz_types::typenum::$byte_size> =
                     ssz_types::FixedVector::from(self.as_ssz_bytes());
                 vector.tree_hash_root()
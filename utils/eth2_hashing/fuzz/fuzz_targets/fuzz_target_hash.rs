@@ -1,9 +1,23 @@
 #![no_main]
 #[macro_use] extern crate libfuzzer_sys;
-extern crate hashing;
+extern crate eth2_hashing;
 
-use hashing::hash;
+use eth2_hashing::{available_hashers, hash, Hasher, Portable};
 
 fuzz_target!(|data: &[u8]| {
-    let _result = hash(data);
+    let expected = Portable.hash(data);
+    assert_eq!(&hash(data)[..], &expected[..]);
+
+    // Every 64 byte prefix of `data` is a batch input, hashed by each backend in one call.
+    let inputs = &data[..data.len() - data.len() % 64];
+    let mut expected_batch = vec![0; inputs.len() / 2];
+    Portable.hash_64_batch(inputs, &mut expected_batch);
+
+    for hasher in available_hashers() {
+        assert_eq!(hasher.hash(data), expected, "{}", hasher.name());
+
+        let mut batch = vec![0; inputs.len() / 2];
+        hasher.hash_64_batch(inputs, &mut batch);
+        assert_eq!(batch, expected_batch, "{}", hasher.name());
+    }
 });
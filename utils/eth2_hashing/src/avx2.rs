@@ -0,0 +1,194 @@
+//! Multi-buffer SHA-256 using AVX2, which hashes eight messages at once.
+
+use super::sha256::{self, BLOCK_LEN, IV, K, PADDING_BLOCK_64};
+use super::{Hasher, Portable, HASH_LEN};
+use std::arch::x86_64::*;
+
+/// The number of messages hashed side by side, one in each 32 bit lane of a 256 bit register.
+const LANES: usize = 8;
+
+/// Hashes batches of 64 byte inputs eight at a time, one in each lane of the AVX2 registers.
+///
+/// There is no speed-up for a single message, so `hash` and any remainder of a batch that does
+/// not fill all eight lanes use `Portable`.
+///
+/// Only obtainable through `Avx2::detect`, which checks that the CPU supports AVX2.
+#[derive(Debug)]
+pub struct Avx2(());
+
+static AVX2: Avx2 = Avx2(());
+
+impl Avx2 {
+    /// Returns the backend if the CPU supports AVX2.
+    pub fn detect() -> Option<&'static Self> {
+        if is_x86_feature_detected!("avx2") {
+            Some(&AVX2)
+        } else {
+            None
+        }
+    }
+}
+
+impl Hasher for Avx2 {
+    fn name(&self) -> &'static str {
+        "avx2"
+    }
+
+    fn hash(&self, input: &[u8]) -> [u8; HASH_LEN] {
+        Portable.hash(input)
+    }
+
+    fn hash_64_batch(&self, inputs: &[u8], outputs: &mut [u8]) {
+        super::check_batch_lengths(inputs, outputs);
+
+        let full_inputs = inputs.len() - inputs.len() % (LANES * BLOCK_LEN);
+        let full_outputs = full_inputs / 2;
+
+        for (input, output) in inputs[..full_inputs]
+            .chunks(LANES * BLOCK_LEN)
+            .zip(outputs[..full_outputs].chunks_mut(LANES * HASH_LEN))
+        {
+            // Safety: `Avx2` only exists once `detect` has checked for AVX2 at runtime with
+            // `is_x86_feature_detected!`.
+            unsafe { hash_64_x8(input, output) }
+        }
+
+        Portable.hash_64_batch(&inputs[full_inputs..], &mut outputs[full_outputs..]);
+    }
+}
+
+macro_rules! rotate_right {
+    ($x: expr, $n: expr) => {
+        _mm256_or_si256(_mm256_srli_epi32($x, $n), _mm256_slli_epi32($x, 32 - $n))
+    };
+}
+
+macro_rules! xor3 {
+    ($a: expr, $b: expr, $c: expr) => {
+        _mm256_xor_si256(_mm256_xor_si256($a, $b), $c)
+    };
+}
+
+/// Hashes eight 64 byte messages from `inputs` into eight digests in `outputs`.
+///
+/// ## Safety
+///
+/// The CPU must support AVX2.
+#[target_feature(enable = "avx2")]
+unsafe fn hash_64_x8(inputs: &[u8], outputs: &mut [u8]) {
+    debug_assert_eq!(inputs.len(), LANES * BLOCK_LEN);
+    debug_assert_eq!(outputs.len(), LANES * HASH_LEN);
+
+    // Transpose the messages so that word `i` of message `lane` sits in lane `lane` of `w[i]`.
+    let mut w = [_mm256_setzero_si256(); 16];
+    for (i, word) in w.iter_mut().enumerate() {
+        let mut lanes = [0_u32; LANES];
+        for (lane, value) in lanes.iter_mut().enumerate() {
+            let start = lane * BLOCK_LEN + i * 4;
+            *value = u32::from_be_bytes([
+                inputs[start],
+                inputs[start + 1],
+                inputs[start + 2],
+                inputs[start + 3],
+            ]);
+        }
+        *word = _mm256_loadu_si256(lanes.as_ptr() as *const __m256i);
+    }
+
+    let mut state = [_mm256_setzero_si256(); 8];
+    for (word, iv) in state.iter_mut().zip(IV.iter()) {
+        *word = _mm256_set1_epi32(*iv as i32);
+    }
+
+    compress_x8(&mut state, w);
+
+    // Every message has the same length, so they all share the same padding block.
+    let mut padding = [_mm256_setzero_si256(); 16];
+    for (word, bytes) in padding.iter_mut().zip(PADDING_BLOCK_64.chunks(4)) {
+        let value = u32::from_be_bytes([bytes[0], bytes[1], bytes[2], bytes[3]]);
+        *word = _mm256_set1_epi32(value as i32);
+    }
+
+    compress_x8(&mut state, padding);
+
+    // Transpose back, giving each message its own state.
+    let mut words = [[0_u32; LANES]; 8];
+    for (lanes, word) in words.iter_mut().zip(state.iter()) {
+        _mm256_storeu_si256(lanes.as_mut_ptr() as *mut __m256i, *word);
+    }
+    for (lane, output) in outputs.chunks_mut(HASH_LEN).enumerate() {
+        let mut lane_state = [0; 8];
+        for (value, lanes) in lane_state.iter_mut().zip(words.iter()) {
+            *value = lanes[lane];
+        }
+        output.copy_from_slice(&sha256::state_to_bytes(&lane_state));
+    }
+}
+
+/// Processes one block per lane, given as its sixteen message words, into `state`.
+#[target_feature(enable = "avx2")]
+unsafe fn compress_x8(state: &mut [__m256i; 8], mut w: [__m256i; 16]) {
+    let [mut a, mut b, mut c, mut d, mut e, mut f, mut g, mut h] = *state;
+
+    for (t, k) in K.iter().enumerate() {
+        // Only the last sixteen words of the message schedule are kept.
+        let wt = if t < 16 {
+            w[t]
+        } else {
+            let w15 = w[(t - 15) % 16];
+            let w2 = w[(t - 2) % 16];
+            let s0 = xor3!(
+                rotate_right!(w15, 7),
+                rotate_right!(w15, 18),
+                _mm256_srli_epi32(w15, 3)
+            );
+            let s1 = xor3!(
+                rotate_right!(w2, 17),
+                rotate_right!(w2, 19),
+                _mm256_srli_epi32(w2, 10)
+            );
+            let next = _mm256_add_epi32(
+                _mm256_add_epi32(w[t % 16], s0),
+                _mm256_add_epi32(w[(t - 7) % 16], s1),
+            );
+            w[t % 16] = next;
+            next
+        };
+
+        let s1 = xor3!(
+            rotate_right!(e, 6),
+            rotate_right!(e, 11),
+            rotate_right!(e, 25)
+        );
+        let ch = _mm256_xor_si256(_mm256_and_si256(e, f), _mm256_andnot_si256(e, g));
+        let t1 = _mm256_add_epi32(
+            _mm256_add_epi32(_mm256_add_epi32(h, s1), _mm256_add_epi32(ch, wt)),
+            _mm256_set1_epi32(*k as i32),
+        );
+
+        let s0 = xor3!(
+            rotate_right!(a, 2),
+            rotate_right!(a, 13),
+            rotate_right!(a, 22)
+        );
+        let maj = xor3!(
+            _mm256_and_si256(a, b),
+            _mm256_and_si256(a, c),
+            _mm256_and_si256(b, c)
+        );
+        let t2 = _mm256_add_epi32(s0, maj);
+
+        h = g;
+        g = f;
+        f = e;
+        e = _mm256_add_epi32(d, t1);
+        d = c;
+        c = b;
+        b = a;
+        a = _mm256_add_epi32(t1, t2);
+    }
+
+    for (word, value) in state.iter_mut().zip([a, b, c, d, e, f, g, h].iter()) {
+        *word = _mm256_add_epi32(*word, *value);
+    }
+}
@@ -1,28 +1,230 @@
-//! Provides a simple hash function utilizing `ring::digest::SHA256`.
+//! Provides the SHA-256 hash function, behind a `Hasher` chosen for the CPU at runtime.
 //!
 //! The purpose of this crate is to provide an abstraction to whatever hash function Ethereum
 //! 2.0 is using. The hash function has been subject to change during the specification process, so
 //! defining it once in this crate makes it easy to replace.
+//!
+//! ## Backends
+//!
+//! - `Portable` uses `ring::digest::SHA256` (or `sha2` on wasm) and is always available.
+//! - `ShaNi` uses the x86 SHA extensions.
+//! - `Avx2` hashes batches of 64 byte inputs eight at a time.
+//!
+//! The free functions use `hasher()`, the fastest backend the CPU supports. All backends return
+//! identical digests.
+//!
+//! Preimages made of several parts can be hashed with a `Context`, without first concatenating
+//! them.
 
 #![allow(clippy::result_unwrap_used)]
 
+// The SIMD backends call `#[target_feature]` functions, which is only sound once the CPU has been
+// checked for the features they enable.
+#[cfg(target_arch = "x86_64")]
+#[allow(unsafe_code)]
+mod avx2;
+#[cfg(target_arch = "x86_64")]
+mod sha256;
+#[cfg(target_arch = "x86_64")]
+#[allow(unsafe_code)]
+mod sha_ni;
+
+#[cfg(target_arch = "x86_64")]
+pub use avx2::Avx2;
+#[cfg(target_arch = "x86_64")]
+pub use sha_ni::ShaNi;
+
 #[cfg(not(target_arch = "wasm32"))]
-use ring::digest::{digest, SHA256};
+use ring::digest::{self, digest, SHA256};
 
 #[cfg(target_arch = "wasm32")]
 use sha2::{Digest, Sha256};
 
+/// The length of the digest returned by `hash_fixed` and `hash32_concat`, in bytes.
+pub const HASH_LEN: usize = 32;
+
+/// An implementation of SHA-256.
+pub trait Hasher: Sync {
+    /// A short name for the backend, for use in test and benchmark output.
+    fn name(&self) -> &'static str;
+
+    /// Returns the digest of `input`.
+    fn hash(&self, input: &[u8]) -> [u8; HASH_LEN];
+
+    /// Hashes each 64 byte chunk of `inputs` into the corresponding 32 byte chunk of `outputs`.
+    ///
+    /// This is the workload of merkleization, where each pair of sibling nodes is hashed into
+    /// their parent.
+    ///
+    /// ## Panics
+    ///
+    /// If `inputs.len()` is not a multiple of 64, or `outputs` is not half the length of `inputs`.
+    fn hash_64_batch(&self, inputs: &[u8], outputs: &mut [u8]) {
+        check_batch_lengths(inputs, outputs);
+
+        for (input, output) in inputs
+            .chunks(2 * HASH_LEN)
+            .zip(outputs.chunks_mut(HASH_LEN))
+        {
+            output.copy_from_slice(&self.hash(input));
+        }
+    }
+}
+
+/// Hashes with `ring::digest::SHA256`, or `sha2` on wasm.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct Portable;
+
+impl Hasher for Portable {
+    fn name(&self) -> &'static str {
+        "portable"
+    }
+
+    fn hash(&self, input: &[u8]) -> [u8; HASH_LEN] {
+        let mut output = [0; HASH_LEN];
+
+        #[cfg(not(target_arch = "wasm32"))]
+        output.copy_from_slice(digest(&SHA256, input).as_ref());
+
+        #[cfg(target_arch = "wasm32")]
+        output.copy_from_slice(Sha256::digest(input).as_ref());
+
+        output
+    }
+}
+
+fn check_batch_lengths(inputs: &[u8], outputs: &[u8]) {
+    assert_eq!(
+        inputs.len() % (2 * HASH_LEN),
+        0,
+        "Batch inputs must be a multiple of 64 bytes"
+    );
+    assert_eq!(
+        inputs.len(),
+        outputs.len() * 2,
+        "Batch outputs must be half the length of the inputs"
+    );
+}
+
+/// Returns the fastest backend that the CPU supports.
+///
+/// `ShaNi` is preferred over `Avx2`, which only speeds up batches.
+pub fn hasher() -> &'static dyn Hasher {
+    #[cfg(target_arch = "x86_64")]
+    {
+        if let Some(hasher) = ShaNi::detect() {
+            return hasher;
+        }
+        if let Some(hasher) = Avx2::detect() {
+            return hasher;
+        }
+    }
+
+    &Portable
+}
+
+/// Returns every backend that the CPU supports, starting with `Portable`.
+pub fn available_hashers() -> Vec<&'static dyn Hasher> {
+    #[allow(unused_mut)]
+    let mut hashers: Vec<&'static dyn Hasher> = vec![&Portable];
+
+    #[cfg(target_arch = "x86_64")]
+    {
+        if let Some(hasher) = ShaNi::detect() {
+            hashers.push(hasher);
+        }
+        if let Some(hasher) = Avx2::detect() {
+            hashers.push(hasher);
+        }
+    }
+
+    hashers
+}
+
 /// Returns the digest of `input`.
 ///
-/// Uses `ring::digest::SHA256`.
+/// This allocates the returned `Vec`, so prefer `hash_fixed` where the digest does not need to be
+/// kept on the heap.
 pub fn hash(input: &[u8]) -> Vec<u8> {
-    #[cfg(not(target_arch = "wasm32"))]
-    let h = digest(&SHA256, input).as_ref().into();
+    hash_fixed(input).to_vec()
+}
+
+/// Returns the digest of `input`, without allocating.
+pub fn hash_fixed(input: &[u8]) -> [u8; HASH_LEN] {
+    hasher().hash(input)
+}
 
+/// Returns the digest of `h1` followed by `h2`, i.e. the parent of two nodes in a Merkle tree.
+///
+/// Unlike hashing the concatenation with `hash`, this does not allocate.
+pub fn hash32_concat(h1: &[u8; HASH_LEN], h2: &[u8; HASH_LEN]) -> [u8; HASH_LEN] {
+    let mut preimage = [0; 2 * HASH_LEN];
+    preimage[..HASH_LEN].copy_from_slice(h1);
+    preimage[HASH_LEN..].copy_from_slice(h2);
+
+    let mut output = [0; HASH_LEN];
+    hasher().hash_64_batch(&preimage, &mut output);
+    output
+}
+
+/// Hashes a preimage that is given in parts, producing the same digest as `hash_fixed` would for
+/// their concatenation.
+///
+/// Uses `ring::digest::Context` (or `sha2` on wasm) rather than a `Hasher` backend.
+#[derive(Clone)]
+pub struct Context {
+    #[cfg(not(target_arch = "wasm32"))]
+    inner: digest::Context,
     #[cfg(target_arch = "wasm32")]
-    let h = Sha256::digest(input).as_ref().into();
+    inner: Sha256,
+}
+
+impl Context {
+    /// Creates a context for an empty preimage.
+    pub fn new() -> Self {
+        Self {
+            #[cfg(not(target_arch = "wasm32"))]
+            inner: digest::Context::new(&SHA256),
+            #[cfg(target_arch = "wasm32")]
+            inner: Sha256::new(),
+        }
+    }
+
+    /// Appends `bytes` to the preimage.
+    pub fn update(&mut self, bytes: &[u8]) {
+        #[cfg(not(target_arch = "wasm32"))]
+        self.inner.update(bytes);
 
-    h
+        #[cfg(target_arch = "wasm32")]
+        self.inner.input(bytes);
+    }
+
+    /// Consumes `self`, returning the digest of the preimage.
+    pub fn finalize(self) -> [u8; HASH_LEN] {
+        let mut output = [0; HASH_LEN];
+
+        #[cfg(not(target_arch = "wasm32"))]
+        output.copy_from_slice(self.inner.finish().as_ref());
+
+        #[cfg(target_arch = "wasm32")]
+        output.copy_from_slice(self.inner.result().as_ref());
+
+        output
+    }
+}
+
+impl Default for Context {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Hashes each 64 byte chunk of `inputs` into the corresponding 32 byte chunk of `outputs`, with
+/// the fastest backend the CPU supports.
+///
+/// See `Hasher::hash_64_batch`.
+pub fn hash_64_batch(inputs: &[u8], outputs: &mut [u8]) {
+    hasher().hash_64_batch(inputs, outputs)
 }
 
 #[cfg(test)]
@@ -42,5 +244,89 @@ mod tests {
         let expected_hex = "b94d27b9934d3e08a52e52d7da7dabfac484efe37a5380ee9088f7ace2efcde9";
         let expected: Vec<u8> = expected_hex.from_hex().unwrap();
         assert_eq!(expected, output);
+        assert_eq!(&expected[..], &hash_fixed(input.as_ref())[..]);
+
+        for hasher in available_hashers() {
+            assert_eq!(
+                &expected[..],
+                &hasher.hash(input.as_ref())[..],
+                "{}",
+                hasher.name()
+            );
+        }
+    }
+
+    #[cfg_attr(not(target_arch = "wasm32"), test)]
+    #[cfg_attr(target_arch = "wasm32", wasm_bindgen_test)]
+    fn test_hash32_concat() {
+        let h1 = hash_fixed(b"left");
+        let h2 = hash_fixed(b"right");
+
+        let expected = hash(&[&h1[..], &h2[..]].concat());
+        assert_eq!(&expected[..], &hash32_concat(&h1, &h2)[..]);
+        assert_ne!(hash32_concat(&h1, &h2), hash32_concat(&h2, &h1));
+    }
+
+    #[cfg_attr(not(target_arch = "wasm32"), test)]
+    #[cfg_attr(target_arch = "wasm32", wasm_bindgen_test)]
+    fn test_backends_match_portable() {
+        // Covers each padding case: a partial block, a block too full for the length, and
+        // whole blocks.
+        for len in 0..300 {
+            let input: Vec<u8> = (0..len).map(|i| (i * 31 + len) as u8).collect();
+            let expected = Portable.hash(&input);
+
+            for hasher in available_hashers() {
+                assert_eq!(
+                    expected,
+                    hasher.hash(&input),
+                    "{}, len: {}",
+                    hasher.name(),
+                    len
+                );
+            }
+        }
+    }
+
+    #[cfg_attr(not(target_arch = "wasm32"), test)]
+    #[cfg_attr(target_arch = "wasm32", wasm_bindgen_test)]
+    fn test_hash_64_batch() {
+        // Batch sizes either side of the eight lanes used by `Avx2`.
+        for &count in &[0, 1, 7, 8, 9, 16, 21] {
+            let inputs: Vec<u8> = (0..count * 64).map(|i| (i * 7 + i / 256) as u8).collect();
+            let expected: Vec<u8> = inputs.chunks(64).flat_map(hash).collect();
+
+            for hasher in available_hashers() {
+                let mut outputs = vec![0; count * 32];
+                hasher.hash_64_batch(&inputs, &mut outputs);
+                assert_eq!(expected, outputs, "{}, count: {}", hasher.name(), count);
+            }
+
+            let mut outputs = vec![0; count * 32];
+            hash_64_batch(&inputs, &mut outputs);
+            assert_eq!(expected, outputs);
+        }
+    }
+
+    #[cfg_attr(not(target_arch = "wasm32"), test)]
+    #[cfg_attr(target_arch = "wasm32", wasm_bindgen_test)]
+    fn test_context() {
+        let input: Vec<u8> = (0..200).map(|i| i as u8).collect();
+
+        for split in 0..=input.len() {
+            let mut context = Context::new();
+            context.update(&input[..split]);
+            context.update(&[]);
+            context.update(&input[split..]);
+            assert_eq!(context.finalize(), hash_fixed(&input), "split: {}", split);
+        }
+
+        assert_eq!(Context::default().finalize(), hash_fixed(&[]));
+    }
+
+    #[test]
+    #[should_panic]
+    fn test_hash_64_batch_bad_lengths() {
+        hash_64_batch(&[0; 64], &mut [0; 64]);
     }
 }
@@ -0,0 +1,132 @@
+//! SHA-256 using the x86 SHA extensions.
+
+use super::sha256::{self, BLOCK_LEN, K, PADDING_BLOCK_64};
+use super::{Hasher, HASH_LEN};
+use std::arch::x86_64::*;
+
+/// Hashes with the `sha256rnds2`, `sha256msg1` and `sha256msg2` instructions.
+///
+/// Only obtainable through `ShaNi::detect`, which checks that the CPU supports them.
+#[derive(Debug)]
+pub struct ShaNi(());
+
+static SHA_NI: ShaNi = ShaNi(());
+
+impl ShaNi {
+    /// Returns the backend if the CPU has the SHA extensions (and SSE4.1, which it also uses).
+    pub fn detect() -> Option<&'static Self> {
+        if is_x86_feature_detected!("sha")
+            && is_x86_feature_detected!("sse2")
+            && is_x86_feature_detected!("ssse3")
+            && is_x86_feature_detected!("sse4.1")
+        {
+            Some(&SHA_NI)
+        } else {
+            None
+        }
+    }
+}
+
+impl Hasher for ShaNi {
+    fn name(&self) -> &'static str {
+        "sha-ni"
+    }
+
+    fn hash(&self, input: &[u8]) -> [u8; HASH_LEN] {
+        // Safety: `ShaNi` only exists once `detect` has checked for the CPU features `compress`
+        // enables at runtime with `is_x86_feature_detected!`.
+        sha256::digest(input, |state, blocks| unsafe { compress(state, blocks) })
+    }
+
+    fn hash_64_batch(&self, inputs: &[u8], outputs: &mut [u8]) {
+        super::check_batch_lengths(inputs, outputs);
+
+        for (input, output) in inputs.chunks(BLOCK_LEN).zip(outputs.chunks_mut(HASH_LEN)) {
+            let mut state = sha256::IV;
+            // Safety: `ShaNi` only exists once `detect` has checked for the CPU features
+            // `compress` enables at runtime with `is_x86_feature_detected!`.
+            unsafe {
+                compress(&mut state, input);
+                compress(&mut state, &PADDING_BLOCK_64);
+            }
+            output.copy_from_slice(&sha256::state_to_bytes(&state));
+        }
+    }
+}
+
+/// Computes the next four words of the message schedule from the previous sixteen.
+#[target_feature(enable = "sha,sse2,ssse3,sse4.1")]
+unsafe fn schedule(w0: __m128i, w1: __m128i, w2: __m128i, w3: __m128i) -> __m128i {
+    let t1 = _mm_sha256msg1_epu32(w0, w1);
+    let t2 = _mm_alignr_epi8(w3, w2, 4);
+    let t3 = _mm_add_epi32(t1, t2);
+    _mm_sha256msg2_epu32(t3, w3)
+}
+
+/// Processes each 64 byte block of `blocks` into `state`.
+///
+/// ## Safety
+///
+/// The CPU must support the SHA extensions, SSE2, SSSE3 and SSE4.1.
+#[target_feature(enable = "sha,sse2,ssse3,sse4.1")]
+unsafe fn compress(state: &mut [u32; 8], blocks: &[u8]) {
+    debug_assert_eq!(blocks.len() % BLOCK_LEN, 0);
+
+    // Reverses the bytes of each 32 bit word, as the message is big-endian.
+    let byte_swap = _mm_set_epi64x(
+        0x0c0d_0e0f_0809_0a0b_u64 as i64,
+        0x0405_0607_0001_0203_u64 as i64,
+    );
+
+    // The round instructions want the state as `ABEF` and `CDGH`.
+    let dcba = _mm_loadu_si128(state.as_ptr() as *const __m128i);
+    let hgfe = _mm_loadu_si128(state.as_ptr().add(4) as *const __m128i);
+    let cdab = _mm_shuffle_epi32(dcba, 0xb1);
+    let efgh = _mm_shuffle_epi32(hgfe, 0x1b);
+    let mut abef = _mm_alignr_epi8(cdab, efgh, 8);
+    let mut cdgh = _mm_blend_epi16(efgh, cdab, 0xf0);
+
+    for block in blocks.chunks(BLOCK_LEN) {
+        let abef_saved = abef;
+        let cdgh_saved = cdgh;
+
+        let block = block.as_ptr() as *const __m128i;
+        let mut w = [
+            _mm_shuffle_epi8(_mm_loadu_si128(block), byte_swap),
+            _mm_shuffle_epi8(_mm_loadu_si128(block.add(1)), byte_swap),
+            _mm_shuffle_epi8(_mm_loadu_si128(block.add(2)), byte_swap),
+            _mm_shuffle_epi8(_mm_loadu_si128(block.add(3)), byte_swap),
+        ];
+
+        // Four rounds at a time, keeping the last sixteen words of the schedule in `w`.
+        for i in 0..16 {
+            let message = if i < 4 {
+                w[i]
+            } else {
+                let next = schedule(w[i % 4], w[(i + 1) % 4], w[(i + 2) % 4], w[(i + 3) % 4]);
+                w[i % 4] = next;
+                next
+            };
+
+            let k = _mm_set_epi32(
+                K[4 * i + 3] as i32,
+                K[4 * i + 2] as i32,
+                K[4 * i + 1] as i32,
+                K[4 * i] as i32,
+            );
+            let message_k = _mm_add_epi32(message, k);
+            cdgh = _mm_sha256rnds2_epu32(cdgh, abef, message_k);
+            abef = _mm_sha256rnds2_epu32(abef, cdgh, _mm_shuffle_epi32(message_k, 0x0e));
+        }
+
+        abef = _mm_add_epi32(abef, abef_saved);
+        cdgh = _mm_add_epi32(cdgh, cdgh_saved);
+    }
+
+    let feba = _mm_shuffle_epi32(abef, 0x1b);
+    let dchg = _mm_shuffle_epi32(cdgh, 0xb1);
+    let dcba = _mm_blend_epi16(feba, dchg, 0xf0);
+    let hgef = _mm_alignr_epi8(dchg, feba, 8);
+    _mm_storeu_si128(state.as_mut_ptr() as *mut __m128i, dcba);
+    _mm_storeu_si128(state.as_mut_ptr().add(4) as *mut __m128i, hgef);
+}
@@ -0,0 +1,23 @@
+#![no_main]
+#[macro_use] extern crate libfuzzer_sys;
+extern crate ssz;
+extern crate ssz_types;
+
+use ssz::{Decode, DecodeError, Encode};
+use ssz_types::typenum::U32;
+use ssz_types::BitList;
+
+// Fuzz BitList::from_ssz_bytes()
+fuzz_target!(|data: &[u8]| {
+    let result: Result<BitList<U32>, DecodeError> = BitList::from_ssz_bytes(data);
+    if let Ok(bitlist) = result {
+        assert!(bitlist.len() <= 32);
+        assert_eq!(bitlist.as_ssz_bytes(), data);
+    } else if data.len() > <BitList<U32> as Decode>::ssz_max_len() {
+        // Too many bytes for 32 bits and a length bit
+        match result {
+            Err(DecodeError::ListTooLong { .. }) | Err(DecodeError::BytesInvalid(_)) => (),
+            other => panic!("unexpected result {:?}", other),
+        }
+    }
+});
@@ -0,0 +1,19 @@
+#![no_main]
+#[macro_use] extern crate libfuzzer_sys;
+extern crate ssz;
+extern crate ssz_types;
+
+use ssz::{Decode, DecodeError, Encode};
+use ssz_types::typenum::U12;
+use ssz_types::BitVector;
+
+// Fuzz BitVector::from_ssz_bytes()
+fuzz_target!(|data: &[u8]| {
+    let result: Result<BitVector<U12>, DecodeError> = BitVector::from_ssz_bytes(data);
+    if data.len() == 2 && data[1] & 0b1111_0000 == 0 {
+        // Valid bitvector of 12 bits, with no bits set past the 12th
+        assert_eq!(result.unwrap().as_ssz_bytes(), data);
+    } else {
+        assert!(result.is_err());
+    }
+});
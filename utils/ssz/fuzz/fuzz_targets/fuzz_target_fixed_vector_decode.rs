@@ -0,0 +1,29 @@
+#![no_main]
+#[macro_use] extern crate libfuzzer_sys;
+extern crate ssz;
+extern crate ssz_types;
+
+use ssz::{Decode, DecodeError, Encode};
+use ssz_types::typenum::{U4, U8};
+use ssz_types::{FixedVector, VariableList};
+
+type Fixed = FixedVector<u16, U8>;
+type Variable = FixedVector<VariableList<u8, U4>, U4>;
+
+// Fuzz FixedVector::from_ssz_bytes()
+fuzz_target!(|data: &[u8]| {
+    let result: Result<Fixed, DecodeError> = Fixed::from_ssz_bytes(data);
+    if data.len() == 16 {
+        // Every 16 bytes are a valid vector of 8 `u16`
+        assert_eq!(result.unwrap().as_ssz_bytes(), data);
+    } else {
+        // Vectors are never padded or truncated
+        assert!(result.is_err());
+    }
+
+    let result: Result<Variable, DecodeError> = Variable::from_ssz_bytes(data);
+    if let Ok(vector) = result {
+        assert_eq!(vector.len(), 4);
+        assert_eq!(vector.as_ssz_bytes(), data);
+    }
+});
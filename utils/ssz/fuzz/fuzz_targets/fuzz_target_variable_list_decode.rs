@@ -0,0 +1,24 @@
+#![no_main]
+#[macro_use] extern crate libfuzzer_sys;
+extern crate ssz;
+extern crate ssz_types;
+
+use ssz::{Decode, DecodeError, Encode};
+use ssz_types::typenum::{U16, U8};
+use ssz_types::VariableList;
+
+type List = VariableList<VariableList<u16, U8>, U16>;
+
+// Fuzz VariableList::from_ssz_bytes()
+fuzz_target!(|data: &[u8]| {
+    let result: Result<List, DecodeError> = List::from_ssz_bytes(data);
+    if let Ok(list) = result {
+        // Valid bytes are canonical, so they are re-encoded exactly
+        assert!(list.len() <= 16);
+        assert!(list.iter().all(|inner| inner.len() <= 8));
+        assert_eq!(list.as_ssz_bytes(), data);
+        assert_eq!(List::from_ssz_reader(&mut &data[..], data.len()), Ok(list));
+    } else if data.len() > <List as Decode>::ssz_max_len() {
+        assert!(List::from_ssz_reader(&mut &data[..], data.len()).is_err());
+    }
+});
@@ -1,4 +1,5 @@
 use super::*;
+use std::fmt;
 
 pub mod impls;
 
@@ -16,10 +17,129 @@ pub enum DecodeError {
     /// - It is `>= bytes.len()`.
     /// - When decoding variable length items, the 1st offset points "backwards" into the fixed
     /// length items (i.e., `length[0] < BYTES_PER_LENGTH_OFFSET`).
-    /// - When decoding variable-length items, the `n`'th offset was less than the `n-1`'th offset.
     OutOfBoundsByte { i: usize },
+    /// When decoding variable-length items, the `n`'th offset was less than the `n-1`'th offset.
+    OffsetsAreDecreasing { offset: usize, previous: usize },
+    /// A fixed-length object was followed by bytes that are not part of it.
+    TrailingBytes { len: usize, expected: usize },
+    /// A list had more items than its maximum length.
+    ListTooLong { len: usize, max: usize },
+    /// The items of a list were fixed-length with a length of zero, so the number of items is
+    /// unknowable.
+    ZeroLengthItem,
     /// The given bytes were invalid for some application-level reason.
     BytesInvalid(String),
+    /// Reading the bytes from a `std::io::Read` failed.
+    Io(String),
+    /// Decoding the field or item at `path` failed with `error`.
+    ///
+    /// Returned by decoders of containers and lists, see `DecodeError::in_field` and
+    /// `DecodeError::in_index`.
+    InPath {
+        path: Vec<PathElement>,
+        error: Box<DecodeError>,
+    },
+}
+
+/// A step from an object into one of its fields or items, locating a `DecodeError::InPath`.
+#[derive(Debug, PartialEq, Clone)]
+pub enum PathElement {
+    /// The field of a container (or the variant of a union) with this name.
+    Field(&'static str),
+    /// The item of a list or vector at this index.
+    Index(usize),
+}
+
+impl DecodeError {
+    /// Returns `self` as having occurred within the field `name` of a container.
+    pub fn in_field(self, name: &'static str) -> Self {
+        self.in_path(PathElement::Field(name))
+    }
+
+    /// Returns `self` as having occurred within the item at `index` of a list.
+    pub fn in_index(self, index: usize) -> Self {
+        self.in_path(PathElement::Index(index))
+    }
+
+    fn in_path(self, element: PathElement) -> Self {
+        match self {
+            DecodeError::InPath { mut path, error } => {
+                path.insert(0, element);
+                DecodeError::InPath { path, error }
+            }
+            error => DecodeError::InPath {
+                path: vec![element],
+                error: Box::new(error),
+            },
+        }
+    }
+
+    /// The path to the field or item that failed to decode, empty if the error is not nested.
+    pub fn path(&self) -> &[PathElement] {
+        match self {
+            DecodeError::InPath { path, .. } => path,
+            _ => &[],
+        }
+    }
+
+    /// The underlying error, without its path.
+    pub fn root(&self) -> &DecodeError {
+        match self {
+            DecodeError::InPath { error, .. } => error,
+            error => error,
+        }
+    }
+}
+
+impl fmt::Display for PathElement {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            PathElement::Field(name) => write!(f, ".{}", name),
+            PathElement::Index(index) => write!(f, "[{}]", index),
+        }
+    }
+}
+
+/// Formats the path like a Rust expression, e.g. `.body.attestations[17].aggregation_bits: ...`.
+impl fmt::Display for DecodeError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        for element in self.path() {
+            write!(f, "{}", element)?;
+        }
+        if !self.path().is_empty() {
+            write!(f, ": ")?;
+        }
+
+        match self.root() {
+            DecodeError::InvalidByteLength { len, expected } => {
+                write!(f, "invalid byte length {}, expected {}", len, expected)
+            }
+            DecodeError::InvalidLengthPrefix { len, expected } => write!(
+                f,
+                "invalid length prefix of {} bytes, expected {}",
+                len, expected
+            ),
+            DecodeError::OutOfBoundsByte { i } => write!(f, "offset {} is out of bounds", i),
+            DecodeError::OffsetsAreDecreasing { offset, previous } => write!(
+                f,
+                "offset {} is less than the previous offset {}",
+                offset, previous
+            ),
+            DecodeError::TrailingBytes { len, expected } => write!(
+                f,
+                "{} trailing bytes after a fixed-length object of {} bytes",
+                len.saturating_sub(*expected),
+                expected
+            ),
+            DecodeError::ListTooLong { len, max } => {
+                write!(f, "list of {} items exceeds the maximum of {}", len, max)
+            }
+            DecodeError::ZeroLengthItem => write!(f, "list items have a fixed length of zero"),
+            DecodeError::BytesInvalid(message) => write!(f, "invalid bytes: {}", message),
+            DecodeError::Io(message) => write!(f, "read failed: {}", message),
+            DecodeError::InPath { .. } => unreachable!("the root error has no path"),
+        }
+    }
 }
 
 /// Provides SSZ decoding (de-serialization) via the `from_ssz_bytes(&bytes)` method.
@@ -41,11 +161,47 @@ pub trait Decode: Sized {
         BYTES_PER_LENGTH_OFFSET
     }
 
+    /// The length of the shortest valid SSZ encoding of this type.
+    ///
+    /// By default, this is `ssz_fixed_len` for fixed-length objects and zero otherwise.
+    /// Variable-length objects should return the length of their smallest value, e.g. an empty
+    /// list.
+    fn ssz_min_len() -> usize {
+        if <Self as Decode>::is_ssz_fixed_len() {
+            <Self as Decode>::ssz_fixed_len()
+        } else {
+            0
+        }
+    }
+
+    /// The length of the longest valid SSZ encoding of this type.
+    ///
+    /// By default, this is `ssz_fixed_len` for fixed-length objects and `usize::max_value()`
+    /// (i.e., unbounded) otherwise. Variable-length objects with a maximum length should return
+    /// it, so that oversized input can be rejected before it is decoded.
+    fn ssz_max_len() -> usize {
+        if <Self as Decode>::is_ssz_fixed_len() {
+            <Self as Decode>::ssz_fixed_len()
+        } else {
+            usize::max_value()
+        }
+    }
+
     /// Attempts to decode `Self` from `bytes`, returning a `DecodeError` on failure.
     ///
     /// The supplied bytes must be the exact length required to decode `Self`, excess bytes will
     /// result in an error.
     fn from_ssz_bytes(bytes: &[u8]) -> Result<Self, DecodeError>;
+
+    /// Attempts to decode `Self` from the next `len` bytes of `reader`.
+    ///
+    /// `len` is checked against `ssz_min_len` and `ssz_max_len` before anything is read. The
+    /// default implementation then reads all `len` bytes into memory and calls `from_ssz_bytes`;
+    /// lists and containers instead read one item at a time. Exactly `len` bytes are consumed
+    /// from `reader` on success.
+    fn from_ssz_reader<R: std::io::Read>(reader: &mut R, len: usize) -> Result<Self, DecodeError> {
+        Self::from_ssz_bytes(&stream::read_ssz_bytes::<Self, R>(reader, len)?)
+    }
 }
 
 #[derive(Copy, Clone, Debug)]
@@ -81,9 +237,19 @@ impl<'a> SszDecoderBuilder<'a> {
 
     /// Declares that some type `T` is the next item in `bytes`.
     pub fn register_type<T: Decode>(&mut self) -> Result<(), DecodeError> {
-        if T::is_ssz_fixed_len() {
+        self.register_type_parameterized(T::is_ssz_fixed_len(), T::ssz_fixed_len())
+    }
+
+    /// Declares that the next item in `bytes` has the given length properties, for items that are
+    /// decoded by something other than a `Decode` implementation.
+    pub fn register_type_parameterized(
+        &mut self,
+        is_ssz_fixed_len: bool,
+        ssz_fixed_len: usize,
+    ) -> Result<(), DecodeError> {
+        if is_ssz_fixed_len {
             let start = self.items_index;
-            self.items_index += T::ssz_fixed_len();
+            self.items_index += ssz_fixed_len;
 
             let slice = self.bytes.get(start..self.items_index).ok_or_else(|| {
                 DecodeError::InvalidByteLength {
@@ -102,7 +268,12 @@ impl<'a> SszDecoderBuilder<'a> {
                 .and_then(|o| Some(o.offset))
                 .unwrap_or_else(|| BYTES_PER_LENGTH_OFFSET);
 
-            if (previous_offset > offset) || (offset > self.bytes.len()) {
+            if previous_offset > offset {
+                return Err(DecodeError::OffsetsAreDecreasing {
+                    offset,
+                    previous: previous_offset,
+                });
+            } else if offset > self.bytes.len() {
                 return Err(DecodeError::OutOfBoundsByte { i: offset });
             }
 
@@ -146,7 +317,7 @@ impl<'a> SszDecoderBuilder<'a> {
         } else {
             // If the container is fixed-length, ensure there are no excess bytes.
             if self.items_index != self.bytes.len() {
-                return Err(DecodeError::InvalidByteLength {
+                return Err(DecodeError::TrailingBytes {
                     len: self.bytes.len(),
                     expected: self.items_index,
                 });
@@ -216,6 +387,27 @@ impl<'a> SszDecoder<'a> {
     pub fn decode_next<T: Decode>(&mut self) -> Result<T, DecodeError> {
         T::from_ssz_bytes(self.items.remove(0))
     }
+
+    /// Decodes the next item with `f` rather than a `Decode` implementation.
+    ///
+    /// # Panics
+    ///
+    /// Panics when attempting to decode more items than actually exist.
+    pub fn decode_next_with<T, F>(&mut self, f: F) -> Result<T, DecodeError>
+    where
+        F: FnOnce(&'a [u8]) -> Result<T, DecodeError>,
+    {
+        f(self.items.remove(0))
+    }
+
+    /// Returns the bytes of the next item without decoding them.
+    ///
+    /// # Panics
+    ///
+    /// Panics when attempting to read more items than actually exist.
+    pub fn next_bytes(&mut self) -> &'a [u8] {
+        self.items.remove(0)
+    }
 }
 
 /// Reads a `BYTES_PER_LENGTH_OFFSET`-byte union index from `bytes`, where `bytes.len() >=
@@ -226,7 +418,7 @@ pub fn read_union_index(bytes: &[u8]) -> Result<usize, DecodeError> {
 
 /// Reads a `BYTES_PER_LENGTH_OFFSET`-byte length from `bytes`, where `bytes.len() >=
 /// BYTES_PER_LENGTH_OFFSET`.
-fn read_offset(bytes: &[u8]) -> Result<usize, DecodeError> {
+pub(crate) fn read_offset(bytes: &[u8]) -> Result<usize, DecodeError> {
     decode_offset(bytes.get(0..BYTES_PER_LENGTH_OFFSET).ok_or_else(|| {
         DecodeError::InvalidLengthPrefix {
             len: bytes.len(),
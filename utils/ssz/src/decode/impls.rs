@@ -67,6 +67,26 @@ macro_rules! impl_decode_for_tuples {
                     }
                 }
 
+                fn ssz_min_len() -> usize {
+                    0usize $(
+                        .saturating_add(if <$T as Decode>::is_ssz_fixed_len() {
+                            <$T as Decode>::ssz_fixed_len()
+                        } else {
+                            BYTES_PER_LENGTH_OFFSET.saturating_add(<$T as Decode>::ssz_min_len())
+                        })
+                    )*
+                }
+
+                fn ssz_max_len() -> usize {
+                    0usize $(
+                        .saturating_add(if <$T as Decode>::is_ssz_fixed_len() {
+                            <$T as Decode>::ssz_fixed_len()
+                        } else {
+                            BYTES_PER_LENGTH_OFFSET.saturating_add(<$T as Decode>::ssz_max_len())
+                        })
+                    )*
+                }
+
                 fn from_ssz_bytes(bytes: &[u8]) -> Result<Self, DecodeError> {
                     let mut builder = SszDecoderBuilder::new(bytes);
 
@@ -245,6 +265,14 @@ impl<T: Decode> Decode for Option<T> {
         false
     }
 
+    fn ssz_min_len() -> usize {
+        BYTES_PER_LENGTH_OFFSET
+    }
+
+    fn ssz_max_len() -> usize {
+        BYTES_PER_LENGTH_OFFSET.saturating_add(<T as Decode>::ssz_max_len())
+    }
+
     fn from_ssz_bytes(bytes: &[u8]) -> Result<Self, DecodeError> {
         if bytes.len() < BYTES_PER_LENGTH_OFFSET {
             return Err(DecodeError::InvalidByteLength {
@@ -369,16 +397,43 @@ impl<T: Decode> Decode for Vec<T> {
     }
 
     fn from_ssz_bytes(bytes: &[u8]) -> Result<Self, DecodeError> {
-        if bytes.is_empty() {
-            Ok(vec![])
-        } else if T::is_ssz_fixed_len() {
-            bytes
-                .chunks(T::ssz_fixed_len())
-                .map(|chunk| T::from_ssz_bytes(chunk))
-                .collect()
-        } else {
-            decode_list_of_variable_length_items(bytes)
+        decode_list(bytes, None)
+    }
+
+    fn from_ssz_reader<R: std::io::Read>(reader: &mut R, len: usize) -> Result<Self, DecodeError> {
+        crate::stream::read_list(reader, len, None)
+    }
+}
+
+/// Returns `DecodeError::ListTooLong` if a list of `len` items exceeds `max_len`.
+pub(crate) fn check_list_len(len: usize, max_len: Option<usize>) -> Result<(), DecodeError> {
+    match max_len {
+        Some(max) if len > max => Err(DecodeError::ListTooLong { len, max }),
+        _ => Ok(()),
+    }
+}
+
+/// Decodes `bytes` as a list of `T`.
+///
+/// If `max_len` is supplied, the number of items is checked against it before any item is decoded
+/// or allocated for, returning `DecodeError::ListTooLong` for a longer list.
+pub fn decode_list<T: Decode>(bytes: &[u8], max_len: Option<usize>) -> Result<Vec<T>, DecodeError> {
+    if bytes.is_empty() {
+        Ok(vec![])
+    } else if T::is_ssz_fixed_len() {
+        let item_len = T::ssz_fixed_len();
+        if item_len == 0 {
+            return Err(DecodeError::ZeroLengthItem);
         }
+        check_list_len(bytes.len() / item_len, max_len)?;
+
+        bytes
+            .chunks(item_len)
+            .enumerate()
+            .map(|(i, chunk)| T::from_ssz_bytes(chunk).map_err(|e| e.in_index(i)))
+            .collect()
+    } else {
+        decode_list_of_variable_length_items(bytes, max_len)
     }
 }
 
@@ -387,8 +442,11 @@ impl<T: Decode> Decode for Vec<T> {
 /// The `ssz::SszDecoder` can also perform this functionality, however it it significantly faster
 /// as it is optimized to read same-typed items whilst `ssz::SszDecoder` supports reading items of
 /// differing types.
+///
+/// The number of items is checked against `max_len` (if any) as in `decode_list`.
 pub fn decode_list_of_variable_length_items<T: Decode>(
     bytes: &[u8],
+    max_len: Option<usize>,
 ) -> Result<Vec<T>, DecodeError> {
     let mut next_variable_byte = read_offset(bytes)?;
 
@@ -410,6 +468,15 @@ pub fn decode_list_of_variable_length_items<T: Decode>(
         });
     }
 
+    // The fixed-length section must fit in `bytes`, which also bounds the allocation below.
+    if next_variable_byte > bytes.len() {
+        return Err(DecodeError::OutOfBoundsByte {
+            i: next_variable_byte,
+        });
+    }
+
+    check_list_len(num_items, max_len)?;
+
     let mut values = Vec::with_capacity(num_items);
     for i in 1..=num_items {
         let slice_option = if i == num_items {
@@ -417,6 +484,13 @@ pub fn decode_list_of_variable_length_items<T: Decode>(
         } else {
             let offset = read_offset(&bytes[(i * BYTES_PER_LENGTH_OFFSET)..])?;
 
+            if offset < next_variable_byte {
+                return Err(DecodeError::OffsetsAreDecreasing {
+                    offset,
+                    previous: next_variable_byte,
+                });
+            }
+
             let start = next_variable_byte;
             next_variable_byte = offset;
 
@@ -427,7 +501,7 @@ pub fn decode_list_of_variable_length_items<T: Decode>(
             i: next_variable_byte,
         })?;
 
-        values.push(T::from_ssz_bytes(slice)?);
+        values.push(T::from_ssz_bytes(slice).map_err(|e| e.in_index(i - 1))?);
     }
 
     Ok(values)
@@ -530,7 +604,10 @@ mod tests {
     fn lengths_are_decreasing() {
         assert_eq!(
             <Vec<Vec<u16>>>::from_ssz_bytes(&[12, 0, 0, 0, 14, 0, 0, 0, 12, 0, 0, 0, 1, 0, 1, 0]),
-            Err(DecodeError::OutOfBoundsByte { i: 12 })
+            Err(DecodeError::OffsetsAreDecreasing {
+                offset: 12,
+                previous: 14
+            })
         );
     }
 
@@ -558,6 +635,58 @@ mod tests {
             <Vec<Vec<u16>>>::from_ssz_bytes(&[8, 0, 0, 0, 9, 0, 0, 0]),
             Err(DecodeError::OutOfBoundsByte { i: 9 })
         );
+        // A first offset claiming about a billion items, with none of them present.
+        assert_eq!(
+            <Vec<Vec<u8>>>::from_ssz_bytes(&[0xfc, 0xff, 0xff, 0xff]),
+            Err(DecodeError::OutOfBoundsByte { i: 0xffff_fffc })
+        );
+    }
+
+    #[test]
+    fn list_max_len() {
+        let bytes = vec![1u16, 2, 3].as_ssz_bytes();
+        assert_eq!(decode_list::<u16>(&bytes, Some(3)), Ok(vec![1, 2, 3]));
+        assert_eq!(
+            decode_list::<u16>(&bytes, Some(2)),
+            Err(DecodeError::ListTooLong { len: 3, max: 2 })
+        );
+
+        // The count comes from the first offset, so the items are never looked at.
+        let bytes = [12, 0, 0, 0, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff];
+        assert_eq!(
+            decode_list::<Vec<u8>>(&bytes, Some(2)),
+            Err(DecodeError::ListTooLong { len: 3, max: 2 })
+        );
+    }
+
+    #[test]
+    fn zero_length_items() {
+        #[derive(Debug, PartialEq)]
+        struct Empty;
+
+        impl Decode for Empty {
+            fn is_ssz_fixed_len() -> bool {
+                true
+            }
+
+            fn ssz_fixed_len() -> usize {
+                0
+            }
+
+            fn from_ssz_bytes(_: &[u8]) -> Result<Self, DecodeError> {
+                Ok(Empty)
+            }
+        }
+
+        assert_eq!(<Vec<Empty>>::from_ssz_bytes(&[]), Ok(vec![]));
+        assert_eq!(
+            <Vec<Empty>>::from_ssz_bytes(&[0]),
+            Err(DecodeError::ZeroLengthItem)
+        );
+        assert_eq!(
+            <Vec<Empty>>::from_ssz_reader(&mut &[0u8][..], 1),
+            Err(DecodeError::ZeroLengthItem)
+        );
     }
 
     #[test]
@@ -27,6 +27,32 @@ pub trait Encode {
         BYTES_PER_LENGTH_OFFSET
     }
 
+    /// The length of the shortest valid SSZ encoding of this type.
+    ///
+    /// By default, this is `ssz_fixed_len` for fixed-length objects and zero otherwise.
+    /// Variable-length objects should return the length of their smallest value, e.g. an empty
+    /// list.
+    fn ssz_min_len() -> usize {
+        if <Self as Encode>::is_ssz_fixed_len() {
+            <Self as Encode>::ssz_fixed_len()
+        } else {
+            0
+        }
+    }
+
+    /// The length of the longest valid SSZ encoding of this type.
+    ///
+    /// By default, this is `ssz_fixed_len` for fixed-length objects and `usize::max_value()`
+    /// (i.e., unbounded) otherwise. Variable-length objects with a maximum length should return
+    /// it, so that oversized input can be rejected before it is decoded.
+    fn ssz_max_len() -> usize {
+        if <Self as Encode>::is_ssz_fixed_len() {
+            <Self as Encode>::ssz_fixed_len()
+        } else {
+            usize::max_value()
+        }
+    }
+
     /// Returns the size (in bytes) when `self` is serialized.
     ///
     /// Returns the same value as `self.as_ssz_bytes().len()` but this method is significantly more
@@ -43,6 +69,14 @@ pub trait Encode {
 
         buf
     }
+
+    /// Writes the full-form encoding of this object to `writer`.
+    ///
+    /// The default implementation writes `as_ssz_bytes`; lists and containers instead write one
+    /// item at a time, so the whole encoding is never held in memory.
+    fn ssz_write<W: std::io::Write>(&self, writer: &mut W) -> std::io::Result<()> {
+        writer.write_all(&self.as_ssz_bytes())
+    }
 }
 
 /// Allow for encoding an ordered series of distinct or indistinct objects as SSZ bytes.
@@ -111,13 +145,23 @@ impl<'a> SszEncoder<'a> {
 
     /// Append some `item` to the SSZ bytes.
     pub fn append<T: Encode>(&mut self, item: &T) {
-        if T::is_ssz_fixed_len() {
-            item.ssz_append(&mut self.buf);
+        self.append_parameterized(T::is_ssz_fixed_len(), |buf| item.ssz_append(buf))
+    }
+
+    /// Append an item that is encoded by `ssz_append` rather than an `Encode` implementation.
+    ///
+    /// `is_ssz_fixed_len` must be as `Encode::is_ssz_fixed_len` would be for the item.
+    pub fn append_parameterized<F>(&mut self, is_ssz_fixed_len: bool, ssz_append: F)
+    where
+        F: FnOnce(&mut Vec<u8>),
+    {
+        if is_ssz_fixed_len {
+            ssz_append(self.buf);
         } else {
             self.buf
                 .append(&mut encode_length(self.offset + self.variable_bytes.len()));
 
-            item.ssz_append(&mut self.variable_bytes);
+            ssz_append(&mut self.variable_bytes);
         }
     }
 
@@ -135,6 +179,33 @@ impl<'a> SszEncoder<'a> {
 /// Encode `index` as a little-endian byte vec of `BYTES_PER_LENGTH_OFFSET` length.
 ///
 /// If `len` is larger than `2 ^ BYTES_PER_LENGTH_OFFSET`, a `debug_assert` is raised.
+///
+/// ## Example
+///
+/// A derived union may have a unit variant, standing for `None`, but only at index 0:
+///
+/// ```rust
+/// use ssz_derive::Encode;
+/// use ssz::Encode;
+///
+/// #[derive(Encode)]
+/// enum Foo {
+///     Empty,
+///     Number(u64),
+/// }
+///
+/// assert_eq!(Foo::Empty.as_ssz_bytes(), ssz::encode_union_index(0));
+/// ```
+///
+/// ```compile_fail
+/// use ssz_derive::Encode;
+///
+/// #[derive(Encode)]
+/// enum Foo {
+///     Number(u64),
+///     Empty,
+/// }
+/// ```
 pub fn encode_union_index(index: usize) -> Vec<u8> {
     encode_length(index)
 }
@@ -62,6 +62,26 @@ macro_rules! impl_encode_for_tuples {
                     }
                 }
 
+                fn ssz_min_len() -> usize {
+                    0usize $(
+                        .saturating_add(if <$T as Encode>::is_ssz_fixed_len() {
+                            <$T as Encode>::ssz_fixed_len()
+                        } else {
+                            BYTES_PER_LENGTH_OFFSET.saturating_add(<$T as Encode>::ssz_min_len())
+                        })
+                    )*
+                }
+
+                fn ssz_max_len() -> usize {
+                    0usize $(
+                        .saturating_add(if <$T as Encode>::is_ssz_fixed_len() {
+                            <$T as Encode>::ssz_fixed_len()
+                        } else {
+                            BYTES_PER_LENGTH_OFFSET.saturating_add(<$T as Encode>::ssz_max_len())
+                        })
+                    )*
+                }
+
                 fn ssz_bytes_len(&self) -> usize {
                     if <Self as Encode>::is_ssz_fixed_len() {
                         <Self as Encode>::ssz_fixed_len()
@@ -206,6 +226,14 @@ impl<T: Encode> Encode for Option<T> {
         false
     }
 
+    fn ssz_min_len() -> usize {
+        BYTES_PER_LENGTH_OFFSET
+    }
+
+    fn ssz_max_len() -> usize {
+        BYTES_PER_LENGTH_OFFSET.saturating_add(<T as Encode>::ssz_max_len())
+    }
+
     fn ssz_bytes_len(&self) -> usize {
         if let Some(some) = self {
             let len = if <T as Encode>::is_ssz_fixed_len() {
@@ -262,6 +290,10 @@ impl<T: Encode> Encode for Vec<T> {
             encoder.finalize();
         }
     }
+
+    fn ssz_write<W: std::io::Write>(&self, writer: &mut W) -> std::io::Result<()> {
+        crate::stream::write_list(self, self.len(), writer)
+    }
 }
 
 impl Encode for bool {
@@ -32,7 +32,13 @@
 //!
 //! ```
 //!
-//! See `examples/` for manual implementations of the `Encode` and `Decode` traits.
+//! See `examples/` for manual implementations of the `Encode` and `Decode` traits, and
+//! `DecodeView` for reading individual fields without decoding the whole object. Objects can
+//! also be written to a `std::io::Write` with `Encode::ssz_write` and read back from a
+//! `std::io::Read` with `Decode::from_ssz_reader`.
+//!
+//! `SszSchema` describes a type at runtime (its SSZ kind, fields, lengths and limits), for tools
+//! that work with types they do not know at compile time.
 
 #![allow(clippy::cast_possible_truncation)]
 #![allow(clippy::decimal_literal_representation)]
@@ -48,11 +54,20 @@
 
 mod decode;
 mod encode;
+mod schema;
+mod stream;
+mod view;
 
 pub use decode::{
-    impls::decode_list_of_variable_length_items, Decode, DecodeError, SszDecoder, SszDecoderBuilder,
+    impls::{decode_list, decode_list_of_variable_length_items},
+    read_union_index, Decode, DecodeError, PathElement, SszDecoder, SszDecoderBuilder,
+};
+pub use encode::{encode_union_index, Encode, SszEncoder};
+pub use schema::{FieldSchema, Schema, SszSchema, VariantSchema};
+pub use stream::{
+    read_list, write_list, SszStreamDecoder, SszStreamDecoderBuilder, SszStreamEncoder,
 };
-pub use encode::{Encode, SszEncoder};
+pub use view::{DecodeView, LazyView, ListIter, ListView};
 
 /// The number of bytes used to represent an offset.
 pub const BYTES_PER_LENGTH_OFFSET: usize = 4;
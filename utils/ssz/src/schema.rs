@@ -0,0 +1,254 @@
+//! Runtime descriptions of SSZ types, for tooling that handles types it does not know at compile
+//! time (e.g. pretty-printing SSZ bytes or generating test vectors).
+//!
+//! `#[derive(SszSchema)]` from `ssz_derive` implements `SszSchema` for containers and unions.
+use super::BYTES_PER_LENGTH_OFFSET;
+use core::num::NonZeroUsize;
+use ethereum_types::{H256, U128, U256};
+use std::fmt;
+
+/// The SSZ kind of a type, with its lengths and limits.
+#[derive(Debug, PartialEq, Clone)]
+pub enum Schema {
+    /// `boolean`.
+    Bool,
+    /// `uintN`, where `N` is `8 * bytes`.
+    Uint { bytes: usize },
+    /// `Vector[element, length]`.
+    Vector { element: Box<Schema>, length: usize },
+    /// `List[element, limit]`. A `Vec` has no limit.
+    List {
+        element: Box<Schema>,
+        limit: Option<usize>,
+    },
+    /// `Bitvector[length]`.
+    Bitvector { length: usize },
+    /// `Bitlist[limit]`.
+    Bitlist { limit: usize },
+    /// A container, with the fields that are serialized, in order.
+    Container {
+        name: &'static str,
+        fields: Vec<FieldSchema>,
+    },
+    /// `Union[..]`, with its variants in order of their union index.
+    Union {
+        name: &'static str,
+        variants: Vec<VariantSchema>,
+    },
+}
+
+/// A field of a `Schema::Container`.
+#[derive(Debug, PartialEq, Clone)]
+pub struct FieldSchema {
+    /// The ident of the field, or its position for an unnamed field.
+    pub name: &'static str,
+    /// The Rust type of the field as written in the container, e.g.
+    /// `VariableList<u64,C::ValidatorRegistryLimit>`, which shows where its lengths come from.
+    pub ty: &'static str,
+    pub schema: Schema,
+}
+
+/// A variant of a `Schema::Union`.
+#[derive(Debug, PartialEq, Clone)]
+pub struct VariantSchema {
+    pub name: &'static str,
+    /// The schema of the value of the variant, or `None` for a variant without one (like `None`
+    /// of `Option<T>`).
+    pub schema: Option<Schema>,
+}
+
+impl Schema {
+    /// Returns `Schema::Uint { bytes: 1 }`, the element of byte vectors and lists.
+    pub fn byte() -> Self {
+        Schema::Uint { bytes: 1 }
+    }
+
+    /// Returns `true` if every value of the type has the same encoded length.
+    pub fn is_fixed_len(&self) -> bool {
+        match self {
+            Schema::Bool | Schema::Uint { .. } | Schema::Bitvector { .. } => true,
+            Schema::Vector { element, .. } => element.is_fixed_len(),
+            Schema::List { .. } | Schema::Bitlist { .. } | Schema::Union { .. } => false,
+            Schema::Container { fields, .. } => {
+                fields.iter().all(|field| field.schema.is_fixed_len())
+            }
+        }
+    }
+
+    /// Returns the number of bytes the type takes in the fixed-length part of a container or
+    /// vector: its encoded length if it is fixed-length, or else that of an offset.
+    pub fn fixed_len(&self) -> usize {
+        if !self.is_fixed_len() {
+            return BYTES_PER_LENGTH_OFFSET;
+        }
+
+        match self {
+            Schema::Bool => 1,
+            Schema::Uint { bytes } => *bytes,
+            Schema::Vector { element, length } => element.fixed_len().saturating_mul(*length),
+            Schema::Bitvector { length } => (length + 7) / 8,
+            Schema::Container { fields, .. } => fields
+                .iter()
+                .fold(0, |len, field| len.saturating_add(field.schema.fixed_len())),
+            Schema::List { .. } | Schema::Bitlist { .. } | Schema::Union { .. } => {
+                unreachable!("Variable-length types are handled above")
+            }
+        }
+    }
+
+    /// Returns the fields of a container, or `None` for any other kind.
+    pub fn fields(&self) -> Option<&[FieldSchema]> {
+        match self {
+            Schema::Container { fields, .. } => Some(fields),
+            _ => None,
+        }
+    }
+
+    /// Returns the field of a container called `name`.
+    pub fn field(&self, name: &str) -> Option<&FieldSchema> {
+        self.fields()?.iter().find(|field| field.name == name)
+    }
+}
+
+/// Formats the schema in the notation of the SSZ specification, e.g. `List[uint64, 4]`.
+/// Containers and unions are written by name.
+impl fmt::Display for Schema {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Schema::Bool => write!(f, "boolean"),
+            Schema::Uint { bytes } => write!(f, "uint{}", bytes * 8),
+            Schema::Vector { element, length } => write!(f, "Vector[{}, {}]", element, length),
+            Schema::List {
+                element,
+                limit: Some(limit),
+            } => write!(f, "List[{}, {}]", element, limit),
+            Schema::List {
+                element,
+                limit: None,
+            } => write!(f, "List[{}]", element),
+            Schema::Bitvector { length } => write!(f, "Bitvector[{}]", length),
+            Schema::Bitlist { limit } => write!(f, "Bitlist[{}]", limit),
+            Schema::Container { name, .. } | Schema::Union { name, .. } => write!(f, "{}", name),
+        }
+    }
+}
+
+/// Provides the `Schema` of a type.
+pub trait SszSchema {
+    fn ssz_schema() -> Schema;
+}
+
+macro_rules! impl_ssz_schema_for_uint {
+    ($type: ident, $bit_size: expr) => {
+        impl SszSchema for $type {
+            fn ssz_schema() -> Schema {
+                Schema::Uint {
+                    bytes: $bit_size / 8,
+                }
+            }
+        }
+    };
+}
+
+impl_ssz_schema_for_uint!(u8, 8);
+impl_ssz_schema_for_uint!(u16, 16);
+impl_ssz_schema_for_uint!(u32, 32);
+impl_ssz_schema_for_uint!(u64, 64);
+impl_ssz_schema_for_uint!(U128, 128);
+impl_ssz_schema_for_uint!(U256, 256);
+
+#[cfg(target_pointer_width = "32")]
+impl_ssz_schema_for_uint!(usize, 32);
+
+#[cfg(target_pointer_width = "64")]
+impl_ssz_schema_for_uint!(usize, 64);
+
+impl SszSchema for NonZeroUsize {
+    fn ssz_schema() -> Schema {
+        usize::ssz_schema()
+    }
+}
+
+impl SszSchema for bool {
+    fn ssz_schema() -> Schema {
+        Schema::Bool
+    }
+}
+
+impl SszSchema for H256 {
+    fn ssz_schema() -> Schema {
+        Schema::Vector {
+            element: Box::new(Schema::byte()),
+            length: 32,
+        }
+    }
+}
+
+macro_rules! impl_ssz_schema_for_u8_array {
+    ($len: expr) => {
+        impl SszSchema for [u8; $len] {
+            fn ssz_schema() -> Schema {
+                Schema::Vector {
+                    element: Box::new(Schema::byte()),
+                    length: $len,
+                }
+            }
+        }
+    };
+}
+
+impl_ssz_schema_for_u8_array!(4);
+impl_ssz_schema_for_u8_array!(32);
+
+impl<T: SszSchema> SszSchema for Vec<T> {
+    fn ssz_schema() -> Schema {
+        Schema::List {
+            element: Box::new(T::ssz_schema()),
+            limit: None,
+        }
+    }
+}
+
+impl<T: SszSchema> SszSchema for Option<T> {
+    fn ssz_schema() -> Schema {
+        Schema::Union {
+            name: "Option",
+            variants: vec![
+                VariantSchema {
+                    name: "None",
+                    schema: None,
+                },
+                VariantSchema {
+                    name: "Some",
+                    schema: Some(T::ssz_schema()),
+                },
+            ],
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn display() {
+        assert_eq!(u64::ssz_schema().to_string(), "uint64");
+        assert_eq!(H256::ssz_schema().to_string(), "Vector[uint8, 32]");
+        assert_eq!(<Vec<bool>>::ssz_schema().to_string(), "List[boolean]");
+        assert_eq!(<Option<u8>>::ssz_schema().to_string(), "Option");
+    }
+
+    #[test]
+    fn lengths() {
+        assert_eq!(U256::ssz_schema().fixed_len(), 32);
+        assert_eq!(<[u8; 4]>::ssz_schema().fixed_len(), 4);
+        assert_eq!(Schema::Bitvector { length: 9 }.fixed_len(), 2);
+        assert!(!<Vec<u8>>::ssz_schema().is_fixed_len());
+        assert_eq!(<Vec<u8>>::ssz_schema().fixed_len(), BYTES_PER_LENGTH_OFFSET);
+        assert_eq!(
+            <Option<u8>>::ssz_schema().fixed_len(),
+            BYTES_PER_LENGTH_OFFSET
+        );
+    }
+}
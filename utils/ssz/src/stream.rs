@@ -0,0 +1,566 @@
+//! Encoding to `std::io::Write` and decoding from `std::io::Read`, without holding the whole
+//! encoding in memory.
+//!
+//! Containers, lists and vectors are written and read one item at a time, using `ssz_bytes_len`
+//! to compute offsets when writing and the offsets themselves to find item lengths when reading.
+//! Everything else is buffered whole.
+
+use super::*;
+use crate::decode::{impls::check_list_len, read_offset};
+use crate::encode::encode_length;
+use std::cmp;
+use std::io::{self, Read, Write};
+
+/// Fixed-length list items are buffered and written or read in batches of about this many bytes.
+const BATCH_LEN: usize = 1 << 16;
+
+/// Writes the SSZ encoding of a container or list to a `Write`.
+///
+/// Every item is first passed to `write_fixed_part`, which writes fixed-length items and the
+/// offsets of variable-length ones. Then every item is passed to `write_variable_part`, in the
+/// same order.
+///
+/// ## Example
+///
+/// ```rust
+/// use ssz_derive::{Encode, Decode};
+/// use ssz::{Encode, SszStreamEncoder};
+///
+/// #[derive(Encode, Decode)]
+/// struct Foo {
+///     a: u64,
+///     b: Vec<u16>,
+/// }
+///
+/// fn main() {
+///     let foo = Foo {
+///         a: 42,
+///         b: vec![1, 3, 3, 7]
+///     };
+///
+///     let mut buf: Vec<u8> = vec![];
+///     let offset = <u64 as Encode>::ssz_fixed_len() + <Vec<u16> as Encode>::ssz_fixed_len();
+///
+///     let mut encoder = SszStreamEncoder::container(&mut buf, offset);
+///
+///     encoder.write_fixed_part(&foo.a).unwrap();
+///     encoder.write_fixed_part(&foo.b).unwrap();
+///     encoder.write_variable_part(&foo.a).unwrap();
+///     encoder.write_variable_part(&foo.b).unwrap();
+///
+///     assert_eq!(foo.as_ssz_bytes(), buf);
+/// }
+/// ```
+pub struct SszStreamEncoder<'a, W> {
+    writer: &'a mut W,
+    offset: usize,
+}
+
+impl<'a, W: Write> SszStreamEncoder<'a, W> {
+    /// Instantiate a new encoder for writing a SSZ list.
+    ///
+    /// Identical to `Self::container`.
+    pub fn list(writer: &'a mut W, num_fixed_bytes: usize) -> Self {
+        Self::container(writer, num_fixed_bytes)
+    }
+
+    /// Instantiate a new encoder for writing a SSZ container.
+    pub fn container(writer: &'a mut W, num_fixed_bytes: usize) -> Self {
+        Self {
+            writer,
+            offset: num_fixed_bytes,
+        }
+    }
+
+    /// Writes `item` if it is fixed-length, or its offset otherwise.
+    pub fn write_fixed_part<T: Encode>(&mut self, item: &T) -> io::Result<()> {
+        if T::is_ssz_fixed_len() {
+            item.ssz_write(self.writer)
+        } else {
+            self.writer.write_all(&encode_length(self.offset))?;
+            self.offset += item.ssz_bytes_len();
+            Ok(())
+        }
+    }
+
+    /// Writes `item` if it is variable-length.
+    pub fn write_variable_part<T: Encode>(&mut self, item: &T) -> io::Result<()> {
+        if T::is_ssz_fixed_len() {
+            Ok(())
+        } else {
+            item.ssz_write(self.writer)
+        }
+    }
+}
+
+/// Writes a list of `len` items to `writer`, one item at a time, batching fixed-length ones.
+///
+/// The streaming counterpart of `ssz_append` for any list type that can be iterated by reference.
+/// `items` is iterated twice when the items are variable-length: once for the offsets and once for
+/// the items themselves.
+pub fn write_list<'a, T, I, W>(items: I, len: usize, writer: &mut W) -> io::Result<()>
+where
+    T: Encode + 'a,
+    I: IntoIterator<Item = &'a T> + Copy,
+    W: Write,
+{
+    if T::is_ssz_fixed_len() {
+        let mut buf = Vec::with_capacity(cmp::min(BATCH_LEN, len * T::ssz_fixed_len()));
+
+        for item in items {
+            item.ssz_append(&mut buf);
+            if buf.len() >= BATCH_LEN {
+                writer.write_all(&buf)?;
+                buf.clear();
+            }
+        }
+
+        writer.write_all(&buf)
+    } else {
+        let mut encoder = SszStreamEncoder::list(writer, len * BYTES_PER_LENGTH_OFFSET);
+
+        for item in items {
+            encoder.write_fixed_part(item)?;
+        }
+        for item in items {
+            encoder.write_variable_part(item)?;
+        }
+
+        Ok(())
+    }
+}
+
+/// Reads exactly `len` bytes, without allocating more than `reader` actually provides.
+pub(crate) fn read_bytes<R: Read>(reader: &mut R, len: usize) -> Result<Vec<u8>, DecodeError> {
+    let mut bytes = vec![];
+    reader
+        .take(len as u64)
+        .read_to_end(&mut bytes)
+        .map_err(|e| DecodeError::Io(e.to_string()))?;
+
+    if bytes.len() == len {
+        Ok(bytes)
+    } else {
+        Err(DecodeError::InvalidByteLength {
+            len: bytes.len(),
+            expected: len,
+        })
+    }
+}
+
+/// Reads the `len` bytes of a `T` into memory, after checking `len` against the length bounds of
+/// `T`.
+pub(crate) fn read_ssz_bytes<T: Decode, R: Read>(
+    reader: &mut R,
+    len: usize,
+) -> Result<Vec<u8>, DecodeError> {
+    if len < T::ssz_min_len() {
+        return Err(DecodeError::InvalidByteLength {
+            len,
+            expected: T::ssz_min_len(),
+        });
+    }
+    if len > T::ssz_max_len() {
+        return Err(DecodeError::InvalidByteLength {
+            len,
+            expected: T::ssz_max_len(),
+        });
+    }
+
+    read_bytes(reader, len)
+}
+
+/// Reads a list of `len` bytes from `reader`, one item at a time.
+///
+/// The streaming counterpart of `decode_list`: the number of items is checked against `max_len`
+/// (if any) before any item is read.
+pub fn read_list<T: Decode, R: Read>(
+    reader: &mut R,
+    len: usize,
+    max_len: Option<usize>,
+) -> Result<Vec<T>, DecodeError> {
+    if len == 0 {
+        return Ok(vec![]);
+    }
+
+    if T::is_ssz_fixed_len() {
+        let item_len = T::ssz_fixed_len();
+        if item_len == 0 {
+            return Err(DecodeError::ZeroLengthItem);
+        }
+        let num_items = len / item_len;
+        if num_items * item_len != len {
+            return Err(DecodeError::InvalidByteLength {
+                len,
+                expected: num_items * item_len,
+            });
+        }
+        check_list_len(num_items, max_len)?;
+
+        let batch_len = cmp::max(BATCH_LEN / item_len, 1) * item_len;
+        let mut items = vec![];
+        let mut remaining = len;
+        while remaining > 0 {
+            let bytes = read_bytes(reader, cmp::min(batch_len, remaining))?;
+            for chunk in bytes.chunks(item_len) {
+                let item = T::from_ssz_bytes(chunk).map_err(|e| e.in_index(items.len()))?;
+                items.push(item);
+            }
+            remaining -= bytes.len();
+        }
+
+        return Ok(items);
+    }
+
+    if len < BYTES_PER_LENGTH_OFFSET {
+        return Err(DecodeError::InvalidLengthPrefix {
+            len,
+            expected: BYTES_PER_LENGTH_OFFSET,
+        });
+    }
+    let first_offset = read_offset(&read_bytes(reader, BYTES_PER_LENGTH_OFFSET)?)?;
+
+    if first_offset < BYTES_PER_LENGTH_OFFSET || first_offset > len {
+        return Err(DecodeError::OutOfBoundsByte { i: first_offset });
+    }
+
+    let num_items = first_offset / BYTES_PER_LENGTH_OFFSET;
+
+    if first_offset != num_items * BYTES_PER_LENGTH_OFFSET {
+        return Err(DecodeError::InvalidByteLength {
+            len: first_offset,
+            expected: num_items * BYTES_PER_LENGTH_OFFSET,
+        });
+    }
+
+    check_list_len(num_items, max_len)?;
+
+    let offset_bytes = read_bytes(reader, first_offset - BYTES_PER_LENGTH_OFFSET)?;
+    let mut offsets = vec![first_offset];
+    for bytes in offset_bytes.chunks(BYTES_PER_LENGTH_OFFSET) {
+        offsets.push(read_offset(bytes)?);
+    }
+    offsets.push(len);
+
+    // Check every offset before reading any item.
+    for pair in offsets.windows(2) {
+        if pair[1] < pair[0] {
+            return Err(DecodeError::OffsetsAreDecreasing {
+                offset: pair[1],
+                previous: pair[0],
+            });
+        } else if pair[1] > len {
+            return Err(DecodeError::OutOfBoundsByte { i: pair[1] });
+        }
+    }
+
+    offsets
+        .windows(2)
+        .enumerate()
+        .map(|(i, pair)| T::from_ssz_reader(reader, pair[1] - pair[0]).map_err(|e| e.in_index(i)))
+        .collect()
+}
+
+/// Where a container's item is found: in the fixed-length part, or after it with some length.
+enum StreamItem {
+    Fixed { start: usize, end: usize },
+    Variable { len: usize },
+}
+
+/// Builds an `SszStreamDecoder`, the streaming counterpart of `SszDecoderBuilder`.
+///
+/// ## Example
+///
+/// ```rust
+/// use ssz_derive::{Encode, Decode};
+/// use ssz::{Encode, SszStreamDecoderBuilder};
+///
+/// #[derive(PartialEq, Debug, Encode, Decode)]
+/// struct Foo {
+///     a: u64,
+///     b: Vec<u16>,
+/// }
+///
+/// fn main() {
+///     let foo = Foo {
+///         a: 42,
+///         b: vec![1, 3, 3, 7]
+///     };
+///
+///     let bytes = foo.as_ssz_bytes();
+///     let mut reader = &bytes[..];
+///
+///     let mut builder = SszStreamDecoderBuilder::new(bytes.len());
+///
+///     builder.register_type::<u64>().unwrap();
+///     builder.register_type::<Vec<u16>>().unwrap();
+///
+///     let mut decoder = builder.build(&mut reader).unwrap();
+///
+///     let decoded_foo = Foo {
+///         a: decoder.decode_next().unwrap(),
+///         b: decoder.decode_next().unwrap(),
+///     };
+///
+///     assert_eq!(foo, decoded_foo);
+/// }
+/// ```
+pub struct SszStreamDecoderBuilder {
+    len: usize,
+    items: Vec<StreamItem>,
+    /// The positions of the offsets of variable-length items within the fixed-length part.
+    offset_positions: Vec<(usize, usize)>,
+    fixed_len: usize,
+}
+
+impl SszStreamDecoderBuilder {
+    /// Instantiate a new builder for decoding a container from the next `len` bytes of a reader.
+    pub fn new(len: usize) -> Self {
+        Self {
+            len,
+            items: vec![],
+            offset_positions: vec![],
+            fixed_len: 0,
+        }
+    }
+
+    /// Declares that some type `T` is the next item of the container.
+    pub fn register_type<T: Decode>(&mut self) -> Result<(), DecodeError> {
+        let start = self.fixed_len;
+
+        if T::is_ssz_fixed_len() {
+            self.fixed_len += T::ssz_fixed_len();
+            self.items.push(StreamItem::Fixed {
+                start,
+                end: self.fixed_len,
+            });
+        } else {
+            self.fixed_len += BYTES_PER_LENGTH_OFFSET;
+            self.offset_positions.push((self.items.len(), start));
+            // The length is filled in once the offsets have been read.
+            self.items.push(StreamItem::Variable { len: 0 });
+        }
+
+        if self.fixed_len > self.len {
+            return Err(DecodeError::InvalidByteLength {
+                len: self.len,
+                expected: self.fixed_len,
+            });
+        }
+
+        Ok(())
+    }
+
+    /// Reads the fixed-length part of the container from `reader` and checks its offsets,
+    /// returning a `SszStreamDecoder` that reads the items themselves.
+    pub fn build<R: Read>(
+        mut self,
+        reader: &mut R,
+    ) -> Result<SszStreamDecoder<'_, R>, DecodeError> {
+        let fixed_bytes = read_bytes(reader, self.fixed_len)?;
+
+        if self.offset_positions.is_empty() {
+            // If the container is fixed-length, ensure there are no excess bytes.
+            if self.fixed_len != self.len {
+                return Err(DecodeError::TrailingBytes {
+                    len: self.len,
+                    expected: self.fixed_len,
+                });
+            }
+        } else {
+            let mut offsets = vec![];
+            for (item, position) in &self.offset_positions {
+                offsets.push((*item, read_offset(&fixed_bytes[*position..])?));
+            }
+
+            // The first offset must point to the byte immediately following the fixed-length
+            // bytes.
+            if offsets[0].1 != self.fixed_len {
+                return Err(DecodeError::OutOfBoundsByte { i: offsets[0].1 });
+            }
+
+            for (i, (item, offset)) in offsets.iter().enumerate() {
+                let end = offsets.get(i + 1).map_or(self.len, |next| next.1);
+                if end < *offset {
+                    return Err(DecodeError::OffsetsAreDecreasing {
+                        offset: end,
+                        previous: *offset,
+                    });
+                } else if end > self.len {
+                    return Err(DecodeError::OutOfBoundsByte { i: end });
+                }
+                self.items[*item] = StreamItem::Variable { len: end - offset };
+            }
+        }
+
+        Ok(SszStreamDecoder {
+            reader,
+            fixed_bytes,
+            items: self.items,
+            next: 0,
+        })
+    }
+}
+
+/// Decodes the items of a container from a `Read`, in order. Should be instantiated using
+/// [`SszStreamDecoderBuilder`](struct.SszStreamDecoderBuilder.html).
+pub struct SszStreamDecoder<'a, R> {
+    reader: &'a mut R,
+    fixed_bytes: Vec<u8>,
+    items: Vec<StreamItem>,
+    next: usize,
+}
+
+impl<'a, R: Read> SszStreamDecoder<'a, R> {
+    /// Decodes the next item.
+    ///
+    /// # Panics
+    ///
+    /// Panics when attempting to decode more items than actually exist.
+    pub fn decode_next<T: Decode>(&mut self) -> Result<T, DecodeError> {
+        let item = &self.items[self.next];
+        self.next += 1;
+
+        match *item {
+            StreamItem::Fixed { start, end } => T::from_ssz_bytes(&self.fixed_bytes[start..end]),
+            StreamItem::Variable { len } => T::from_ssz_reader(self.reader, len),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Hands out at most one byte per read.
+    struct Trickle<'a>(&'a [u8]);
+
+    impl<'a> Read for Trickle<'a> {
+        fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+            let n = self.0.take(1).read(buf)?;
+            self.0 = &self.0[n..];
+            Ok(n)
+        }
+    }
+
+    /// Fails every read.
+    struct Broken;
+
+    impl Read for Broken {
+        fn read(&mut self, _: &mut [u8]) -> io::Result<usize> {
+            Err(io::ErrorKind::BrokenPipe.into())
+        }
+    }
+
+    fn write<T: Encode>(item: &T) -> Vec<u8> {
+        let mut bytes = vec![];
+        item.ssz_write(&mut bytes).unwrap();
+        bytes
+    }
+
+    #[test]
+    fn lists() {
+        let fixed = (0..100_000u32).collect::<Vec<_>>();
+        let bytes = write(&fixed);
+        assert_eq!(bytes, fixed.as_ssz_bytes());
+        assert_eq!(
+            <Vec<u32>>::from_ssz_reader(&mut &bytes[..], bytes.len()),
+            Ok(fixed)
+        );
+
+        let variable = vec![vec![], vec![1u16], vec![2, 3]];
+        let bytes = write(&variable);
+        assert_eq!(bytes, variable.as_ssz_bytes());
+        assert_eq!(
+            <Vec<Vec<u16>>>::from_ssz_reader(&mut Trickle(&bytes), bytes.len()),
+            Ok(variable)
+        );
+    }
+
+    #[test]
+    fn reads_stop_after_len() {
+        let bytes = vec![1u8, 2, 3].as_ssz_bytes();
+        let mut reader = &bytes[..];
+
+        assert_eq!(<Vec<u8>>::from_ssz_reader(&mut reader, 2), Ok(vec![1, 2]));
+        assert_eq!(reader, &[3]);
+    }
+
+    #[test]
+    fn invalid_lists() {
+        assert_eq!(
+            <Vec<u16>>::from_ssz_reader(&mut &[0u8; 3][..], 3),
+            Err(DecodeError::InvalidByteLength {
+                len: 3,
+                expected: 2
+            })
+        );
+
+        // The second offset points before the first.
+        let bytes = [8, 0, 0, 0, 7, 0, 0, 0, 0];
+        assert_eq!(
+            <Vec<Vec<u8>>>::from_ssz_reader(&mut &bytes[..], bytes.len()),
+            Err(DecodeError::OffsetsAreDecreasing {
+                offset: 7,
+                previous: 8
+            })
+        );
+
+        // The first offset points past the end.
+        let bytes = [12, 0, 0, 0, 0];
+        assert_eq!(
+            <Vec<Vec<u8>>>::from_ssz_reader(&mut &bytes[..], bytes.len()),
+            Err(DecodeError::OutOfBoundsByte { i: 12 })
+        );
+    }
+
+    #[test]
+    fn list_max_len() {
+        let bytes = vec![vec![1u8], vec![2], vec![3]].as_ssz_bytes();
+
+        assert_eq!(
+            read_list::<Vec<u8>, _>(&mut &bytes[..], bytes.len(), Some(3)),
+            Ok(vec![vec![1], vec![2], vec![3]])
+        );
+        // Only the first offset is read.
+        let mut reader = &bytes[..];
+        assert_eq!(
+            read_list::<Vec<u8>, _>(&mut reader, bytes.len(), Some(2)),
+            Err(DecodeError::ListTooLong { len: 3, max: 2 })
+        );
+        assert_eq!(reader.len(), bytes.len() - BYTES_PER_LENGTH_OFFSET);
+
+        let bytes = vec![1u16, 2, 3].as_ssz_bytes();
+        assert_eq!(
+            read_list::<u16, _>(&mut Broken, bytes.len(), Some(2)),
+            Err(DecodeError::ListTooLong { len: 3, max: 2 })
+        );
+    }
+
+    #[test]
+    fn short_and_failed_reads() {
+        let bytes = vec![1u16, 2].as_ssz_bytes();
+        assert_eq!(
+            <Vec<u16>>::from_ssz_reader(&mut &bytes[..], 6),
+            Err(DecodeError::InvalidByteLength {
+                len: 4,
+                expected: 6
+            })
+        );
+        assert_eq!(
+            u64::from_ssz_reader(&mut Broken, 8),
+            Err(DecodeError::Io("broken pipe".to_string()))
+        );
+    }
+
+    #[test]
+    fn length_bounds_are_checked_before_reading() {
+        assert_eq!(
+            u64::from_ssz_reader(&mut Broken, 9),
+            Err(DecodeError::InvalidByteLength {
+                len: 9,
+                expected: 8
+            })
+        );
+    }
+}
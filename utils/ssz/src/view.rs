@@ -0,0 +1,406 @@
+//! Borrowed, lazily-decoded views over SSZ bytes.
+//!
+//! A view checks the structure of its bytes (lengths and offsets) once, when it is created, and
+//! only decodes the parts that are read. Views of containers are generated by
+//! `#[derive(DecodeView)]`.
+
+use super::*;
+use crate::decode::{read_offset, read_union_index};
+use std::marker::PhantomData;
+use std::num::NonZeroUsize;
+
+use ethereum_types::{H256, U128, U256};
+
+/// Provides a borrowed view over the SSZ encoding of `Self` via `view_ssz_bytes(&bytes)`.
+///
+/// Types that are cheap to decode (integers, hashes, etc.) are their own view, lists are viewed
+/// through a `ListView` and containers through the `FooView` generated by
+/// `#[derive(DecodeView)]`.
+///
+/// ## Example
+///
+/// ```rust
+/// use ssz_derive::{Decode, DecodeView, Encode};
+/// use ssz::{DecodeView, Encode};
+///
+/// #[derive(Encode, Decode, DecodeView)]
+/// struct Foo {
+///     a: u64,
+///     b: Vec<u16>,
+/// }
+///
+/// fn main() {
+///     let bytes = Foo { a: 42, b: vec![1, 3, 3, 7] }.as_ssz_bytes();
+///
+///     let view = Foo::view_ssz_bytes(&bytes).unwrap();
+///
+///     assert_eq!(view.a(), Ok(42));
+///     assert_eq!(view.b().unwrap().len(), 4);
+///     assert_eq!(view.b().unwrap().get(3), Some(Ok(7)));
+/// }
+/// ```
+pub trait DecodeView<'a>: Decode {
+    type View;
+
+    /// Checks the structure of `bytes` and returns a view over them, without decoding any
+    /// variable-length items.
+    fn view_ssz_bytes(bytes: &'a [u8]) -> Result<Self::View, DecodeError>;
+}
+
+/// A view over an SSZ list (or vector) of `T`.
+#[derive(Debug)]
+pub struct ListView<'a, T> {
+    bytes: &'a [u8],
+    len: usize,
+    _phantom: PhantomData<T>,
+}
+
+impl<'a, T: DecodeView<'a>> ListView<'a, T> {
+    /// Checks that `bytes` can be split into items of `T`: a whole number of fixed-length items,
+    /// or in-bounds and non-decreasing offsets for variable-length items.
+    pub fn new(bytes: &'a [u8]) -> Result<Self, DecodeError> {
+        let len = if bytes.is_empty() {
+            0
+        } else if T::is_ssz_fixed_len() {
+            let item_len = T::ssz_fixed_len();
+            if item_len == 0 {
+                return Err(DecodeError::ZeroLengthItem);
+            }
+            let len = bytes.len() / item_len;
+            if len * item_len != bytes.len() {
+                return Err(DecodeError::InvalidByteLength {
+                    len: bytes.len(),
+                    expected: len * item_len,
+                });
+            }
+            len
+        } else {
+            check_offsets(bytes)?
+        };
+
+        Ok(Self {
+            bytes,
+            len,
+            _phantom: PhantomData,
+        })
+    }
+
+    /// The number of items in the list.
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    pub fn as_ssz_bytes(&self) -> &'a [u8] {
+        self.bytes
+    }
+
+    /// Returns the SSZ bytes of the `i`'th item, or `None` if it is out of bounds.
+    pub fn item_bytes(&self, i: usize) -> Option<&'a [u8]> {
+        if i >= self.len {
+            None
+        } else if T::is_ssz_fixed_len() {
+            let item_len = T::ssz_fixed_len();
+            Some(&self.bytes[i * item_len..(i + 1) * item_len])
+        } else {
+            let start = self.offset(i);
+            let end = if i + 1 == self.len {
+                self.bytes.len()
+            } else {
+                self.offset(i + 1)
+            };
+            Some(&self.bytes[start..end])
+        }
+    }
+
+    /// Returns a view of the `i`'th item, or `None` if it is out of bounds.
+    pub fn get(&self, i: usize) -> Option<Result<T::View, DecodeError>> {
+        self.item_bytes(i).map(T::view_ssz_bytes)
+    }
+
+    /// Iterates over views of every item.
+    pub fn iter(&self) -> ListIter<'a, T> {
+        ListIter {
+            list: *self,
+            next: 0,
+        }
+    }
+
+    /// Reads the offset of the `i`'th item, which was checked by `new`.
+    fn offset(&self, i: usize) -> usize {
+        read_offset(&self.bytes[i * BYTES_PER_LENGTH_OFFSET..])
+            .expect("offsets are checked when the view is created")
+    }
+}
+
+impl<'a, T> Clone for ListView<'a, T> {
+    fn clone(&self) -> Self {
+        *self
+    }
+}
+
+impl<'a, T> Copy for ListView<'a, T> {}
+
+/// An iterator over the items of a `ListView`.
+pub struct ListIter<'a, T> {
+    list: ListView<'a, T>,
+    next: usize,
+}
+
+impl<'a, T: DecodeView<'a>> Iterator for ListIter<'a, T> {
+    type Item = Result<T::View, DecodeError>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let item = self.list.get(self.next)?;
+        self.next += 1;
+        Some(item)
+    }
+}
+
+/// Checks the offsets of a list of variable-length items, returning the number of items.
+///
+/// Follows the same rules as `decode_list_of_variable_length_items`.
+fn check_offsets(bytes: &[u8]) -> Result<usize, DecodeError> {
+    let first_offset = read_offset(bytes)?;
+
+    if first_offset < BYTES_PER_LENGTH_OFFSET {
+        return Err(DecodeError::OutOfBoundsByte { i: first_offset });
+    }
+
+    let num_items = first_offset / BYTES_PER_LENGTH_OFFSET;
+
+    if first_offset != num_items * BYTES_PER_LENGTH_OFFSET {
+        return Err(DecodeError::InvalidByteLength {
+            len: first_offset,
+            expected: num_items * BYTES_PER_LENGTH_OFFSET,
+        });
+    }
+
+    let mut previous_offset = first_offset;
+    for i in 1..=num_items {
+        let offset = if i == num_items {
+            bytes.len()
+        } else {
+            read_offset(&bytes[i * BYTES_PER_LENGTH_OFFSET..])?
+        };
+
+        if offset < previous_offset {
+            return Err(DecodeError::OffsetsAreDecreasing {
+                offset,
+                previous: previous_offset,
+            });
+        } else if offset > bytes.len() {
+            return Err(DecodeError::OutOfBoundsByte { i: offset });
+        }
+        previous_offset = offset;
+    }
+
+    Ok(num_items)
+}
+
+/// A view that leaves its bytes undecoded until `decode` is called.
+///
+/// Used for types that are expensive to decode, e.g., BLS public keys and signatures, which
+/// involve point decompression.
+#[derive(Debug)]
+pub struct LazyView<'a, T> {
+    bytes: &'a [u8],
+    _phantom: PhantomData<T>,
+}
+
+impl<'a, T: Decode> LazyView<'a, T> {
+    /// Checks `bytes` against the SSZ length bounds of `T`.
+    pub fn new(bytes: &'a [u8]) -> Result<Self, DecodeError> {
+        let len = bytes.len();
+        if len < T::ssz_min_len() {
+            Err(DecodeError::InvalidByteLength {
+                len,
+                expected: T::ssz_min_len(),
+            })
+        } else if len > T::ssz_max_len() {
+            Err(DecodeError::InvalidByteLength {
+                len,
+                expected: T::ssz_max_len(),
+            })
+        } else {
+            Ok(Self {
+                bytes,
+                _phantom: PhantomData,
+            })
+        }
+    }
+
+    pub fn as_ssz_bytes(&self) -> &'a [u8] {
+        self.bytes
+    }
+
+    pub fn decode(&self) -> Result<T, DecodeError> {
+        T::from_ssz_bytes(self.bytes)
+    }
+}
+
+impl<'a, T> Clone for LazyView<'a, T> {
+    fn clone(&self) -> Self {
+        *self
+    }
+}
+
+impl<'a, T> Copy for LazyView<'a, T> {}
+
+macro_rules! impl_decode_view_by_decoding {
+    ($($type: ty),*) => {
+        $(
+            impl<'a> DecodeView<'a> for $type {
+                type View = Self;
+
+                fn view_ssz_bytes(bytes: &'a [u8]) -> Result<Self, DecodeError> {
+                    Self::from_ssz_bytes(bytes)
+                }
+            }
+        )*
+    };
+}
+
+impl_decode_view_by_decoding!(
+    u8,
+    u16,
+    u32,
+    u64,
+    usize,
+    bool,
+    NonZeroUsize,
+    H256,
+    U128,
+    U256,
+    [u8; 4],
+    [u8; 32]
+);
+
+impl<'a, T: DecodeView<'a>> DecodeView<'a> for Vec<T> {
+    type View = ListView<'a, T>;
+
+    fn view_ssz_bytes(bytes: &'a [u8]) -> Result<Self::View, DecodeError> {
+        ListView::new(bytes)
+    }
+}
+
+/// The SSZ union type.
+impl<'a, T: DecodeView<'a>> DecodeView<'a> for Option<T> {
+    type View = Option<T::View>;
+
+    fn view_ssz_bytes(bytes: &'a [u8]) -> Result<Self::View, DecodeError> {
+        if bytes.len() < BYTES_PER_LENGTH_OFFSET {
+            return Err(DecodeError::InvalidByteLength {
+                len: bytes.len(),
+                expected: BYTES_PER_LENGTH_OFFSET,
+            });
+        }
+
+        let (index_bytes, value_bytes) = bytes.split_at(BYTES_PER_LENGTH_OFFSET);
+
+        match read_union_index(index_bytes)? {
+            0 => Ok(None),
+            1 => Ok(Some(T::view_ssz_bytes(value_bytes)?)),
+            index => Err(DecodeError::BytesInvalid(format!(
+                "{} is not a valid union index for Option<T>",
+                index
+            ))),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn fixed_len_list() {
+        let bytes = vec![1u16, 2, 3].as_ssz_bytes();
+        let view = <Vec<u16>>::view_ssz_bytes(&bytes).unwrap();
+
+        assert_eq!(view.len(), 3);
+        assert_eq!(view.item_bytes(1), Some(&[2, 0][..]));
+        assert_eq!(view.get(2), Some(Ok(3)));
+        assert_eq!(view.get(3), None);
+        assert_eq!(
+            view.iter().collect::<Result<Vec<_>, _>>(),
+            Ok(vec![1, 2, 3])
+        );
+
+        assert_eq!(
+            <Vec<u16>>::view_ssz_bytes(&bytes[..5]).map(|view| view.len()),
+            Err(DecodeError::InvalidByteLength {
+                len: 5,
+                expected: 4
+            })
+        );
+    }
+
+    #[test]
+    fn variable_len_list() {
+        let list = vec![vec![1u8], vec![], vec![2, 3]];
+        let bytes = list.as_ssz_bytes();
+        let view = <Vec<Vec<u8>>>::view_ssz_bytes(&bytes).unwrap();
+
+        assert_eq!(view.len(), 3);
+        assert_eq!(view.item_bytes(1), Some(&[][..]));
+        assert_eq!(view.item_bytes(2), Some(&[2, 3][..]));
+
+        let items = view
+            .iter()
+            .map(|item| item?.iter().collect::<Result<Vec<u8>, _>>())
+            .collect::<Result<Vec<_>, _>>();
+        assert_eq!(items, Ok(list));
+    }
+
+    #[test]
+    fn invalid_offsets() {
+        // The second offset points before the first.
+        let bytes = vec![8, 0, 0, 0, 7, 0, 0, 0, 0];
+        assert_eq!(
+            <Vec<Vec<u8>>>::view_ssz_bytes(&bytes).map(|view| view.len()),
+            Err(DecodeError::OffsetsAreDecreasing {
+                offset: 7,
+                previous: 8
+            })
+        );
+
+        // The second offset points past the end.
+        let bytes = vec![8, 0, 0, 0, 10, 0, 0, 0, 0];
+        assert_eq!(
+            <Vec<Vec<u8>>>::view_ssz_bytes(&bytes).map(|view| view.len()),
+            Err(DecodeError::OutOfBoundsByte { i: 10 })
+        );
+    }
+
+    #[test]
+    fn option() {
+        let bytes = Some(vec![5u8]).as_ssz_bytes();
+        let view = <Option<Vec<u8>>>::view_ssz_bytes(&bytes).unwrap();
+        assert_eq!(view.map(|view| view.get(0)), Some(Some(Ok(5))));
+
+        let bytes = (None as Option<u8>).as_ssz_bytes();
+        assert_eq!(<Option<u8>>::view_ssz_bytes(&bytes), Ok(None));
+    }
+
+    #[test]
+    fn lazy() {
+        assert!(LazyView::<u64>::new(&[0; 8]).is_ok());
+        assert_eq!(
+            LazyView::<u64>::new(&[1, 0, 0, 0, 0, 0, 0, 0])
+                .unwrap()
+                .decode(),
+            Ok(1)
+        );
+        assert_eq!(
+            LazyView::<u64>::new(&[0; 7]).map(|view| view.as_ssz_bytes()),
+            Err(DecodeError::InvalidByteLength {
+                len: 7,
+                expected: 8
+            })
+        );
+    }
+}
@@ -1,6 +1,6 @@
 use ethereum_types::H256;
-use ssz::{Decode, DecodeError, Encode};
-use ssz_derive::{Decode, Encode};
+use ssz::{Decode, DecodeError, DecodeView, Encode, PathElement};
+use ssz_derive::{Decode, DecodeView, Encode};
 
 mod round_trip {
     use super::*;
@@ -9,6 +9,15 @@ mod round_trip {
         for item in items {
             let encoded = &item.as_ssz_bytes();
             assert_eq!(item.ssz_bytes_len(), encoded.len());
+
+            let mut written = vec![];
+            item.ssz_write(&mut written).unwrap();
+            assert_eq!(&written, encoded);
+            assert_eq!(
+                T::from_ssz_reader(&mut &encoded[..], encoded.len()).as_ref(),
+                Ok(&item)
+            );
+
             assert_eq!(T::from_ssz_bytes(&encoded), Ok(item));
         }
     }
@@ -116,7 +125,7 @@ mod round_trip {
 
         assert_eq!(
             FixedLen::from_ssz_bytes(&bytes),
-            Err(DecodeError::InvalidByteLength {
+            Err(DecodeError::TrailingBytes {
                 len: 15,
                 expected: 14,
             })
@@ -283,7 +292,11 @@ mod round_trip {
 
         assert_eq!(
             ThreeVariableLen::from_ssz_bytes(&bytes),
-            Err(DecodeError::OutOfBoundsByte { i: 14 })
+            Err(DecodeError::OffsetsAreDecreasing {
+                offset: 14,
+                previous: 15
+            }
+            .in_field("d"))
         );
     }
 
@@ -378,3 +391,887 @@ mod round_trip {
         round_trip(vec);
     }
 }
+
+mod len_bounds {
+    use super::*;
+
+    fn assert_bounds<T: Encode + Decode>(min: usize, max: usize) {
+        assert_eq!(<T as Encode>::ssz_min_len(), min);
+        assert_eq!(<T as Encode>::ssz_max_len(), max);
+        assert_eq!(<T as Decode>::ssz_min_len(), min);
+        assert_eq!(<T as Decode>::ssz_max_len(), max);
+    }
+
+    #[derive(Debug, PartialEq, Encode, Decode)]
+    struct Fixed {
+        a: u16,
+        b: H256,
+    }
+
+    #[derive(Debug, PartialEq, Encode, Decode)]
+    struct Bounded {
+        a: u8,
+        b: Option<u64>,
+        c: Option<Fixed>,
+    }
+
+    #[derive(Debug, PartialEq, Encode, Decode)]
+    struct Unbounded {
+        a: u32,
+        b: Vec<u8>,
+    }
+
+    #[derive(Debug, PartialEq, Encode, Decode)]
+    struct Skipped {
+        a: u32,
+        #[ssz(skip_serializing)]
+        #[ssz(skip_deserializing)]
+        b: Vec<u8>,
+    }
+
+    #[test]
+    fn fixed_len() {
+        assert_bounds::<bool>(1, 1);
+        assert_bounds::<u64>(8, 8);
+        assert_bounds::<H256>(32, 32);
+        assert_bounds::<(u8, u16)>(3, 3);
+        assert_bounds::<Fixed>(34, 34);
+        assert_bounds::<Skipped>(4, 4);
+    }
+
+    #[test]
+    fn variable_len() {
+        assert_bounds::<Option<u64>>(4, 12);
+        assert_bounds::<Option<Option<u8>>>(4, 9);
+        assert_bounds::<Vec<u8>>(0, usize::max_value());
+        assert_bounds::<Option<Vec<u8>>>(4, usize::max_value());
+    }
+
+    #[test]
+    fn containers() {
+        // `a`, then an offset and union selector for each of `b` and `c`.
+        assert_bounds::<Bounded>(1 + 8 + 8, 1 + (4 + 12) + (4 + 4 + 34));
+        assert_bounds::<(u8, Option<u64>, Option<Fixed>)>(17, 59);
+        assert_bounds::<Unbounded>(8, usize::max_value());
+        assert_bounds::<(Vec<u8>, Vec<u8>)>(8, usize::max_value());
+    }
+
+    #[test]
+    fn encodings_are_within_bounds() {
+        let values = vec![
+            Bounded {
+                a: 1,
+                b: None,
+                c: None,
+            },
+            Bounded {
+                a: 1,
+                b: Some(2),
+                c: Some(Fixed {
+                    a: 3,
+                    b: H256::repeat_byte(4),
+                }),
+            },
+        ];
+        let lens = values
+            .iter()
+            .map(|value| value.as_ssz_bytes().len())
+            .collect::<Vec<_>>();
+
+        assert_eq!(
+            lens,
+            vec![
+                <Bounded as Encode>::ssz_min_len(),
+                <Bounded as Encode>::ssz_max_len()
+            ]
+        );
+    }
+}
+
+mod views {
+    use super::*;
+
+    #[derive(Debug, PartialEq, Encode, Decode, DecodeView)]
+    struct Inner {
+        a: u16,
+        b: Vec<u8>,
+    }
+
+    #[derive(Debug, PartialEq, Encode, Decode, DecodeView)]
+    struct Outer<T: Encode + Decode> {
+        a: T,
+        inner: Vec<Inner>,
+        #[ssz(skip_serializing)]
+        #[ssz(skip_deserializing)]
+        skipped: u64,
+        c: Option<H256>,
+    }
+
+    fn outer() -> Outer<u8> {
+        Outer {
+            a: 1,
+            inner: vec![
+                Inner { a: 2, b: vec![3] },
+                Inner {
+                    a: 4,
+                    b: vec![5, 6],
+                },
+            ],
+            skipped: 0,
+            c: Some(H256::repeat_byte(7)),
+        }
+    }
+
+    #[test]
+    fn fields() {
+        let bytes = outer().as_ssz_bytes();
+        let view = Outer::<u8>::view_ssz_bytes(&bytes).unwrap();
+
+        assert_eq!(view.as_ssz_bytes(), &bytes[..]);
+        assert_eq!(view.a(), Ok(1));
+        assert_eq!(view.c(), Ok(Some(H256::repeat_byte(7))));
+
+        let inner = view.inner().unwrap();
+        assert_eq!(inner.len(), 2);
+
+        let second = inner.get(1).unwrap().unwrap();
+        assert_eq!(second.a(), Ok(4));
+        assert_eq!(second.b().unwrap().get(1), Some(Ok(6)));
+        assert_eq!(
+            Inner::from_ssz_bytes(second.as_ssz_bytes()),
+            Ok(Inner {
+                a: 4,
+                b: vec![5, 6],
+            })
+        );
+    }
+
+    #[test]
+    fn offsets_are_checked_once() {
+        let mut bytes = outer().as_ssz_bytes();
+        // Point the offset of `inner` past the offset of `c`.
+        bytes[1] = 200;
+
+        assert_eq!(
+            Outer::<u8>::view_ssz_bytes(&bytes).map(|view| view.as_ssz_bytes()),
+            Err(DecodeError::OutOfBoundsByte { i: 200 }.in_field("inner"))
+        );
+    }
+
+    #[test]
+    fn nested_items_are_checked_when_read() {
+        let mut bytes = outer().as_ssz_bytes();
+        // Corrupt the second offset in the list of `Inner`.
+        let inner_start = 1 + 4 + 4;
+        bytes[inner_start + 4] = 0xff;
+
+        let view = Outer::<u8>::view_ssz_bytes(&bytes).unwrap();
+        assert_eq!(view.a(), Ok(1));
+        assert_eq!(
+            view.inner().map(|inner| inner.len()),
+            Err(DecodeError::OutOfBoundsByte { i: 0xff })
+        );
+    }
+}
+
+mod streaming {
+    use super::*;
+
+    #[derive(Debug, PartialEq, Encode, Decode)]
+    struct Inner {
+        a: u16,
+        b: Vec<u8>,
+    }
+
+    #[derive(Debug, PartialEq, Encode, Decode)]
+    struct Outer {
+        a: u8,
+        inner: Vec<Inner>,
+        c: Vec<u16>,
+        #[ssz(skip_serializing)]
+        #[ssz(skip_deserializing)]
+        skipped: u64,
+    }
+
+    fn outer() -> Outer {
+        Outer {
+            a: 1,
+            inner: vec![
+                Inner { a: 2, b: vec![] },
+                Inner {
+                    a: 3,
+                    b: vec![4, 5],
+                },
+            ],
+            c: vec![6, 7],
+            skipped: 0,
+        }
+    }
+
+    #[test]
+    fn write_matches_as_ssz_bytes() {
+        let mut written = vec![];
+        outer().ssz_write(&mut written).unwrap();
+
+        assert_eq!(written, outer().as_ssz_bytes());
+    }
+
+    #[test]
+    fn read_stops_at_len() {
+        let mut bytes = outer().as_ssz_bytes();
+        let len = bytes.len();
+        bytes.extend_from_slice(&[42, 42]);
+
+        let mut reader = &bytes[..];
+        assert_eq!(Outer::from_ssz_reader(&mut reader, len), Ok(outer()));
+        assert_eq!(reader, &[42, 42]);
+    }
+
+    #[test]
+    fn short_read() {
+        let bytes = outer().as_ssz_bytes();
+
+        assert_eq!(
+            Outer::from_ssz_reader(&mut &bytes[..bytes.len() - 1], bytes.len()),
+            Err(DecodeError::InvalidByteLength {
+                len: 3,
+                expected: 4
+            }
+            .in_field("c"))
+        );
+    }
+
+    #[test]
+    fn invalid_offsets() {
+        let mut bytes = outer().as_ssz_bytes();
+        // Point the offset of `c` before the offset of `inner`.
+        bytes[5] = 0;
+
+        assert_eq!(
+            Outer::from_ssz_reader(&mut &bytes[..], bytes.len()),
+            Err(DecodeError::OffsetsAreDecreasing {
+                offset: 0,
+                previous: 9
+            })
+        );
+    }
+
+    #[test]
+    fn fixed_len_mismatch() {
+        let bytes = Inner { a: 1, b: vec![] }.as_ssz_bytes();
+
+        assert_eq!(
+            <(u16, u16)>::from_ssz_reader(&mut &bytes[..], 5),
+            Err(DecodeError::InvalidByteLength {
+                len: 5,
+                expected: 4
+            })
+        );
+        assert_eq!(
+            Inner::from_ssz_reader(&mut &bytes[..], 3),
+            Err(DecodeError::InvalidByteLength {
+                len: 3,
+                expected: 6
+            }
+            .in_field("b"))
+        );
+    }
+}
+
+mod unions {
+    use super::*;
+
+    #[derive(Debug, PartialEq, Encode, Decode)]
+    struct Fixed {
+        a: u16,
+        b: u8,
+    }
+
+    #[derive(Debug, PartialEq, Encode, Decode)]
+    enum Union {
+        Empty,
+        Number(u64),
+        Fixed(Fixed),
+        List(Vec<u16>),
+    }
+
+    #[derive(Debug, PartialEq, Encode, Decode)]
+    struct Container {
+        a: u8,
+        b: Union,
+        c: Option<Union>,
+    }
+
+    #[test]
+    fn encode() {
+        assert_eq!(Union::Empty.as_ssz_bytes(), vec![0, 0, 0, 0]);
+        assert_eq!(
+            Union::Number(7).as_ssz_bytes(),
+            vec![1, 0, 0, 0, 7, 0, 0, 0, 0, 0, 0, 0]
+        );
+        assert_eq!(
+            Union::Fixed(Fixed { a: 1, b: 2 }).as_ssz_bytes(),
+            vec![2, 0, 0, 0, 1, 0, 2]
+        );
+        assert_eq!(
+            Union::List(vec![3, 4]).as_ssz_bytes(),
+            vec![3, 0, 0, 0, 3, 0, 4, 0]
+        );
+    }
+
+    #[test]
+    fn round_trip() {
+        let items = vec![
+            Union::Empty,
+            Union::Number(u64::max_value()),
+            Union::Fixed(Fixed { a: 1, b: 2 }),
+            Union::List(vec![]),
+            Union::List(vec![1, 2, 3]),
+        ];
+
+        for item in items {
+            let bytes = item.as_ssz_bytes();
+            assert_eq!(item.ssz_bytes_len(), bytes.len());
+            assert_eq!(Union::from_ssz_bytes(&bytes), Ok(item));
+        }
+
+        let container = Container {
+            a: 1,
+            b: Union::List(vec![2]),
+            c: Some(Union::Empty),
+        };
+        assert_eq!(
+            Container::from_ssz_bytes(&container.as_ssz_bytes()),
+            Ok(container)
+        );
+    }
+
+    #[test]
+    fn len_bounds() {
+        assert_eq!(<Union as Encode>::ssz_min_len(), 4);
+        assert_eq!(<Union as Decode>::ssz_min_len(), 4);
+        assert_eq!(<Union as Encode>::ssz_max_len(), usize::max_value());
+        assert_eq!(<Option<Fixed> as Decode>::ssz_max_len(), 7);
+    }
+
+    #[test]
+    fn invalid_selector() {
+        assert_eq!(
+            Union::from_ssz_bytes(&[4, 0, 0, 0]),
+            Err(DecodeError::BytesInvalid(
+                "4 is not a valid union index for Union".to_string()
+            ))
+        );
+        assert_eq!(
+            Union::from_ssz_bytes(&[0, 0, 0]),
+            Err(DecodeError::InvalidByteLength {
+                len: 3,
+                expected: 4
+            })
+        );
+    }
+
+    #[test]
+    fn invalid_value() {
+        // A unit variant has no value.
+        assert_eq!(
+            Union::from_ssz_bytes(&[0, 0, 0, 0, 1]),
+            Err(DecodeError::TrailingBytes {
+                len: 5,
+                expected: 4
+            })
+        );
+        assert_eq!(
+            Union::from_ssz_bytes(&[2, 0, 0, 0, 1, 0]),
+            Err(DecodeError::InvalidByteLength {
+                len: 2,
+                expected: 3
+            }
+            .in_field("b")
+            .in_field("Fixed"))
+        );
+    }
+}
+
+mod newtypes {
+    use super::*;
+
+    #[derive(Debug, PartialEq, Encode, Decode)]
+    #[ssz(transparent)]
+    struct Slot(u64);
+
+    #[derive(Debug, PartialEq, Encode, Decode, DecodeView)]
+    #[ssz(transparent)]
+    struct Roots(Vec<H256>);
+
+    #[derive(Debug, PartialEq, Encode, Decode)]
+    #[ssz(transparent)]
+    struct Named {
+        inner: Vec<u8>,
+    }
+
+    #[derive(Debug, PartialEq, Encode, Decode)]
+    struct Tuple(u8, Vec<u16>, Slot);
+
+    #[derive(Debug, PartialEq, Encode, Decode)]
+    struct Fields {
+        a: u8,
+        b: Vec<u16>,
+        c: u64,
+    }
+
+    #[derive(Debug, PartialEq, Encode, Decode)]
+    struct Skipping(
+        u8,
+        #[ssz(skip_serializing)]
+        #[ssz(skip_deserializing)]
+        u32,
+        u16,
+    );
+
+    #[test]
+    fn transparent() {
+        assert_eq!(Slot(42).as_ssz_bytes(), 42u64.as_ssz_bytes());
+        assert!(<Slot as Encode>::is_ssz_fixed_len());
+        assert_eq!(<Slot as Decode>::ssz_fixed_len(), 8);
+        assert_eq!(Slot::from_ssz_bytes(&[1, 0, 0, 0, 0, 0, 0, 0]), Ok(Slot(1)));
+        assert_eq!(
+            <Vec<Slot>>::from_ssz_bytes(&vec![1u64, 2].as_ssz_bytes()),
+            Ok(vec![Slot(1), Slot(2)])
+        );
+
+        let named = Named {
+            inner: vec![1, 2, 3],
+        };
+        assert_eq!(named.as_ssz_bytes(), vec![1, 2, 3]);
+        assert_eq!(Named::from_ssz_bytes(&[1, 2, 3]), Ok(named));
+    }
+
+    #[test]
+    fn transparent_view() {
+        let roots = Roots(vec![H256::zero(), H256::from([1; 32])]);
+        let bytes = roots.as_ssz_bytes();
+
+        let view = Roots::view_ssz_bytes(&bytes).unwrap();
+        assert_eq!(view.len(), 2);
+        assert_eq!(view.get(1), Some(Ok(H256::from([1; 32]))));
+    }
+
+    #[test]
+    fn tuple_struct() {
+        let tuple = Tuple(1, vec![2, 3], Slot(4));
+        let fields = Fields {
+            a: 1,
+            b: vec![2, 3],
+            c: 4,
+        };
+
+        assert_eq!(tuple.as_ssz_bytes(), fields.as_ssz_bytes());
+        assert_eq!(tuple.ssz_bytes_len(), fields.ssz_bytes_len());
+        assert_eq!(Tuple::from_ssz_bytes(&fields.as_ssz_bytes()), Ok(tuple));
+    }
+
+    #[test]
+    fn tuple_struct_skipping() {
+        let bytes = Skipping(1, 2, 3).as_ssz_bytes();
+
+        assert_eq!(bytes, vec![1, 3, 0]);
+        assert_eq!(Skipping::from_ssz_bytes(&bytes), Ok(Skipping(1, 0, 3)));
+    }
+}
+
+mod field_attributes {
+    use super::*;
+    use std::time::Duration;
+
+    /// Encodes a `Duration` as a `u64` of whole seconds.
+    mod duration_secs {
+        pub mod encode {
+            use ssz::Encode;
+            use std::time::Duration;
+
+            pub fn is_ssz_fixed_len() -> bool {
+                true
+            }
+
+            pub fn ssz_fixed_len() -> usize {
+                8
+            }
+
+            pub fn ssz_bytes_len(_: &Duration) -> usize {
+                8
+            }
+
+            pub fn ssz_append(duration: &Duration, buf: &mut Vec<u8>) {
+                duration.as_secs().ssz_append(buf)
+            }
+        }
+
+        pub mod decode {
+            use ssz::{Decode, DecodeError};
+            use std::time::Duration;
+
+            pub fn is_ssz_fixed_len() -> bool {
+                true
+            }
+
+            pub fn ssz_fixed_len() -> usize {
+                8
+            }
+
+            pub fn from_ssz_bytes(bytes: &[u8]) -> Result<Duration, DecodeError> {
+                u64::from_ssz_bytes(bytes).map(Duration::from_secs)
+            }
+        }
+    }
+
+    /// Encodes a `String` as its UTF-8 bytes.
+    mod utf8 {
+        pub mod encode {
+            pub fn is_ssz_fixed_len() -> bool {
+                false
+            }
+
+            pub fn ssz_fixed_len() -> usize {
+                ssz::BYTES_PER_LENGTH_OFFSET
+            }
+
+            pub fn ssz_bytes_len(string: &str) -> usize {
+                string.len()
+            }
+
+            pub fn ssz_append(string: &str, buf: &mut Vec<u8>) {
+                buf.extend_from_slice(string.as_bytes())
+            }
+        }
+
+        pub mod decode {
+            use ssz::DecodeError;
+
+            pub fn is_ssz_fixed_len() -> bool {
+                false
+            }
+
+            pub fn ssz_fixed_len() -> usize {
+                ssz::BYTES_PER_LENGTH_OFFSET
+            }
+
+            pub fn from_ssz_bytes(bytes: &[u8]) -> Result<String, DecodeError> {
+                String::from_utf8(bytes.to_vec())
+                    .map_err(|e| DecodeError::BytesInvalid(format!("{}", e)))
+            }
+        }
+    }
+
+    #[derive(Debug, PartialEq, Encode, Decode, DecodeView)]
+    struct Record {
+        a: u16,
+        #[ssz(with = "duration_secs")]
+        timeout: Duration,
+        #[ssz(with = "utf8")]
+        name: String,
+        b: Vec<u8>,
+    }
+
+    /// Does not implement `Default`, unlike each of its skipped fields.
+    #[derive(Debug, PartialEq, Encode, Decode)]
+    struct Defaulting {
+        a: u8,
+        #[ssz(default)]
+        cache: Option<H256>,
+        b: u16,
+        #[ssz(skip_serializing)]
+        #[ssz(skip_deserializing)]
+        unused: Vec<u8>,
+        #[ssz(with = "duration_secs")]
+        timeout: Duration,
+    }
+
+    fn record() -> Record {
+        Record {
+            a: 1,
+            timeout: Duration::from_secs(2),
+            name: "three".to_string(),
+            b: vec![4, 5],
+        }
+    }
+
+    #[test]
+    fn with() {
+        let record = record();
+        let bytes = record.as_ssz_bytes();
+
+        #[derive(Debug, PartialEq, Encode, Decode)]
+        struct Equivalent {
+            a: u16,
+            timeout: u64,
+            name: Vec<u8>,
+            b: Vec<u8>,
+        }
+
+        let equivalent = Equivalent {
+            a: 1,
+            timeout: 2,
+            name: b"three".to_vec(),
+            b: vec![4, 5],
+        };
+
+        assert_eq!(bytes, equivalent.as_ssz_bytes());
+        assert_eq!(record.ssz_bytes_len(), bytes.len());
+        assert!(!<Record as Encode>::is_ssz_fixed_len());
+        assert_eq!(<Record as Decode>::ssz_min_len(), 2 + 8 + 4 + 4);
+        assert_eq!(Record::from_ssz_bytes(&bytes), Ok(record));
+    }
+
+    #[test]
+    fn with_errors() {
+        let mut bytes = record().as_ssz_bytes();
+        // The first byte of `name`.
+        bytes[2 + 8 + 4 + 4] = 0xff;
+
+        match Record::from_ssz_bytes(&bytes) {
+            Err(DecodeError::InPath {
+                ref path,
+                ref error,
+            }) if path == &[PathElement::Field("name")] => match **error {
+                DecodeError::BytesInvalid(_) => (),
+                ref other => panic!("expected invalid UTF-8, got {:?}", other),
+            },
+            other => panic!("expected an error in `name`, got {:?}", other),
+        }
+        // `timeout` is registered as fixed-length, ending at byte 10.
+        assert_eq!(
+            Record::from_ssz_bytes(&bytes[..9]),
+            Err(DecodeError::InvalidByteLength {
+                len: 9,
+                expected: 10
+            }
+            .in_field("timeout"))
+        );
+    }
+
+    #[test]
+    fn with_streaming() {
+        let record = record();
+        let bytes = record.as_ssz_bytes();
+
+        let mut written = vec![];
+        record.ssz_write(&mut written).unwrap();
+        assert_eq!(written, bytes);
+
+        let decoded = Record::from_ssz_reader(&mut &bytes[..], bytes.len()).unwrap();
+        assert_eq!(decoded, record);
+    }
+
+    #[test]
+    fn with_view() {
+        let bytes = record().as_ssz_bytes();
+        let view = Record::view_ssz_bytes(&bytes).unwrap();
+
+        assert_eq!(view.a(), Ok(1));
+        assert_eq!(view.timeout(), Ok(Duration::from_secs(2)));
+        assert_eq!(view.name(), Ok("three".to_string()));
+        assert_eq!(view.b().unwrap().len(), 2);
+    }
+
+    #[test]
+    fn default() {
+        let defaulting = Defaulting {
+            a: 1,
+            cache: Some(H256::from([7; 32])),
+            b: 2,
+            unused: vec![3],
+            timeout: Duration::from_secs(4),
+        };
+        let bytes = defaulting.as_ssz_bytes();
+
+        assert_eq!(bytes, vec![1, 2, 0, 4, 0, 0, 0, 0, 0, 0, 0]);
+        assert_eq!(
+            Defaulting::from_ssz_bytes(&bytes),
+            Ok(Defaulting {
+                a: 1,
+                cache: None,
+                b: 2,
+                unused: vec![],
+                timeout: Duration::from_secs(4),
+            })
+        );
+    }
+}
+
+mod error_paths {
+    use super::*;
+
+    #[derive(Debug, PartialEq, Encode, Decode)]
+    struct Attestation {
+        data: u64,
+        aggregation_bits: Vec<bool>,
+    }
+
+    #[derive(Debug, PartialEq, Encode, Decode)]
+    struct Body {
+        graffiti: H256,
+        attestations: Vec<Attestation>,
+    }
+
+    #[derive(Debug, PartialEq, Encode, Decode)]
+    struct Block {
+        slot: u64,
+        body: Body,
+    }
+
+    fn block() -> Block {
+        Block {
+            slot: 1,
+            body: Body {
+                graffiti: H256::zero(),
+                attestations: (0..3)
+                    .map(|data| Attestation {
+                        data,
+                        aggregation_bits: vec![true, false],
+                    })
+                    .collect(),
+            },
+        }
+    }
+
+    #[test]
+    fn nested_field_and_index() {
+        let mut bytes = block().as_ssz_bytes();
+        // The last byte is the second bit of the last attestation.
+        *bytes.last_mut().unwrap() = 2;
+
+        let error = Block::from_ssz_bytes(&bytes).unwrap_err();
+        assert_eq!(
+            error.path(),
+            &[
+                PathElement::Field("body"),
+                PathElement::Field("attestations"),
+                PathElement::Index(2),
+                PathElement::Field("aggregation_bits"),
+                PathElement::Index(1),
+            ]
+        );
+        assert_eq!(
+            error.root(),
+            &DecodeError::BytesInvalid("Out-of-range for boolean: 2".to_string())
+        );
+        assert_eq!(
+            error.to_string(),
+            ".body.attestations[2].aggregation_bits[1]: invalid bytes: Out-of-range for boolean: 2"
+        );
+
+        // Streaming attaches the same path.
+        assert_eq!(
+            Block::from_ssz_reader(&mut &bytes[..], bytes.len()),
+            Err(error)
+        );
+    }
+
+    #[test]
+    fn container_errors_have_no_path() {
+        let mut bytes = block().as_ssz_bytes();
+        // The offset of `body` must point to the end of the fixed-length part of `Block`.
+        bytes[8] = 13;
+
+        let error = Block::from_ssz_bytes(&bytes).unwrap_err();
+        assert_eq!(error, DecodeError::OutOfBoundsByte { i: 13 });
+        assert_eq!(error.to_string(), "offset 13 is out of bounds");
+
+        let error = DecodeError::OffsetsAreDecreasing {
+            offset: 4,
+            previous: 8,
+        };
+        assert!(error.path().is_empty());
+        assert_eq!(error.root(), &error);
+        assert_eq!(
+            error.to_string(),
+            "offset 4 is less than the previous offset 8"
+        );
+    }
+}
+
+mod schema {
+    use super::*;
+    use ssz::{FieldSchema, Schema, SszSchema, VariantSchema};
+    use ssz_derive::SszSchema;
+
+    #[derive(SszSchema)]
+    #[ssz(transparent)]
+    struct Slot(u64);
+
+    #[derive(SszSchema)]
+    struct Fields {
+        a: u8,
+        #[ssz(skip_serializing)]
+        #[ssz(skip_deserializing)]
+        skipped: u32,
+        b: Vec<Slot>,
+        c: H256,
+    }
+
+    #[derive(SszSchema)]
+    enum Union {
+        Empty,
+        Fields(Fields),
+    }
+
+    fn fields_schema() -> Schema {
+        Schema::Container {
+            name: "Fields",
+            fields: vec![
+                FieldSchema {
+                    name: "a",
+                    ty: "u8",
+                    schema: Schema::Uint { bytes: 1 },
+                },
+                FieldSchema {
+                    name: "b",
+                    ty: "Vec<Slot>",
+                    schema: Schema::List {
+                        element: Box::new(Schema::Uint { bytes: 8 }),
+                        limit: None,
+                    },
+                },
+                FieldSchema {
+                    name: "c",
+                    ty: "H256",
+                    schema: H256::ssz_schema(),
+                },
+            ],
+        }
+    }
+
+    #[test]
+    fn container() {
+        let schema = Fields::ssz_schema();
+        assert_eq!(schema, fields_schema());
+        assert_eq!(schema.to_string(), "Fields");
+        assert_eq!(
+            schema.field("b").map(|field| field.schema.to_string()),
+            Some("List[uint64]".to_string())
+        );
+        assert!(schema.field("skipped").is_none());
+        assert!(!schema.is_fixed_len());
+    }
+
+    #[test]
+    fn union() {
+        assert_eq!(
+            Union::ssz_schema(),
+            Schema::Union {
+                name: "Union",
+                variants: vec![
+                    VariantSchema {
+                        name: "Empty",
+                        schema: None,
+                    },
+                    VariantSchema {
+                        name: "Fields",
+                        schema: Some(fields_schema()),
+                    },
+                ],
+            }
+        );
+    }
+}
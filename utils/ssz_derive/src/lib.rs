@@ -1,8 +1,10 @@
 #![recursion_limit = "128"]
 #![allow(clippy::needless_borrow)]
-//! Provides procedural derive macros for the `Encode` and `Decode` traits of the `eth2_ssz` crate.
+//! Provides procedural derive macros for the `Encode`, `Decode`, `DecodeView` and `SszSchema`
+//! traits of the `eth2_ssz` crate.
 //!
-//! Supports field attributes, see each derive macro for more information.
+//! Supports structs with named fields and enums (as SSZ unions), as well as field attributes. See
+//! each derive macro for more information.
 
 extern crate proc_macro;
 
@@ -10,62 +12,180 @@ use proc_macro::TokenStream;
 use quote::quote;
 use syn::{parse_macro_input, DeriveInput};
 
-/// Returns a Vec of `syn::Ident` for each named field in the struct, whilst filtering out fields
-/// that should not be serialized.
+/// Returns the `syn::Member` used to access a field: its ident for a named field, or its position
+/// `index` for an unnamed field (like in a tuple struct).
+fn field_member(index: usize, field: &syn::Field) -> syn::Member {
+    match &field.ident {
+        Some(ident) => syn::Member::Named(ident.clone()),
+        None => syn::Member::Unnamed(index.into()),
+    }
+}
+
+/// Returns the name of a field in the path of a `ssz::DecodeError`: its ident for a named field,
+/// or its position `index` for an unnamed field.
+fn field_name(index: usize, field: &syn::Field) -> String {
+    match &field.ident {
+        Some(ident) => ident.to_string(),
+        None => index.to_string(),
+    }
+}
+
+/// Returns true if some field has an attribute declaring it should not be serialized.
 ///
-/// # Panics
-/// Any unnamed struct field (like in a tuple struct) will raise a panic at compile time.
-fn get_serializable_named_field_idents<'a>(
-    struct_data: &'a syn::DataStruct,
-) -> Vec<&'a syn::Ident> {
-    struct_data
-        .fields
-        .iter()
-        .filter_map(|f| {
-            if should_skip_serializing(&f) {
-                None
-            } else {
-                Some(match &f.ident {
-                    Some(ref ident) => ident,
-                    _ => panic!("ssz_derive only supports named struct fields."),
-                })
-            }
+/// The field attributes are: `#[ssz(skip_serializing)]` and `#[ssz(default)]`
+fn should_skip_serializing(field: &syn::Field) -> bool {
+    is_default(field)
+        || field.attrs.iter().any(|attr| {
+            attr.path.is_ident("ssz")
+                && attr.tts.to_string().replace(" ", "") == "(skip_serializing)"
         })
-        .collect()
 }
 
-/// Returns a Vec of `syn::Type` for each named field in the struct, whilst filtering out fields
-/// that should not be serialized.
-fn get_serializable_field_types<'a>(struct_data: &'a syn::DataStruct) -> Vec<&'a syn::Type> {
-    struct_data
-        .fields
+/// Returns true if some field has an attribute declaring it is neither serialized nor
+/// deserialized, and instead instantiated from its `Default` implementation.
+///
+/// The field attribute is: `#[ssz(default)]`
+fn is_default(field: &syn::Field) -> bool {
+    field.attrs.iter().any(|attr| {
+        attr.path.is_ident("ssz") && attr.tts.to_string().replace(" ", "") == "(default)"
+    })
+}
+
+/// Returns the module named by a field attribute declaring that the field is encoded and decoded
+/// by the functions of that module, rather than by the `Encode` and `Decode` implementations of
+/// its type.
+///
+/// The field attribute is: `#[ssz(with = "module")]`
+fn get_ssz_with(field: &syn::Field) -> Option<syn::Path> {
+    field
+        .attrs
         .iter()
-        .filter_map(|f| {
-            if should_skip_serializing(&f) {
-                None
-            } else {
-                Some(&f.ty)
+        .filter(|attr| attr.path.is_ident("ssz"))
+        .filter_map(|attr| match attr.parse_meta() {
+            Ok(syn::Meta::List(list)) => Some(list.nested),
+            _ => None,
+        })
+        .flatten()
+        .find_map(|nested| match nested {
+            syn::NestedMeta::Meta(syn::Meta::NameValue(ref name_value))
+                if name_value.ident == "with" =>
+            {
+                match &name_value.lit {
+                    syn::Lit::Str(module) => Some(
+                        module
+                            .parse()
+                            .expect("ssz_derive expects a module path in #[ssz(with = \"...\")]."),
+                    ),
+                    _ => panic!("ssz_derive expects a string in #[ssz(with = \"...\")]."),
+                }
             }
+            _ => None,
         })
-        .collect()
 }
 
-/// Returns true if some field has an attribute declaring it should not be serialized.
+/// Returns true if the struct has an attribute declaring that it should be encoded exactly like
+/// its only field.
 ///
-/// The field attribute is: `#[ssz(skip_serializing)]`
-fn should_skip_serializing(field: &syn::Field) -> bool {
-    field.attrs.iter().any(|attr| {
-        attr.path.is_ident("ssz") && attr.tts.to_string().replace(" ", "") == "(skip_serializing)"
+/// The struct attribute is: `#[ssz(transparent)]`
+fn is_transparent(item: &DeriveInput) -> bool {
+    item.attrs.iter().any(|attr| {
+        attr.path.is_ident("ssz") && attr.tts.to_string().replace(" ", "") == "(transparent)"
     })
 }
 
-/// Implements `ssz::Encode` for some `struct`.
+/// Returns the member and type of the only field of a `#[ssz(transparent)]` struct.
 ///
-/// Fields are encoded in the order they are defined.
+/// # Panics
+/// A struct without exactly one field will raise a panic at compile time.
+fn get_transparent_field(struct_data: &syn::DataStruct) -> (syn::Member, &syn::Type) {
+    let mut fields = struct_data.fields.iter();
+
+    match (fields.next(), fields.next()) {
+        (Some(field), None) => (field_member(0, field), &field.ty),
+        _ => panic!("ssz_derive only supports #[ssz(transparent)] on structs with one field."),
+    }
+}
+
+/// Quotes for the length properties of a container field.
+struct FieldLen {
+    is_fixed_len: proc_macro2::TokenStream,
+    fixed_len: proc_macro2::TokenStream,
+    /// The minimum encoded length: its fixed length, or an offset plus the minimum length of its
+    /// variable-length part.
+    min_len: proc_macro2::TokenStream,
+    /// The maximum encoded length, like `min_len`.
+    max_len: proc_macro2::TokenStream,
+}
+
+/// Returns the length properties of `field`, from either the `trait_path` (`ssz::Encode` or
+/// `ssz::Decode`) implementation of its type or the `submodule` (`encode` or `decode`) of its
+/// `#[ssz(with = "module")]` module.
+///
+/// A `with` module has no length bounds, so its variable-length fields are assumed to be
+/// unbounded.
+fn field_len(
+    field: &syn::Field,
+    trait_path: proc_macro2::TokenStream,
+    submodule: proc_macro2::TokenStream,
+) -> FieldLen {
+    let ty = &field.ty;
+    let (is_fixed_len, fixed_len, min_var_len, max_var_len) = match get_ssz_with(field) {
+        Some(module) => (
+            quote! { #module::#submodule::is_ssz_fixed_len() },
+            quote! { #module::#submodule::ssz_fixed_len() },
+            quote! { 0 },
+            quote! { usize::max_value() },
+        ),
+        None => (
+            quote! { <#ty as #trait_path>::is_ssz_fixed_len() },
+            quote! { <#ty as #trait_path>::ssz_fixed_len() },
+            quote! { <#ty as #trait_path>::ssz_min_len() },
+            quote! { <#ty as #trait_path>::ssz_max_len() },
+        ),
+    };
+    let len = |var_len: proc_macro2::TokenStream| {
+        quote! {
+            if #is_fixed_len {
+                #fixed_len
+            } else {
+                ssz::BYTES_PER_LENGTH_OFFSET.saturating_add(#var_len)
+            }
+        }
+    };
+
+    FieldLen {
+        min_len: len(min_var_len),
+        max_len: len(max_var_len),
+        is_fixed_len,
+        fixed_len,
+    }
+}
+
+/// Implements `ssz::Encode` for some `struct` or `enum`.
+///
+/// Fields are encoded in the order they are defined. The generated `ssz_write` streams each field
+/// to the writer in turn.
+///
+/// An `enum` is encoded as an SSZ union: the index of the variant, followed by the encoding of
+/// its single unnamed field. Only the first variant may be a unit variant, which encodes only its
+/// index, like `None` does for `Option<T>`.
+///
+/// Tuple structs are encoded like structs with named fields, in the order of their positions.
+///
+/// ## Struct attributes
+///
+/// - `#[ssz(transparent)]`: the struct, which must have exactly one field, is encoded exactly
+///   like that field. Useful for newtypes, e.g. `struct Slot(u64)`.
 ///
 /// ## Field attributes
 ///
 /// - `#[ssz(skip_serializing)]`: the field will not be serialized.
+/// - `#[ssz(default)]`: the field will not be serialized (see `#[derive(Decode)]`).
+/// - `#[ssz(with = "module")]`: the field is encoded by `module::encode`, which must provide
+///   `is_ssz_fixed_len() -> bool`, `ssz_fixed_len() -> usize`, `ssz_bytes_len(&T) -> usize` and
+///   `ssz_append(&T, &mut Vec<u8>)`, like the `Encode` methods of the same names. Useful for
+///   foreign types that cannot implement `Encode`. The generated `ssz_write` buffers the whole
+///   container.
 #[proc_macro_derive(Encode, attributes(ssz))]
 pub fn ssz_encode_derive(input: TokenStream) -> TokenStream {
     let item = parse_macro_input!(input as DeriveInput);
@@ -75,22 +195,92 @@ pub fn ssz_encode_derive(input: TokenStream) -> TokenStream {
 
     let struct_data = match &item.data {
         syn::Data::Struct(s) => s,
-        _ => panic!("ssz_derive only supports structs."),
+        syn::Data::Enum(e) => return ssz_encode_derive_enum(&item, e),
+        _ => panic!("ssz_derive only supports structs and enums."),
     };
 
-    let field_idents = get_serializable_named_field_idents(&struct_data);
-    let field_idents_a = get_serializable_named_field_idents(&struct_data);
-    let field_types_a = get_serializable_field_types(&struct_data);
-    let field_types_b = field_types_a.clone();
-    let field_types_d = field_types_a.clone();
-    let field_types_e = field_types_a.clone();
-    let field_types_f = field_types_a.clone();
+    if is_transparent(&item) {
+        return ssz_encode_derive_transparent(&item, struct_data);
+    }
+
+    let mut is_fixed_lens = vec![];
+    let mut fixed_lens = vec![];
+    let mut min_lens = vec![];
+    let mut max_lens = vec![];
+    let mut bytes_lens = vec![];
+    let mut appends = vec![];
+    let mut field_members = vec![];
+    let mut has_with_fields = false;
+
+    for (i, field) in struct_data.fields.iter().enumerate() {
+        if should_skip_serializing(field) {
+            continue;
+        }
+
+        let member = field_member(i, field);
+        let len = field_len(field, quote! { ssz::Encode }, quote! { encode });
+        let is_fixed_len = &len.is_fixed_len;
+
+        match get_ssz_with(field) {
+            Some(module) => {
+                has_with_fields = true;
+                bytes_lens.push(quote! { #module::encode::ssz_bytes_len(&self.#member) });
+                appends.push(quote! {
+                    encoder.append_parameterized(#is_fixed_len, |buf| {
+                        #module::encode::ssz_append(&self.#member, buf)
+                    });
+                });
+            }
+            None => {
+                bytes_lens.push(quote! { self.#member.ssz_bytes_len() });
+                appends.push(quote! { encoder.append(&self.#member); });
+            }
+        }
+
+        is_fixed_lens.push(len.is_fixed_len);
+        fixed_lens.push(len.fixed_len);
+        min_lens.push(len.min_len);
+        max_lens.push(len.max_len);
+        field_members.push(member);
+    }
+
+    let is_fixed_lens_a = &is_fixed_lens;
+    let fixed_lens_a = &fixed_lens;
+
+    // A `with` module can only append to a buffer, so containers with such fields fall back to the
+    // default `ssz_write`, which buffers the whole container.
+    let ssz_write = if has_with_fields {
+        quote! {}
+    } else {
+        let field_members_a = &field_members;
+        let field_members_b = &field_members;
+
+        quote! {
+            fn ssz_write<W: std::io::Write>(&self, writer: &mut W) -> std::io::Result<()> {
+                let offset = #(
+                        #fixed_lens_a +
+                    )*
+                        0;
+
+                let mut encoder = ssz::SszStreamEncoder::container(writer, offset);
+
+                #(
+                    encoder.write_fixed_part(&self.#field_members_a)?;
+                )*
+                #(
+                    encoder.write_variable_part(&self.#field_members_b)?;
+                )*
+
+                Ok(())
+            }
+        }
+    };
 
     let output = quote! {
         impl #impl_generics ssz::Encode for #name #ty_generics #where_clause {
             fn is_ssz_fixed_len() -> bool {
                 #(
-                    <#field_types_a as ssz::Encode>::is_ssz_fixed_len() &&
+                    #is_fixed_lens_a &&
                 )*
                     true
             }
@@ -98,7 +288,7 @@ pub fn ssz_encode_derive(input: TokenStream) -> TokenStream {
             fn ssz_fixed_len() -> usize {
                 if <Self as ssz::Encode>::is_ssz_fixed_len() {
                     #(
-                        <#field_types_b as ssz::Encode>::ssz_fixed_len() +
+                        #fixed_lens_a +
                     )*
                         0
                 } else {
@@ -106,17 +296,29 @@ pub fn ssz_encode_derive(input: TokenStream) -> TokenStream {
                 }
             }
 
+            fn ssz_min_len() -> usize {
+                0usize #(
+                    .saturating_add(#min_lens)
+                )*
+            }
+
+            fn ssz_max_len() -> usize {
+                0usize #(
+                    .saturating_add(#max_lens)
+                )*
+            }
+
             fn ssz_bytes_len(&self) -> usize {
                 if <Self as ssz::Encode>::is_ssz_fixed_len() {
                     <Self as ssz::Encode>::ssz_fixed_len()
                 } else {
                     let mut len = 0;
                     #(
-                        if <#field_types_d as ssz::Encode>::is_ssz_fixed_len() {
-                            len += <#field_types_e as ssz::Encode>::ssz_fixed_len();
+                        if #is_fixed_lens_a {
+                            len += #fixed_lens_a;
                         } else {
                             len += ssz::BYTES_PER_LENGTH_OFFSET;
-                            len += self.#field_idents_a.ssz_bytes_len();
+                            len += #bytes_lens;
                         }
                     )*
 
@@ -126,18 +328,168 @@ pub fn ssz_encode_derive(input: TokenStream) -> TokenStream {
 
             fn ssz_append(&self, buf: &mut Vec<u8>) {
                 let offset = #(
-                        <#field_types_f as ssz::Encode>::ssz_fixed_len() +
+                        #fixed_lens_a +
                     )*
                         0;
 
                 let mut encoder = ssz::SszEncoder::container(buf, offset);
 
                 #(
-                    encoder.append(&self.#field_idents);
+                    #appends
                 )*
 
                 encoder.finalize();
             }
+
+            #ssz_write
+        }
+    };
+    output.into()
+}
+
+/// Implements `ssz::Encode` for a `#[ssz(transparent)]` struct, delegating to its only field.
+fn ssz_encode_derive_transparent(item: &DeriveInput, struct_data: &syn::DataStruct) -> TokenStream {
+    let name = &item.ident;
+    let (impl_generics, ty_generics, where_clause) = &item.generics.split_for_impl();
+    let (member, ty) = get_transparent_field(struct_data);
+
+    let output = quote! {
+        impl #impl_generics ssz::Encode for #name #ty_generics #where_clause {
+            fn is_ssz_fixed_len() -> bool {
+                <#ty as ssz::Encode>::is_ssz_fixed_len()
+            }
+
+            fn ssz_fixed_len() -> usize {
+                <#ty as ssz::Encode>::ssz_fixed_len()
+            }
+
+            fn ssz_min_len() -> usize {
+                <#ty as ssz::Encode>::ssz_min_len()
+            }
+
+            fn ssz_max_len() -> usize {
+                <#ty as ssz::Encode>::ssz_max_len()
+            }
+
+            fn ssz_bytes_len(&self) -> usize {
+                self.#member.ssz_bytes_len()
+            }
+
+            fn ssz_append(&self, buf: &mut Vec<u8>) {
+                self.#member.ssz_append(buf)
+            }
+
+            fn ssz_write<W: std::io::Write>(&self, writer: &mut W) -> std::io::Result<()> {
+                self.#member.ssz_write(writer)
+            }
+        }
+    };
+    output.into()
+}
+
+/// Returns the ident of each variant of an enum that is to be treated as an SSZ union, along with
+/// the type of its field (`None` for unit variants).
+///
+/// # Panics
+/// Any variant with named fields, or more than one unnamed field, will raise a panic at compile
+/// time. So will a unit variant other than the first, as only union index 0 may stand for `None`.
+fn get_union_variants(enum_data: &syn::DataEnum) -> Vec<(&syn::Ident, Option<&syn::Type>)> {
+    if enum_data.variants.is_empty() {
+        panic!("ssz_derive does not support enums without variants.");
+    }
+
+    enum_data
+        .variants
+        .iter()
+        .enumerate()
+        .map(|(index, variant)| {
+            let ty = match &variant.fields {
+                syn::Fields::Unit if index == 0 => None,
+                syn::Fields::Unit => {
+                    panic!("ssz_derive only supports a unit variant as the first enum variant.")
+                }
+                syn::Fields::Unnamed(fields) if fields.unnamed.len() == 1 => {
+                    Some(&fields.unnamed[0].ty)
+                }
+                _ => panic!("ssz_derive only supports enum variants with one unnamed field."),
+            };
+            (&variant.ident, ty)
+        })
+        .collect()
+}
+
+/// Implements `ssz::Encode` for an `enum`, as an SSZ union.
+fn ssz_encode_derive_enum(item: &DeriveInput, enum_data: &syn::DataEnum) -> TokenStream {
+    let name = &item.ident;
+    let (impl_generics, ty_generics, where_clause) = &item.generics.split_for_impl();
+
+    let mut min_lens = vec![];
+    let mut max_lens = vec![];
+    let mut bytes_lens = vec![];
+    let mut appends = vec![];
+
+    for (index, (ident, ty)) in get_union_variants(enum_data).into_iter().enumerate() {
+        match ty {
+            Some(ty) => {
+                min_lens.push(quote! { <#ty as ssz::Encode>::ssz_min_len() });
+                max_lens.push(quote! { <#ty as ssz::Encode>::ssz_max_len() });
+                bytes_lens.push(quote! {
+                    #name::#ident(inner) => inner.ssz_bytes_len()
+                });
+                appends.push(quote! {
+                    #name::#ident(inner) => {
+                        buf.append(&mut ssz::encode_union_index(#index));
+                        inner.ssz_append(buf);
+                    }
+                });
+            }
+            None => {
+                min_lens.push(quote! { 0 });
+                max_lens.push(quote! { 0 });
+                bytes_lens.push(quote! {
+                    #name::#ident => 0
+                });
+                appends.push(quote! {
+                    #name::#ident => buf.append(&mut ssz::encode_union_index(#index))
+                });
+            }
+        }
+    }
+
+    let output = quote! {
+        impl #impl_generics ssz::Encode for #name #ty_generics #where_clause {
+            fn is_ssz_fixed_len() -> bool {
+                false
+            }
+
+            fn ssz_min_len() -> usize {
+                let lens: &[usize] = &[#(#min_lens),*];
+                ssz::BYTES_PER_LENGTH_OFFSET
+                    .saturating_add(lens.iter().cloned().min().unwrap_or(0))
+            }
+
+            fn ssz_max_len() -> usize {
+                let lens: &[usize] = &[#(#max_lens),*];
+                ssz::BYTES_PER_LENGTH_OFFSET
+                    .saturating_add(lens.iter().cloned().max().unwrap_or(0))
+            }
+
+            fn ssz_bytes_len(&self) -> usize {
+                let len = match self {
+                    #(
+                        #bytes_lens,
+                    )*
+                };
+                ssz::BYTES_PER_LENGTH_OFFSET + len
+            }
+
+            fn ssz_append(&self, buf: &mut Vec<u8>) {
+                match self {
+                    #(
+                        #appends,
+                    )*
+                }
+            }
         }
     };
     output.into()
@@ -145,23 +497,43 @@ pub fn ssz_encode_derive(input: TokenStream) -> TokenStream {
 
 /// Returns true if some field has an attribute declaring it should not be deserialized.
 ///
-/// The field attribute is: `#[ssz(skip_deserializing)]`
+/// The field attributes are: `#[ssz(skip_deserializing)]` and `#[ssz(default)]`
 fn should_skip_deserializing(field: &syn::Field) -> bool {
-    field.attrs.iter().any(|attr| {
-        attr.path.is_ident("ssz") && attr.tts.to_string().replace(" ", "") == "(skip_deserializing)"
-    })
+    is_default(field)
+        || field.attrs.iter().any(|attr| {
+            attr.path.is_ident("ssz")
+                && attr.tts.to_string().replace(" ", "") == "(skip_deserializing)"
+        })
 }
 
-/// Implements `ssz::Decode` for some `struct`.
+/// Implements `ssz::Decode` for some `struct` or `enum`.
+///
+/// Fields are decoded in the order they are defined. The generated `from_ssz_reader` reads the
+/// fixed-length part first, then streams each variable-length field from the reader.
+///
+/// Errors from a field are returned as `DecodeError::InPath`, with the name of the field (or its
+/// position, for tuple structs) prepended to the path.
+///
+/// An `enum` is decoded as an SSZ union (see `#[derive(Encode)]`), returning an error for a union
+/// index without a matching variant. Errors from a variant's field are located by the name of the
+/// variant.
 ///
-/// Fields are decoded in the order they are defined.
+/// ## Struct attributes
+///
+/// - `#[ssz(transparent)]`: the struct is decoded from the encoding of its only field.
 ///
 /// ## Field attributes
 ///
 /// - `#[ssz(skip_deserializing)]`: during de-serialization the field will be instantiated from a
 /// `Default` implementation. The decoder will assume that the field was not serialized at all
 /// (e.g., if it has been serialized, an error will be raised instead of `Default` overriding it).
-#[proc_macro_derive(Decode)]
+/// - `#[ssz(default)]`: the field is neither serialized nor deserialized, and is instantiated from
+///   the `Default` implementation of its type. Only that field's type needs `Default`.
+/// - `#[ssz(with = "module")]`: the field is decoded by `module::decode`, which must provide
+///   `is_ssz_fixed_len() -> bool`, `ssz_fixed_len() -> usize` and
+///   `from_ssz_bytes(&[u8]) -> Result<T, DecodeError>`, like the `Decode` methods of the same
+///   names. The generated `from_ssz_reader` reads the whole container before decoding it.
+#[proc_macro_derive(Decode, attributes(ssz))]
 pub fn ssz_decode_derive(input: TokenStream) -> TokenStream {
     let item = parse_macro_input!(input as DeriveInput);
 
@@ -170,47 +542,114 @@ pub fn ssz_decode_derive(input: TokenStream) -> TokenStream {
 
     let struct_data = match &item.data {
         syn::Data::Struct(s) => s,
-        _ => panic!("ssz_derive only supports structs."),
+        syn::Data::Enum(e) => return ssz_decode_derive_enum(&item, e),
+        _ => panic!("ssz_derive only supports structs and enums."),
     };
 
+    if is_transparent(&item) {
+        return ssz_decode_derive_transparent(&item, struct_data);
+    }
+
     let mut register_types = vec![];
     let mut decodes = vec![];
     let mut is_fixed_lens = vec![];
     let mut fixed_lens = vec![];
+    let mut min_lens = vec![];
+    let mut max_lens = vec![];
+    let mut has_with_fields = false;
 
     // Build quotes for fields that should be deserialized and those that should be built from
     // `Default`.
-    for field in &struct_data.fields {
-        match &field.ident {
-            Some(ref ident) => {
-                if should_skip_deserializing(field) {
-                    // Field should not be deserialized; use a `Default` impl to instantiate.
+    for (i, field) in struct_data.fields.iter().enumerate() {
+        let member = field_member(i, field);
+
+        if should_skip_deserializing(field) {
+            // Field should not be deserialized; use a `Default` impl to instantiate.
+            decodes.push(quote! {
+                #member: <_>::default()
+            });
+        } else {
+            let len = field_len(field, quote! { ssz::Decode }, quote! { decode });
+            let field_name = field_name(i, field);
+
+            match get_ssz_with(field) {
+                Some(module) => {
+                    has_with_fields = true;
+                    let is_fixed_len = &len.is_fixed_len;
+                    let fixed_len = &len.fixed_len;
+
+                    register_types.push(quote! {
+                        builder
+                            .register_type_parameterized(#is_fixed_len, #fixed_len)
+                            .map_err(|e| e.in_field(#field_name))?;
+                    });
+
                     decodes.push(quote! {
-                        #ident: <_>::default()
+                        #member: decoder
+                            .decode_next_with(#module::decode::from_ssz_bytes)
+                            .map_err(|e| e.in_field(#field_name))?
                     });
-                } else {
+                }
+                None => {
                     let ty = &field.ty;
 
                     register_types.push(quote! {
-                        builder.register_type::<#ty>()?;
+                        builder
+                            .register_type::<#ty>()
+                            .map_err(|e| e.in_field(#field_name))?;
                     });
 
                     decodes.push(quote! {
-                        #ident: decoder.decode_next()?
+                        #member: decoder
+                            .decode_next()
+                            .map_err(|e| e.in_field(#field_name))?
                     });
+                }
+            }
 
-                    is_fixed_lens.push(quote! {
-                        <#ty as ssz::Decode>::is_ssz_fixed_len()
-                    });
+            is_fixed_lens.push(len.is_fixed_len);
+            fixed_lens.push(len.fixed_len);
+            min_lens.push(len.min_len);
+            max_lens.push(len.max_len);
+        }
+    }
+
+    // A `with` module can only decode from a slice, so containers with such fields fall back to
+    // the default `from_ssz_reader`, which reads the whole container first.
+    let from_ssz_reader = if has_with_fields {
+        quote! {}
+    } else {
+        let register_types = &register_types;
+        let decodes = &decodes;
 
-                    fixed_lens.push(quote! {
-                        <#ty as ssz::Decode>::ssz_fixed_len()
+        quote! {
+            fn from_ssz_reader<R: std::io::Read>(
+                reader: &mut R,
+                len: usize,
+            ) -> Result<Self, ssz::DecodeError> {
+                if len > <Self as ssz::Decode>::ssz_max_len() {
+                    return Err(ssz::DecodeError::InvalidByteLength {
+                        len,
+                        expected: <Self as ssz::Decode>::ssz_max_len(),
                     });
                 }
+
+                let mut builder = ssz::SszStreamDecoderBuilder::new(len);
+
+                #(
+                    #register_types
+                )*
+
+                let mut decoder = builder.build(reader)?;
+
+                Ok(Self {
+                    #(
+                        #decodes,
+                    )*
+                })
             }
-            _ => panic!("ssz_derive only supports named struct fields."),
-        };
-    }
+        }
+    };
 
     let output = quote! {
         impl #impl_generics ssz::Decode for #name #ty_generics #where_clause {
@@ -232,6 +671,18 @@ pub fn ssz_decode_derive(input: TokenStream) -> TokenStream {
                 }
             }
 
+            fn ssz_min_len() -> usize {
+                0usize #(
+                    .saturating_add(#min_lens)
+                )*
+            }
+
+            fn ssz_max_len() -> usize {
+                0usize #(
+                    .saturating_add(#max_lens)
+                )*
+            }
+
             fn from_ssz_bytes(bytes: &[u8]) -> Result<Self, ssz::DecodeError> {
                 let mut builder = ssz::SszDecoderBuilder::new(bytes);
 
@@ -247,6 +698,393 @@ pub fn ssz_decode_derive(input: TokenStream) -> TokenStream {
                     )*
                 })
             }
+
+            #from_ssz_reader
+        }
+    };
+    output.into()
+}
+
+/// Implements `ssz::Decode` for a `#[ssz(transparent)]` struct, delegating to its only field.
+fn ssz_decode_derive_transparent(item: &DeriveInput, struct_data: &syn::DataStruct) -> TokenStream {
+    let name = &item.ident;
+    let (impl_generics, ty_generics, where_clause) = &item.generics.split_for_impl();
+    let (member, ty) = get_transparent_field(struct_data);
+    let member_a = member.clone();
+
+    let output = quote! {
+        impl #impl_generics ssz::Decode for #name #ty_generics #where_clause {
+            fn is_ssz_fixed_len() -> bool {
+                <#ty as ssz::Decode>::is_ssz_fixed_len()
+            }
+
+            fn ssz_fixed_len() -> usize {
+                <#ty as ssz::Decode>::ssz_fixed_len()
+            }
+
+            fn ssz_min_len() -> usize {
+                <#ty as ssz::Decode>::ssz_min_len()
+            }
+
+            fn ssz_max_len() -> usize {
+                <#ty as ssz::Decode>::ssz_max_len()
+            }
+
+            fn from_ssz_bytes(bytes: &[u8]) -> Result<Self, ssz::DecodeError> {
+                Ok(Self {
+                    #member: <#ty as ssz::Decode>::from_ssz_bytes(bytes)?,
+                })
+            }
+
+            fn from_ssz_reader<R: std::io::Read>(
+                reader: &mut R,
+                len: usize,
+            ) -> Result<Self, ssz::DecodeError> {
+                Ok(Self {
+                    #member_a: <#ty as ssz::Decode>::from_ssz_reader(reader, len)?,
+                })
+            }
+        }
+    };
+    output.into()
+}
+
+/// Implements `ssz::Decode` for an `enum`, as an SSZ union.
+fn ssz_decode_derive_enum(item: &DeriveInput, enum_data: &syn::DataEnum) -> TokenStream {
+    let name = &item.ident;
+    let (impl_generics, ty_generics, where_clause) = &item.generics.split_for_impl();
+
+    let mut min_lens = vec![];
+    let mut max_lens = vec![];
+    let mut decodes = vec![];
+
+    for (index, (ident, ty)) in get_union_variants(enum_data).into_iter().enumerate() {
+        match ty {
+            Some(ty) => {
+                min_lens.push(quote! { <#ty as ssz::Decode>::ssz_min_len() });
+                max_lens.push(quote! { <#ty as ssz::Decode>::ssz_max_len() });
+                let variant_name = ident.to_string();
+                decodes.push(quote! {
+                    #index => <#ty as ssz::Decode>::from_ssz_bytes(value_bytes)
+                        .map(#name::#ident)
+                        .map_err(|e| e.in_field(#variant_name))
+                });
+            }
+            None => {
+                min_lens.push(quote! { 0 });
+                max_lens.push(quote! { 0 });
+                // A unit variant is encoded as its index alone; reject any trailing bytes.
+                decodes.push(quote! {
+                    #index if value_bytes.is_empty() => Ok(#name::#ident),
+                    #index => Err(ssz::DecodeError::TrailingBytes {
+                        len: bytes.len(),
+                        expected: ssz::BYTES_PER_LENGTH_OFFSET,
+                    })
+                });
+            }
+        }
+    }
+
+    let output = quote! {
+        impl #impl_generics ssz::Decode for #name #ty_generics #where_clause {
+            fn is_ssz_fixed_len() -> bool {
+                false
+            }
+
+            fn ssz_min_len() -> usize {
+                let lens: &[usize] = &[#(#min_lens),*];
+                ssz::BYTES_PER_LENGTH_OFFSET
+                    .saturating_add(lens.iter().cloned().min().unwrap_or(0))
+            }
+
+            fn ssz_max_len() -> usize {
+                let lens: &[usize] = &[#(#max_lens),*];
+                ssz::BYTES_PER_LENGTH_OFFSET
+                    .saturating_add(lens.iter().cloned().max().unwrap_or(0))
+            }
+
+            fn from_ssz_bytes(bytes: &[u8]) -> Result<Self, ssz::DecodeError> {
+                if bytes.len() < ssz::BYTES_PER_LENGTH_OFFSET {
+                    return Err(ssz::DecodeError::InvalidByteLength {
+                        len: bytes.len(),
+                        expected: ssz::BYTES_PER_LENGTH_OFFSET,
+                    });
+                }
+
+                let (index_bytes, value_bytes) = bytes.split_at(ssz::BYTES_PER_LENGTH_OFFSET);
+
+                match ssz::read_union_index(index_bytes)? {
+                    #(
+                        #decodes,
+                    )*
+                    index => Err(ssz::DecodeError::BytesInvalid(format!(
+                        "{} is not a valid union index for {}",
+                        index,
+                        stringify!(#name)
+                    ))),
+                }
+            }
+        }
+    };
+    output.into()
+}
+
+/// Implements `ssz::DecodeView` for some `struct`, generating a `FooView<'a>` for a struct
+/// `Foo`.
+///
+/// The view checks the offsets of the struct's fields when it is created and has a method per
+/// field, returning a view of that field.
+///
+/// ## Struct attributes
+///
+/// - `#[ssz(transparent)]`: no view struct is generated; the view is that of the only field.
+///
+/// ## Field attributes
+///
+/// - `#[ssz(skip_deserializing)]` or `#[ssz(default)]`: the field is not part of the encoding and
+///   has no method.
+/// - `#[ssz(with = "module")]`: the field's method decodes it with `module::decode` (see
+///   `#[derive(Decode)]`), as there is no view of it.
+#[proc_macro_derive(DecodeView, attributes(ssz))]
+pub fn ssz_decode_view_derive(input: TokenStream) -> TokenStream {
+    let item = parse_macro_input!(input as DeriveInput);
+
+    let name = &item.ident;
+    let vis = &item.vis;
+    let view_name = syn::Ident::new(&format!("{}View", name), name.span());
+    let (_, ty_generics, where_clause) = &item.generics.split_for_impl();
+
+    let struct_data = match &item.data {
+        syn::Data::Struct(s) => s,
+        _ => panic!("ssz_derive only supports structs."),
+    };
+
+    if is_transparent(&item) {
+        let (_, ty) = get_transparent_field(struct_data);
+        let mut view_generics = item.generics.clone();
+        view_generics.params.insert(0, syn::parse_quote!('a));
+        view_generics
+            .make_where_clause()
+            .predicates
+            .push(syn::parse_quote!(#ty: ssz::DecodeView<'a>));
+        let (view_impl_generics, _, view_where_clause) = view_generics.split_for_impl();
+
+        let output = quote! {
+            impl #view_impl_generics ssz::DecodeView<'a> for #name #ty_generics #view_where_clause {
+                type View = <#ty as ssz::DecodeView<'a>>::View;
+
+                fn view_ssz_bytes(bytes: &'a [u8]) -> Result<Self::View, ssz::DecodeError> {
+                    <#ty as ssz::DecodeView<'a>>::view_ssz_bytes(bytes)
+                }
+            }
+        };
+        return output.into();
+    }
+
+    let fields = struct_data
+        .fields
+        .iter()
+        .filter(|field| !should_skip_deserializing(field))
+        .map(|field| match &field.ident {
+            Some(ref ident) => (ident, &field.ty, get_ssz_with(field)),
+            _ => panic!(
+                "ssz_derive only supports DecodeView on tuple structs with #[ssz(transparent)]."
+            ),
+        })
+        .collect::<Vec<_>>();
+
+    // The view's methods need a view of every field type, besides those decoded by a `with`
+    // module.
+    let mut view_generics = item.generics.clone();
+    view_generics.params.insert(0, syn::parse_quote!('a));
+    for (_, ty, _) in fields.iter().filter(|(_, _, with)| with.is_none()) {
+        view_generics
+            .make_where_clause()
+            .predicates
+            .push(syn::parse_quote!(#ty: ssz::DecodeView<'a>));
+    }
+    let (view_impl_generics, view_ty_generics, view_where_clause) = view_generics.split_for_impl();
+
+    let field_count = fields.len();
+    let register_types = fields.iter().map(|(ident, ty, with)| {
+        let field_name = ident.to_string();
+        match with {
+            Some(module) => quote! {
+                builder
+                    .register_type_parameterized(
+                        #module::decode::is_ssz_fixed_len(),
+                        #module::decode::ssz_fixed_len(),
+                    )
+                    .map_err(|e| e.in_field(#field_name))?;
+            },
+            None => quote! {
+                builder
+                    .register_type::<#ty>()
+                    .map_err(|e| e.in_field(#field_name))?;
+            },
+        }
+    });
+    let next_bytes = fields.iter().map(|_| quote! { decoder.next_bytes() });
+    // Fields decoded by a `with` module have no view, so their methods decode them instead.
+    let accessors = fields
+        .iter()
+        .enumerate()
+        .map(|(i, (ident, ty, with))| match with {
+            Some(module) => quote! {
+                pub fn #ident(&self) -> Result<#ty, ssz::DecodeError> {
+                    #module::decode::from_ssz_bytes(self.fields[#i])
+                }
+            },
+            None => quote! {
+                pub fn #ident(&self) -> Result<<#ty as ssz::DecodeView<'a>>::View, ssz::DecodeError> {
+                    <#ty as ssz::DecodeView<'a>>::view_ssz_bytes(self.fields[#i])
+                }
+            },
+        });
+    let doc = format!(
+        "A borrowed view over the SSZ encoding of a `{}`, see `ssz::DecodeView`.",
+        name
+    );
+
+    let output = quote! {
+        #[doc = #doc]
+        #vis struct #view_name #view_impl_generics #where_clause {
+            bytes: &'a [u8],
+            fields: [&'a [u8]; #field_count],
+            _phantom: std::marker::PhantomData<#name #ty_generics>,
+        }
+
+        impl #view_impl_generics #view_name #view_ty_generics #view_where_clause {
+            pub fn as_ssz_bytes(&self) -> &'a [u8] {
+                self.bytes
+            }
+
+            #(
+                #accessors
+            )*
+        }
+
+        impl #view_impl_generics Clone for #view_name #view_ty_generics #view_where_clause {
+            fn clone(&self) -> Self {
+                *self
+            }
+        }
+
+        impl #view_impl_generics Copy for #view_name #view_ty_generics #view_where_clause {}
+
+        impl #view_impl_generics ssz::DecodeView<'a> for #name #ty_generics #view_where_clause {
+            type View = #view_name #view_ty_generics;
+
+            fn view_ssz_bytes(bytes: &'a [u8]) -> Result<Self::View, ssz::DecodeError> {
+                let mut builder = ssz::SszDecoderBuilder::new(bytes);
+
+                #(
+                    #register_types
+                )*
+
+                let mut decoder = builder.build()?;
+
+                Ok(#view_name {
+                    bytes,
+                    fields: [#(#next_bytes),*],
+                    _phantom: std::marker::PhantomData,
+                })
+            }
+        }
+    };
+    output.into()
+}
+
+/// Implements `ssz::SszSchema` for some `struct` or `enum`, describing it as a container or, for
+/// an `enum`, a union.
+///
+/// Fields are listed in the order they are encoded, leaving out those that are not serialized
+/// (see `#[derive(Encode)]`), each with the Rust type it is declared with.
+///
+/// ## Struct attributes
+///
+/// - `#[ssz(transparent)]`: the schema is that of the only field.
+///
+/// ## Field attributes
+///
+/// - `#[ssz(with = "module")]`: the schema of the field is `module::ssz_schema()`.
+#[proc_macro_derive(SszSchema, attributes(ssz))]
+pub fn ssz_schema_derive(input: TokenStream) -> TokenStream {
+    let item = parse_macro_input!(input as DeriveInput);
+
+    let name = &item.ident;
+    let name_str = name.to_string();
+    let (impl_generics, ty_generics, where_clause) = &item.generics.split_for_impl();
+
+    let schema = match &item.data {
+        syn::Data::Struct(struct_data) if is_transparent(&item) => {
+            let (_, ty) = get_transparent_field(struct_data);
+            quote! { <#ty as ssz::SszSchema>::ssz_schema() }
+        }
+        syn::Data::Struct(struct_data) => {
+            let fields = struct_data
+                .fields
+                .iter()
+                .enumerate()
+                .filter(|(_, field)| !should_skip_serializing(field))
+                .map(|(i, field)| {
+                    let name = field_name(i, field);
+                    let ty = &field.ty;
+                    let ty_str = quote! { #ty }.to_string().replace(" ", "");
+                    let schema = match get_ssz_with(field) {
+                        Some(module) => quote! { #module::ssz_schema() },
+                        None => quote! { <#ty as ssz::SszSchema>::ssz_schema() },
+                    };
+
+                    quote! {
+                        ssz::FieldSchema {
+                            name: #name,
+                            ty: #ty_str,
+                            schema: #schema,
+                        }
+                    }
+                });
+
+            quote! {
+                ssz::Schema::Container {
+                    name: #name_str,
+                    fields: vec![#(#fields),*],
+                }
+            }
+        }
+        syn::Data::Enum(enum_data) => {
+            let variants = get_union_variants(enum_data)
+                .into_iter()
+                .map(|(ident, ty)| {
+                    let name = ident.to_string();
+                    let schema = match ty {
+                        Some(ty) => quote! { Some(<#ty as ssz::SszSchema>::ssz_schema()) },
+                        None => quote! { None },
+                    };
+
+                    quote! {
+                        ssz::VariantSchema {
+                            name: #name,
+                            schema: #schema,
+                        }
+                    }
+                });
+
+            quote! {
+                ssz::Schema::Union {
+                    name: #name_str,
+                    variants: vec![#(#variants),*],
+                }
+            }
+        }
+        _ => panic!("ssz_derive only supports structs and enums."),
+    };
+
+    let output = quote! {
+        impl #impl_generics ssz::SszSchema for #name #ty_generics #where_clause {
+            fn ssz_schema() -> ssz::Schema {
+                #schema
+            }
         }
     };
     output.into()
@@ -0,0 +1,535 @@
+//! Snappy compression of SSZ payloads, as used by the `ssz_snappy` network encoding.
+//!
+//! Gossip messages are compressed as a single Snappy block. Req/resp payloads are prefixed with
+//! their uncompressed length as an unsigned varint, followed by the payload in the Snappy
+//! [framing format](https://github.com/google/snappy/blob/master/framing_format.txt).
+//!
+//! Every decoder takes the largest uncompressed length the caller is prepared to accept, which
+//! should be the maximum SSZ length of the expected type. The declared length is checked against
+//! it before anything is decompressed, so a small malicious payload cannot make us allocate
+//! far more memory than a valid one would.
+//!
+//! ## Example
+//!
+//! ```rust
+//! use ssz::{Decode, Encode};
+//!
+//! let list: Vec<u64> = vec![42; 1024];
+//!
+//! let compressed = ssz_snappy::compress_block(&list.as_ssz_bytes()).unwrap();
+//! assert!(compressed.len() < list.len() * 8);
+//!
+//! let decoded: Vec<u64> = ssz_snappy::decode_block(&compressed, 1024 * 8).unwrap();
+//! assert_eq!(decoded, list);
+//!
+//! // Claims to decompress to more than the caller allows.
+//! assert!(ssz_snappy::decode_block::<Vec<u64>>(&compressed, 16).is_err());
+//! ```
+
+use snap::raw::{decompress_len, max_compress_len, Decoder, Encoder};
+use ssz::{Decode, DecodeError};
+
+/// The longest valid encoding of a `u64` varint.
+pub const MAX_VARINT_LEN: usize = 10;
+
+/// The largest amount of data in a single frame chunk, before compression.
+const MAX_CHUNK_DATA_LEN: usize = 1 << 16;
+const STREAM_IDENTIFIER: &[u8] = b"sNaPpY";
+const CHUNK_HEADER_LEN: usize = 4;
+const CHECKSUM_LEN: usize = 4;
+
+const CHUNK_STREAM_IDENTIFIER: u8 = 0xff;
+const CHUNK_COMPRESSED: u8 = 0x00;
+const CHUNK_UNCOMPRESSED: u8 = 0x01;
+/// Chunk types up to this one are reserved and must not be skipped.
+const LAST_RESERVED_UNSKIPPABLE_CHUNK: u8 = 0x7f;
+
+#[derive(Debug, PartialEq)]
+pub enum Error {
+    InvalidVarint,
+    TruncatedVarint,
+    /// The uncompressed length exceeds what the caller allows.
+    TooLarge {
+        len: usize,
+        max: usize,
+    },
+    /// The input ends before the data it describes.
+    Truncated,
+    /// The Snappy block itself is malformed.
+    Snappy(String),
+    MissingStreamIdentifier,
+    ReservedChunk(u8),
+    ChecksumMismatch,
+    /// The frames decompress to more or less data than the length prefix declared.
+    LengthMismatch {
+        expected: usize,
+        found: usize,
+    },
+    /// This many bytes follow the final frame of a payload that should end the input.
+    TrailingBytes(usize),
+    Ssz(DecodeError),
+}
+
+impl From<DecodeError> for Error {
+    fn from(e: DecodeError) -> Self {
+        Error::Ssz(e)
+    }
+}
+
+impl From<snap::Error> for Error {
+    fn from(e: snap::Error) -> Self {
+        Error::Snappy(e.to_string())
+    }
+}
+
+/// Compresses `ssz_bytes` as a single Snappy block.
+pub fn compress_block(ssz_bytes: &[u8]) -> Result<Vec<u8>, Error> {
+    Ok(Encoder::new().compress_vec(ssz_bytes)?)
+}
+
+/// The longest Snappy block that `len` bytes can compress to.
+pub fn max_compressed_len(len: usize) -> usize {
+    max_compress_len(len)
+}
+
+/// Decompresses a single Snappy block, failing if it would exceed `max_len` bytes.
+pub fn decompress_block(bytes: &[u8], max_len: usize) -> Result<Vec<u8>, Error> {
+    let len = decompress_len(bytes)?;
+    check_len(len, max_len)?;
+
+    let mut ssz_bytes = vec![0; len];
+    Decoder::new().decompress(bytes, &mut ssz_bytes)?;
+    Ok(ssz_bytes)
+}
+
+/// Decompresses a single Snappy block and decodes it as `T`.
+pub fn decode_block<T: Decode>(bytes: &[u8], max_len: usize) -> Result<T, Error> {
+    let ssz_bytes = decompress_block(bytes, max_len)?;
+    Ok(T::from_ssz_bytes(&ssz_bytes)?)
+}
+
+/// Appends the uncompressed length of `ssz_bytes` as a varint, followed by `ssz_bytes` in the
+/// Snappy framing format.
+pub fn encode_framed(ssz_bytes: &[u8], buf: &mut Vec<u8>) -> Result<(), Error> {
+    encode_varint(ssz_bytes.len() as u64, buf);
+    write_chunk(CHUNK_STREAM_IDENTIFIER, STREAM_IDENTIFIER, buf);
+
+    let mut encoder = Encoder::new();
+    let mut compressed = vec![0; max_compress_len(MAX_CHUNK_DATA_LEN)];
+
+    for data in ssz_bytes.chunks(MAX_CHUNK_DATA_LEN) {
+        let compressed_len = encoder.compress(data, &mut compressed)?;
+
+        let mut body = masked_crc32c(data).to_le_bytes().to_vec();
+        // Data that does not compress is sent as is.
+        if compressed_len < data.len() {
+            body.extend_from_slice(&compressed[..compressed_len]);
+            write_chunk(CHUNK_COMPRESSED, &body, buf);
+        } else {
+            body.extend_from_slice(data);
+            write_chunk(CHUNK_UNCOMPRESSED, &body, buf);
+        }
+    }
+
+    Ok(())
+}
+
+/// Reads a payload written by `encode_framed` from the start of `bytes`, returning the
+/// uncompressed bytes and the number of input bytes consumed.
+///
+/// Fails before decompressing anything if the declared length exceeds `max_len`, and as soon as
+/// the frames produce more data than declared.
+pub fn decode_framed(bytes: &[u8], max_len: usize) -> Result<(Vec<u8>, usize), Error> {
+    let (len, mut offset) = decode_varint(bytes)?;
+    if len > max_len as u64 {
+        return Err(Error::TooLarge {
+            len: len as usize,
+            max: max_len,
+        });
+    }
+    let len = len as usize;
+
+    let (chunk_type, body) = read_chunk(bytes, &mut offset)?;
+    if chunk_type != CHUNK_STREAM_IDENTIFIER || body != STREAM_IDENTIFIER {
+        return Err(Error::MissingStreamIdentifier);
+    }
+
+    let mut ssz_bytes = Vec::with_capacity(len);
+    let mut decoder = Decoder::new();
+
+    while ssz_bytes.len() < len {
+        let (chunk_type, body) = read_chunk(bytes, &mut offset)?;
+        let remaining = len - ssz_bytes.len();
+
+        let data = match chunk_type {
+            CHUNK_COMPRESSED | CHUNK_UNCOMPRESSED => {
+                if body.len() < CHECKSUM_LEN {
+                    return Err(Error::Truncated);
+                }
+                let (checksum, data) = body.split_at(CHECKSUM_LEN);
+
+                let data = if chunk_type == CHUNK_COMPRESSED {
+                    let data_len = decompress_len(data)?;
+                    if data_len > MAX_CHUNK_DATA_LEN || data_len > remaining {
+                        return Err(Error::LengthMismatch {
+                            expected: len,
+                            found: ssz_bytes.len() + data_len,
+                        });
+                    }
+                    decoder.decompress_vec(data)?
+                } else {
+                    if data.len() > MAX_CHUNK_DATA_LEN || data.len() > remaining {
+                        return Err(Error::LengthMismatch {
+                            expected: len,
+                            found: ssz_bytes.len() + data.len(),
+                        });
+                    }
+                    data.to_vec()
+                };
+
+                if checksum != &masked_crc32c(&data).to_le_bytes()[..] {
+                    return Err(Error::ChecksumMismatch);
+                }
+                data
+            }
+            CHUNK_STREAM_IDENTIFIER => {
+                if body != STREAM_IDENTIFIER {
+                    return Err(Error::MissingStreamIdentifier);
+                }
+                continue;
+            }
+            chunk_type if chunk_type <= LAST_RESERVED_UNSKIPPABLE_CHUNK => {
+                return Err(Error::ReservedChunk(chunk_type));
+            }
+            // Padding and other skippable chunks.
+            _ => continue,
+        };
+
+        ssz_bytes.extend_from_slice(&data);
+    }
+
+    Ok((ssz_bytes, offset))
+}
+
+/// Reads a framed payload and decodes it as `T`, failing if any input is left over.
+pub fn decode_framed_ssz<T: Decode>(bytes: &[u8], max_len: usize) -> Result<T, Error> {
+    let (ssz_bytes, consumed) = decode_framed(bytes, max_len)?;
+    if consumed != bytes.len() {
+        return Err(Error::TrailingBytes(bytes.len() - consumed));
+    }
+    Ok(T::from_ssz_bytes(&ssz_bytes)?)
+}
+
+/// Appends `value` as an unsigned LEB128 varint, as used by protobuf.
+pub fn encode_varint(mut value: u64, buf: &mut Vec<u8>) {
+    loop {
+        let byte = (value & 0x7f) as u8;
+        value >>= 7;
+        if value == 0 {
+            buf.push(byte);
+            return;
+        }
+        buf.push(byte | 0x80);
+    }
+}
+
+/// Reads an unsigned LEB128 varint from the start of `bytes`, returning the value and the number
+/// of bytes it occupied.
+pub fn decode_varint(bytes: &[u8]) -> Result<(u64, usize), Error> {
+    let mut value = 0_u64;
+
+    for (i, byte) in bytes.iter().take(MAX_VARINT_LEN).enumerate() {
+        let low_bits = u64::from(byte & 0x7f);
+        let shift = 7 * i as u32;
+
+        if shift == 63 && low_bits > 1 {
+            return Err(Error::InvalidVarint);
+        }
+        value |= low_bits << shift;
+
+        if byte & 0x80 == 0 {
+            // Reject padded encodings so every value has exactly one representation.
+            if i > 0 && *byte == 0 {
+                return Err(Error::InvalidVarint);
+            }
+            return Ok((value, i + 1));
+        }
+    }
+
+    if bytes.len() < MAX_VARINT_LEN {
+        Err(Error::TruncatedVarint)
+    } else {
+        Err(Error::InvalidVarint)
+    }
+}
+
+fn check_len(len: usize, max: usize) -> Result<(), Error> {
+    if len > max {
+        Err(Error::TooLarge { len, max })
+    } else {
+        Ok(())
+    }
+}
+
+fn write_chunk(chunk_type: u8, body: &[u8], buf: &mut Vec<u8>) {
+    let len = body.len() as u32;
+    buf.push(chunk_type);
+    buf.extend_from_slice(&len.to_le_bytes()[..3]);
+    buf.extend_from_slice(body);
+}
+
+fn read_chunk<'a>(bytes: &'a [u8], offset: &mut usize) -> Result<(u8, &'a [u8]), Error> {
+    let header = bytes
+        .get(*offset..*offset + CHUNK_HEADER_LEN)
+        .ok_or(Error::Truncated)?;
+    let len = usize::from(header[1]) | usize::from(header[2]) << 8 | usize::from(header[3]) << 16;
+
+    let start = *offset + CHUNK_HEADER_LEN;
+    let body = bytes.get(start..start + len).ok_or(Error::Truncated)?;
+    *offset = start + len;
+
+    Ok((header[0], body))
+}
+
+/// The CRC-32C of `data`, masked as the framing format requires.
+fn masked_crc32c(data: &[u8]) -> u32 {
+    let crc = crc32c(data);
+    crc.rotate_right(15).wrapping_add(0xa282_ead8)
+}
+
+/// CRC-32C (Castagnoli), computed bitwise.
+fn crc32c(data: &[u8]) -> u32 {
+    const POLYNOMIAL: u32 = 0x82f6_3b78;
+
+    let mut crc = !0_u32;
+    for byte in data {
+        crc ^= u32::from(*byte);
+        for _ in 0..8 {
+            crc = if crc & 1 == 1 {
+                (crc >> 1) ^ POLYNOMIAL
+            } else {
+                crc >> 1
+            };
+        }
+    }
+    !crc
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Compressible, but not trivially so.
+    fn payload(len: usize) -> Vec<u8> {
+        (0..len)
+            .map(|i| (i % 251) as u8 ^ (i / 1000) as u8)
+            .collect()
+    }
+
+    #[test]
+    fn crc32c_check_value() {
+        assert_eq!(crc32c(b"123456789"), 0xe306_9283);
+        assert_eq!(crc32c(b""), 0);
+    }
+
+    #[test]
+    fn block_round_trip() {
+        for len in &[0, 1, 100, 100_000] {
+            let data = payload(*len);
+            let compressed = compress_block(&data).unwrap();
+            assert_eq!(decompress_block(&compressed, *len), Ok(data));
+        }
+    }
+
+    #[test]
+    fn oversized_block_is_rejected_before_decompressing() {
+        let compressed = compress_block(&vec![0; 1 << 20]).unwrap();
+        assert!(compressed.len() < 1 << 16);
+
+        assert_eq!(
+            decompress_block(&compressed, 1024),
+            Err(Error::TooLarge {
+                len: 1 << 20,
+                max: 1024
+            })
+        );
+    }
+
+    #[test]
+    fn framed_round_trip() {
+        // Spans several frame chunks, the last of them partial.
+        for len in &[0, 1, 100, MAX_CHUNK_DATA_LEN, 3 * MAX_CHUNK_DATA_LEN + 7] {
+            let data = payload(*len);
+            let mut buf = vec![];
+            encode_framed(&data, &mut buf).unwrap();
+            // A following payload must be left alone.
+            encode_framed(&[1, 2, 3], &mut buf).unwrap();
+
+            let (decoded, consumed) = decode_framed(&buf, *len).unwrap();
+            assert_eq!(decoded, data);
+            assert_eq!(
+                decode_framed(&buf[consumed..], 3),
+                Ok((vec![1, 2, 3], buf.len() - consumed))
+            );
+        }
+    }
+
+    #[test]
+    fn incompressible_data_is_sent_uncompressed() {
+        let mut state = 0x1234_5678_u32;
+        let data = (0..1000)
+            .map(|_| {
+                state ^= state << 13;
+                state ^= state >> 17;
+                state ^= state << 5;
+                state as u8
+            })
+            .collect::<Vec<_>>();
+
+        let mut buf = vec![];
+        encode_framed(&data, &mut buf).unwrap();
+
+        let (_, prefix_len) = decode_varint(&buf).unwrap();
+        let chunk = prefix_len + CHUNK_HEADER_LEN + STREAM_IDENTIFIER.len();
+        assert_eq!(buf[chunk], CHUNK_UNCOMPRESSED);
+        assert_eq!(decode_framed(&buf, data.len()), Ok((data, buf.len())));
+    }
+
+    #[test]
+    fn framed_length_prefix_is_enforced() {
+        let data = payload(1000);
+        let mut buf = vec![];
+        encode_framed(&data, &mut buf).unwrap();
+
+        assert_eq!(
+            decode_framed(&buf, 999),
+            Err(Error::TooLarge {
+                len: 1000,
+                max: 999
+            })
+        );
+
+        // A prefix smaller than the frames that follow it.
+        let mut lying = vec![];
+        encode_varint(10, &mut lying);
+        let (_, prefix_len) = decode_varint(&buf).unwrap();
+        lying.extend_from_slice(&buf[prefix_len..]);
+        assert_eq!(
+            decode_framed(&lying, 1000),
+            Err(Error::LengthMismatch {
+                expected: 10,
+                found: 1000
+            })
+        );
+
+        // A prefix larger than the frames that follow it.
+        let mut short = vec![];
+        encode_varint(2000, &mut short);
+        short.extend_from_slice(&buf[prefix_len..]);
+        assert_eq!(decode_framed(&short, 2000), Err(Error::Truncated));
+    }
+
+    #[test]
+    fn corrupt_frames() {
+        let data = payload(1000);
+        let mut buf = vec![];
+        encode_framed(&data, &mut buf).unwrap();
+        let (_, prefix_len) = decode_varint(&buf).unwrap();
+        let first_chunk = prefix_len + CHUNK_HEADER_LEN + STREAM_IDENTIFIER.len();
+
+        let mut no_identifier = buf[..prefix_len].to_vec();
+        no_identifier.extend_from_slice(&buf[first_chunk..]);
+        assert_eq!(
+            decode_framed(&no_identifier, 1000),
+            Err(Error::MissingStreamIdentifier)
+        );
+
+        let mut bad_checksum = buf.clone();
+        bad_checksum[first_chunk + CHUNK_HEADER_LEN] ^= 1;
+        assert_eq!(
+            decode_framed(&bad_checksum, 1000),
+            Err(Error::ChecksumMismatch)
+        );
+
+        let mut reserved = buf.clone();
+        reserved[first_chunk] = 0x02;
+        assert_eq!(
+            decode_framed(&reserved, 1000),
+            Err(Error::ReservedChunk(0x02))
+        );
+
+        assert_eq!(
+            decode_framed(&buf[..buf.len() - 1], 1000),
+            Err(Error::Truncated)
+        );
+    }
+
+    #[test]
+    fn padding_chunks_are_skipped() {
+        let mut buf = vec![];
+        encode_framed(&[7; 100], &mut buf).unwrap();
+
+        let (_, prefix_len) = decode_varint(&buf).unwrap();
+        let first_chunk = prefix_len + CHUNK_HEADER_LEN + STREAM_IDENTIFIER.len();
+        let mut padded = buf[..first_chunk].to_vec();
+        write_chunk(0xfe, &[0; 5], &mut padded);
+        padded.extend_from_slice(&buf[first_chunk..]);
+
+        assert_eq!(
+            decode_framed(&padded, 100),
+            Ok((vec![7; 100], padded.len()))
+        );
+    }
+
+    #[test]
+    fn typed_decoding() {
+        let list = vec![3_u64; 100];
+        let ssz_bytes = ssz::Encode::as_ssz_bytes(&list);
+
+        let mut buf = vec![];
+        encode_framed(&ssz_bytes, &mut buf).unwrap();
+        assert_eq!(decode_framed_ssz::<Vec<u64>>(&buf, 800), Ok(list.clone()));
+
+        let compressed = compress_block(&ssz_bytes[..ssz_bytes.len() - 1]).unwrap();
+        match decode_block::<Vec<u64>>(&compressed, 800) {
+            Err(Error::Ssz(_)) => {}
+            other => panic!("unexpected result: {:?}", other),
+        }
+    }
+
+    #[test]
+    fn trailing_bytes_are_rejected() {
+        let ssz_bytes = ssz::Encode::as_ssz_bytes(&vec![3_u64; 100]);
+        let mut buf = vec![];
+        encode_framed(&ssz_bytes, &mut buf).unwrap();
+
+        let mut padded = buf.clone();
+        padded.push(0);
+        assert_eq!(
+            decode_framed_ssz::<Vec<u64>>(&padded, 800),
+            Err(Error::TrailingBytes(1))
+        );
+
+        // A second, complete payload is still left over.
+        let mut second = vec![];
+        encode_framed(&[1, 2, 3], &mut second).unwrap();
+        let mut doubled = buf.clone();
+        doubled.extend_from_slice(&second);
+        assert_eq!(
+            decode_framed_ssz::<Vec<u64>>(&doubled, 800),
+            Err(Error::TrailingBytes(second.len()))
+        );
+    }
+
+    #[test]
+    fn varints() {
+        let mut buf = vec![];
+        encode_varint(300, &mut buf);
+        assert_eq!(buf, vec![0xac, 0x02]);
+        assert_eq!(decode_varint(&buf), Ok((300, 2)));
+
+        assert_eq!(decode_varint(&[0x80]), Err(Error::TruncatedVarint));
+        assert_eq!(decode_varint(&[0x81, 0x00]), Err(Error::InvalidVarint));
+        assert_eq!(decode_varint(&[0x80; 11]), Err(Error::InvalidVarint));
+    }
+}
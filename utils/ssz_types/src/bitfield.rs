@@ -1,13 +1,16 @@
 #![allow(clippy::cognitive_complexity)]
 #![allow(clippy::range_plus_one)]
 
-use crate::tree_hash::bitfield_bytes_tree_hash_root;
+use crate::tree_hash::{
+    bitfield_bytes_tree_hash_node, bitfield_bytes_tree_hash_root, bitfield_tree_hash_gindex,
+    list_tree_hash_gindex,
+};
 use crate::Error;
 use core::marker::PhantomData;
 use serde::de::{Deserialize, Deserializer};
 use serde::ser::{Serialize, Serializer};
 use serde_hex::{encode as hex_encode, PrefixedHexVisitor};
-use ssz::{Decode, Encode};
+use ssz::{Decode, DecodeView, Encode};
 use typenum::Unsigned;
 
 /// A marker trait applied to `Variable` and `Fixed` that defines the behaviour of a `Bitfield`.
@@ -194,7 +197,7 @@ impl<N: Unsigned + Clone> Bitfield<Variable<N>> {
             Self::from_raw_bytes(bytes, len)
         } else {
             Err(Error::OutOfBounds {
-                i: Self::max_len(),
+                i: len,
                 len: Self::max_len(),
             })
         }
@@ -474,11 +477,28 @@ impl<'a, T: BitfieldBehaviour> Iterator for BitIter<'a, T> {
     }
 }
 
+impl<N: Unsigned + Clone> ssz::SszSchema for Bitfield<Variable<N>> {
+    fn ssz_schema() -> ssz::Schema {
+        ssz::Schema::Bitlist {
+            limit: N::to_usize(),
+        }
+    }
+}
+
 impl<N: Unsigned + Clone> Encode for Bitfield<Variable<N>> {
     fn is_ssz_fixed_len() -> bool {
         false
     }
 
+    /// An empty `BitList` is encoded as a single byte holding the length bit.
+    fn ssz_min_len() -> usize {
+        1
+    }
+
+    fn ssz_max_len() -> usize {
+        bytes_for_bit_len(N::to_usize() + 1)
+    }
+
     fn ssz_bytes_len(&self) -> usize {
         // We could likely do better than turning this into bytes and reading the length, however
         // it is kept this way for simplicity.
@@ -495,13 +515,51 @@ impl<N: Unsigned + Clone> Decode for Bitfield<Variable<N>> {
         false
     }
 
+    fn ssz_min_len() -> usize {
+        1
+    }
+
+    fn ssz_max_len() -> usize {
+        bytes_for_bit_len(N::to_usize() + 1)
+    }
+
+    /// Checks the length (in bits) against `N`, as marked by the highest set bit of the last byte,
+    /// before copying `bytes`.
     fn from_ssz_bytes(bytes: &[u8]) -> Result<Self, ssz::DecodeError> {
+        let last_byte = match bytes.last() {
+            Some(byte) => *byte,
+            None => {
+                return Err(ssz::DecodeError::InvalidByteLength {
+                    len: 0,
+                    expected: 1,
+                })
+            }
+        };
+
+        if last_byte != 0 {
+            let len = (bytes.len() - 1) * 8 + (7 - last_byte.leading_zeros() as usize);
+            if len > N::to_usize() {
+                return Err(ssz::DecodeError::ListTooLong {
+                    len,
+                    max: N::to_usize(),
+                });
+            }
+        }
+
         Self::from_bytes(bytes.to_vec()).map_err(|e| {
             ssz::DecodeError::BytesInvalid(format!("BitList failed to decode: {:?}", e))
         })
     }
 }
 
+impl<N: Unsigned + Clone> ssz::SszSchema for Bitfield<Fixed<N>> {
+    fn ssz_schema() -> ssz::Schema {
+        ssz::Schema::Bitvector {
+            length: N::to_usize(),
+        }
+    }
+}
+
 impl<N: Unsigned + Clone> Encode for Bitfield<Fixed<N>> {
     fn is_ssz_fixed_len() -> bool {
         true
@@ -536,6 +594,22 @@ impl<N: Unsigned + Clone> Decode for Bitfield<Fixed<N>> {
     }
 }
 
+impl<'a, N: Unsigned + Clone> DecodeView<'a> for Bitfield<Variable<N>> {
+    type View = Self;
+
+    fn view_ssz_bytes(bytes: &'a [u8]) -> Result<Self, ssz::DecodeError> {
+        Self::from_ssz_bytes(bytes)
+    }
+}
+
+impl<'a, N: Unsigned + Clone> DecodeView<'a> for Bitfield<Fixed<N>> {
+    type View = Self;
+
+    fn view_ssz_bytes(bytes: &'a [u8]) -> Result<Self, ssz::DecodeError> {
+        Self::from_ssz_bytes(bytes)
+    }
+}
+
 impl<N: Unsigned + Clone> Serialize for Bitfield<Variable<N>> {
     /// Serde serialization is compliant with the Ethereum YAML test format.
     fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
@@ -593,12 +667,22 @@ impl<N: Unsigned + Clone> tree_hash::TreeHash for Bitfield<Variable<N>> {
         unreachable!("List should never be packed.")
     }
 
-    fn tree_hash_root(&self) -> Vec<u8> {
+    fn tree_hash_root(&self) -> tree_hash::Hash256 {
         // Note: we use `as_slice` because it does _not_ have the length-delimiting bit set (or
         // present).
         let root = bitfield_bytes_tree_hash_root::<N>(self.as_slice());
         tree_hash::mix_in_length(&root, self.len())
     }
+
+    fn tree_hash_gindex(path: &[tree_hash::PathElement]) -> Result<u64, tree_hash::ProofError> {
+        list_tree_hash_gindex(path, bitfield_tree_hash_gindex::<N>)
+    }
+
+    fn tree_hash_node(&self, gindex: u64) -> Option<tree_hash::Hash256> {
+        tree_hash::proof::mix_in_length_node(gindex, self.len(), |data_gindex| {
+            bitfield_bytes_tree_hash_node::<N>(self.as_slice(), data_gindex)
+        })
+    }
 }
 
 impl<N: Unsigned + Clone> tree_hash::TreeHash for Bitfield<Fixed<N>> {
@@ -614,9 +698,17 @@ impl<N: Unsigned + Clone> tree_hash::TreeHash for Bitfield<Fixed<N>> {
         unreachable!("Vector should never be packed.")
     }
 
-    fn tree_hash_root(&self) -> Vec<u8> {
+    fn tree_hash_root(&self) -> tree_hash::Hash256 {
         bitfield_bytes_tree_hash_root::<N>(self.as_slice())
     }
+
+    fn tree_hash_gindex(path: &[tree_hash::PathElement]) -> Result<u64, tree_hash::ProofError> {
+        bitfield_tree_hash_gindex::<N>(path)
+    }
+
+    fn tree_hash_node(&self, gindex: u64) -> Option<tree_hash::Hash256> {
+        bitfield_bytes_tree_hash_node::<N>(self.as_slice(), gindex)
+    }
 }
 
 #[cfg(test)]
@@ -733,6 +825,12 @@ mod bitvector {
         }
     }
 
+    #[test]
+    fn ssz_len_bounds() {
+        assert_eq!(<BitVector16 as Encode>::ssz_min_len(), 2);
+        assert_eq!(<BitVector16 as Decode>::ssz_max_len(), 2);
+    }
+
     #[test]
     fn excess_bits_nimbus() {
         let bad = vec![0b0001_1111];
@@ -823,6 +921,29 @@ mod bitlist {
         assert!(BitList8::from_ssz_bytes(&[0b0000_0001, 0b0000_0100]).is_err());
     }
 
+    #[test]
+    fn ssz_decode_empty() {
+        assert_eq!(
+            BitList8::from_ssz_bytes(&[]),
+            Err(ssz::DecodeError::InvalidByteLength {
+                len: 0,
+                expected: 1
+            })
+        );
+    }
+
+    #[test]
+    fn ssz_decode_too_long() {
+        assert_eq!(
+            BitList1::from_ssz_bytes(&[0b0000_0100]),
+            Err(ssz::DecodeError::ListTooLong { len: 2, max: 1 })
+        );
+        assert_eq!(
+            BitList8::from_ssz_bytes(&[0b0000_0000, 0b0000_0010]),
+            Err(ssz::DecodeError::ListTooLong { len: 9, max: 8 })
+        );
+    }
+
     #[test]
     fn ssz_decode_extra_bytes() {
         assert!(BitList0::from_ssz_bytes(&[0b0000_0001, 0b0000_0000]).is_err());
@@ -1196,4 +1317,56 @@ mod bitlist {
             assert_eq!(bitfield.ssz_bytes_len(), bytes.len(), "i = {}", i);
         }
     }
+
+    #[test]
+    fn ssz_len_bounds() {
+        assert_eq!(<BitList0 as Encode>::ssz_min_len(), 1);
+        assert_eq!(<BitList0 as Encode>::ssz_max_len(), 1);
+        assert_eq!(<BitList8 as Decode>::ssz_max_len(), 2);
+        assert_eq!(<BitList1024 as Decode>::ssz_max_len(), 129);
+
+        assert_eq!(
+            BitList0::with_capacity(0).unwrap().as_ssz_bytes().len(),
+            <BitList0 as Encode>::ssz_min_len()
+        );
+        assert_eq!(
+            BitList16::with_capacity(16).unwrap().as_ssz_bytes().len(),
+            <BitList16 as Encode>::ssz_max_len()
+        );
+    }
+
+    #[test]
+    fn proofs() {
+        use tree_hash::{generalized_index, Proof, ProofError, TreeHash};
+
+        // 1024 bits fill four chunks.
+        assert_eq!(generalized_index::<BitList1024>("[0]"), Ok(8));
+        assert_eq!(generalized_index::<BitList1024>("[1000]"), Ok(11));
+        assert_eq!(generalized_index::<BitList1024>("__len__"), Ok(3));
+        assert_eq!(
+            generalized_index::<BitList1024>("[1024]"),
+            Err(ProofError::IndexOutOfBounds {
+                index: 1024,
+                max: 1024
+            })
+        );
+        assert_eq!(
+            generalized_index::<BitList1024>("[0].a"),
+            Err(ProofError::UnexpectedPathElement(".a".to_string()))
+        );
+
+        let mut bitfield = BitList1024::with_capacity(300).unwrap();
+        bitfield.set(299, true).unwrap();
+        let root = bitfield.tree_hash_root();
+        for gindex in &[1, 2, 3, 4, 5, 8, 9, 10, 11] {
+            assert!(
+                Proof::new(&bitfield, *gindex).unwrap().verify(&root),
+                "{}",
+                gindex
+            );
+        }
+        // There is nothing below the length.
+        assert_eq!(bitfield.tree_hash_node(6), None);
+        assert_eq!(bitfield.tree_hash_node(3), Some(300u64.tree_hash_root()));
+    }
 }
@@ -1,4 +1,4 @@
-use crate::tree_hash::vec_tree_hash_root;
+use crate::tree_hash::{vec_tree_hash_gindex, vec_tree_hash_node, vec_tree_hash_root};
 use crate::Error;
 use serde_derive::{Deserialize, Serialize};
 use std::marker::PhantomData;
@@ -162,9 +162,26 @@ where
         unreachable!("Vector should never be packed.")
     }
 
-    fn tree_hash_root(&self) -> Vec<u8> {
+    fn tree_hash_root(&self) -> tree_hash::Hash256 {
         vec_tree_hash_root::<T, N>(&self.vec)
     }
+
+    fn tree_hash_gindex(path: &[tree_hash::PathElement]) -> Result<u64, tree_hash::ProofError> {
+        vec_tree_hash_gindex::<T, N>(path)
+    }
+
+    fn tree_hash_node(&self, gindex: u64) -> Option<tree_hash::Hash256> {
+        vec_tree_hash_node::<T, N>(&self.vec, gindex)
+    }
+}
+
+impl<T: ssz::SszSchema, N: Unsigned> ssz::SszSchema for FixedVector<T, N> {
+    fn ssz_schema() -> ssz::Schema {
+        ssz::Schema::Vector {
+            element: Box::new(T::ssz_schema()),
+            length: N::to_usize(),
+        }
+    }
 }
 
 impl<T, N: Unsigned> ssz::Encode for FixedVector<T, N>
@@ -183,6 +200,26 @@ where
         }
     }
 
+    fn ssz_min_len() -> usize {
+        if T::is_ssz_fixed_len() {
+            <Self as ssz::Encode>::ssz_fixed_len()
+        } else {
+            ssz::BYTES_PER_LENGTH_OFFSET
+                .saturating_add(T::ssz_min_len())
+                .saturating_mul(N::to_usize())
+        }
+    }
+
+    fn ssz_max_len() -> usize {
+        if T::is_ssz_fixed_len() {
+            <Self as ssz::Encode>::ssz_fixed_len()
+        } else {
+            ssz::BYTES_PER_LENGTH_OFFSET
+                .saturating_add(T::ssz_max_len())
+                .saturating_mul(N::to_usize())
+        }
+    }
+
     fn ssz_bytes_len(&self) -> usize {
         self.vec.ssz_bytes_len()
     }
@@ -204,6 +241,10 @@ where
             encoder.finalize();
         }
     }
+
+    fn ssz_write<W: std::io::Write>(&self, writer: &mut W) -> std::io::Result<()> {
+        self.vec.ssz_write(writer)
+    }
 }
 
 impl<T, N: Unsigned> ssz::Decode for FixedVector<T, N>
@@ -222,30 +263,96 @@ where
         }
     }
 
+    fn ssz_min_len() -> usize {
+        if T::is_ssz_fixed_len() {
+            <Self as ssz::Decode>::ssz_fixed_len()
+        } else {
+            ssz::BYTES_PER_LENGTH_OFFSET
+                .saturating_add(T::ssz_min_len())
+                .saturating_mul(N::to_usize())
+        }
+    }
+
+    fn ssz_max_len() -> usize {
+        if T::is_ssz_fixed_len() {
+            <Self as ssz::Decode>::ssz_fixed_len()
+        } else {
+            ssz::BYTES_PER_LENGTH_OFFSET
+                .saturating_add(T::ssz_max_len())
+                .saturating_mul(N::to_usize())
+        }
+    }
+
+    /// Returns an error for more than `N` items before decoding any of them.
     fn from_ssz_bytes(bytes: &[u8]) -> Result<Self, ssz::DecodeError> {
         if bytes.is_empty() {
             Err(ssz::DecodeError::InvalidByteLength {
                 len: 0,
                 expected: 1,
             })
-        } else if T::is_ssz_fixed_len() {
-            bytes
-                .chunks(T::ssz_fixed_len())
-                .map(|chunk| T::from_ssz_bytes(chunk))
-                .collect::<Result<Vec<T>, _>>()
-                .and_then(|vec| {
-                    if vec.len() == N::to_usize() {
-                        Ok(vec.into())
-                    } else {
-                        Err(ssz::DecodeError::BytesInvalid(format!(
-                            "wrong number of vec elements, got: {}, expected: {}",
-                            vec.len(),
-                            N::to_usize()
-                        )))
-                    }
-                })
         } else {
-            ssz::decode_list_of_variable_length_items(bytes).and_then(|vec| Ok(vec.into()))
+            ssz::decode_list(bytes, Some(N::to_usize())).and_then(from_exact_vec)
+        }
+    }
+
+    fn from_ssz_reader<R: std::io::Read>(
+        reader: &mut R,
+        len: usize,
+    ) -> Result<Self, ssz::DecodeError> {
+        if len == 0 {
+            Err(ssz::DecodeError::InvalidByteLength {
+                len: 0,
+                expected: 1,
+            })
+        } else if T::is_ssz_fixed_len() && len != <Self as ssz::Decode>::ssz_fixed_len() {
+            Err(ssz::DecodeError::InvalidByteLength {
+                len,
+                expected: <Self as ssz::Decode>::ssz_fixed_len(),
+            })
+        } else if len > <Self as ssz::Decode>::ssz_max_len() {
+            Err(ssz::DecodeError::InvalidByteLength {
+                len,
+                expected: <Self as ssz::Decode>::ssz_max_len(),
+            })
+        } else {
+            ssz::read_list(reader, len, Some(N::to_usize())).and_then(from_exact_vec)
+        }
+    }
+}
+
+/// Converts a decoded `vec` into a `FixedVector`, which must have exactly `N` items rather than
+/// being truncated or padded.
+fn from_exact_vec<T: Default, N: Unsigned>(
+    vec: Vec<T>,
+) -> Result<FixedVector<T, N>, ssz::DecodeError> {
+    if vec.len() == N::to_usize() {
+        Ok(vec.into())
+    } else {
+        Err(ssz::DecodeError::BytesInvalid(format!(
+            "wrong number of vec elements, got: {}, expected: {}",
+            vec.len(),
+            N::to_usize()
+        )))
+    }
+}
+
+impl<'a, T, N: Unsigned> ssz::DecodeView<'a> for FixedVector<T, N>
+where
+    T: ssz::DecodeView<'a> + Default,
+{
+    type View = ssz::ListView<'a, T>;
+
+    fn view_ssz_bytes(bytes: &'a [u8]) -> Result<Self::View, ssz::DecodeError> {
+        let view = ssz::ListView::new(bytes)?;
+
+        if view.len() == N::to_usize() {
+            Ok(view)
+        } else {
+            Err(ssz::DecodeError::BytesInvalid(format!(
+                "wrong number of vec elements, got: {}, expected: {}",
+                view.len(),
+                N::to_usize()
+            )))
         }
     }
 }
@@ -253,6 +360,7 @@ where
 #[cfg(test)]
 mod test {
     use super::*;
+    use crate::VariableList;
     use ssz::*;
     use tree_hash::{merkle_root, TreeHash};
     use tree_hash_derive::TreeHash;
@@ -320,9 +428,66 @@ mod test {
         assert_eq!(<FixedVector<u16, U2> as Encode>::ssz_fixed_len(), 4);
     }
 
+    #[test]
+    fn ssz_len_bounds() {
+        assert_eq!(<FixedVector<u16, U8> as Encode>::ssz_min_len(), 16);
+        assert_eq!(<FixedVector<u16, U8> as Encode>::ssz_max_len(), 16);
+
+        type Nested = FixedVector<VariableList<u8, U2>, U3>;
+        assert_eq!(
+            <Nested as Decode>::ssz_min_len(),
+            3 * BYTES_PER_LENGTH_OFFSET
+        );
+        assert_eq!(
+            <Nested as Decode>::ssz_max_len(),
+            3 * (BYTES_PER_LENGTH_OFFSET + 2)
+        );
+
+        let empty: Nested = vec![VariableList::default(); 3].into();
+        assert_eq!(
+            empty.as_ssz_bytes().len(),
+            <Nested as Encode>::ssz_min_len()
+        );
+    }
+
+    #[test]
+    fn ssz_decode_wrong_len() {
+        let bytes = vec![vec![1u8], vec![2], vec![3]].as_ssz_bytes();
+
+        // Neither truncated nor padded.
+        assert_eq!(
+            FixedVector::<Vec<u8>, U2>::from_ssz_bytes(&bytes),
+            Err(DecodeError::ListTooLong { len: 3, max: 2 })
+        );
+        assert!(FixedVector::<Vec<u8>, U4>::from_ssz_bytes(&bytes).is_err());
+        assert!(FixedVector::<Vec<u8>, U4>::from_ssz_reader(&mut &bytes[..], bytes.len()).is_err());
+    }
+
+    #[test]
+    fn view() {
+        let bytes = FixedVector::<u16, U4>::from(vec![1, 2, 3, 4]).as_ssz_bytes();
+
+        let view = FixedVector::<u16, U4>::view_ssz_bytes(&bytes).unwrap();
+        assert_eq!(
+            view.iter().collect::<Result<Vec<_>, _>>(),
+            Ok(vec![1, 2, 3, 4])
+        );
+
+        assert!(FixedVector::<u16, U8>::view_ssz_bytes(&bytes).is_err());
+    }
+
     fn ssz_round_trip<T: Encode + Decode + std::fmt::Debug + PartialEq>(item: T) {
         let encoded = &item.as_ssz_bytes();
         assert_eq!(item.ssz_bytes_len(), encoded.len());
+
+        let mut written = vec![];
+        item.ssz_write(&mut written).unwrap();
+        assert_eq!(&written, encoded);
+        assert_eq!(
+            T::from_ssz_reader(&mut &encoded[..], encoded.len()).as_ref(),
+            Ok(&item)
+        );
+
         assert_eq!(T::from_ssz_bytes(&encoded), Ok(item));
     }
 
@@ -330,6 +495,7 @@ mod test {
     fn ssz_round_trip_u16_len_8() {
         ssz_round_trip::<FixedVector<u16, U8>>(vec![42; 8].into());
         ssz_round_trip::<FixedVector<u16, U8>>(vec![0; 8].into());
+        ssz_round_trip::<FixedVector<Vec<u8>, U2>>(vec![vec![1], vec![2, 3]].into());
     }
 
     #[test]
@@ -375,24 +541,160 @@ mod test {
         assert_eq!(fixed.tree_hash_root(), merkle_root(&[0; 32], 0));
 
         let fixed: FixedVector<A, U1> = FixedVector::from(vec![a]);
-        assert_eq!(fixed.tree_hash_root(), merkle_root(&a.tree_hash_root(), 0));
+        assert_eq!(
+            fixed.tree_hash_root(),
+            merkle_root(a.tree_hash_root().as_bytes(), 0)
+        );
 
         let fixed: FixedVector<A, U8> = FixedVector::from(vec![a; 8]);
         assert_eq!(
             fixed.tree_hash_root(),
-            merkle_root(&repeat(&a.tree_hash_root(), 8), 0)
+            merkle_root(&repeat(a.tree_hash_root().as_bytes(), 8), 0)
         );
 
         let fixed: FixedVector<A, U13> = FixedVector::from(vec![a; 13]);
         assert_eq!(
             fixed.tree_hash_root(),
-            merkle_root(&repeat(&a.tree_hash_root(), 13), 0)
+            merkle_root(&repeat(a.tree_hash_root().as_bytes(), 13), 0)
         );
 
         let fixed: FixedVector<A, U16> = FixedVector::from(vec![a; 16]);
         assert_eq!(
             fixed.tree_hash_root(),
-            merkle_root(&repeat(&a.tree_hash_root(), 16), 0)
+            merkle_root(&repeat(a.tree_hash_root().as_bytes(), 16), 0)
+        );
+    }
+
+    #[derive(Clone, Copy, TreeHash, Default)]
+    struct TupleA(u32, u32);
+
+    #[derive(Clone, Copy, TreeHash, Default)]
+    #[tree_hash(transparent)]
+    struct Wrapped(u8);
+
+    #[test]
+    fn tree_hash_tuple_and_transparent_structs() {
+        let a = A { a: 0, b: 1 };
+        assert_eq!(TupleA(0, 1).tree_hash_root(), a.tree_hash_root());
+
+        let fixed: FixedVector<TupleA, U8> = FixedVector::from(vec![TupleA(0, 1); 8]);
+        assert_eq!(
+            fixed.tree_hash_root(),
+            merkle_root(&repeat(a.tree_hash_root().as_bytes(), 8), 0)
+        );
+
+        // Transparent structs are packed like their field.
+        let source: Vec<u8> = (0..16).collect();
+        let wrapped: FixedVector<Wrapped, U16> =
+            FixedVector::from(source.iter().cloned().map(Wrapped).collect::<Vec<_>>());
+        assert_eq!(wrapped.tree_hash_root(), merkle_root(&source, 0));
+    }
+
+    fn length_root(bytes: &[u8]) -> tree_hash::Hash256 {
+        tree_hash::Hash256::from_low_u64_le(bytes.len() as u64)
+    }
+
+    #[derive(Clone, Default, TreeHash)]
+    #[tree_hash(cached)]
+    struct WithOverride {
+        a: u32,
+        #[tree_hash(with = "length_root")]
+        b: Vec<u8>,
+    }
+
+    #[derive(Clone, Default, TreeHash)]
+    #[tree_hash(cached)]
+    struct Nested {
+        #[tree_hash(cached)]
+        inner: WithOverride,
+        c: u64,
+    }
+
+    #[derive(Clone, Copy, TreeHash, Default)]
+    #[tree_hash(struct_behaviour = "transparent")]
+    struct Behaviour(u8);
+
+    #[test]
+    fn tree_hash_field_overrides() {
+        use tree_hash::{generalized_index, CachedTreeHash, Proof, ProofError};
+
+        let value = WithOverride {
+            a: 1,
+            b: vec![0; 3],
+        };
+        let leaves = [value.a.tree_hash_root(), length_root(&value.b)];
+        assert_eq!(value.new_tree_hash_cache().leaves(), &leaves[..]);
+        assert_eq!(
+            value.tree_hash_root(),
+            merkle_root(&[leaves[0].as_bytes(), leaves[1].as_bytes()].concat(), 0)
+        );
+
+        assert_eq!(generalized_index::<WithOverride>("b"), Ok(3));
+        assert_eq!(
+            generalized_index::<WithOverride>("b[0]"),
+            Err(ProofError::UnexpectedPathElement("[0]".to_string()))
+        );
+        let proof = Proof::new(&value, 3).unwrap();
+        assert_eq!(proof.leaf, length_root(&value.b));
+        assert!(proof.verify(&value.tree_hash_root()));
+
+        let mut cache = WithOverride::default().new_tree_hash_cache();
+        assert_eq!(
+            value.recalculate_tree_hash_root(&mut cache),
+            value.tree_hash_root()
+        );
+
+        assert_eq!(Behaviour(7).tree_hash_root(), 7u8.tree_hash_root());
+    }
+
+    #[test]
+    fn cached_fields_keep_their_caches() {
+        use tree_hash::{CachedTreeHash, TreeHashCache};
+
+        let mut value = Nested::default();
+        let mut cache = value.new_tree_hash_cache();
+        assert_eq!(cache.root(), value.tree_hash_root());
+        assert_eq!(cache.fields(), &[value.inner.new_tree_hash_cache()][..]);
+
+        value.inner.b = vec![0; 5];
+        value.c = 3;
+        assert_eq!(
+            value.recalculate_tree_hash_root(&mut cache),
+            value.tree_hash_root()
+        );
+        assert_eq!(cache, value.new_tree_hash_cache());
+
+        // A cache without field caches is rebuilt.
+        let mut flat = TreeHashCache::new(&[]);
+        assert_eq!(
+            value.recalculate_tree_hash_root(&mut flat),
+            value.tree_hash_root()
+        );
+        assert_eq!(flat, value.new_tree_hash_cache());
+    }
+
+    #[test]
+    fn proofs() {
+        use tree_hash::{generalized_index, Proof, ProofError};
+
+        type Roots = FixedVector<[u8; 32], U3>;
+        let roots = Roots::from(vec![[1; 32], [2; 32], [3; 32]]);
+        let root = roots.tree_hash_root();
+
+        assert_eq!(generalized_index::<Roots>("[2]"), Ok(6));
+        assert_eq!(
+            generalized_index::<Roots>("__len__"),
+            Err(ProofError::UnexpectedPathElement(".__len__".to_string()))
+        );
+        assert_eq!(
+            generalized_index::<Roots>("[2][0]"),
+            Err(ProofError::UnexpectedPathElement("[0]".to_string()))
         );
+
+        let proof = Proof::new(&roots, 6).unwrap();
+        assert_eq!(proof.leaf, tree_hash::Hash256::repeat_byte(3));
+        assert!(proof.verify(&root));
+        assert!(Proof::new(&roots, 7).unwrap().verify(&root));
+        assert!(Proof::new(&roots, 12).is_err());
     }
 }
@@ -0,0 +1,512 @@
+use crate::persistent_tree::{Iter, Tree};
+use crate::tree_hash::{list_tree_hash_gindex, vec_tree_hash_gindex};
+use crate::{Error, VariableList};
+use serde::de::{Deserialize, Deserializer, Error as _};
+use serde::ser::{Serialize, Serializer};
+use std::fmt;
+use std::marker::PhantomData;
+use std::ops::Index;
+use tree_hash::TreeHash;
+use typenum::Unsigned;
+
+/// Emulates a SSZ `List`, like `VariableList`, but keeps its values in a persistent Merkle tree.
+///
+/// Cloning a `PersistentList` is `O(1)`: clones share the nodes of the tree until one of them
+/// changes. Changing a value copies and rehashes only the `O(log N)` nodes above it, so the
+/// `tree_hash_root` of a list that was hashed before is cheap to find again after a few changes.
+///
+/// The SSZ encoding, tree hash and serde representation are the same as those of a
+/// `VariableList<T, N>`.
+///
+/// ## Example
+///
+/// ```
+/// use ssz_types::{PersistentList, typenum};
+///
+/// let base: PersistentList<u64, typenum::U8> = PersistentList::new(vec![1, 2, 3]).unwrap();
+///
+/// // The clone shares all of `base` until it is changed.
+/// let mut long = base.clone();
+/// long.push(4).unwrap();
+/// long.set(0, 42).unwrap();
+///
+/// assert_eq!(base.iter().cloned().collect::<Vec<_>>(), vec![1, 2, 3]);
+/// assert_eq!(long.iter().cloned().collect::<Vec<_>>(), vec![42, 2, 3, 4]);
+///
+/// // Pushing beyond the maximum length is an error.
+/// let mut full: PersistentList<u64, typenum::U1> = PersistentList::new(vec![1]).unwrap();
+/// assert!(full.push(2).is_err());
+/// ```
+pub struct PersistentList<T, N> {
+    tree: Tree<T>,
+    _phantom: PhantomData<N>,
+}
+
+impl<T: TreeHash, N: Unsigned> PersistentList<T, N> {
+    /// Returns `Ok` if the given `vec` has no more than `N` values, otherwise returns `Err`.
+    pub fn new(vec: Vec<T>) -> Result<Self, Error> {
+        if vec.len() <= N::to_usize() {
+            Ok(Self {
+                tree: Tree::new::<N, _>(vec),
+                _phantom: PhantomData,
+            })
+        } else {
+            Err(Error::OutOfBounds {
+                i: vec.len(),
+                len: Self::max_len(),
+            })
+        }
+    }
+
+    /// Create an empty list.
+    pub fn empty() -> Self {
+        Self {
+            tree: Tree::new::<N, _>(vec![]),
+            _phantom: PhantomData,
+        }
+    }
+
+    /// Returns the number of values presently in `self`.
+    pub fn len(&self) -> usize {
+        self.tree.len()
+    }
+
+    /// True if `self` does not contain any values.
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Returns the type-level maximum length.
+    pub fn max_len() -> usize {
+        N::to_usize()
+    }
+
+    /// Returns a reference to the value at `i`, or `None` if `i` is out of bounds.
+    pub fn get(&self, i: usize) -> Option<&T> {
+        self.tree.get(i)
+    }
+
+    /// Returns an iterator over the values, in order.
+    pub fn iter(&self) -> Iter<'_, T> {
+        self.tree.iter()
+    }
+
+    /// Returns a `Vec` holding a copy of each value.
+    pub fn to_vec(&self) -> Vec<T>
+    where
+        T: Clone,
+    {
+        self.iter().cloned().collect()
+    }
+}
+
+impl<T: TreeHash + Clone, N: Unsigned> PersistentList<T, N> {
+    /// Replaces the value at `i`.
+    ///
+    /// Returns `Err` if `i` is out of bounds.
+    pub fn set(&mut self, i: usize, value: T) -> Result<(), Error> {
+        self.update(i, |old| *old = value)
+    }
+
+    /// Calls `f` with a mutable reference to the value at `i`.
+    ///
+    /// Returns `Err` if `i` is out of bounds.
+    pub fn update<F: FnOnce(&mut T)>(&mut self, i: usize, f: F) -> Result<(), Error> {
+        if i < self.len() {
+            self.tree.update(i, f);
+            Ok(())
+        } else {
+            Err(Error::OutOfBounds { i, len: self.len() })
+        }
+    }
+
+    /// Appends `value` to the back of `self`.
+    ///
+    /// Returns `Err` if this would cause `self` to be longer than `N`.
+    pub fn push(&mut self, value: T) -> Result<(), Error> {
+        if self.len() < Self::max_len() {
+            self.tree.push(value);
+            Ok(())
+        } else {
+            Err(Error::OutOfBounds {
+                i: self.len() + 1,
+                len: Self::max_len(),
+            })
+        }
+    }
+}
+
+impl<T, N> Clone for PersistentList<T, N> {
+    fn clone(&self) -> Self {
+        Self {
+            tree: self.tree.clone(),
+            _phantom: PhantomData,
+        }
+    }
+}
+
+impl<T: TreeHash + fmt::Debug, N: Unsigned> fmt::Debug for PersistentList<T, N> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_list().entries(self.iter()).finish()
+    }
+}
+
+impl<T: TreeHash + PartialEq, N: Unsigned> PartialEq for PersistentList<T, N> {
+    fn eq(&self, other: &Self) -> bool {
+        self.len() == other.len() && self.iter().eq(other.iter())
+    }
+}
+
+impl<T: TreeHash + Eq, N: Unsigned> Eq for PersistentList<T, N> {}
+
+impl<T: TreeHash, N: Unsigned> Default for PersistentList<T, N> {
+    fn default() -> Self {
+        Self::empty()
+    }
+}
+
+/// Truncates `vec` to `N` values, like `VariableList::from` does.
+impl<T: TreeHash, N: Unsigned> From<Vec<T>> for PersistentList<T, N> {
+    fn from(mut vec: Vec<T>) -> Self {
+        vec.truncate(N::to_usize());
+
+        Self {
+            tree: Tree::new::<N, _>(vec),
+            _phantom: PhantomData,
+        }
+    }
+}
+
+impl<T: TreeHash, N: Unsigned> From<VariableList<T, N>> for PersistentList<T, N> {
+    fn from(list: VariableList<T, N>) -> Self {
+        let vec: Vec<T> = list.into();
+        vec.into()
+    }
+}
+
+impl<T: TreeHash + Clone, N: Unsigned> From<PersistentList<T, N>> for VariableList<T, N> {
+    fn from(list: PersistentList<T, N>) -> Self {
+        list.to_vec().into()
+    }
+}
+
+impl<T: TreeHash, N: Unsigned> Index<usize> for PersistentList<T, N> {
+    type Output = T;
+
+    fn index(&self, i: usize) -> &T {
+        self.get(i).expect("PersistentList index out of bounds")
+    }
+}
+
+impl<'a, T: TreeHash, N: Unsigned> IntoIterator for &'a PersistentList<T, N> {
+    type Item = &'a T;
+    type IntoIter = Iter<'a, T>;
+
+    fn into_iter(self) -> Iter<'a, T> {
+        self.iter()
+    }
+}
+
+impl<T: TreeHash, N: Unsigned> tree_hash::TreeHash for PersistentList<T, N> {
+    fn tree_hash_type() -> tree_hash::TreeHashType {
+        tree_hash::TreeHashType::List
+    }
+
+    fn tree_hash_packed_encoding(&self) -> Vec<u8> {
+        unreachable!("List should never be packed.")
+    }
+
+    fn tree_hash_packing_factor() -> usize {
+        unreachable!("List should never be packed.")
+    }
+
+    fn tree_hash_root(&self) -> tree_hash::Hash256 {
+        tree_hash::mix_in_length(&self.tree.root(), self.len())
+    }
+
+    fn tree_hash_gindex(path: &[tree_hash::PathElement]) -> Result<u64, tree_hash::ProofError> {
+        list_tree_hash_gindex(path, vec_tree_hash_gindex::<T, N>)
+    }
+
+    fn tree_hash_node(&self, gindex: u64) -> Option<tree_hash::Hash256> {
+        tree_hash::proof::mix_in_length_node(gindex, self.len(), |data_gindex| {
+            self.tree.node(data_gindex)
+        })
+    }
+}
+
+impl<T: ssz::SszSchema, N: Unsigned> ssz::SszSchema for PersistentList<T, N> {
+    fn ssz_schema() -> ssz::Schema {
+        ssz::Schema::List {
+            element: Box::new(T::ssz_schema()),
+            limit: Some(N::to_usize()),
+        }
+    }
+}
+
+impl<T, N: Unsigned> ssz::Encode for PersistentList<T, N>
+where
+    T: ssz::Encode + TreeHash,
+{
+    fn is_ssz_fixed_len() -> bool {
+        <VariableList<T, N> as ssz::Encode>::is_ssz_fixed_len()
+    }
+
+    fn ssz_fixed_len() -> usize {
+        <VariableList<T, N> as ssz::Encode>::ssz_fixed_len()
+    }
+
+    fn ssz_min_len() -> usize {
+        <VariableList<T, N> as ssz::Encode>::ssz_min_len()
+    }
+
+    fn ssz_max_len() -> usize {
+        <VariableList<T, N> as ssz::Encode>::ssz_max_len()
+    }
+
+    fn ssz_bytes_len(&self) -> usize {
+        if T::is_ssz_fixed_len() {
+            T::ssz_fixed_len() * self.len()
+        } else {
+            self.iter()
+                .map(|item| ssz::BYTES_PER_LENGTH_OFFSET + item.ssz_bytes_len())
+                .sum()
+        }
+    }
+
+    fn ssz_append(&self, buf: &mut Vec<u8>) {
+        if T::is_ssz_fixed_len() {
+            buf.reserve(T::ssz_fixed_len() * self.len());
+
+            for item in self {
+                item.ssz_append(buf);
+            }
+        } else {
+            let mut encoder = ssz::SszEncoder::list(buf, self.len() * ssz::BYTES_PER_LENGTH_OFFSET);
+
+            for item in self {
+                encoder.append(item);
+            }
+
+            encoder.finalize();
+        }
+    }
+
+    fn ssz_write<W: std::io::Write>(&self, writer: &mut W) -> std::io::Result<()> {
+        ssz::write_list(self, self.len(), writer)
+    }
+}
+
+impl<T, N: Unsigned> ssz::Decode for PersistentList<T, N>
+where
+    T: ssz::Decode + TreeHash,
+{
+    fn is_ssz_fixed_len() -> bool {
+        <VariableList<T, N> as ssz::Decode>::is_ssz_fixed_len()
+    }
+
+    fn ssz_fixed_len() -> usize {
+        <VariableList<T, N> as ssz::Decode>::ssz_fixed_len()
+    }
+
+    fn ssz_min_len() -> usize {
+        <VariableList<T, N> as ssz::Decode>::ssz_min_len()
+    }
+
+    fn ssz_max_len() -> usize {
+        <VariableList<T, N> as ssz::Decode>::ssz_max_len()
+    }
+
+    fn from_ssz_bytes(bytes: &[u8]) -> Result<Self, ssz::DecodeError> {
+        VariableList::<T, N>::from_ssz_bytes(bytes).map(Into::into)
+    }
+
+    fn from_ssz_reader<R: std::io::Read>(
+        reader: &mut R,
+        len: usize,
+    ) -> Result<Self, ssz::DecodeError> {
+        VariableList::<T, N>::from_ssz_reader(reader, len).map(Into::into)
+    }
+}
+
+impl<'a, T, N: Unsigned> ssz::DecodeView<'a> for PersistentList<T, N>
+where
+    T: ssz::DecodeView<'a> + TreeHash,
+{
+    type View = <VariableList<T, N> as ssz::DecodeView<'a>>::View;
+
+    fn view_ssz_bytes(bytes: &'a [u8]) -> Result<Self::View, ssz::DecodeError> {
+        VariableList::<T, N>::view_ssz_bytes(bytes)
+    }
+}
+
+impl<T: TreeHash + Serialize, N: Unsigned> Serialize for PersistentList<T, N> {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.collect_seq(self.iter())
+    }
+}
+
+impl<'de, T, N: Unsigned> Deserialize<'de> for PersistentList<T, N>
+where
+    T: TreeHash + Deserialize<'de>,
+{
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let vec = Vec::deserialize(deserializer)?;
+        Self::new(vec).map_err(|e| D::Error::custom(format!("PersistentList {:?}", e)))
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use serde::de::value;
+    use ssz::*;
+    use tree_hash::{generalized_index, Proof};
+    use typenum::*;
+
+    #[test]
+    fn new() {
+        assert!(PersistentList::<u64, U4>::new(vec![42; 5]).is_err());
+        assert_eq!(
+            PersistentList::<u64, U4>::new(vec![42; 3]).unwrap().len(),
+            3
+        );
+        assert_eq!(PersistentList::<u64, U4>::new(vec![]).unwrap().len(), 0);
+        assert_eq!(PersistentList::<u64, U4>::from(vec![42; 5]).len(), 4);
+    }
+
+    #[test]
+    fn set_update_and_push() {
+        let mut list: PersistentList<u64, U4> = PersistentList::new(vec![1, 2]).unwrap();
+
+        assert_eq!(list.set(1, 20), Ok(()));
+        assert_eq!(list.update(0, |value| *value += 10), Ok(()));
+        assert_eq!(list.set(2, 3), Err(Error::OutOfBounds { i: 2, len: 2 }));
+        assert_eq!(list.push(3), Ok(()));
+        assert_eq!(list.push(4), Ok(()));
+        assert_eq!(list.push(5), Err(Error::OutOfBounds { i: 5, len: 4 }));
+
+        assert_eq!(list.to_vec(), vec![11, 20, 3, 4]);
+        assert_eq!(list[2], 3);
+        assert_eq!(list.get(4), None);
+    }
+
+    /// A composite value, hashed as a vector.
+    type A = crate::FixedVector<u16, U3>;
+
+    fn a(x: u16, y: u16) -> A {
+        vec![x, y, 0].into()
+    }
+
+    fn check_against_variable_list<T, N>(list: &PersistentList<T, N>)
+    where
+        T: TreeHash + Encode + Decode + Clone + PartialEq + fmt::Debug,
+        N: Unsigned,
+    {
+        let variable_list: VariableList<T, N> = list.clone().into();
+
+        assert_eq!(list.tree_hash_root(), variable_list.tree_hash_root());
+        assert_eq!(list.as_ssz_bytes(), variable_list.as_ssz_bytes());
+        assert_eq!(list.ssz_bytes_len(), variable_list.ssz_bytes_len());
+        let mut written = vec![];
+        list.ssz_write(&mut written).unwrap();
+        assert_eq!(written, list.as_ssz_bytes());
+        assert_eq!(
+            PersistentList::<T, N>::from_ssz_bytes(&list.as_ssz_bytes()).as_ref(),
+            Ok(list)
+        );
+        for gindex in 0..128 {
+            assert_eq!(
+                list.tree_hash_node(gindex),
+                variable_list.tree_hash_node(gindex),
+                "{}",
+                gindex
+            );
+        }
+    }
+
+    #[test]
+    fn same_encoding_and_tree_hash_as_variable_list() {
+        for len in 0..=13 {
+            let values: Vec<u16> = (0..len).collect();
+            check_against_variable_list(&PersistentList::<u16, U13>::new(values).unwrap());
+        }
+
+        let mut list: PersistentList<A, U5> = PersistentList::empty();
+        check_against_variable_list(&list);
+        for i in 0..5 {
+            list.push(a(i, 7)).unwrap();
+            check_against_variable_list(&list);
+        }
+        list.update(3, |a| a[1] = 8).unwrap();
+        check_against_variable_list(&list);
+
+        let mut nested: PersistentList<VariableList<u8, U40>, U3> = PersistentList::empty();
+        check_against_variable_list(&nested);
+        nested.push(vec![1, 2, 3].into()).unwrap();
+        nested.push(vec![4; 40].into()).unwrap();
+        check_against_variable_list(&nested);
+    }
+
+    #[test]
+    fn ssz_write_matches_as_ssz_bytes() {
+        // More than one write batch of fixed-length items.
+        let list: PersistentList<u64, U16384> = (0..10_000).collect::<Vec<_>>().into();
+        let mut written = vec![];
+        list.ssz_write(&mut written).unwrap();
+        assert_eq!(written, list.as_ssz_bytes());
+
+        let nested: PersistentList<VariableList<u8, U40>, U8> =
+            vec![vec![].into(), vec![1, 2].into(), vec![3; 40].into()].into();
+        let mut written = vec![];
+        nested.ssz_write(&mut written).unwrap();
+        assert_eq!(written, nested.as_ssz_bytes());
+    }
+
+    #[test]
+    fn clones_are_independent() {
+        let original: PersistentList<A, U1024> =
+            (0..100).map(|x| a(x, 0)).collect::<Vec<_>>().into();
+        let root = original.tree_hash_root();
+
+        let mut changed = original.clone();
+        changed.update(50, |a| a[1] = 1).unwrap();
+        changed.push(a(0, 0)).unwrap();
+
+        assert_eq!(original.tree_hash_root(), root);
+        assert_eq!(original.len(), 100);
+        assert_eq!(original[50], a(50, 0));
+        assert_eq!(changed[50], a(50, 1));
+        check_against_variable_list(&changed);
+
+        let gindex = generalized_index::<PersistentList<A, U1024>>("[50][1]").unwrap();
+        let proof = Proof::new(&changed, gindex).unwrap();
+        assert!(proof.verify(&changed.tree_hash_root()));
+        assert!(!proof.verify(&root));
+    }
+
+    #[test]
+    fn decode_errors_match_variable_list() {
+        // Five values, one more than fits.
+        let bytes = vec![0; 5 * 6];
+        assert_eq!(
+            PersistentList::<A, U4>::from_ssz_bytes(&bytes),
+            VariableList::<A, U4>::from_ssz_bytes(&bytes).map(Into::into)
+        );
+        assert!(PersistentList::<A, U4>::from_ssz_bytes(&bytes).is_err());
+    }
+
+    #[test]
+    fn deserialize() {
+        fn from_values(values: Vec<u64>) -> Result<PersistentList<u64, U3>, value::Error> {
+            PersistentList::deserialize(value::SeqDeserializer::new(values.into_iter()))
+        }
+
+        assert_eq!(
+            from_values(vec![1, 2, 3]),
+            Ok(PersistentList::from(vec![1, 2, 3]))
+        );
+        assert_eq!(from_values(vec![]), Ok(PersistentList::empty()));
+        assert!(from_values(vec![1, 2, 3, 4]).is_err());
+    }
+}
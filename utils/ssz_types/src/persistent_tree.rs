@@ -0,0 +1,388 @@
+use std::cmp;
+use std::sync::Arc;
+use tree_hash::{get_zero_hash, hash_concat, Hash256, TreeHash, TreeHashType, BYTES_PER_CHUNK};
+use typenum::Unsigned;
+
+/// A node of a `Tree`.
+#[derive(Debug, Clone)]
+enum Node<T> {
+    /// A subtree of the given height that holds only padding.
+    Zero(usize),
+    /// A leaf holding up to `Tree::items_per_leaf` values.
+    Leaf { values: Vec<T>, hash: Hash256 },
+    Internal {
+        left: Arc<Node<T>>,
+        right: Arc<Node<T>>,
+        hash: Hash256,
+    },
+}
+
+impl<T: TreeHash> Node<T> {
+    fn leaf(values: Vec<T>) -> Self {
+        let hash = match T::tree_hash_type() {
+            TreeHashType::Basic => {
+                let mut bytes = Vec::with_capacity(BYTES_PER_CHUNK);
+                for value in &values {
+                    bytes.append(&mut value.tree_hash_packed_encoding());
+                }
+                bytes.resize(BYTES_PER_CHUNK, 0);
+                Hash256::from_slice(&bytes)
+            }
+            TreeHashType::Container | TreeHashType::List | TreeHashType::Vector => {
+                values[0].tree_hash_root()
+            }
+        };
+
+        Node::Leaf { values, hash }
+    }
+
+    fn internal(left: Arc<Node<T>>, right: Arc<Node<T>>) -> Self {
+        let hash = hash_concat(left.hash().as_bytes(), right.hash().as_bytes());
+
+        Node::Internal { left, right, hash }
+    }
+
+    fn hash(&self) -> Hash256 {
+        match self {
+            Node::Zero(height) => Hash256::from_slice(get_zero_hash(*height)),
+            Node::Leaf { hash, .. } | Node::Internal { hash, .. } => *hash,
+        }
+    }
+}
+
+/// A persistent binary Merkle tree holding the values of a `PersistentList` or a
+/// `PersistentVector`, laid out exactly like the tree `TreeHash` builds for a `VariableList` or
+/// `FixedVector` (before any length is mixed in).
+///
+/// Each node stores its hash and is shared between clones of the tree, so cloning is `O(1)`.
+/// Changing a value copies (if shared) and rehashes only the nodes on the path from its leaf to
+/// the root.
+#[derive(Debug)]
+pub struct Tree<T> {
+    root: Arc<Node<T>>,
+    len: usize,
+    capacity: usize,
+    depth: usize,
+}
+
+impl<T> Clone for Tree<T> {
+    fn clone(&self) -> Self {
+        Self {
+            root: Arc::clone(&self.root),
+            len: self.len,
+            capacity: self.capacity,
+            depth: self.depth,
+        }
+    }
+}
+
+impl<T: TreeHash> Tree<T> {
+    /// Returns a tree with room for up to `N` values, holding `values`.
+    ///
+    /// `values` must not yield more than `N` items.
+    pub fn new<N: Unsigned, I: IntoIterator<Item = T>>(values: I) -> Self {
+        let leaf_count = cmp::max(N::to_usize(), 1);
+        let leaf_count = (leaf_count + Self::items_per_leaf() - 1) / Self::items_per_leaf();
+        let depth = leaf_count.next_power_of_two().trailing_zeros() as usize;
+
+        let mut values = values.into_iter().peekable();
+        let mut len = 0;
+        let root = Self::build(&mut values, &mut len, depth);
+        assert!(
+            values.peek().is_none() && len <= N::to_usize(),
+            "Tree::new given too many values"
+        );
+
+        Self {
+            root,
+            len,
+            capacity: N::to_usize(),
+            depth,
+        }
+    }
+
+    /// Builds a subtree of `height` from the next values of `values`, counting them in `len`.
+    fn build<I>(values: &mut std::iter::Peekable<I>, len: &mut usize, height: usize) -> Arc<Node<T>>
+    where
+        I: Iterator<Item = T>,
+    {
+        if values.peek().is_none() {
+            Arc::new(Node::Zero(height))
+        } else if height == 0 {
+            let leaf: Vec<T> = values.take(Self::items_per_leaf()).collect();
+            *len += leaf.len();
+            Arc::new(Node::leaf(leaf))
+        } else {
+            let left = Self::build(values, len, height - 1);
+            let right = Self::build(values, len, height - 1);
+            Arc::new(Node::internal(left, right))
+        }
+    }
+
+    /// The number of values that share a leaf: basic values are packed into chunks.
+    fn items_per_leaf() -> usize {
+        match T::tree_hash_type() {
+            TreeHashType::Basic => T::tree_hash_packing_factor(),
+            TreeHashType::Container | TreeHashType::List | TreeHashType::Vector => 1,
+        }
+    }
+
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    pub fn get(&self, i: usize) -> Option<&T> {
+        if i >= self.len {
+            return None;
+        }
+
+        let leaf_index = i / Self::items_per_leaf();
+        let mut node = &*self.root;
+        for level in (0..self.depth).rev() {
+            node = match node {
+                Node::Internal { left, right, .. } if (leaf_index >> level) & 1 == 0 => left,
+                Node::Internal { right, .. } => right,
+                Node::Zero(_) | Node::Leaf { .. } => return None,
+            };
+        }
+
+        match node {
+            Node::Leaf { values, .. } => values.get(i % Self::items_per_leaf()),
+            Node::Zero(_) | Node::Internal { .. } => None,
+        }
+    }
+
+    /// Returns the root of the tree, which is the `vec_tree_hash_root` of its values.
+    pub fn root(&self) -> Hash256 {
+        self.root.hash()
+    }
+
+    /// Returns the node at `gindex`, like `vec_tree_hash_node` does for a slice of values.
+    pub fn node(&self, gindex: u64) -> Option<Hash256> {
+        if gindex == 0 {
+            return None;
+        }
+
+        let gindex_depth = 63 - gindex.leading_zeros() as usize;
+        let mut node = &*self.root;
+        for step in 0..cmp::min(gindex_depth, self.depth) {
+            let go_right = (gindex >> (gindex_depth - 1 - step)) & 1 == 1;
+            node = match node {
+                Node::Internal { right, .. } if go_right => right,
+                Node::Internal { left, .. } => left,
+                // Padding leaves have nothing below them.
+                Node::Zero(height) if gindex_depth <= self.depth => {
+                    return Some(Hash256::from_slice(get_zero_hash(
+                        height - (gindex_depth - step),
+                    )));
+                }
+                Node::Zero(_) | Node::Leaf { .. } => return None,
+            };
+        }
+
+        if gindex_depth <= self.depth {
+            return Some(node.hash());
+        }
+
+        let below = gindex_depth - self.depth;
+        match (node, T::tree_hash_type()) {
+            (Node::Leaf { values, .. }, TreeHashType::Container)
+            | (Node::Leaf { values, .. }, TreeHashType::List)
+            | (Node::Leaf { values, .. }, TreeHashType::Vector) => {
+                values[0].tree_hash_node((1 << below) | (gindex & ((1 << below) - 1)))
+            }
+            _ => None,
+        }
+    }
+
+    pub fn iter(&self) -> Iter<'_, T> {
+        Iter {
+            stack: vec![&self.root],
+            leaf: [].iter(),
+        }
+    }
+}
+
+impl<T: TreeHash + Clone> Tree<T> {
+    /// Calls `f` with the value at `i`, which must be less than `len`.
+    pub fn update<F: FnOnce(&mut T)>(&mut self, i: usize, f: F) {
+        assert!(i < self.len, "Tree::update index out of bounds");
+
+        let position = i % Self::items_per_leaf();
+        Self::modify(
+            &mut self.root,
+            self.depth,
+            i / Self::items_per_leaf(),
+            |values| f(&mut values[position]),
+        );
+    }
+
+    /// Appends `value`, which there must be room for.
+    pub fn push(&mut self, value: T) {
+        assert!(self.len < self.capacity, "Tree::push beyond capacity");
+
+        Self::modify(
+            &mut self.root,
+            self.depth,
+            self.len / Self::items_per_leaf(),
+            |values| values.push(value),
+        );
+        self.len += 1;
+    }
+
+    /// Applies `f` to the values of leaf `leaf_index` below `node`, which has `height`, copying
+    /// any shared nodes on the way and rehashing every node on the path.
+    fn modify<F>(node: &mut Arc<Node<T>>, height: usize, leaf_index: usize, f: F)
+    where
+        F: FnOnce(&mut Vec<T>),
+    {
+        if let Node::Zero(_) = **node {
+            *node = Arc::new(if height == 0 {
+                Node::Leaf {
+                    values: vec![],
+                    hash: Hash256::zero(),
+                }
+            } else {
+                Node::Internal {
+                    left: Arc::new(Node::Zero(height - 1)),
+                    right: Arc::new(Node::Zero(height - 1)),
+                    hash: Hash256::zero(),
+                }
+            });
+        }
+
+        let node = Arc::make_mut(node);
+        match node {
+            Node::Leaf { values, .. } => {
+                f(values);
+                *node = Node::leaf(std::mem::take(values));
+            }
+            Node::Internal { left, right, hash } => {
+                if (leaf_index >> (height - 1)) & 1 == 0 {
+                    Self::modify(left, height - 1, leaf_index, f);
+                } else {
+                    Self::modify(right, height - 1, leaf_index, f);
+                }
+                *hash = hash_concat(left.hash().as_bytes(), right.hash().as_bytes());
+            }
+            Node::Zero(_) => unreachable!("zero nodes are expanded above"),
+        }
+    }
+}
+
+/// Iterates over the values of a `Tree` in order.
+pub struct Iter<'a, T> {
+    stack: Vec<&'a Node<T>>,
+    leaf: std::slice::Iter<'a, T>,
+}
+
+impl<'a, T> Iterator for Iter<'a, T> {
+    type Item = &'a T;
+
+    fn next(&mut self) -> Option<&'a T> {
+        loop {
+            if let Some(value) = self.leaf.next() {
+                return Some(value);
+            }
+
+            match self.stack.pop()? {
+                Node::Zero(_) => (),
+                Node::Leaf { values, .. } => self.leaf = values.iter(),
+                Node::Internal { left, right, .. } => {
+                    self.stack.push(right);
+                    self.stack.push(left);
+                }
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::tree_hash::{vec_tree_hash_node, vec_tree_hash_root};
+    use typenum::*;
+
+    #[test]
+    fn matches_vec_tree_hash() {
+        fn check<T: TreeHash + Clone + PartialEq + std::fmt::Debug, N: Unsigned>(values: Vec<T>) {
+            let tree = Tree::new::<N, _>(values.clone());
+            assert_eq!(tree.len(), values.len());
+            assert_eq!(tree.iter().cloned().collect::<Vec<_>>(), values);
+            assert_eq!(tree.root(), vec_tree_hash_root::<T, N>(&values));
+            for gindex in 0..64 {
+                assert_eq!(
+                    tree.node(gindex),
+                    vec_tree_hash_node::<T, N>(&values, gindex),
+                    "{}",
+                    gindex
+                );
+            }
+        }
+
+        check::<u64, U0>(vec![]);
+        check::<u64, U1>(vec![1]);
+        check::<u64, U13>(vec![]);
+        check::<u64, U13>((0..5).collect());
+        check::<u64, U13>((0..13).collect());
+        check::<u8, U100>((0..70).collect());
+        check::<[u8; 32], U1>(vec![[1; 32]]);
+        check::<[u8; 32], U7>(vec![[1; 32], [2; 32], [3; 32]]);
+        check::<crate::FixedVector<u16, U17>, U5>(vec![vec![1; 17].into(), vec![2; 17].into()]);
+    }
+
+    #[test]
+    fn copy_on_write() {
+        let mut tree = Tree::new::<U1024, _>(0..100u64);
+        let original = tree.clone();
+
+        tree.update(42, |value| *value = 1000);
+        tree.push(100);
+
+        assert_eq!(original.get(42), Some(&42));
+        assert_eq!(original.get(100), None);
+        assert_eq!(original.len(), 100);
+        assert_eq!(
+            original.root(),
+            vec_tree_hash_root::<u64, U1024>(&(0..100).collect::<Vec<_>>())
+        );
+
+        let mut values: Vec<u64> = (0..101).collect();
+        values[42] = 1000;
+        assert_eq!(tree.get(42), Some(&1000));
+        assert_eq!(tree.len(), 101);
+        assert_eq!(tree.root(), vec_tree_hash_root::<u64, U1024>(&values));
+
+        // Only the nodes on the path from the changed leaves to the root are copied.
+        match (&*tree.root, &*original.root) {
+            (Node::Internal { left: a, .. }, Node::Internal { left: b, .. }) => {
+                assert!(!Arc::ptr_eq(a, b))
+            }
+            _ => panic!("expected internal nodes"),
+        }
+        match (&*tree.root, &*original.root) {
+            (Node::Internal { right: a, .. }, Node::Internal { right: b, .. }) => {
+                assert!(Arc::ptr_eq(a, b))
+            }
+            _ => panic!("expected internal nodes"),
+        }
+    }
+
+    #[test]
+    fn push_to_capacity() {
+        let mut tree = Tree::new::<U5, _>(vec![]);
+        let mut values = vec![];
+        for i in 0..5u16 {
+            tree.push([i as u8; 32]);
+            values.push([i as u8; 32]);
+            assert_eq!(tree.root(), vec_tree_hash_root::<[u8; 32], U5>(&values));
+        }
+    }
+
+    #[test]
+    #[should_panic]
+    fn push_beyond_capacity() {
+        let mut tree = Tree::new::<U3, _>(vec![1u64, 2, 3]);
+        tree.push(4);
+    }
+}
@@ -0,0 +1,445 @@
+use crate::persistent_tree::{Iter, Tree};
+use crate::tree_hash::vec_tree_hash_gindex;
+use crate::{Error, FixedVector};
+use serde::de::{Deserialize, Deserializer, Error as _};
+use serde::ser::{Serialize, Serializer};
+use std::fmt;
+use std::marker::PhantomData;
+use std::ops::Index;
+use tree_hash::TreeHash;
+use typenum::Unsigned;
+
+/// Emulates a SSZ `Vector`, like `FixedVector`, but keeps its values in a persistent Merkle tree.
+///
+/// Clones share the nodes of the tree until one of them changes, see `PersistentList`.
+///
+/// The SSZ encoding, tree hash and serde representation are the same as those of a
+/// `FixedVector<T, N>`.
+///
+/// ## Example
+///
+/// ```
+/// use ssz_types::{PersistentVector, typenum};
+///
+/// let base: PersistentVector<u64, typenum::U4> = PersistentVector::from(vec![1, 2]);
+///
+/// let mut changed = base.clone();
+/// changed.set(3, 4).unwrap();
+///
+/// // Padded with default values up to the fixed length.
+/// assert_eq!(base.to_vec(), vec![1, 2, 0, 0]);
+/// assert_eq!(changed.to_vec(), vec![1, 2, 0, 4]);
+/// ```
+pub struct PersistentVector<T, N> {
+    tree: Tree<T>,
+    _phantom: PhantomData<N>,
+}
+
+impl<T: TreeHash, N: Unsigned> PersistentVector<T, N> {
+    /// Returns `Ok` if the given `vec` has exactly `N` values, otherwise returns `Err`.
+    pub fn new(vec: Vec<T>) -> Result<Self, Error> {
+        if vec.len() == Self::capacity() {
+            Ok(Self {
+                tree: Tree::new::<N, _>(vec),
+                _phantom: PhantomData,
+            })
+        } else {
+            Err(Error::OutOfBounds {
+                i: vec.len(),
+                len: Self::capacity(),
+            })
+        }
+    }
+
+    /// Returns a vector with every value set to `elem`.
+    pub fn from_elem(elem: T) -> Self
+    where
+        T: Clone,
+    {
+        Self {
+            tree: Tree::new::<N, _>(vec![elem; N::to_usize()]),
+            _phantom: PhantomData,
+        }
+    }
+
+    pub fn len(&self) -> usize {
+        self.tree.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    pub fn capacity() -> usize {
+        N::to_usize()
+    }
+
+    /// Returns a reference to the value at `i`, or `None` if `i` is out of bounds.
+    pub fn get(&self, i: usize) -> Option<&T> {
+        self.tree.get(i)
+    }
+
+    /// Returns an iterator over the values, in order.
+    pub fn iter(&self) -> Iter<'_, T> {
+        self.tree.iter()
+    }
+
+    /// Returns a `Vec` holding a copy of each value.
+    pub fn to_vec(&self) -> Vec<T>
+    where
+        T: Clone,
+    {
+        self.iter().cloned().collect()
+    }
+}
+
+impl<T: TreeHash + Clone, N: Unsigned> PersistentVector<T, N> {
+    /// Replaces the value at `i`.
+    ///
+    /// Returns `Err` if `i` is out of bounds.
+    pub fn set(&mut self, i: usize, value: T) -> Result<(), Error> {
+        self.update(i, |old| *old = value)
+    }
+
+    /// Calls `f` with a mutable reference to the value at `i`.
+    ///
+    /// Returns `Err` if `i` is out of bounds.
+    pub fn update<F: FnOnce(&mut T)>(&mut self, i: usize, f: F) -> Result<(), Error> {
+        if i < self.len() {
+            self.tree.update(i, f);
+            Ok(())
+        } else {
+            Err(Error::OutOfBounds { i, len: self.len() })
+        }
+    }
+}
+
+impl<T, N> Clone for PersistentVector<T, N> {
+    fn clone(&self) -> Self {
+        Self {
+            tree: self.tree.clone(),
+            _phantom: PhantomData,
+        }
+    }
+}
+
+impl<T: TreeHash + fmt::Debug, N: Unsigned> fmt::Debug for PersistentVector<T, N> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_list().entries(self.iter()).finish()
+    }
+}
+
+impl<T: TreeHash + PartialEq, N: Unsigned> PartialEq for PersistentVector<T, N> {
+    fn eq(&self, other: &Self) -> bool {
+        self.iter().eq(other.iter())
+    }
+}
+
+impl<T: TreeHash + Eq, N: Unsigned> Eq for PersistentVector<T, N> {}
+
+/// Unlike that of `FixedVector`, the default `PersistentVector` holds `N` default values.
+impl<T: TreeHash + Default, N: Unsigned> Default for PersistentVector<T, N> {
+    fn default() -> Self {
+        Self::from(vec![])
+    }
+}
+
+/// Pads (or truncates) `vec` to `N` values, like `FixedVector::from` does.
+impl<T: TreeHash + Default, N: Unsigned> From<Vec<T>> for PersistentVector<T, N> {
+    fn from(mut vec: Vec<T>) -> Self {
+        vec.resize_with(Self::capacity(), Default::default);
+
+        Self {
+            tree: Tree::new::<N, _>(vec),
+            _phantom: PhantomData,
+        }
+    }
+}
+
+impl<T: TreeHash + Default, N: Unsigned> From<FixedVector<T, N>> for PersistentVector<T, N> {
+    fn from(vector: FixedVector<T, N>) -> Self {
+        let vec: Vec<T> = vector.into();
+        vec.into()
+    }
+}
+
+impl<T: TreeHash + Default + Clone, N: Unsigned> From<PersistentVector<T, N>>
+    for FixedVector<T, N>
+{
+    fn from(vector: PersistentVector<T, N>) -> Self {
+        vector.to_vec().into()
+    }
+}
+
+impl<T: TreeHash, N: Unsigned> Index<usize> for PersistentVector<T, N> {
+    type Output = T;
+
+    fn index(&self, i: usize) -> &T {
+        self.get(i).expect("PersistentVector index out of bounds")
+    }
+}
+
+impl<'a, T: TreeHash, N: Unsigned> IntoIterator for &'a PersistentVector<T, N> {
+    type Item = &'a T;
+    type IntoIter = Iter<'a, T>;
+
+    fn into_iter(self) -> Iter<'a, T> {
+        self.iter()
+    }
+}
+
+impl<T: TreeHash, N: Unsigned> tree_hash::TreeHash for PersistentVector<T, N> {
+    fn tree_hash_type() -> tree_hash::TreeHashType {
+        tree_hash::TreeHashType::Vector
+    }
+
+    fn tree_hash_packed_encoding(&self) -> Vec<u8> {
+        unreachable!("Vector should never be packed.")
+    }
+
+    fn tree_hash_packing_factor() -> usize {
+        unreachable!("Vector should never be packed.")
+    }
+
+    fn tree_hash_root(&self) -> tree_hash::Hash256 {
+        self.tree.root()
+    }
+
+    fn tree_hash_gindex(path: &[tree_hash::PathElement]) -> Result<u64, tree_hash::ProofError> {
+        vec_tree_hash_gindex::<T, N>(path)
+    }
+
+    fn tree_hash_node(&self, gindex: u64) -> Option<tree_hash::Hash256> {
+        self.tree.node(gindex)
+    }
+}
+
+impl<T: ssz::SszSchema, N: Unsigned> ssz::SszSchema for PersistentVector<T, N> {
+    fn ssz_schema() -> ssz::Schema {
+        ssz::Schema::Vector {
+            element: Box::new(T::ssz_schema()),
+            length: N::to_usize(),
+        }
+    }
+}
+
+impl<T, N: Unsigned> ssz::Encode for PersistentVector<T, N>
+where
+    T: ssz::Encode + TreeHash,
+{
+    fn is_ssz_fixed_len() -> bool {
+        <FixedVector<T, N> as ssz::Encode>::is_ssz_fixed_len()
+    }
+
+    fn ssz_fixed_len() -> usize {
+        <FixedVector<T, N> as ssz::Encode>::ssz_fixed_len()
+    }
+
+    fn ssz_min_len() -> usize {
+        <FixedVector<T, N> as ssz::Encode>::ssz_min_len()
+    }
+
+    fn ssz_max_len() -> usize {
+        <FixedVector<T, N> as ssz::Encode>::ssz_max_len()
+    }
+
+    fn ssz_bytes_len(&self) -> usize {
+        if T::is_ssz_fixed_len() {
+            T::ssz_fixed_len() * self.len()
+        } else {
+            self.iter()
+                .map(|item| ssz::BYTES_PER_LENGTH_OFFSET + item.ssz_bytes_len())
+                .sum()
+        }
+    }
+
+    fn ssz_append(&self, buf: &mut Vec<u8>) {
+        if T::is_ssz_fixed_len() {
+            buf.reserve(T::ssz_fixed_len() * self.len());
+
+            for item in self {
+                item.ssz_append(buf);
+            }
+        } else {
+            let mut encoder = ssz::SszEncoder::list(buf, self.len() * ssz::BYTES_PER_LENGTH_OFFSET);
+
+            for item in self {
+                encoder.append(item);
+            }
+
+            encoder.finalize();
+        }
+    }
+
+    fn ssz_write<W: std::io::Write>(&self, writer: &mut W) -> std::io::Result<()> {
+        ssz::write_list(self, self.len(), writer)
+    }
+}
+
+impl<T, N: Unsigned> ssz::Decode for PersistentVector<T, N>
+where
+    T: ssz::Decode + TreeHash + Default,
+{
+    fn is_ssz_fixed_len() -> bool {
+        <FixedVector<T, N> as ssz::Decode>::is_ssz_fixed_len()
+    }
+
+    fn ssz_fixed_len() -> usize {
+        <FixedVector<T, N> as ssz::Decode>::ssz_fixed_len()
+    }
+
+    fn ssz_min_len() -> usize {
+        <FixedVector<T, N> as ssz::Decode>::ssz_min_len()
+    }
+
+    fn ssz_max_len() -> usize {
+        <FixedVector<T, N> as ssz::Decode>::ssz_max_len()
+    }
+
+    fn from_ssz_bytes(bytes: &[u8]) -> Result<Self, ssz::DecodeError> {
+        FixedVector::<T, N>::from_ssz_bytes(bytes).map(Into::into)
+    }
+
+    fn from_ssz_reader<R: std::io::Read>(
+        reader: &mut R,
+        len: usize,
+    ) -> Result<Self, ssz::DecodeError> {
+        FixedVector::<T, N>::from_ssz_reader(reader, len).map(Into::into)
+    }
+}
+
+impl<'a, T, N: Unsigned> ssz::DecodeView<'a> for PersistentVector<T, N>
+where
+    T: ssz::DecodeView<'a> + TreeHash + Default,
+{
+    type View = <FixedVector<T, N> as ssz::DecodeView<'a>>::View;
+
+    fn view_ssz_bytes(bytes: &'a [u8]) -> Result<Self::View, ssz::DecodeError> {
+        FixedVector::<T, N>::view_ssz_bytes(bytes)
+    }
+}
+
+impl<T: TreeHash + Serialize, N: Unsigned> Serialize for PersistentVector<T, N> {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.collect_seq(self.iter())
+    }
+}
+
+impl<'de, T, N: Unsigned> Deserialize<'de> for PersistentVector<T, N>
+where
+    T: TreeHash + Deserialize<'de>,
+{
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let vec = Vec::deserialize(deserializer)?;
+        Self::new(vec).map_err(|e| D::Error::custom(format!("PersistentVector {:?}", e)))
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use serde::de::value;
+    use ssz::*;
+    use typenum::*;
+
+    #[test]
+    fn new() {
+        assert!(PersistentVector::<u64, U4>::new(vec![42; 5]).is_err());
+        assert!(PersistentVector::<u64, U4>::new(vec![42; 3]).is_err());
+        assert_eq!(
+            PersistentVector::<u64, U4>::new(vec![42; 4]).unwrap().len(),
+            4
+        );
+        assert_eq!(
+            PersistentVector::<u64, U4>::from(vec![42; 5]).to_vec(),
+            vec![42; 4]
+        );
+        assert_eq!(PersistentVector::<u64, U4>::default().to_vec(), vec![0; 4]);
+        assert_eq!(
+            PersistentVector::<u64, U4>::from_elem(7).to_vec(),
+            vec![7; 4]
+        );
+    }
+
+    #[test]
+    fn set_and_update() {
+        let mut vector: PersistentVector<u64, U4> = PersistentVector::from(vec![1, 2]);
+
+        assert_eq!(vector.set(3, 4), Ok(()));
+        assert_eq!(vector.update(0, |value| *value += 10), Ok(()));
+        assert_eq!(vector.set(4, 5), Err(Error::OutOfBounds { i: 4, len: 4 }));
+
+        assert_eq!(vector.to_vec(), vec![11, 2, 0, 4]);
+        assert_eq!(vector[3], 4);
+    }
+
+    fn check_against_fixed_vector<T, N>(vector: &PersistentVector<T, N>)
+    where
+        T: TreeHash + Encode + Decode + Default + Clone + PartialEq + fmt::Debug,
+        N: Unsigned,
+    {
+        let fixed_vector: FixedVector<T, N> = vector.clone().into();
+
+        assert_eq!(vector.tree_hash_root(), fixed_vector.tree_hash_root());
+        assert_eq!(vector.as_ssz_bytes(), fixed_vector.as_ssz_bytes());
+        assert_eq!(vector.ssz_bytes_len(), fixed_vector.ssz_bytes_len());
+        let mut written = vec![];
+        vector.ssz_write(&mut written).unwrap();
+        assert_eq!(written, vector.as_ssz_bytes());
+        assert_eq!(
+            PersistentVector::<T, N>::from_ssz_bytes(&vector.as_ssz_bytes()).as_ref(),
+            Ok(vector)
+        );
+        for gindex in 0..64 {
+            assert_eq!(
+                vector.tree_hash_node(gindex),
+                fixed_vector.tree_hash_node(gindex),
+                "{}",
+                gindex
+            );
+        }
+    }
+
+    #[test]
+    fn same_encoding_and_tree_hash_as_fixed_vector() {
+        let mut vector: PersistentVector<u8, U40> = PersistentVector::from(vec![]);
+        check_against_fixed_vector(&vector);
+        vector.set(39, 1).unwrap();
+        check_against_fixed_vector(&vector);
+
+        let mut roots: PersistentVector<[u8; 32], U9> = PersistentVector::from(vec![]);
+        let original = roots.clone();
+        roots.set(8, [8; 32]).unwrap();
+        check_against_fixed_vector(&roots);
+        check_against_fixed_vector(&original);
+        assert_eq!(original[8], [0; 32]);
+
+        let variable: PersistentVector<crate::VariableList<u16, U4>, U3> =
+            PersistentVector::from(vec![vec![1].into(), vec![2, 3].into()]);
+        check_against_fixed_vector(&variable);
+    }
+
+    #[test]
+    fn decode_errors_match_fixed_vector() {
+        for bytes in &[vec![], vec![0; 8 * 3], vec![0; 8 * 5]] {
+            assert_eq!(
+                PersistentVector::<u64, U4>::from_ssz_bytes(bytes),
+                FixedVector::<u64, U4>::from_ssz_bytes(bytes).map(Into::into)
+            );
+        }
+    }
+
+    #[test]
+    fn deserialize() {
+        fn from_values(values: Vec<u64>) -> Result<PersistentVector<u64, U3>, value::Error> {
+            PersistentVector::deserialize(value::SeqDeserializer::new(values.into_iter()))
+        }
+
+        assert_eq!(
+            from_values(vec![1, 2, 3]),
+            Ok(PersistentVector::from(vec![1, 2, 3]))
+        );
+        assert!(from_values(vec![1, 2]).is_err());
+        assert!(from_values(vec![1, 2, 3, 4]).is_err());
+    }
+}
@@ -1,14 +1,17 @@
-use tree_hash::{merkle_root, TreeHash, TreeHashType, BYTES_PER_CHUNK};
+use tree_hash::proof::{concat_generalized_indices, subtree_gindex, subtree_node};
+use tree_hash::{
+    merkle_root, Hash256, PathElement, ProofError, TreeHash, TreeHashType, BYTES_PER_CHUNK,
+};
 use typenum::Unsigned;
 
-/// A helper function providing common functionality between the `TreeHash` implementations for
-/// `FixedVector` and `VariableList`.
-pub fn vec_tree_hash_root<T, N>(vec: &[T]) -> Vec<u8>
+/// Returns the leaves of the tree of a `FixedVector` or `VariableList` holding `vec`, along with
+/// the minimum number of leaves the tree is padded out to.
+fn vec_tree_hash_leaves<T, N>(vec: &[T]) -> (Vec<u8>, usize)
 where
     T: TreeHash,
     N: Unsigned,
 {
-    let (leaves, minimum_chunk_count) = match T::tree_hash_type() {
+    match T::tree_hash_type() {
         TreeHashType::Basic => {
             let mut leaves =
                 Vec::with_capacity((BYTES_PER_CHUNK / T::tree_hash_packing_factor()) * vec.len());
@@ -26,23 +29,139 @@ where
             let mut leaves = Vec::with_capacity(vec.len() * BYTES_PER_CHUNK);
 
             for item in vec {
-                leaves.append(&mut item.tree_hash_root())
+                leaves.extend_from_slice(item.tree_hash_root().as_bytes())
             }
 
             let minimum_chunk_count = N::to_usize();
 
             (leaves, minimum_chunk_count)
         }
-    };
+    }
+}
+
+/// A helper function providing common functionality between the `TreeHash` implementations for
+/// `FixedVector` and `VariableList`.
+pub fn vec_tree_hash_root<T, N>(vec: &[T]) -> Hash256
+where
+    T: TreeHash,
+    N: Unsigned,
+{
+    let (leaves, minimum_chunk_count) = vec_tree_hash_leaves::<T, N>(vec);
 
     merkle_root(&leaves, minimum_chunk_count)
 }
 
+/// Returns the generalized index of `path` in the tree whose root `vec_tree_hash_root::<T, N>`
+/// returns. The path must start with an index below `N`.
+///
+/// Basic values are packed, so the index of one of them is the index of the chunk holding it.
+pub fn vec_tree_hash_gindex<T, N>(path: &[PathElement]) -> Result<u64, ProofError>
+where
+    T: TreeHash,
+    N: Unsigned,
+{
+    let (index, rest) = match path.split_first() {
+        None => return Ok(1),
+        Some((PathElement::Index(index), rest)) => (*index, rest),
+        Some((other, _)) => return Err(ProofError::UnexpectedPathElement(other.to_string())),
+    };
+    if index >= N::to_usize() {
+        return Err(ProofError::IndexOutOfBounds {
+            index,
+            max: N::to_usize(),
+        });
+    }
+
+    let inner = T::tree_hash_gindex(rest)?;
+    match T::tree_hash_type() {
+        TreeHashType::Basic => {
+            let values_per_chunk = T::tree_hash_packing_factor();
+            let chunk_count = (N::to_usize() + values_per_chunk - 1) / values_per_chunk;
+            subtree_gindex(chunk_count, index / values_per_chunk, inner)
+        }
+        TreeHashType::Container | TreeHashType::List | TreeHashType::Vector => {
+            subtree_gindex(N::to_usize(), index, inner)
+        }
+    }
+}
+
+/// Returns the node at `gindex` of the tree whose root `vec_tree_hash_root::<T, N>(vec)` returns.
+pub fn vec_tree_hash_node<T, N>(vec: &[T], gindex: u64) -> Option<Hash256>
+where
+    T: TreeHash,
+    N: Unsigned,
+{
+    let (leaves, minimum_chunk_count) = vec_tree_hash_leaves::<T, N>(vec);
+
+    subtree_node(
+        &leaves,
+        minimum_chunk_count,
+        gindex,
+        |i, inner| match T::tree_hash_type() {
+            TreeHashType::Basic => None,
+            _ => vec[i].tree_hash_node(inner),
+        },
+    )
+}
+
+/// Returns the generalized index of `path` in the tree of a list, whose data has the generalized
+/// index `2` and whose length has the generalized index `3`.
+///
+/// `data_gindex` finds the generalized index of a path in the data tree.
+pub fn list_tree_hash_gindex<F>(path: &[PathElement], data_gindex: F) -> Result<u64, ProofError>
+where
+    F: FnOnce(&[PathElement]) -> Result<u64, ProofError>,
+{
+    match path.split_first() {
+        None => Ok(1),
+        Some((PathElement::Length, [])) => Ok(3),
+        Some((PathElement::Length, rest)) => {
+            Err(ProofError::UnexpectedPathElement(rest[0].to_string()))
+        }
+        Some(_) => concat_generalized_indices(2, data_gindex(path)?),
+    }
+}
+
 /// A helper function providing common functionality for finding the Merkle root of some bytes that
 /// represent a bitfield.
-pub fn bitfield_bytes_tree_hash_root<N: Unsigned>(bytes: &[u8]) -> Vec<u8> {
-    let byte_size = (N::to_usize() + 7) / 8;
-    let minimum_chunk_count = (byte_size + BYTES_PER_CHUNK - 1) / BYTES_PER_CHUNK;
+pub fn bitfield_bytes_tree_hash_root<N: Unsigned>(bytes: &[u8]) -> Hash256 {
+    merkle_root(bytes, bitfield_chunk_count::<N>())
+}
+
+/// Returns the generalized index of `path` in the tree whose root
+/// `bitfield_bytes_tree_hash_root::<N>` returns: the chunk holding the bit at the index the path
+/// consists of.
+pub fn bitfield_tree_hash_gindex<N: Unsigned>(path: &[PathElement]) -> Result<u64, ProofError> {
+    let index = match path.split_first() {
+        None => return Ok(1),
+        Some((PathElement::Index(index), [])) => *index,
+        Some((PathElement::Index(_), rest)) => {
+            return Err(ProofError::UnexpectedPathElement(rest[0].to_string()))
+        }
+        Some((other, _)) => return Err(ProofError::UnexpectedPathElement(other.to_string())),
+    };
+    if index >= N::to_usize() {
+        return Err(ProofError::IndexOutOfBounds {
+            index,
+            max: N::to_usize(),
+        });
+    }
 
-    merkle_root(bytes, minimum_chunk_count)
+    subtree_gindex(
+        bitfield_chunk_count::<N>(),
+        index / (BYTES_PER_CHUNK * 8),
+        1,
+    )
+}
+
+/// Returns the node at `gindex` of the tree whose root `bitfield_bytes_tree_hash_root::<N>(bytes)`
+/// returns.
+pub fn bitfield_bytes_tree_hash_node<N: Unsigned>(bytes: &[u8], gindex: u64) -> Option<Hash256> {
+    subtree_node(bytes, bitfield_chunk_count::<N>(), gindex, |_, _| None)
+}
+
+/// Returns the number of chunks a bitfield of up to `N` bits is packed into.
+fn bitfield_chunk_count<N: Unsigned>() -> usize {
+    let byte_size = (N::to_usize() + 7) / 8;
+    (byte_size + BYTES_PER_CHUNK - 1) / BYTES_PER_CHUNK
 }
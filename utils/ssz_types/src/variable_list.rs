@@ -1,4 +1,6 @@
-use crate::tree_hash::vec_tree_hash_root;
+use crate::tree_hash::{
+    list_tree_hash_gindex, vec_tree_hash_gindex, vec_tree_hash_node, vec_tree_hash_root,
+};
 use crate::Error;
 use serde_derive::{Deserialize, Serialize};
 use std::marker::PhantomData;
@@ -189,11 +191,30 @@ where
         unreachable!("List should never be packed.")
     }
 
-    fn tree_hash_root(&self) -> Vec<u8> {
+    fn tree_hash_root(&self) -> tree_hash::Hash256 {
         let root = vec_tree_hash_root::<T, N>(&self.vec);
 
         tree_hash::mix_in_length(&root, self.len())
     }
+
+    fn tree_hash_gindex(path: &[tree_hash::PathElement]) -> Result<u64, tree_hash::ProofError> {
+        list_tree_hash_gindex(path, vec_tree_hash_gindex::<T, N>)
+    }
+
+    fn tree_hash_node(&self, gindex: u64) -> Option<tree_hash::Hash256> {
+        tree_hash::proof::mix_in_length_node(gindex, self.len(), |data_gindex| {
+            vec_tree_hash_node::<T, N>(&self.vec, data_gindex)
+        })
+    }
+}
+
+impl<T: ssz::SszSchema, N: Unsigned> ssz::SszSchema for VariableList<T, N> {
+    fn ssz_schema() -> ssz::Schema {
+        ssz::Schema::List {
+            element: Box::new(T::ssz_schema()),
+            limit: Some(N::to_usize()),
+        }
+    }
 }
 
 impl<T, N: Unsigned> ssz::Encode for VariableList<T, N>
@@ -208,6 +229,19 @@ where
         <Vec<T>>::ssz_fixed_len()
     }
 
+    fn ssz_min_len() -> usize {
+        0
+    }
+
+    fn ssz_max_len() -> usize {
+        let item_max_len = if T::is_ssz_fixed_len() {
+            T::ssz_fixed_len()
+        } else {
+            ssz::BYTES_PER_LENGTH_OFFSET.saturating_add(T::ssz_max_len())
+        };
+        item_max_len.saturating_mul(N::to_usize())
+    }
+
     fn ssz_bytes_len(&self) -> usize {
         self.vec.ssz_bytes_len()
     }
@@ -215,6 +249,10 @@ where
     fn ssz_append(&self, buf: &mut Vec<u8>) {
         self.vec.ssz_append(buf)
     }
+
+    fn ssz_write<W: std::io::Write>(&self, writer: &mut W) -> std::io::Result<()> {
+        self.vec.ssz_write(writer)
+    }
 }
 
 impl<T, N: Unsigned> ssz::Decode for VariableList<T, N>
@@ -229,10 +267,50 @@ where
         <Vec<T>>::ssz_fixed_len()
     }
 
+    fn ssz_min_len() -> usize {
+        0
+    }
+
+    fn ssz_max_len() -> usize {
+        let item_max_len = if T::is_ssz_fixed_len() {
+            T::ssz_fixed_len()
+        } else {
+            ssz::BYTES_PER_LENGTH_OFFSET.saturating_add(T::ssz_max_len())
+        };
+        item_max_len.saturating_mul(N::to_usize())
+    }
+
+    /// Returns `DecodeError::ListTooLong` for more than `N` items, before decoding any of them.
     fn from_ssz_bytes(bytes: &[u8]) -> Result<Self, ssz::DecodeError> {
-        let vec = <Vec<T>>::from_ssz_bytes(bytes)?;
+        ssz::decode_list(bytes, Some(N::to_usize())).map(Into::into)
+    }
 
-        Self::new(vec).map_err(|e| ssz::DecodeError::BytesInvalid(format!("VariableList {:?}", e)))
+    /// Returns `DecodeError::ListTooLong` for more than `N` items, before reading any of them.
+    fn from_ssz_reader<R: std::io::Read>(
+        reader: &mut R,
+        len: usize,
+    ) -> Result<Self, ssz::DecodeError> {
+        ssz::read_list(reader, len, Some(N::to_usize())).map(Into::into)
+    }
+}
+
+impl<'a, T, N: Unsigned> ssz::DecodeView<'a> for VariableList<T, N>
+where
+    T: ssz::DecodeView<'a>,
+{
+    type View = ssz::ListView<'a, T>;
+
+    fn view_ssz_bytes(bytes: &'a [u8]) -> Result<Self::View, ssz::DecodeError> {
+        let view = ssz::ListView::new(bytes)?;
+
+        if view.len() <= N::to_usize() {
+            Ok(view)
+        } else {
+            Err(ssz::DecodeError::ListTooLong {
+                len: view.len(),
+                max: N::to_usize(),
+            })
+        }
     }
 }
 
@@ -259,6 +337,35 @@ mod test {
         assert!(fixed.is_ok());
     }
 
+    #[test]
+    fn ssz_len_bounds() {
+        assert_eq!(<VariableList<u16, U4> as Encode>::ssz_min_len(), 0);
+        assert_eq!(<VariableList<u16, U4> as Encode>::ssz_max_len(), 8);
+
+        type Nested = VariableList<VariableList<u8, U2>, U3>;
+        assert_eq!(<Nested as Decode>::ssz_min_len(), 0);
+        assert_eq!(
+            <Nested as Decode>::ssz_max_len(),
+            3 * (BYTES_PER_LENGTH_OFFSET + 2)
+        );
+
+        let full: Nested = vec![vec![1, 2].into(); 3].into();
+        assert_eq!(full.as_ssz_bytes().len(), <Nested as Encode>::ssz_max_len());
+    }
+
+    #[test]
+    fn view() {
+        let bytes = vec![1u16, 2, 3, 4, 5].as_ssz_bytes();
+
+        let view = VariableList::<u16, U5>::view_ssz_bytes(&bytes).unwrap();
+        assert_eq!(view.get(4), Some(Ok(5)));
+
+        assert_eq!(
+            VariableList::<u16, U4>::view_ssz_bytes(&bytes).map(|view| view.len()),
+            Err(DecodeError::ListTooLong { len: 5, max: 4 })
+        );
+    }
+
     #[test]
     fn indexing() {
         let vec = vec![1, 2];
@@ -309,6 +416,15 @@ mod test {
     fn round_trip<T: Encode + Decode + std::fmt::Debug + PartialEq>(item: T) {
         let encoded = &item.as_ssz_bytes();
         assert_eq!(item.ssz_bytes_len(), encoded.len());
+
+        let mut written = vec![];
+        item.ssz_write(&mut written).unwrap();
+        assert_eq!(&written, encoded);
+        assert_eq!(
+            T::from_ssz_reader(&mut &encoded[..], encoded.len()).as_ref(),
+            Ok(&item)
+        );
+
         assert_eq!(T::from_ssz_bytes(&encoded), Ok(item));
     }
 
@@ -318,7 +434,39 @@ mod test {
         round_trip::<VariableList<u16, U8>>(vec![0; 8].into());
     }
 
-    fn root_with_length(bytes: &[u8], len: usize) -> Vec<u8> {
+    #[test]
+    fn too_many_items() {
+        let bytes = vec![vec![0u8]; 3].as_ssz_bytes();
+
+        assert_eq!(
+            VariableList::<Vec<u8>, U2>::from_ssz_bytes(&bytes),
+            Err(DecodeError::ListTooLong { len: 3, max: 2 })
+        );
+        assert_eq!(
+            VariableList::<Vec<u8>, U2>::from_ssz_reader(&mut &bytes[..], bytes.len()),
+            Err(DecodeError::ListTooLong { len: 3, max: 2 })
+        );
+
+        let bytes = vec![0u16; 3].as_ssz_bytes();
+        assert_eq!(
+            VariableList::<u16, U2>::from_ssz_bytes(&bytes),
+            Err(DecodeError::ListTooLong { len: 3, max: 2 })
+        );
+    }
+
+    #[test]
+    fn oversized_stream_is_not_read() {
+        let bytes = vec![0u16; 3].as_ssz_bytes();
+        let mut reader = &bytes[..];
+
+        assert_eq!(
+            VariableList::<u16, U2>::from_ssz_reader(&mut reader, bytes.len()),
+            Err(DecodeError::ListTooLong { len: 3, max: 2 })
+        );
+        assert_eq!(reader.len(), bytes.len());
+    }
+
+    fn root_with_length(bytes: &[u8], len: usize) -> tree_hash::Hash256 {
         let root = merkle_root(bytes, 0);
         tree_hash::mix_in_length(&root, len)
     }
@@ -369,7 +517,7 @@ mod test {
         output
     }
 
-    fn padded_root_with_length(bytes: &[u8], len: usize, min_nodes: usize) -> Vec<u8> {
+    fn padded_root_with_length(bytes: &[u8], len: usize, min_nodes: usize) -> tree_hash::Hash256 {
         let root = merkle_root(bytes, min_nodes);
         tree_hash::mix_in_length(&root, len)
     }
@@ -388,7 +536,7 @@ mod test {
             let fixed: VariableList<A, U1> = VariableList::from(vec![a; i]);
             assert_eq!(
                 fixed.tree_hash_root(),
-                padded_root_with_length(&repeat(&a.tree_hash_root(), i), i, 1),
+                padded_root_with_length(&repeat(a.tree_hash_root().as_bytes(), i), i, 1),
                 "U1 {}",
                 i
             );
@@ -398,7 +546,7 @@ mod test {
             let fixed: VariableList<A, U8> = VariableList::from(vec![a; i]);
             assert_eq!(
                 fixed.tree_hash_root(),
-                padded_root_with_length(&repeat(&a.tree_hash_root(), i), i, 8),
+                padded_root_with_length(&repeat(a.tree_hash_root().as_bytes(), i), i, 8),
                 "U8 {}",
                 i
             );
@@ -408,7 +556,7 @@ mod test {
             let fixed: VariableList<A, U13> = VariableList::from(vec![a; i]);
             assert_eq!(
                 fixed.tree_hash_root(),
-                padded_root_with_length(&repeat(&a.tree_hash_root(), i), i, 13),
+                padded_root_with_length(&repeat(a.tree_hash_root().as_bytes(), i), i, 13),
                 "U13 {}",
                 i
             );
@@ -418,10 +566,63 @@ mod test {
             let fixed: VariableList<A, U16> = VariableList::from(vec![a; i]);
             assert_eq!(
                 fixed.tree_hash_root(),
-                padded_root_with_length(&repeat(&a.tree_hash_root(), i), i, 16),
+                padded_root_with_length(&repeat(a.tree_hash_root().as_bytes(), i), i, 16),
                 "U16 {}",
                 i
             );
         }
     }
+
+    #[test]
+    fn proofs() {
+        use tree_hash::{generalized_index, MultiProof, Proof, ProofError};
+
+        type List = VariableList<A, U5>;
+        let list = List::from(vec![A { a: 1, b: 2 }, A { a: 3, b: 4 }, A { a: 5, b: 6 }]);
+        let root = list.tree_hash_root();
+
+        // The data root is at 2 and the length at 3. The data tree has 8 leaves and each `A` has
+        // two leaves below its own.
+        assert_eq!(generalized_index::<List>("__len__"), Ok(3));
+        assert_eq!(generalized_index::<List>("[1]"), Ok(17));
+        assert_eq!(generalized_index::<List>("[1].b"), Ok(35));
+        assert_eq!(
+            generalized_index::<List>("[5]"),
+            Err(ProofError::IndexOutOfBounds { index: 5, max: 5 })
+        );
+        assert_eq!(
+            generalized_index::<List>("[1].c"),
+            Err(ProofError::UnknownField("c".to_string()))
+        );
+
+        assert_eq!(list.tree_hash_node(1), Some(root));
+        assert_eq!(list.tree_hash_node(3), Some(3u64.tree_hash_root()));
+        assert_eq!(list.tree_hash_node(35), Some(4u32.tree_hash_root()));
+        // Beyond the length of the list there are only padding leaves.
+        assert_eq!(list.tree_hash_node(19), Some(tree_hash::Hash256::zero()));
+        assert_eq!(list.tree_hash_node(39), None);
+
+        for gindex in &[1, 2, 3, 17, 35, 19] {
+            assert!(
+                Proof::new(&list, *gindex).unwrap().verify(&root),
+                "{}",
+                gindex
+            );
+        }
+        let proof = MultiProof::new(&list, &[3, 33, 35]).unwrap();
+        assert!(proof.verify(&root));
+        assert!(!proof.verify(&List::from(vec![]).tree_hash_root()));
+
+        // Packed values share a chunk.
+        type Packed = VariableList<u64, U8>;
+        let packed = Packed::from(vec![7; 6]);
+        assert_eq!(generalized_index::<Packed>("[3]"), Ok(4));
+        assert_eq!(generalized_index::<Packed>("[4]"), Ok(5));
+        let proof = Proof::new(&packed, 5).unwrap();
+        assert!(proof.verify(&packed.tree_hash_root()));
+        assert_eq!(
+            proof.leaf,
+            merkle_root(&[7u64.to_le_bytes(), 7u64.to_le_bytes()].concat(), 0)
+        );
+    }
 }
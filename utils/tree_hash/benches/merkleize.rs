@@ -0,0 +1,42 @@
+use criterion::Criterion;
+use criterion::{black_box, criterion_group, criterion_main, Benchmark};
+use tree_hash::{merkleize_padded, merkleize_parallel, BYTES_PER_CHUNK};
+
+/// Leaf sets shaped like the largest lists and vectors of a mainnet `BeaconState` with 300,000
+/// validators: `(name, leaf chunks, minimum leaves)`.
+const LEAF_SETS: &[(&str, usize, usize)] = &[
+    ("validators", 300_000, 1 << 40),
+    ("balances", 300_000 / 4, 1 << 38),
+    ("randao_mixes", 1 << 16, 1 << 16),
+];
+
+fn bench_leaf_set(c: &mut Criterion, name: &str, chunks: usize, min_leaves: usize) {
+    let bytes: Vec<u8> = (0..chunks * BYTES_PER_CHUNK).map(|i| i as u8).collect();
+
+    assert_eq!(
+        merkleize_parallel(&bytes, min_leaves),
+        merkleize_padded(&bytes, min_leaves),
+        "merkleize_parallel must match merkleize_padded"
+    );
+
+    let parallel_bytes = bytes.clone();
+    c.bench(
+        &format!("merkleize/{}", name),
+        Benchmark::new("padded", move |b| {
+            b.iter(|| black_box(merkleize_padded(&bytes, min_leaves)))
+        })
+        .with_function("parallel", move |b| {
+            b.iter(|| black_box(merkleize_parallel(&parallel_bytes, min_leaves)))
+        })
+        .sample_size(10),
+    );
+}
+
+fn all_benches(c: &mut Criterion) {
+    for (name, chunks, min_leaves) in LEAF_SETS {
+        bench_leaf_set(c, name, *chunks, *min_leaves);
+    }
+}
+
+criterion_group!(benches, all_benches,);
+criterion_main!(benches);
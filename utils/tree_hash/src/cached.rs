@@ -0,0 +1,193 @@
+//! Incremental tree hashing for containers, which keeps the nodes of the tree over a container's
+//! field roots so that only the nodes above roots that changed are rehashed.
+//!
+//! A field marked `#[tree_hash(cached)]` keeps a cache of its own, so its root is recalculated
+//! rather than recomputed. Every other field root comes from `tree_hash_root`, which is cheap for
+//! basic values, small containers and `PersistentList`s and `PersistentVector`s, which keep their
+//! trees hashed as they are modified.
+use crate::{hash_concat, Hash256, TreeHash};
+use std::cmp;
+
+/// A `TreeHash` type whose root can be recomputed with a `TreeHashCache`.
+///
+/// Implemented by `#[derive(TreeHash)]` on containers with the `#[tree_hash(cached)]` attribute.
+pub trait CachedTreeHash: TreeHash {
+    /// Returns a cache holding the tree of `self`, and the caches of its cached fields.
+    fn new_tree_hash_cache(&self) -> TreeHashCache;
+
+    /// Returns the root of `self`, recalculating the roots of cached fields with their own caches
+    /// and rehashing only the parts of the tree in `cache` above field roots that changed since it
+    /// was last updated.
+    ///
+    /// Equal to `tree_hash_root`, no matter which value of the type the cache was built from.
+    fn recalculate_tree_hash_root(&self, cache: &mut TreeHashCache) -> Hash256;
+}
+
+/// Every node of a Merkle tree, padded with zero leaves to a power of two, as `merkle_root` builds
+/// it.
+#[derive(Debug, PartialEq, Clone)]
+pub struct TreeHashCache {
+    /// The number of leaves, before padding.
+    len: usize,
+    /// The nodes at each height of the tree, starting with the padded leaves and ending with the
+    /// root.
+    layers: Vec<Vec<Hash256>>,
+    /// The caches of the fields whose roots are leaves of the tree, for those that have one.
+    fields: Vec<TreeHashCache>,
+}
+
+impl TreeHashCache {
+    /// Builds the tree over `leaves`.
+    pub fn new(leaves: &[Hash256]) -> Self {
+        Self::with_fields(leaves, vec![])
+    }
+
+    /// Builds the tree over `leaves`, keeping the caches `fields` that some of them were computed
+    /// with.
+    pub fn with_fields(leaves: &[Hash256], fields: Vec<TreeHashCache>) -> Self {
+        let mut layer = leaves.to_vec();
+        layer.resize(
+            cmp::max(leaves.len(), 1).next_power_of_two(),
+            Hash256::zero(),
+        );
+
+        let mut layers = vec![layer];
+        while layers[layers.len() - 1].len() > 1 {
+            let parents = layers[layers.len() - 1]
+                .chunks(2)
+                .map(|pair| hash_concat(pair[0].as_bytes(), pair[1].as_bytes()))
+                .collect();
+            layers.push(parents);
+        }
+
+        Self {
+            len: leaves.len(),
+            layers,
+            fields,
+        }
+    }
+
+    /// Returns the number of leaves, before padding.
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    /// Returns `true` if the tree has no leaves.
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    /// Returns the root of the tree.
+    pub fn root(&self) -> Hash256 {
+        self.layers[self.layers.len() - 1][0]
+    }
+
+    /// Returns the leaves, before padding.
+    pub fn leaves(&self) -> &[Hash256] {
+        &self.layers[0][..self.len]
+    }
+
+    /// Returns the caches of the fields, in the order they were given.
+    pub fn fields(&self) -> &[TreeHashCache] {
+        &self.fields
+    }
+
+    /// Returns the caches of the fields mutably, to recalculate their roots before `update`.
+    pub fn fields_mut(&mut self) -> &mut [TreeHashCache] {
+        &mut self.fields
+    }
+
+    /// Replaces the leaves with `leaves` and returns the new root, rehashing only the nodes above
+    /// leaves that changed.
+    ///
+    /// The tree is rebuilt if the number of leaves changed. The caches of the fields are kept.
+    pub fn update(&mut self, leaves: &[Hash256]) -> Hash256 {
+        if leaves.len() != self.len {
+            let Self { len, layers, .. } = Self::new(leaves);
+            self.len = len;
+            self.layers = layers;
+            return self.root();
+        }
+
+        let mut dirty: Vec<usize> = leaves
+            .iter()
+            .zip(self.layers[0].iter())
+            .enumerate()
+            .filter(|(_, (new, old))| new != old)
+            .map(|(i, _)| i)
+            .collect();
+
+        for &i in &dirty {
+            self.layers[0][i] = leaves[i];
+        }
+
+        for height in 1..self.layers.len() {
+            // `dirty` is sorted, so each parent is rehashed once.
+            for i in dirty.iter_mut() {
+                *i /= 2;
+            }
+            dirty.dedup();
+
+            let (children, parents) = self.layers.split_at_mut(height);
+            let children = &children[height - 1];
+            for &i in &dirty {
+                parents[0][i] =
+                    hash_concat(children[2 * i].as_bytes(), children[2 * i + 1].as_bytes());
+            }
+        }
+
+        self.root()
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::merkle_root;
+
+    fn leaves(len: usize, seed: u8) -> Vec<Hash256> {
+        (0..len)
+            .map(|i| Hash256::repeat_byte(seed.wrapping_add(i as u8)))
+            .collect()
+    }
+
+    fn reference_root(leaves: &[Hash256]) -> Hash256 {
+        let bytes: Vec<u8> = leaves
+            .iter()
+            .flat_map(|leaf| leaf.as_bytes().to_vec())
+            .collect();
+        merkle_root(&bytes, 0)
+    }
+
+    #[test]
+    fn new_matches_merkle_root() {
+        for len in 0..20 {
+            let leaves = leaves(len, 1);
+            let cache = TreeHashCache::new(&leaves);
+
+            assert_eq!(cache.len(), len);
+            assert_eq!(cache.leaves(), &leaves[..]);
+            assert_eq!(cache.root(), reference_root(&leaves), "len: {}", len);
+        }
+    }
+
+    #[test]
+    fn update_matches_merkle_root() {
+        let mut current = leaves(13, 1);
+        let mut cache = TreeHashCache::new(&current);
+
+        for i in 0..13 {
+            current[i] = Hash256::repeat_byte(200 + i as u8);
+            current[12 - i] = Hash256::zero();
+
+            assert_eq!(cache.update(&current), reference_root(&current), "i: {}", i);
+            assert_eq!(cache, TreeHashCache::new(&current));
+        }
+
+        let mut cache = TreeHashCache::with_fields(&current, vec![TreeHashCache::new(&current)]);
+        let longer = leaves(17, 9);
+        assert_eq!(cache.update(&longer), reference_root(&longer));
+        assert_eq!(cache.len(), 17);
+        assert_eq!(cache.fields(), &[TreeHashCache::new(&current)][..]);
+    }
+}
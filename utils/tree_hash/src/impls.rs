@@ -17,8 +17,8 @@ macro_rules! impl_for_bitsize {
             }
 
             #[allow(clippy::cast_lossless)]
-            fn tree_hash_root(&self) -> Vec<u8> {
-                int_to_bytes32(*self as u64)
+            fn tree_hash_root(&self) -> Hash256 {
+                Hash256::from(int_to_bytes32(*self as u64))
             }
         }
     };
@@ -43,8 +43,8 @@ impl TreeHash for bool {
         u8::tree_hash_packing_factor()
     }
 
-    fn tree_hash_root(&self) -> Vec<u8> {
-        int_to_bytes32(*self as u64)
+    fn tree_hash_root(&self) -> Hash256 {
+        Hash256::from(int_to_bytes32(*self as u64))
     }
 }
 
@@ -63,7 +63,7 @@ macro_rules! impl_for_u8_array {
                 unreachable!("bytesN should never be packed.")
             }
 
-            fn tree_hash_root(&self) -> Vec<u8> {
+            fn tree_hash_root(&self) -> Hash256 {
                 merkle_root(&self[..], 0)
             }
         }
@@ -88,7 +88,7 @@ impl TreeHash for U128 {
         2
     }
 
-    fn tree_hash_root(&self) -> Vec<u8> {
+    fn tree_hash_root(&self) -> Hash256 {
         merkle_root(&self.tree_hash_packed_encoding(), 0)
     }
 }
@@ -108,7 +108,7 @@ impl TreeHash for U256 {
         1
     }
 
-    fn tree_hash_root(&self) -> Vec<u8> {
+    fn tree_hash_root(&self) -> Hash256 {
         merkle_root(&self.tree_hash_packed_encoding(), 0)
     }
 }
@@ -126,8 +126,8 @@ impl TreeHash for H256 {
         1
     }
 
-    fn tree_hash_root(&self) -> Vec<u8> {
-        merkle_root(&self.as_bytes().to_vec(), 0)
+    fn tree_hash_root(&self) -> Hash256 {
+        *self
     }
 }
 
@@ -151,7 +151,7 @@ macro_rules! impl_for_list {
                 unimplemented!("TreeHash is not implemented for Vec or slice")
             }
 
-            fn tree_hash_root(&self) -> Vec<u8> {
+            fn tree_hash_root(&self) -> Hash256 {
                 unimplemented!("TreeHash is not implemented for Vec or slice")
             }
         }
@@ -162,10 +162,10 @@ impl_for_list!(Vec<T>);
 impl_for_list!(&[T]);
 
 /// Returns `int` as little-endian bytes with a length of 32.
-fn int_to_bytes32(int: u64) -> Vec<u8> {
-    let mut vec = int.to_le_bytes().to_vec();
-    vec.resize(32, 0);
-    vec
+fn int_to_bytes32(int: u64) -> [u8; 32] {
+    let mut bytes = [0; 32];
+    bytes[..8].copy_from_slice(&int.to_le_bytes());
+    bytes
 }
 
 #[cfg(test)]
@@ -179,8 +179,8 @@ mod test {
 
         let false_bytes: Vec<u8> = vec![0; 32];
 
-        assert_eq!(true.tree_hash_root(), true_bytes);
-        assert_eq!(false.tree_hash_root(), false_bytes);
+        assert_eq!(true.tree_hash_root().as_bytes(), &true_bytes[..]);
+        assert_eq!(false.tree_hash_root().as_bytes(), &false_bytes[..]);
     }
 
     #[test]
@@ -10,33 +10,46 @@
 #[macro_use]
 extern crate lazy_static;
 
+mod cached;
 pub mod impls;
 mod merkleize_padded;
+mod merkleize_parallel;
 mod merkleize_standard;
+pub mod proof;
 
-pub use merkleize_padded::merkleize_padded;
+pub use cached::{CachedTreeHash, TreeHashCache};
+pub use merkleize_padded::{get_zero_hash, hash_concat, merkleize_padded};
+pub use merkleize_parallel::{merkleize_parallel, PARALLEL_THRESHOLD};
 pub use merkleize_standard::merkleize_standard;
+pub use proof::{generalized_index, MultiProof, PathElement, Proof, ProofError};
+
+/// The type of every root and node of a Merkle tree.
+pub type Hash256 = ethereum_types::H256;
 
 pub const BYTES_PER_CHUNK: usize = 32;
 pub const HASHSIZE: usize = 32;
 pub const MERKLE_HASH_CHUNK: usize = 2 * BYTES_PER_CHUNK;
 
-/// Alias to `merkleize_padded(&bytes, minimum_chunk_count)`
+/// Alias to `merkleize_parallel(&bytes, minimum_chunk_count)`, which is `merkleize_padded` for all
+/// but large inputs.
 ///
 /// If `minimum_chunk_count < bytes / BYTES_PER_CHUNK`, padding will be added for the difference
 /// between the two.
-pub fn merkle_root(bytes: &[u8], minimum_chunk_count: usize) -> Vec<u8> {
-    merkleize_padded(&bytes, minimum_chunk_count)
+pub fn merkle_root(bytes: &[u8], minimum_chunk_count: usize) -> Hash256 {
+    merkleize_parallel(&bytes, minimum_chunk_count)
 }
 
 /// Returns the node created by hashing `root` and `length`.
 ///
 /// Used in `TreeHash` for inserting the length of a list above it's root.
-pub fn mix_in_length(root: &[u8], length: usize) -> Vec<u8> {
-    let mut length_bytes = length.to_le_bytes().to_vec();
-    length_bytes.resize(BYTES_PER_CHUNK, 0);
-
-    merkleize_padded::hash_concat(root, &length_bytes)
+pub fn mix_in_length(root: &Hash256, length: usize) -> Hash256 {
+    let mut length_bytes = [0; BYTES_PER_CHUNK];
+    length_bytes[..8].copy_from_slice(&(length as u64).to_le_bytes());
+
+    Hash256::from(eth2_hashing::hash32_concat(
+        root.as_fixed_bytes(),
+        &length_bytes,
+    ))
 }
 
 #[derive(Debug, PartialEq, Clone)]
@@ -54,11 +67,32 @@ pub trait TreeHash {
 
     fn tree_hash_packing_factor() -> usize;
 
-    fn tree_hash_root(&self) -> Vec<u8>;
+    fn tree_hash_root(&self) -> Hash256;
+
+    /// Returns the generalized index of the node `path` leads to in the tree of `Self`.
+    ///
+    /// The default implementation is for types whose tree has no addressable nodes below the root.
+    fn tree_hash_gindex(path: &[PathElement]) -> Result<u64, ProofError> {
+        match path.first() {
+            None => Ok(1),
+            Some(element) => Err(ProofError::UnexpectedPathElement(element.to_string())),
+        }
+    }
+
+    /// Returns the node at `gindex` of the tree of `self`, or `None` if there is no such node.
+    ///
+    /// The default implementation only knows the root.
+    fn tree_hash_node(&self, gindex: u64) -> Option<Hash256> {
+        if gindex == 1 {
+            Some(self.tree_hash_root())
+        } else {
+            None
+        }
+    }
 }
 
 pub trait SignedRoot: TreeHash {
-    fn signed_root(&self) -> Vec<u8>;
+    fn signed_root(&self) -> Hash256;
 }
 
 #[macro_export]
@@ -77,7 +111,7 @@ macro_rules! tree_hash_ssz_encoding_as_vector {
                 unreachable!("Vector should never be packed.")
             }
 
-            fn tree_hash_root(&self) -> Vec<u8> {
+            fn tree_hash_root(&self) -> tree_hash::Hash256 {
                 tree_hash::merkle_root(&ssz::ssz_encode(self))
             }
         }
@@ -100,7 +134,7 @@ macro_rules! tree_hash_ssz_encoding_as_list {
                 unreachable!("List should never be packed.")
             }
 
-            fn tree_hash_root(&self) -> Vec<u8> {
+            fn tree_hash_root(&self) -> tree_hash::Hash256 {
                 ssz::ssz_encode(self).tree_hash_root()
             }
         }
@@ -117,9 +151,9 @@ mod test {
             let mut preimage = vec![42; BYTES_PER_CHUNK];
             preimage.append(&mut vec![42]);
             preimage.append(&mut vec![0; BYTES_PER_CHUNK - 1]);
-            eth2_hashing::hash(&preimage)
+            Hash256::from_slice(&eth2_hashing::hash(&preimage))
         };
 
-        assert_eq!(mix_in_length(&[42; BYTES_PER_CHUNK], 42), hash);
+        assert_eq!(mix_in_length(&Hash256::repeat_byte(42), 42), hash);
     }
 }
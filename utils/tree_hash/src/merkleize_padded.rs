@@ -1,5 +1,5 @@
-use super::BYTES_PER_CHUNK;
-use eth2_hashing::hash;
+use super::{Hash256, BYTES_PER_CHUNK};
+use eth2_hashing::{hash32_concat, hash_64_batch, Context};
 
 /// The size of the cache that stores padding nodes for a given height.
 ///
@@ -10,11 +10,11 @@ pub const MAX_TREE_DEPTH: usize = 48;
 
 lazy_static! {
     /// Cached zero hashes where `ZERO_HASHES[i]` is the hash of a Merkle tree with 2^i zero leaves.
-    static ref ZERO_HASHES: Vec<Vec<u8>> = {
-        let mut hashes = vec![vec![0; 32]; MAX_TREE_DEPTH + 1];
+    static ref ZERO_HASHES: Vec<Hash256> = {
+        let mut hashes = vec![Hash256::zero(); MAX_TREE_DEPTH + 1];
 
         for i in 0..MAX_TREE_DEPTH {
-            hashes[i + 1] = hash_concat(&hashes[i], &hashes[i]);
+            hashes[i + 1] = hash_concat(hashes[i].as_bytes(), hashes[i].as_bytes());
         }
 
         hashes
@@ -49,12 +49,12 @@ lazy_static! {
 ///
 /// _Note: there are some minor memory overheads, including a handful of usizes and a list of
 /// `MAX_TREE_DEPTH` hashes as `lazy_static` constants._
-pub fn merkleize_padded(bytes: &[u8], min_leaves: usize) -> Vec<u8> {
+pub fn merkleize_padded(bytes: &[u8], min_leaves: usize) -> Hash256 {
     // If the bytes are just one chunk or less, pad to one chunk and return without hashing.
     if bytes.len() <= BYTES_PER_CHUNK && min_leaves <= 1 {
-        let mut o = bytes.to_vec();
-        o.resize(BYTES_PER_CHUNK, 0);
-        return o;
+        let mut o = [0; BYTES_PER_CHUNK];
+        o[..bytes.len()].copy_from_slice(bytes);
+        return Hash256::from(o);
     }
 
     assert!(
@@ -89,34 +89,32 @@ pub fn merkleize_padded(bytes: &[u8], min_leaves: usize) -> Vec<u8> {
     // Create a parent in the `chunks` buffer for every two chunks in `bytes`.
     //
     // I.e., do the first round of hashing, hashing from the `bytes` slice and filling the `chunks`
-    // struct.
-    for i in 0..initial_parents_with_values {
+    // struct. Every parent with two full chunks is hashed in a single batch, which lets
+    // `eth2_hashing` use a multi-buffer backend for this, the widest round of the tree.
+    let full_parents = std::cmp::min(
+        bytes.len() / (BYTES_PER_CHUNK * 2),
+        initial_parents_with_values,
+    );
+    hash_64_batch(
+        &bytes[..full_parents * BYTES_PER_CHUNK * 2],
+        &mut chunks.0[..full_parents * BYTES_PER_CHUNK],
+    );
+
+    // Unable to get all the bytes for the last parent, get a small slice and pad it out.
+    for i in full_parents..initial_parents_with_values {
         let start = i * BYTES_PER_CHUNK * 2;
 
-        // Hash two chunks, creating a parent chunk.
-        let hash = match bytes.get(start..start + BYTES_PER_CHUNK * 2) {
-            // All bytes are available, hash as usual.
-            Some(slice) => hash(slice),
-            // Unable to get all the bytes, get a small slice and pad it out.
-            None => {
-                let mut preimage = bytes
-                    .get(start..)
-                    .expect("`i` can only be larger than zero if there are bytes to read")
-                    .to_vec();
-                preimage.resize(BYTES_PER_CHUNK * 2, 0);
-                hash(&preimage)
-            }
-        };
+        let partial = bytes
+            .get(start..)
+            .expect("`i` can only be larger than zero if there are bytes to read");
 
-        assert_eq!(
-            hash.len(),
-            BYTES_PER_CHUNK,
-            "Hashes should be exactly one chunk"
-        );
+        let mut context = Context::new();
+        context.update(partial);
+        context.update(&[0; BYTES_PER_CHUNK * 2][partial.len()..]);
 
         // Store the parent node.
         chunks
-            .set(i, &hash)
+            .set(i, &context.finalize())
             .expect("Buffer should always have capacity for parent nodes")
     }
 
@@ -157,7 +155,7 @@ pub fn merkleize_padded(bytes: &[u8], min_leaves: usize) -> Vec<u8> {
 
             // Store a parent node.
             chunks
-                .set(i, &hash)
+                .set(i, hash.as_bytes())
                 .expect("Buf is adequate size for parent");
         }
 
@@ -172,7 +170,7 @@ pub fn merkleize_padded(bytes: &[u8], min_leaves: usize) -> Vec<u8> {
 
     assert_eq!(root.len(), BYTES_PER_CHUNK, "Only one chunk should remain");
 
-    root
+    Hash256::from_slice(&root)
 }
 
 /// A helper struct for storing words of `BYTES_PER_CHUNK` size in a flat byte array.
@@ -228,23 +226,25 @@ impl ChunkStore {
 }
 
 /// Returns a cached padding node for a given height.
-fn get_zero_hash(height: usize) -> &'static [u8] {
+pub fn get_zero_hash(height: usize) -> &'static [u8] {
     if height <= MAX_TREE_DEPTH {
-        &ZERO_HASHES[height]
+        ZERO_HASHES[height].as_bytes()
     } else {
         panic!("Tree exceeds MAX_TREE_DEPTH of {}", MAX_TREE_DEPTH)
     }
 }
 
-/// Concatenate two vectors.
-fn concat(mut vec1: Vec<u8>, mut vec2: Vec<u8>) -> Vec<u8> {
-    vec1.append(&mut vec2);
-    vec1
-}
-
 /// Compute the hash of two other hashes concatenated.
-pub fn hash_concat(h1: &[u8], h2: &[u8]) -> Vec<u8> {
-    hash(&concat(h1.to_vec(), h2.to_vec()))
+///
+/// Both `h1` and `h2` must be `BYTES_PER_CHUNK` bytes long. `eth2_hashing::hash32_concat` is used
+/// to hash them without allocating.
+pub fn hash_concat(h1: &[u8], h2: &[u8]) -> Hash256 {
+    let mut left = [0; BYTES_PER_CHUNK];
+    let mut right = [0; BYTES_PER_CHUNK];
+    left.copy_from_slice(h1);
+    right.copy_from_slice(h2);
+
+    Hash256::from(hash32_concat(&left, &right))
 }
 
 /// Returns the next even number following `n`. If `n` is even, `n` is returned.
@@ -256,8 +256,8 @@ fn next_even_number(n: usize) -> usize {
 mod test {
     use super::*;
 
-    pub fn reference_root(bytes: &[u8]) -> Vec<u8> {
-        crate::merkleize_standard(&bytes)[0..32].to_vec()
+    pub fn reference_root(bytes: &[u8]) -> Hash256 {
+        Hash256::from_slice(&crate::merkleize_standard(&bytes)[0..32])
     }
 
     macro_rules! common_tests {
@@ -316,7 +316,7 @@ mod test {
                 let input = vec![0; 10 * BYTES_PER_CHUNK];
                 let min_nodes = 2usize.pow(MAX_TREE_DEPTH as u32);
                 assert_eq!(
-                    merkleize_padded(&input, min_nodes),
+                    merkleize_padded(&input, min_nodes).as_bytes(),
                     get_zero_hash(MAX_TREE_DEPTH)
                 );
             }
@@ -0,0 +1,150 @@
+use super::{get_zero_hash, hash_concat, merkleize_padded, Hash256, BYTES_PER_CHUNK};
+use rayon::prelude::*;
+use std::cmp;
+
+/// Leaf sets with fewer chunks than this are merkleized on the calling thread, since splitting
+/// them up costs more than it saves.
+pub const PARALLEL_THRESHOLD: usize = 1 << 12;
+
+/// The smallest number of leaves in a subtree that gets its own task.
+const MIN_SUBTREE_LEAVES: usize = 1 << 10;
+
+/// Merkleize `bytes` and return the root, optionally padding the tree out to `min_leaves` number of
+/// leaves, exactly like `merkleize_padded` does.
+///
+/// If `bytes` holds at least `PARALLEL_THRESHOLD` chunks, the leaves are split into equally sized
+/// subtrees (roughly one for each thread in the `rayon` thread pool) that are merkleized in
+/// parallel. Their roots are then combined on the calling thread, using cached padding nodes for
+/// any subtrees that hold only padding.
+///
+/// Smaller inputs are passed straight to `merkleize_padded`.
+pub fn merkleize_parallel(bytes: &[u8], min_leaves: usize) -> Hash256 {
+    let leaves_with_values = (bytes.len() + (BYTES_PER_CHUNK - 1)) / BYTES_PER_CHUNK;
+
+    if leaves_with_values < PARALLEL_THRESHOLD {
+        return merkleize_padded(bytes, min_leaves);
+    }
+
+    let subtree_leaves = cmp::max(
+        MIN_SUBTREE_LEAVES,
+        (leaves_with_values / rayon::current_num_threads()).next_power_of_two(),
+    );
+
+    merkleize_subtrees(bytes, min_leaves, subtree_leaves)
+}
+
+/// Merkleizes `bytes` as subtrees of `subtree_leaves` leaves each, which must be a power of two,
+/// then combines their roots.
+fn merkleize_subtrees(bytes: &[u8], min_leaves: usize, subtree_leaves: usize) -> Hash256 {
+    let leaves_with_values = (bytes.len() + (BYTES_PER_CHUNK - 1)) / BYTES_PER_CHUNK;
+    let num_leaves = cmp::max(leaves_with_values, min_leaves).next_power_of_two();
+
+    if bytes.is_empty() || num_leaves <= subtree_leaves {
+        return merkleize_padded(bytes, min_leaves);
+    }
+
+    let subtree_roots = bytes
+        .par_chunks(subtree_leaves * BYTES_PER_CHUNK)
+        .map(|subtree| merkleize_padded(subtree, subtree_leaves))
+        .collect::<Vec<_>>();
+
+    // The roots sit at this height above the leaves, and the root of the whole tree at
+    // `root_height`.
+    let subtree_height = subtree_leaves.trailing_zeros() as usize;
+    let root_height = num_leaves.trailing_zeros() as usize;
+
+    combine_roots(subtree_roots, subtree_height, root_height)
+}
+
+/// Hashes `nodes`, which are at `height` above the leaves, up to a single root at `root_height`.
+///
+/// `nodes` must not be empty. Missing nodes on the right of each height are padding.
+fn combine_roots(mut nodes: Vec<Hash256>, height: usize, root_height: usize) -> Hash256 {
+    for height in height..root_height {
+        nodes = nodes
+            .chunks(2)
+            .map(|pair| match pair.get(1) {
+                Some(right) => hash_concat(pair[0].as_bytes(), right.as_bytes()),
+                None => hash_concat(pair[0].as_bytes(), get_zero_hash(height)),
+            })
+            .collect();
+    }
+
+    assert_eq!(nodes.len(), 1, "Only one node should remain");
+
+    nodes.remove(0)
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::merkleize_standard;
+
+    fn reference_root(bytes: &[u8], min_leaves: usize) -> Hash256 {
+        let mut padded = bytes.to_vec();
+        padded.resize(
+            cmp::max(
+                padded.len(),
+                min_leaves.next_power_of_two() * BYTES_PER_CHUNK,
+            ),
+            0,
+        );
+
+        Hash256::from_slice(&merkleize_standard(&padded)[0..32])
+    }
+
+    fn bytes(len: usize) -> Vec<u8> {
+        (0..len).map(|i| (i * 7 + i / 256) as u8).collect()
+    }
+
+    #[test]
+    fn subtrees_match_reference() {
+        for &subtree_leaves in &[1, 2, 4, 8] {
+            for len in (0..40 * BYTES_PER_CHUNK).step_by(13) {
+                for &min_leaves in &[0, 1, 3, 16, 64, 100] {
+                    let input = bytes(len);
+                    let root = merkleize_subtrees(&input, min_leaves, subtree_leaves);
+
+                    assert_eq!(
+                        root,
+                        reference_root(&input, min_leaves),
+                        "len: {}, min_leaves: {}, subtree_leaves: {}",
+                        len,
+                        min_leaves,
+                        subtree_leaves
+                    );
+                    assert_eq!(root, merkleize_padded(&input, min_leaves));
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn large_inputs_match_merkleize_padded() {
+        for &chunks in &[
+            PARALLEL_THRESHOLD - 1,
+            PARALLEL_THRESHOLD,
+            PARALLEL_THRESHOLD + 1,
+            3 * PARALLEL_THRESHOLD + 17,
+        ] {
+            let input = bytes(chunks * BYTES_PER_CHUNK - 5);
+
+            for &min_leaves in &[0, 1 << 20, 1 << 40] {
+                assert_eq!(
+                    merkleize_parallel(&input, min_leaves),
+                    merkleize_padded(&input, min_leaves),
+                    "chunks: {}, min_leaves: {}",
+                    chunks,
+                    min_leaves
+                );
+            }
+        }
+
+        let input = bytes(2 * PARALLEL_THRESHOLD * BYTES_PER_CHUNK);
+        assert_eq!(
+            merkleize_parallel(&input, 0),
+            reference_root(&input, 0),
+            "merkleize_standard"
+        );
+    }
+}
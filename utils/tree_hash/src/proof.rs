@@ -0,0 +1,496 @@
+//! Merkle proofs for any `TreeHash` type, addressed by generalized index.
+//!
+//! A generalized index numbers the nodes of a binary Merkle tree breadth-first: the root is `1`
+//! and the children of node `i` are `2 * i` and `2 * i + 1`. The tree of a value is the tree its
+//! `tree_hash_root` is computed from, with the trees of its fields and elements hanging below
+//! their leaves and the length of a list mixed in at the top.
+//!
+//! Paths follow the spec notation, without the type name: `finalized_checkpoint.root`,
+//! `validators[3].effective_balance` or `historical_roots.__len__`.
+use crate::merkleize_padded::hash_concat;
+use crate::{merkleize_padded, Hash256, TreeHash, BYTES_PER_CHUNK};
+use std::cmp;
+use std::collections::{BTreeMap, BTreeSet};
+use std::fmt;
+
+/// The number of bits in a generalized index.
+const GENERALIZED_INDEX_BITS: u32 = 64;
+
+/// One step of a path from the root of a value to one of its nodes.
+#[derive(Debug, PartialEq, Clone, Copy)]
+pub enum PathElement<'a> {
+    /// A field of a container.
+    Field(&'a str),
+    /// An element of a list or vector.
+    Index(usize),
+    /// The length of a list (`__len__`).
+    Length,
+}
+
+impl<'a> fmt::Display for PathElement<'a> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            PathElement::Field(name) => write!(f, ".{}", name),
+            PathElement::Index(index) => write!(f, "[{}]", index),
+            PathElement::Length => write!(f, ".__len__"),
+        }
+    }
+}
+
+#[derive(Debug, PartialEq, Clone)]
+pub enum ProofError {
+    /// The path could not be parsed.
+    InvalidPath(String),
+    /// The path names a field the container does not have.
+    UnknownField(String),
+    /// The path element cannot be applied to the type it was applied to, e.g. a field of a list or
+    /// any element below a basic value.
+    UnexpectedPathElement(String),
+    /// The path indexes past the maximum length of a list or the length of a vector.
+    IndexOutOfBounds { index: usize, max: usize },
+    /// The generalized index does not fit in 64 bits.
+    GeneralizedIndexOverflow,
+    /// The value does not have a node at the generalized index.
+    NodeNotFound(u64),
+}
+
+/// Splits a path in spec notation into its elements.
+///
+/// Returns an error if the path is empty or malformed.
+pub fn parse_path(path: &str) -> Result<Vec<PathElement<'_>>, ProofError> {
+    let invalid = || ProofError::InvalidPath(path.to_string());
+    let mut elements = vec![];
+
+    for (i, part) in path.split('.').enumerate() {
+        let (name, mut indices) = match part.find('[') {
+            Some(start) => part.split_at(start),
+            None => (part, ""),
+        };
+
+        match name {
+            "" if i > 0 || indices.is_empty() => return Err(invalid()),
+            "" => (),
+            "__len__" => elements.push(PathElement::Length),
+            name => elements.push(PathElement::Field(name)),
+        }
+
+        while !indices.is_empty() {
+            let end = indices.find(']').ok_or_else(invalid)?;
+            let index = indices[1..end].parse().map_err(|_| invalid())?;
+            elements.push(PathElement::Index(index));
+            indices = &indices[end + 1..];
+            if !indices.is_empty() && !indices.starts_with('[') {
+                return Err(invalid());
+            }
+        }
+    }
+
+    Ok(elements)
+}
+
+/// Returns the generalized index of the node `path` leads to in the tree of a `T`.
+pub fn generalized_index<T: TreeHash>(path: &str) -> Result<u64, ProofError> {
+    T::tree_hash_gindex(&parse_path(path)?)
+}
+
+/// Returns the depth of `gindex` in its tree, where the root has a depth of `0`.
+fn depth(gindex: u64) -> u32 {
+    GENERALIZED_INDEX_BITS - 1 - gindex.leading_zeros()
+}
+
+/// Returns the generalized index of the node at `inner` in the subtree rooted at `outer`.
+///
+/// Both indices must be non-zero.
+pub fn concat_generalized_indices(outer: u64, inner: u64) -> Result<u64, ProofError> {
+    let inner_depth = depth(inner);
+    if outer.leading_zeros() < inner_depth {
+        return Err(ProofError::GeneralizedIndexOverflow);
+    }
+
+    Ok((outer << inner_depth) | (inner ^ (1 << inner_depth)))
+}
+
+/// Returns the generalized index of the node at `inner` below leaf `leaf` of a tree with
+/// `leaf_count` leaves (rounded up to a power of two).
+pub fn subtree_gindex(leaf_count: usize, leaf: usize, inner: u64) -> Result<u64, ProofError> {
+    let leaf_count = cmp::max(leaf_count, 1).next_power_of_two() as u64;
+
+    concat_generalized_indices(leaf_count + leaf as u64, inner)
+}
+
+/// Returns the node at `gindex` of the tree built from `chunks`, padded out to at least
+/// `min_leaves` leaves, exactly as `merkle_root(chunks, min_leaves)` builds it.
+///
+/// Nodes below the leaf at `i` are looked up with `descend(i, inner)`, where `inner` is the
+/// generalized index relative to that leaf. Padding leaves have nothing below them.
+pub fn subtree_node<F>(chunks: &[u8], min_leaves: usize, gindex: u64, descend: F) -> Option<Hash256>
+where
+    F: FnOnce(usize, u64) -> Option<Hash256>,
+{
+    if gindex == 0 {
+        return None;
+    }
+
+    let chunk_count = (chunks.len() + BYTES_PER_CHUNK - 1) / BYTES_PER_CHUNK;
+    let leaf_count = cmp::max(cmp::max(chunk_count, min_leaves), 1).next_power_of_two();
+    let tree_depth = leaf_count.trailing_zeros();
+    let gindex_depth = depth(gindex);
+
+    if gindex_depth <= tree_depth {
+        let span = 1 << (tree_depth - gindex_depth);
+        let start = (gindex - (1 << gindex_depth)) as usize * span * BYTES_PER_CHUNK;
+        let end = cmp::min(start + span * BYTES_PER_CHUNK, chunks.len());
+        let bytes = if start < end {
+            &chunks[start..end]
+        } else {
+            &[]
+        };
+
+        Some(merkleize_padded(bytes, span))
+    } else {
+        let below = gindex_depth - tree_depth;
+        let leaf = (gindex >> below) as usize - leaf_count;
+        if leaf >= chunk_count {
+            return None;
+        }
+
+        descend(leaf, (1 << below) | (gindex & ((1 << below) - 1)))
+    }
+}
+
+/// Returns the node at `gindex` of a list tree: the root of its data (found with `data_node`) with
+/// `length` mixed in.
+pub fn mix_in_length_node<F>(gindex: u64, length: usize, data_node: F) -> Option<Hash256>
+where
+    F: FnOnce(u64) -> Option<Hash256>,
+{
+    match gindex {
+        0 => None,
+        1 => Some(crate::mix_in_length(&data_node(1)?, length)),
+        3 => subtree_node(&(length as u64).to_le_bytes(), 1, 1, |_, _| None),
+        _ => {
+            let below = depth(gindex) - 1;
+            if gindex >> below != 2 {
+                return None;
+            }
+            data_node((1 << below) | (gindex & ((1 << below) - 1)))
+        }
+    }
+}
+
+/// Returns the node of `value` at `gindex`, or an error if there is no such node.
+fn node<T: TreeHash>(value: &T, gindex: u64) -> Result<Hash256, ProofError> {
+    value
+        .tree_hash_node(gindex)
+        .ok_or(ProofError::NodeNotFound(gindex))
+}
+
+/// Proves the node at a single generalized index.
+#[derive(Debug, PartialEq, Clone)]
+pub struct Proof {
+    pub gindex: u64,
+    pub leaf: Hash256,
+    /// The siblings of the leaf and of each of its ancestors below the root, from the bottom up.
+    pub branch: Vec<Hash256>,
+}
+
+impl Proof {
+    /// Builds a proof of the node of `value` at `gindex`.
+    pub fn new<T: TreeHash>(value: &T, gindex: u64) -> Result<Self, ProofError> {
+        let leaf = node(value, gindex)?;
+        let branch = branch_indices(gindex)
+            .into_iter()
+            .map(|sibling| node(value, sibling))
+            .collect::<Result<_, _>>()?;
+
+        Ok(Proof {
+            gindex,
+            leaf,
+            branch,
+        })
+    }
+
+    /// Returns true if the proof is valid for a tree with the given `root`.
+    pub fn verify(&self, root: &Hash256) -> bool {
+        if self.gindex == 0 || self.branch.len() != depth(self.gindex) as usize {
+            return false;
+        }
+
+        let mut gindex = self.gindex;
+        let mut node = self.leaf;
+        for sibling in &self.branch {
+            node = if gindex & 1 == 1 {
+                hash_concat(sibling.as_bytes(), node.as_bytes())
+            } else {
+                hash_concat(node.as_bytes(), sibling.as_bytes())
+            };
+            gindex >>= 1;
+        }
+
+        node == *root
+    }
+}
+
+/// Proves the nodes at several generalized indices at once, sharing the nodes their branches have
+/// in common.
+#[derive(Debug, PartialEq, Clone)]
+pub struct MultiProof {
+    pub gindices: Vec<u64>,
+    /// The nodes at `gindices`, in the same order.
+    pub leaves: Vec<Hash256>,
+    /// The nodes at `MultiProof::helper_indices(&gindices)`, in the same order.
+    pub helpers: Vec<Hash256>,
+}
+
+impl MultiProof {
+    /// Builds a proof of the nodes of `value` at `gindices`.
+    pub fn new<T: TreeHash>(value: &T, gindices: &[u64]) -> Result<Self, ProofError> {
+        let leaves = gindices
+            .iter()
+            .map(|&gindex| node(value, gindex))
+            .collect::<Result<_, _>>()?;
+        let helpers = Self::helper_indices(gindices)
+            .into_iter()
+            .map(|gindex| node(value, gindex))
+            .collect::<Result<_, _>>()?;
+
+        Ok(MultiProof {
+            gindices: gindices.to_vec(),
+            leaves,
+            helpers,
+        })
+    }
+
+    /// Returns the generalized indices of the extra nodes needed to prove `gindices`, in
+    /// decreasing order.
+    ///
+    /// These are the siblings along each branch that cannot be computed from the nodes at
+    /// `gindices` themselves.
+    pub fn helper_indices(gindices: &[u64]) -> Vec<u64> {
+        let mut helpers = BTreeSet::new();
+        let mut paths = BTreeSet::new();
+
+        for &gindex in gindices {
+            helpers.extend(branch_indices(gindex));
+            paths.extend(path_indices(gindex));
+        }
+
+        let mut helpers: Vec<u64> = helpers.difference(&paths).cloned().collect();
+        helpers.reverse();
+        helpers
+    }
+
+    /// Returns true if the proof is valid for a tree with the given `root`.
+    pub fn verify(&self, root: &Hash256) -> bool {
+        let helper_indices = Self::helper_indices(&self.gindices);
+        if self.leaves.len() != self.gindices.len()
+            || self.helpers.len() != helper_indices.len()
+            || self.gindices.contains(&0)
+        {
+            return false;
+        }
+
+        let mut nodes: BTreeMap<u64, Hash256> = BTreeMap::new();
+        let known = self.gindices.iter().zip(&self.leaves);
+        for (&gindex, node) in known.chain(helper_indices.iter().zip(&self.helpers)) {
+            nodes.insert(gindex, *node);
+        }
+
+        // Hash siblings together from the deepest nodes up. The keys of a `BTreeMap` are ordered,
+        // so the largest remaining key is always the deepest node not yet combined, and a right
+        // child is always combined before its left sibling comes up.
+        let mut pending: Vec<u64> = nodes.keys().cloned().collect();
+        while let Some(gindex) = pending.pop() {
+            if gindex <= 1 || (gindex & 1 == 0 && nodes.contains_key(&(gindex | 1))) {
+                continue;
+            }
+            let parent = match (nodes.get(&(gindex & !1)), nodes.get(&(gindex | 1))) {
+                (Some(left), Some(right)) => hash_concat(left.as_bytes(), right.as_bytes()),
+                _ => return false,
+            };
+            // A node proven alongside one of its descendants must agree with it.
+            if let Some(known) = nodes.get(&(gindex / 2)) {
+                if *known != parent {
+                    return false;
+                }
+                continue;
+            }
+            nodes.insert(gindex / 2, parent);
+            let position = pending
+                .iter()
+                .position(|&other| other > gindex / 2)
+                .unwrap_or(pending.len());
+            pending.insert(position, gindex / 2);
+        }
+
+        nodes.get(&1) == Some(root)
+    }
+}
+
+/// Returns the generalized indices of the siblings of `gindex` and of each of its ancestors below
+/// the root, from the bottom up.
+fn branch_indices(gindex: u64) -> Vec<u64> {
+    path_indices(gindex).into_iter().map(|i| i ^ 1).collect()
+}
+
+/// Returns `gindex` and the generalized indices of its ancestors below the root, from the bottom up.
+fn path_indices(gindex: u64) -> Vec<u64> {
+    let mut indices = vec![];
+    let mut gindex = gindex;
+    while gindex > 1 {
+        indices.push(gindex);
+        gindex >>= 1;
+    }
+    indices
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::merkle_root;
+
+    #[test]
+    fn parse_paths() {
+        assert_eq!(
+            parse_path("finalized_checkpoint.root"),
+            Ok(vec![
+                PathElement::Field("finalized_checkpoint"),
+                PathElement::Field("root")
+            ])
+        );
+        assert_eq!(
+            parse_path("validators[3].effective_balance"),
+            Ok(vec![
+                PathElement::Field("validators"),
+                PathElement::Index(3),
+                PathElement::Field("effective_balance")
+            ])
+        );
+        assert_eq!(
+            parse_path("[1][2].__len__"),
+            Ok(vec![
+                PathElement::Index(1),
+                PathElement::Index(2),
+                PathElement::Length
+            ])
+        );
+
+        for invalid in &["", "a.", ".a", "a..b", "a[", "a[x]", "a[1]b", "a.[1]"] {
+            assert_eq!(
+                parse_path(invalid),
+                Err(ProofError::InvalidPath(invalid.to_string())),
+                "{}",
+                invalid
+            );
+        }
+    }
+
+    #[test]
+    fn concat_indices() {
+        assert_eq!(concat_generalized_indices(1, 1), Ok(1));
+        assert_eq!(concat_generalized_indices(1, 6), Ok(6));
+        assert_eq!(concat_generalized_indices(6, 1), Ok(6));
+        assert_eq!(concat_generalized_indices(3, 5), Ok(13));
+        assert_eq!(
+            concat_generalized_indices(1 << 40, 1 << 24),
+            Err(ProofError::GeneralizedIndexOverflow)
+        );
+
+        assert_eq!(subtree_gindex(3, 2, 1), Ok(6));
+        assert_eq!(subtree_gindex(1, 0, 3), Ok(3));
+    }
+
+    #[test]
+    fn subtree_nodes_match_merkle_root() {
+        let chunks: Vec<u8> = (0..5 * BYTES_PER_CHUNK as u8).collect();
+
+        assert_eq!(
+            subtree_node(&chunks, 0, 1, |_, _| None),
+            Some(merkle_root(&chunks, 0))
+        );
+        // The second half of an 8 leaf tree holds the fifth chunk and three padding chunks.
+        assert_eq!(
+            subtree_node(&chunks, 0, 3, |_, _| None),
+            Some(merkle_root(&chunks[4 * BYTES_PER_CHUNK..], 4))
+        );
+        assert_eq!(
+            subtree_node(&chunks, 0, 9, |_, _| None),
+            Some(Hash256::from_slice(
+                &chunks[BYTES_PER_CHUNK..2 * BYTES_PER_CHUNK]
+            ))
+        );
+        assert_eq!(
+            subtree_node(&chunks, 16, 31, |_, _| None),
+            Some(Hash256::zero())
+        );
+        assert_eq!(
+            subtree_node(&chunks, 0, 18, |leaf, inner| Some(
+                Hash256::from_low_u64_be((leaf as u64) << 8 | inner)
+            )),
+            Some(Hash256::from_low_u64_be(0x0102))
+        );
+        // Padding leaves have nothing below them.
+        assert_eq!(
+            subtree_node(&chunks, 0, 30, |_, _| Some(Hash256::zero())),
+            None
+        );
+    }
+
+    #[test]
+    fn single_and_multi_proofs() {
+        let leaves: Vec<u8> = (0..8 * BYTES_PER_CHUNK).map(|i| (i / 3) as u8).collect();
+        let root = merkle_root(&leaves, 0);
+        // Stand in for a `TreeHash` value with a precomputed tree.
+        struct Chunks(Vec<u8>);
+        impl TreeHash for Chunks {
+            fn tree_hash_type() -> crate::TreeHashType {
+                crate::TreeHashType::Vector
+            }
+            fn tree_hash_packed_encoding(&self) -> Vec<u8> {
+                unreachable!()
+            }
+            fn tree_hash_packing_factor() -> usize {
+                unreachable!()
+            }
+            fn tree_hash_root(&self) -> Hash256 {
+                merkle_root(&self.0, 0)
+            }
+            fn tree_hash_node(&self, gindex: u64) -> Option<Hash256> {
+                subtree_node(&self.0, 0, gindex, |_, _| None)
+            }
+        }
+        let value = Chunks(leaves);
+
+        for gindex in 1..16 {
+            let proof = Proof::new(&value, gindex).unwrap();
+            assert!(proof.verify(&root), "{}", gindex);
+
+            let mut tampered = proof.clone();
+            tampered.leaf.as_bytes_mut()[0] ^= 1;
+            assert!(!tampered.verify(&root), "{}", gindex);
+        }
+        assert_eq!(Proof::new(&value, 16), Err(ProofError::NodeNotFound(16)));
+
+        assert_eq!(MultiProof::helper_indices(&[9, 14]), vec![15, 8, 6, 5]);
+        assert_eq!(MultiProof::helper_indices(&[8, 9, 5]), vec![3]);
+
+        for gindices in &[vec![9, 14], vec![8, 9, 5], vec![1], vec![2, 3], vec![12, 7]] {
+            let proof = MultiProof::new(&value, gindices).unwrap();
+            assert!(proof.verify(&root), "{:?}", gindices);
+
+            let mut tampered = proof.clone();
+            tampered.leaves[0].as_bytes_mut()[0] ^= 1;
+            assert!(!tampered.verify(&root), "{:?}", gindices);
+        }
+
+        let mut proof = MultiProof::new(&value, &[9, 14]).unwrap();
+        proof.helpers.pop();
+        assert!(!proof.verify(&root));
+
+        // A forged node below another proven node is not hidden by its genuine ancestor.
+        let mut nested = MultiProof::new(&value, &[2, 4]).unwrap();
+        assert!(nested.verify(&root));
+        nested.leaves[1].as_bytes_mut()[0] ^= 1;
+        assert!(!nested.verify(&root));
+    }
+}
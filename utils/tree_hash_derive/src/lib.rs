@@ -7,40 +7,272 @@ use proc_macro::TokenStream;
 use quote::quote;
 use syn::{parse_macro_input, DeriveInput};
 
-/// Returns a Vec of `syn::Ident` for each named field in the struct, whilst filtering out fields
-/// that should not be hashed.
+/// Returns the `syn::Member` used to access a field: its ident for a named field, or its position
+/// `index` for an unnamed field (like in a tuple struct).
+fn field_member(index: usize, field: &syn::Field) -> syn::Member {
+    match &field.ident {
+        Some(ident) => syn::Member::Named(ident.clone()),
+        None => syn::Member::Unnamed(index.into()),
+    }
+}
+
+/// The options set by the `#[tree_hash(..)]` attributes of a struct.
+#[derive(Default)]
+struct StructOpts {
+    /// `transparent` or `struct_behaviour = "transparent"`.
+    transparent: bool,
+    /// `cached`.
+    cached: bool,
+}
+
+/// The options set by the `#[tree_hash(..)]` attributes of a field.
+#[derive(Default)]
+struct FieldOpts {
+    /// `skip_hashing`.
+    skip_hashing: bool,
+    /// `with = "path::to::fn"`.
+    with: Option<syn::Path>,
+    /// `cached`.
+    cached: bool,
+}
+
+/// Returns the items inside every `#[<name>(..)]` attribute in `attrs`.
+fn attribute_items(attrs: &[syn::Attribute], name: &str) -> Vec<syn::NestedMeta> {
+    attrs
+        .iter()
+        .filter(|attr| attr.path.is_ident(name))
+        .flat_map(|attr| match attr.parse_meta() {
+            Ok(syn::Meta::List(list)) => list.nested.into_iter().collect::<Vec<_>>(),
+            _ => panic!(
+                "tree_hash_derive expects attributes of the form #[{}(..)]",
+                name
+            ),
+        })
+        .collect()
+}
+
+fn parse_struct_opts(item: &DeriveInput) -> StructOpts {
+    let mut opts = StructOpts::default();
+
+    for nested in attribute_items(&item.attrs, "tree_hash") {
+        match nested {
+            syn::NestedMeta::Meta(syn::Meta::Word(ref ident)) if ident == "transparent" => {
+                opts.transparent = true
+            }
+            syn::NestedMeta::Meta(syn::Meta::Word(ref ident)) if ident == "cached" => {
+                opts.cached = true
+            }
+            syn::NestedMeta::Meta(syn::Meta::NameValue(ref name_value))
+                if name_value.ident == "struct_behaviour" =>
+            {
+                match &name_value.lit {
+                    syn::Lit::Str(behaviour) if behaviour.value() == "transparent" => {
+                        opts.transparent = true
+                    }
+                    syn::Lit::Str(behaviour) if behaviour.value() == "container" => {
+                        opts.transparent = false
+                    }
+                    _ => panic!(
+                        "tree_hash_derive expects struct_behaviour = \"container\" or \"transparent\""
+                    ),
+                }
+            }
+            _ => panic!("tree_hash_derive found an unknown struct attribute."),
+        }
+    }
+
+    opts
+}
+
+fn parse_field_opts(field: &syn::Field) -> FieldOpts {
+    let mut opts = FieldOpts::default();
+
+    for nested in attribute_items(&field.attrs, "tree_hash") {
+        match nested {
+            syn::NestedMeta::Meta(syn::Meta::Word(ref ident)) if ident == "skip_hashing" => {
+                opts.skip_hashing = true
+            }
+            syn::NestedMeta::Meta(syn::Meta::Word(ref ident)) if ident == "cached" => {
+                opts.cached = true
+            }
+            syn::NestedMeta::Meta(syn::Meta::NameValue(ref name_value))
+                if name_value.ident == "with" =>
+            {
+                opts.with = match &name_value.lit {
+                    syn::Lit::Str(path) => Some(
+                        path.parse()
+                            .expect("tree_hash_derive expects with = \"path::to::fn\""),
+                    ),
+                    _ => panic!("tree_hash_derive expects with = \"path::to::fn\""),
+                }
+            }
+            _ => panic!("tree_hash_derive found an unknown field attribute."),
+        }
+    }
+
+    if opts.cached && opts.with.is_some() {
+        panic!("tree_hash_derive does not support both cached and with on one field.");
+    }
+
+    opts
+}
+
+/// Returns the fields of the struct that should be hashed, with their position among them.
 ///
-/// # Panics
-/// Any unnamed struct field (like in a tuple struct) will raise a panic at compile time.
-fn get_hashable_named_field_idents<'a>(struct_data: &'a syn::DataStruct) -> Vec<&'a syn::Ident> {
+/// The field attribute that skips a field is: `#[tree_hash(skip_hashing)]`
+fn get_hashable_fields(struct_data: &syn::DataStruct) -> Vec<(usize, &syn::Field, FieldOpts)> {
     struct_data
         .fields
         .iter()
-        .filter_map(|f| {
-            if should_skip_hashing(&f) {
-                None
+        .enumerate()
+        .map(|(i, f)| (i, f, parse_field_opts(f)))
+        .filter(|(_, _, opts)| !opts.skip_hashing)
+        .collect()
+}
+
+/// Returns an expression for the root of each field that should be hashed, which is its
+/// `tree_hash_root` unless it has a `#[tree_hash(with = "..")]` function.
+fn get_hashable_field_roots(struct_data: &syn::DataStruct) -> Vec<proc_macro2::TokenStream> {
+    get_hashable_fields(struct_data)
+        .into_iter()
+        .map(|(i, f, opts)| {
+            let member = field_member(i, f);
+            match opts.with {
+                Some(with) => quote! { #with(&self.#member) },
+                None => quote! { tree_hash::TreeHash::tree_hash_root(&self.#member) },
+            }
+        })
+        .collect()
+}
+
+/// Returns the fields that should be hashed and are marked `#[tree_hash(cached)]`, in order.
+fn get_cached_field_members(struct_data: &syn::DataStruct) -> Vec<syn::Member> {
+    get_hashable_fields(struct_data)
+        .into_iter()
+        .filter(|(_, _, opts)| opts.cached)
+        .map(|(i, f, _)| field_member(i, f))
+        .collect()
+}
+
+/// Returns an expression for the root of each field that should be hashed, for
+/// `CachedTreeHash::new_tree_hash_cache` (`new == true`) or
+/// `CachedTreeHash::recalculate_tree_hash_root`.
+///
+/// A field marked `#[tree_hash(cached)]` uses its cache among `fields` in `new_tree_hash_cache`, or
+/// among the fields of `cache` in `recalculate_tree_hash_root`. Other fields use their root.
+fn get_cached_field_roots(
+    struct_data: &syn::DataStruct,
+    new: bool,
+) -> Vec<proc_macro2::TokenStream> {
+    let roots = get_hashable_field_roots(struct_data);
+    let mut position = 0_usize;
+
+    get_hashable_fields(struct_data)
+        .into_iter()
+        .zip(roots)
+        .map(|((i, f, opts), root)| {
+            if !opts.cached {
+                return root;
+            }
+            let member = field_member(i, f);
+            let index = position;
+            position += 1;
+            if new {
+                quote! { fields[#index].root() }
             } else {
-                Some(match &f.ident {
-                    Some(ref ident) => ident,
-                    _ => panic!("tree_hash_derive only supports named struct fields."),
-                })
+                quote! {
+                    tree_hash::CachedTreeHash::recalculate_tree_hash_root(
+                        &self.#member,
+                        &mut cache.fields_mut()[#index],
+                    )
+                }
             }
         })
         .collect()
 }
 
-/// Returns true if some field has an attribute declaring it should not be hashed.
+/// Returns the name of each field in the struct that should be hashed, as used in proof paths: its
+/// ident, or its position for an unnamed field.
+fn get_hashable_field_names(struct_data: &syn::DataStruct) -> Vec<String> {
+    get_hashable_fields(struct_data)
+        .into_iter()
+        .map(|(i, f, _)| match &f.ident {
+            Some(ident) => ident.to_string(),
+            None => i.to_string(),
+        })
+        .collect()
+}
+
+/// Returns an expression for the generalized index of `rest` within the tree of each field that
+/// should be hashed.
 ///
-/// The field attribute is: `#[tree_hash(skip_hashing)]`
-fn should_skip_hashing(field: &syn::Field) -> bool {
-    field.attrs.iter().any(|attr| {
-        attr.path.is_ident("tree_hash") && attr.tts.to_string().replace(" ", "") == "(skip_hashing)"
-    })
+/// A field with a `#[tree_hash(with = "..")]` function is opaque: only its root can be addressed.
+fn get_hashable_field_gindices(struct_data: &syn::DataStruct) -> Vec<proc_macro2::TokenStream> {
+    get_hashable_fields(struct_data)
+        .into_iter()
+        .map(|(_, f, opts)| {
+            let ty = &f.ty;
+            match opts.with {
+                Some(_) => quote! {
+                    match rest.first() {
+                        None => 1,
+                        Some(element) => {
+                            return Err(tree_hash::ProofError::UnexpectedPathElement(
+                                element.to_string(),
+                            ))
+                        }
+                    }
+                },
+                None => quote! { <#ty as tree_hash::TreeHash>::tree_hash_gindex(rest)? },
+            }
+        })
+        .collect()
+}
+
+/// Returns an expression for the node at `inner` within the tree of each field that should be
+/// hashed.
+fn get_hashable_field_nodes(struct_data: &syn::DataStruct) -> Vec<proc_macro2::TokenStream> {
+    get_hashable_fields(struct_data)
+        .into_iter()
+        .map(|(i, f, opts)| {
+            let member = field_member(i, f);
+            match opts.with {
+                Some(with) => quote! {
+                    if inner == 1 {
+                        Some(#with(&self.#member))
+                    } else {
+                        None
+                    }
+                },
+                None => quote! { self.#member.tree_hash_node(inner) },
+            }
+        })
+        .collect()
 }
 
 /// Implements `tree_hash::TreeHash` for some `struct`.
 ///
-/// Fields are hashed in the order they are defined.
+/// Fields are hashed in the order they are defined, or in the order of their positions for a tuple
+/// struct.
+///
+/// ## Struct attributes
+///
+/// - `#[tree_hash(transparent)]` or `#[tree_hash(struct_behaviour = "transparent")]`: the struct,
+///   which must have exactly one field, is hashed (and packed into lists) exactly like that field.
+///   `struct_behaviour = "container"` is the default.
+/// - `#[tree_hash(cached)]`: also implements `tree_hash::CachedTreeHash`, so that the root can be
+///   recomputed with a `tree_hash::TreeHashCache`, rehashing only the nodes above field roots that
+///   changed. The roots of fields that are not cached themselves are still computed every time.
+///
+/// ## Field attributes
+///
+/// - `#[tree_hash(skip_hashing)]`: the field will not be hashed.
+/// - `#[tree_hash(with = "path::to::fn")]`: the root of the field is `fn(&field)`, which must
+///   return a `tree_hash::Hash256`, rather than its `tree_hash_root`. Proofs can reach the field
+///   but nothing below it.
+/// - `#[tree_hash(cached)]`: in a `#[tree_hash(cached)]` struct, the field keeps its own cache in
+///   the struct's, and its root is recalculated with it. The field must implement
+///   `tree_hash::CachedTreeHash`.
 #[proc_macro_derive(TreeHash, attributes(tree_hash))]
 pub fn tree_hash_derive(input: TokenStream) -> TokenStream {
     let item = parse_macro_input!(input as DeriveInput);
@@ -53,7 +285,26 @@ pub fn tree_hash_derive(input: TokenStream) -> TokenStream {
         _ => panic!("tree_hash_derive only supports structs."),
     };
 
-    let idents = get_hashable_named_field_idents(&struct_data);
+    let opts = parse_struct_opts(&item);
+
+    if opts.transparent {
+        return tree_hash_derive_transparent(&item, struct_data, &opts);
+    }
+
+    let roots = get_hashable_field_roots(&struct_data);
+    let roots_a = roots.clone();
+    let names = get_hashable_field_names(&struct_data);
+    let gindices = get_hashable_field_gindices(&struct_data);
+    let nodes = get_hashable_field_nodes(&struct_data);
+    let num_fields = roots.len();
+    let positions: Vec<usize> = (0..num_fields).collect();
+    let positions_a = positions.clone();
+
+    let cached_impl = if opts.cached {
+        cached_tree_hash_impl(&item, struct_data)
+    } else {
+        quote! {}
+    };
 
     let output = quote! {
         impl #impl_generics tree_hash::TreeHash for #name #ty_generics #where_clause {
@@ -69,16 +320,172 @@ pub fn tree_hash_derive(input: TokenStream) -> TokenStream {
                 unreachable!("Struct should never be packed.")
             }
 
-            fn tree_hash_root(&self) -> Vec<u8> {
+            fn tree_hash_root(&self) -> tree_hash::Hash256 {
                 let mut leaves = Vec::with_capacity(4 * tree_hash::HASHSIZE);
 
                 #(
-                    leaves.append(&mut self.#idents.tree_hash_root());
+                    leaves.extend_from_slice(#roots.as_bytes());
                 )*
 
                 tree_hash::merkle_root(&leaves, 0)
             }
+
+            fn tree_hash_gindex(
+                path: &[tree_hash::PathElement],
+            ) -> Result<u64, tree_hash::ProofError> {
+                let (first, rest) = match path.split_first() {
+                    Some(split) => split,
+                    None => return Ok(1),
+                };
+
+                let (position, inner) = match first {
+                    #(
+                        tree_hash::PathElement::Field(#names) => (#positions, #gindices),
+                    )*
+                    tree_hash::PathElement::Field(name) => {
+                        return Err(tree_hash::ProofError::UnknownField(name.to_string()))
+                    }
+                    other => {
+                        return Err(tree_hash::ProofError::UnexpectedPathElement(other.to_string()))
+                    }
+                };
+
+                tree_hash::proof::subtree_gindex(#num_fields, position, inner)
+            }
+
+            fn tree_hash_node(&self, gindex: u64) -> Option<tree_hash::Hash256> {
+                let mut leaves = Vec::with_capacity(4 * tree_hash::HASHSIZE);
+
+                #(
+                    leaves.extend_from_slice(#roots_a.as_bytes());
+                )*
+
+                tree_hash::proof::subtree_node(&leaves, 0, gindex, |position, inner| {
+                    match position {
+                        #(
+                            #positions_a => #nodes,
+                        )*
+                        _ => None,
+                    }
+                })
+            }
         }
+
+        #cached_impl
+    };
+    output.into()
+}
+
+/// Implements `tree_hash::CachedTreeHash` for a `#[tree_hash(cached)]` container.
+fn cached_tree_hash_impl(
+    item: &DeriveInput,
+    struct_data: &syn::DataStruct,
+) -> proc_macro2::TokenStream {
+    let name = &item.ident;
+    let (impl_generics, ty_generics, where_clause) = &item.generics.split_for_impl();
+
+    let cached_members = get_cached_field_members(&struct_data);
+    let num_cached = cached_members.len();
+    let new_roots = get_cached_field_roots(&struct_data, true);
+    let recalculated_roots = get_cached_field_roots(&struct_data, false);
+
+    quote! {
+        impl #impl_generics tree_hash::CachedTreeHash for #name #ty_generics #where_clause {
+            fn new_tree_hash_cache(&self) -> tree_hash::TreeHashCache {
+                let fields = vec![
+                    #(tree_hash::CachedTreeHash::new_tree_hash_cache(&self.#cached_members)),*
+                ];
+                let leaves = [#(#new_roots),*];
+                tree_hash::TreeHashCache::with_fields(&leaves, fields)
+            }
+
+            fn recalculate_tree_hash_root(
+                &self,
+                cache: &mut tree_hash::TreeHashCache,
+            ) -> tree_hash::Hash256 {
+                // Not built by `new_tree_hash_cache`, so there are no field caches to reuse.
+                if cache.fields().len() != #num_cached {
+                    *cache = self.new_tree_hash_cache();
+                    return cache.root();
+                }
+
+                let leaves = [#(#recalculated_roots),*];
+                cache.update(&leaves)
+            }
+        }
+    }
+}
+
+/// Implements `tree_hash::TreeHash` for a `#[tree_hash(transparent)]` struct, delegating to its
+/// only field.
+fn tree_hash_derive_transparent(
+    item: &DeriveInput,
+    struct_data: &syn::DataStruct,
+    opts: &StructOpts,
+) -> TokenStream {
+    let name = &item.ident;
+    let (impl_generics, ty_generics, where_clause) = &item.generics.split_for_impl();
+
+    let mut fields = struct_data.fields.iter();
+    let (member, ty) = match (fields.next(), fields.next()) {
+        (Some(field), None) => (field_member(0, field), &field.ty),
+        _ => panic!(
+            "tree_hash_derive only supports #[tree_hash(transparent)] on structs with one field."
+        ),
+    };
+    let member_a = member.clone();
+    let member_b = member.clone();
+    let member_c = member.clone();
+
+    let cached_impl = if opts.cached {
+        quote! {
+            impl #impl_generics tree_hash::CachedTreeHash for #name #ty_generics #where_clause {
+                fn new_tree_hash_cache(&self) -> tree_hash::TreeHashCache {
+                    tree_hash::CachedTreeHash::new_tree_hash_cache(&self.#member_c)
+                }
+
+                fn recalculate_tree_hash_root(
+                    &self,
+                    cache: &mut tree_hash::TreeHashCache,
+                ) -> tree_hash::Hash256 {
+                    tree_hash::CachedTreeHash::recalculate_tree_hash_root(&self.#member_c, cache)
+                }
+            }
+        }
+    } else {
+        quote! {}
+    };
+
+    let output = quote! {
+        impl #impl_generics tree_hash::TreeHash for #name #ty_generics #where_clause {
+            fn tree_hash_type() -> tree_hash::TreeHashType {
+                <#ty as tree_hash::TreeHash>::tree_hash_type()
+            }
+
+            fn tree_hash_packed_encoding(&self) -> Vec<u8> {
+                self.#member.tree_hash_packed_encoding()
+            }
+
+            fn tree_hash_packing_factor() -> usize {
+                <#ty as tree_hash::TreeHash>::tree_hash_packing_factor()
+            }
+
+            fn tree_hash_root(&self) -> tree_hash::Hash256 {
+                self.#member_a.tree_hash_root()
+            }
+
+            fn tree_hash_gindex(
+                path: &[tree_hash::PathElement],
+            ) -> Result<u64, tree_hash::ProofError> {
+                <#ty as tree_hash::TreeHash>::tree_hash_gindex(path)
+            }
+
+            fn tree_hash_node(&self, gindex: u64) -> Option<tree_hash::Hash256> {
+                self.#member_b.tree_hash_node(gindex)
+            }
+        }
+
+        #cached_impl
     };
     output.into()
 }
@@ -95,16 +502,16 @@ pub fn tree_hash_signed_root_derive(input: TokenStream) -> TokenStream {
         _ => panic!("tree_hash_derive only supports structs."),
     };
 
-    let idents = get_signed_root_named_field_idents(&struct_data);
-    let num_elems = idents.len();
+    let roots = get_signed_root_field_roots(&struct_data);
+    let num_elems = roots.len();
 
     let output = quote! {
         impl #impl_generics tree_hash::SignedRoot for #name #ty_generics #where_clause {
-            fn signed_root(&self) -> Vec<u8> {
+            fn signed_root(&self) -> tree_hash::Hash256 {
                 let mut leaves = Vec::with_capacity(#num_elems * tree_hash::HASHSIZE);
 
                 #(
-                    leaves.append(&mut self.#idents.tree_hash_root());
+                    leaves.extend_from_slice(#roots.as_bytes());
                 )*
 
                 tree_hash::merkle_root(&leaves, 0)
@@ -114,18 +521,19 @@ pub fn tree_hash_signed_root_derive(input: TokenStream) -> TokenStream {
     output.into()
 }
 
-fn get_signed_root_named_field_idents(struct_data: &syn::DataStruct) -> Vec<&syn::Ident> {
+/// Returns an expression for the root of each field that is part of the signed root, honouring
+/// `#[tree_hash(with = "..")]` like `TreeHash` does.
+fn get_signed_root_field_roots(struct_data: &syn::DataStruct) -> Vec<proc_macro2::TokenStream> {
     struct_data
         .fields
         .iter()
-        .filter_map(|f| {
-            if should_skip_signed_root(&f) {
-                None
-            } else {
-                Some(match &f.ident {
-                    Some(ref ident) => ident,
-                    _ => panic!("tree_hash_derive only supports named struct fields"),
-                })
+        .enumerate()
+        .filter(|(_, f)| !should_skip_signed_root(f))
+        .map(|(i, f)| {
+            let member = field_member(i, f);
+            match parse_field_opts(f).with {
+                Some(with) => quote! { #with(&self.#member) },
+                None => quote! { tree_hash::TreeHash::tree_hash_root(&self.#member) },
             }
         })
         .collect()